@@ -0,0 +1,6 @@
+-- Record the result of merging a pull request and who merged it
+
+ALTER TABLE pull_requests
+    ADD COLUMN merge_commit_sha VARCHAR(40) NULL AFTER is_merged,
+    ADD COLUMN merged_by_id VARCHAR(30) NULL AFTER merge_commit_sha,
+    ADD FOREIGN KEY (merged_by_id) REFERENCES users(id) ON DELETE SET NULL;
@@ -0,0 +1,6 @@
+-- Cached result of the background mergeability check
+-- mergeable is NULL until a check has run; mergeable_state is one of unknown, clean, dirty
+
+ALTER TABLE pull_requests
+    ADD COLUMN mergeable BOOLEAN NULL AFTER is_merged,
+    ADD COLUMN mergeable_state VARCHAR(20) NOT NULL DEFAULT 'unknown' AFTER mergeable;
@@ -0,0 +1,16 @@
+-- Edit history for issue and pull request comments
+-- Each row keeps the body a comment had before an edit
+
+CREATE TABLE IF NOT EXISTS comment_edits (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('edit_', REPLACE(UUID(), '-', ''))),
+    comment_id VARCHAR(30) NOT NULL,
+    previous_body TEXT NOT NULL,
+    edited_by_id VARCHAR(30),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (comment_id) REFERENCES comments(id) ON DELETE CASCADE,
+    FOREIGN KEY (edited_by_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_comments_issue ON comments(issue_id, created_at);
+CREATE INDEX idx_comments_pull_request ON comments(pull_request_id, created_at);
+CREATE INDEX idx_comment_edits_comment ON comment_edits(comment_id, created_at);
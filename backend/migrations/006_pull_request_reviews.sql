@@ -0,0 +1,42 @@
+-- Pull request reviews and the line comments attached to them
+-- A review thread is a root comment (in_reply_to_id IS NULL) plus its replies;
+-- resolution and outdating are tracked on every comment of the thread
+
+CREATE TABLE IF NOT EXISTS pull_request_reviews (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('review_', REPLACE(UUID(), '-', ''))),
+    pull_request_id VARCHAR(30) NOT NULL,
+    author_id VARCHAR(30) NOT NULL,
+    state ENUM('APPROVE', 'REQUEST_CHANGES', 'COMMENT') NOT NULL,
+    body TEXT,
+    commit_id VARCHAR(40) NOT NULL,
+    submitted_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (pull_request_id) REFERENCES pull_requests(id) ON DELETE CASCADE,
+    FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS review_comments (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('rc_', REPLACE(UUID(), '-', ''))),
+    pull_request_id VARCHAR(30) NOT NULL,
+    review_id VARCHAR(30),
+    author_id VARCHAR(30) NOT NULL,
+    in_reply_to_id VARCHAR(30),
+    path VARCHAR(1024) NOT NULL,
+    line INTEGER NOT NULL,
+    commit_id VARCHAR(40) NOT NULL,
+    original_line INTEGER NOT NULL,
+    original_commit_id VARCHAR(40) NOT NULL,
+    body TEXT NOT NULL,
+    is_resolved BOOLEAN DEFAULT FALSE NOT NULL,
+    resolved_by_id VARCHAR(30),
+    is_outdated BOOLEAN DEFAULT FALSE NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (pull_request_id) REFERENCES pull_requests(id) ON DELETE CASCADE,
+    FOREIGN KEY (review_id) REFERENCES pull_request_reviews(id) ON DELETE CASCADE,
+    FOREIGN KEY (author_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (in_reply_to_id) REFERENCES review_comments(id) ON DELETE CASCADE,
+    FOREIGN KEY (resolved_by_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_reviews_pull_request ON pull_request_reviews(pull_request_id, submitted_at);
+CREATE INDEX idx_review_comments_pull_request ON review_comments(pull_request_id, created_at);
@@ -0,0 +1,32 @@
+-- Per-branch protection rules, enforced when merging pull requests and on git push
+
+CREATE TABLE IF NOT EXISTS branch_protections (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('bp_', REPLACE(UUID(), '-', ''))),
+    repository_id VARCHAR(30) NOT NULL,
+    branch VARCHAR(255) NOT NULL,
+    required_approving_reviews INTEGER DEFAULT 0 NOT NULL,
+    allow_force_pushes BOOLEAN DEFAULT FALSE NOT NULL,
+    allow_deletions BOOLEAN DEFAULT FALSE NOT NULL,
+    restrict_pushes BOOLEAN DEFAULT FALSE NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE,
+    UNIQUE KEY unique_repo_branch_protection (repository_id, branch)
+);
+
+-- Status check contexts that must succeed before a pull request can merge
+CREATE TABLE IF NOT EXISTS branch_protection_status_checks (
+    protection_id VARCHAR(30) NOT NULL,
+    context VARCHAR(255) NOT NULL,
+    PRIMARY KEY (protection_id, context),
+    FOREIGN KEY (protection_id) REFERENCES branch_protections(id) ON DELETE CASCADE
+);
+
+-- Users allowed to push when restrict_pushes is set
+CREATE TABLE IF NOT EXISTS branch_protection_push_allowances (
+    protection_id VARCHAR(30) NOT NULL,
+    user_id VARCHAR(30) NOT NULL,
+    PRIMARY KEY (protection_id, user_id),
+    FOREIGN KEY (protection_id) REFERENCES branch_protections(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,40 @@
+-- Organization membership and invitations
+-- Org-owned repositories keep the creating user in owner_id, so personal
+-- repository names only need to be unique among repos without an organization
+
+CREATE TABLE IF NOT EXISTS organization_members (
+    organization_id VARCHAR(30) NOT NULL,
+    user_id VARCHAR(30) NOT NULL,
+    role ENUM('owner', 'member', 'billing_manager') DEFAULT 'member' NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (organization_id, user_id),
+    FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS organization_invitations (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('inv_', REPLACE(UUID(), '-', ''))),
+    organization_id VARCHAR(30) NOT NULL,
+    invitee_id VARCHAR(30) NOT NULL,
+    inviter_id VARCHAR(30) NOT NULL,
+    role ENUM('owner', 'member', 'billing_manager') DEFAULT 'member' NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (invitee_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (inviter_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE KEY unique_org_invitee (organization_id, invitee_id)
+);
+
+CREATE INDEX idx_org_members_user ON organization_members(user_id);
+CREATE INDEX idx_org_invitations_invitee ON organization_invitations(invitee_id);
+
+-- Existing organizations keep their creator as an owner
+INSERT IGNORE INTO organization_members (organization_id, user_id, role, created_at)
+SELECT id, owner_id, 'owner', created_at FROM organizations;
+
+CREATE INDEX idx_repositories_owner ON repositories(owner_id);
+
+ALTER TABLE repositories
+    ADD COLUMN personal_owner_id VARCHAR(30) AS (IF(organization_id IS NULL, owner_id, NULL)) VIRTUAL,
+    DROP INDEX unique_owner_repo,
+    ADD UNIQUE KEY unique_owner_repo (personal_owner_id, name);
@@ -0,0 +1,39 @@
+-- Teams inside organizations; a child team's members inherit the repository
+-- grants of every ancestor team
+
+CREATE TABLE IF NOT EXISTS teams (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('team_', REPLACE(UUID(), '-', ''))),
+    organization_id VARCHAR(30) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    slug VARCHAR(255) NOT NULL,
+    description TEXT,
+    parent_id VARCHAR(30),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (parent_id) REFERENCES teams(id) ON DELETE SET NULL,
+    UNIQUE KEY unique_org_team_slug (organization_id, slug)
+);
+
+CREATE TABLE IF NOT EXISTS team_members (
+    team_id VARCHAR(30) NOT NULL,
+    user_id VARCHAR(30) NOT NULL,
+    role ENUM('member', 'maintainer') DEFAULT 'member' NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (team_id, user_id),
+    FOREIGN KEY (team_id) REFERENCES teams(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS team_repositories (
+    team_id VARCHAR(30) NOT NULL,
+    repository_id VARCHAR(30) NOT NULL,
+    permission ENUM('read', 'triage', 'write', 'maintain', 'admin') DEFAULT 'read' NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (team_id, repository_id),
+    FOREIGN KEY (team_id) REFERENCES teams(id) ON DELETE CASCADE,
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_team_members_user ON team_members(user_id);
+CREATE INDEX idx_team_repositories_repo ON team_repositories(repository_id);
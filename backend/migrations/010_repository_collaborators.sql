@@ -0,0 +1,27 @@
+-- Repository collaborators and the invitations that create them
+
+CREATE TABLE IF NOT EXISTS repository_collaborators (
+    repository_id VARCHAR(30) NOT NULL,
+    user_id VARCHAR(30) NOT NULL,
+    permission ENUM('read', 'triage', 'write', 'maintain', 'admin') DEFAULT 'write' NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (repository_id, user_id),
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS repository_invitations (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('rinv_', REPLACE(UUID(), '-', ''))),
+    repository_id VARCHAR(30) NOT NULL,
+    invitee_id VARCHAR(30) NOT NULL,
+    inviter_id VARCHAR(30) NOT NULL,
+    permission ENUM('read', 'triage', 'write', 'maintain', 'admin') DEFAULT 'write' NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE,
+    FOREIGN KEY (invitee_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (inviter_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE KEY unique_repo_invitee (repository_id, invitee_id)
+);
+
+CREATE INDEX idx_collaborators_user ON repository_collaborators(user_id);
+CREATE INDEX idx_repo_invitations_invitee ON repository_invitations(invitee_id);
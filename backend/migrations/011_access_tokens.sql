@@ -0,0 +1,16 @@
+-- Personal access tokens; only a SHA-256 hash of each token is stored
+
+CREATE TABLE IF NOT EXISTS access_tokens (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('pat_', REPLACE(UUID(), '-', ''))),
+    user_id VARCHAR(30) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    token_hash CHAR(64) UNIQUE NOT NULL,
+    token_last_eight CHAR(8) NOT NULL,
+    scopes VARCHAR(255) DEFAULT '' NOT NULL, -- Comma-separated, e.g. "repo,read:user"
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_used TIMESTAMP NULL,
+    expires_at TIMESTAMP NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_access_tokens_user ON access_tokens(user_id);
@@ -0,0 +1,15 @@
+-- SSH public keys used to authenticate git over SSH
+
+CREATE TABLE IF NOT EXISTS ssh_keys (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('key_', REPLACE(UUID(), '-', ''))),
+    user_id VARCHAR(30) NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    key_type VARCHAR(64) NOT NULL,
+    key_data TEXT NOT NULL,
+    fingerprint VARCHAR(255) UNIQUE NOT NULL, -- SHA256:<base64>, as printed by ssh-keygen -l
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_used TIMESTAMP NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_ssh_keys_user ON ssh_keys(user_id);
@@ -0,0 +1,29 @@
+-- TOTP two-factor authentication and one-time recovery codes
+
+ALTER TABLE users
+    ADD COLUMN two_factor_enabled BOOLEAN DEFAULT FALSE NOT NULL;
+
+-- One row per enrollment; two_factor_enabled is only set once a first code confirms it
+CREATE TABLE IF NOT EXISTS two_factor_secrets (
+    user_id VARCHAR(30) PRIMARY KEY,
+    secret VARCHAR(64) NOT NULL, -- Base32, as shown to authenticator apps
+    last_used_step BIGINT NULL, -- Most recent accepted 30-second step, so a code works only once
+    failed_attempts INT DEFAULT 0 NOT NULL,
+    locked_until TIMESTAMP NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS two_factor_recovery_codes (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('rc_', REPLACE(UUID(), '-', ''))),
+    user_id VARCHAR(30) NOT NULL,
+    code_hash CHAR(64) NOT NULL,
+    used_at TIMESTAMP NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_recovery_codes_user ON two_factor_recovery_codes(user_id);
+
+ALTER TABLE organizations
+    ADD COLUMN two_factor_required BOOLEAN DEFAULT FALSE NOT NULL;
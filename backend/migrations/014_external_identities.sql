@@ -0,0 +1,14 @@
+-- Accounts at external OpenID Connect providers linked to local users
+
+CREATE TABLE IF NOT EXISTS user_identities (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('ident_', REPLACE(UUID(), '-', ''))),
+    user_id VARCHAR(30) NOT NULL,
+    provider VARCHAR(64) NOT NULL, -- Provider name from OIDC_PROVIDERS
+    subject VARCHAR(255) NOT NULL, -- The provider's stable user id (the sub claim)
+    email VARCHAR(255) NULL, -- As reported by the provider when the link was made
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_used TIMESTAMP NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE KEY unique_provider_subject (provider, subject),
+    UNIQUE KEY unique_user_provider (user_id, provider)
+);
@@ -0,0 +1,61 @@
+-- DevIT as an OAuth 2.0 authorization server for third-party applications
+
+CREATE TABLE IF NOT EXISTS oauth_applications (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('app_', REPLACE(UUID(), '-', ''))),
+    owner_id VARCHAR(30) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    description TEXT NULL,
+    homepage_url VARCHAR(500) NULL,
+    client_id VARCHAR(32) UNIQUE NOT NULL,
+    client_secret_hash CHAR(64) NOT NULL,
+    redirect_uris TEXT NOT NULL, -- Newline-separated; a redirect_uri must match one exactly
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_oauth_applications_owner ON oauth_applications(owner_id);
+
+-- Consent a user has given an application, so returning users are not asked again
+CREATE TABLE IF NOT EXISTS oauth_authorizations (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('oauth_', REPLACE(UUID(), '-', ''))),
+    application_id VARCHAR(30) NOT NULL,
+    user_id VARCHAR(30) NOT NULL,
+    scopes VARCHAR(255) DEFAULT '' NOT NULL, -- Comma-separated, as for access_tokens
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (application_id) REFERENCES oauth_applications(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE KEY unique_application_user (application_id, user_id)
+);
+
+CREATE TABLE IF NOT EXISTS oauth_authorization_codes (
+    code_hash CHAR(64) PRIMARY KEY,
+    application_id VARCHAR(30) NOT NULL,
+    user_id VARCHAR(30) NOT NULL,
+    redirect_uri VARCHAR(500) NOT NULL,
+    scopes VARCHAR(255) DEFAULT '' NOT NULL,
+    code_challenge VARCHAR(128) NOT NULL, -- PKCE S256 challenge
+    expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (application_id) REFERENCES oauth_applications(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Only SHA-256 hashes of access and refresh tokens are stored
+CREATE TABLE IF NOT EXISTS oauth_access_tokens (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('oat_', REPLACE(UUID(), '-', ''))),
+    application_id VARCHAR(30) NOT NULL,
+    user_id VARCHAR(30) NOT NULL,
+    token_hash CHAR(64) UNIQUE NOT NULL,
+    refresh_token_hash CHAR(64) UNIQUE NOT NULL,
+    scopes VARCHAR(255) DEFAULT '' NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    refresh_expires_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    last_used TIMESTAMP NULL,
+    FOREIGN KEY (application_id) REFERENCES oauth_applications(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_oauth_access_tokens_grant ON oauth_access_tokens(application_id, user_id);
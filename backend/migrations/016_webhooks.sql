@@ -0,0 +1,46 @@
+-- Outbound webhooks on repositories and organizations, with a log of every delivery
+
+CREATE TABLE IF NOT EXISTS webhooks (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('hook_', REPLACE(UUID(), '-', ''))),
+    repository_id VARCHAR(30) NULL, -- Exactly one of repository_id and organization_id is set
+    organization_id VARCHAR(30) NULL,
+    url VARCHAR(500) NOT NULL,
+    secret VARCHAR(255) NULL, -- Kept in plain text because every payload is signed with it
+    events VARCHAR(255) DEFAULT 'push' NOT NULL, -- Comma-separated event names
+    active BOOLEAN DEFAULT TRUE NOT NULL,
+    created_by VARCHAR(30) NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_webhooks_repository ON webhooks(repository_id);
+CREATE INDEX idx_webhooks_organization ON webhooks(organization_id);
+
+-- One row per delivery; retries update the row, redeliveries add a new one with the same guid
+CREATE TABLE IF NOT EXISTS webhook_deliveries (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('whd_', REPLACE(UUID(), '-', ''))),
+    webhook_id VARCHAR(30) NOT NULL,
+    guid CHAR(36) NOT NULL, -- Sent as X-DevIT-Delivery
+    event VARCHAR(32) NOT NULL,
+    action VARCHAR(32) NULL,
+    redelivery BOOLEAN DEFAULT FALSE NOT NULL,
+    request_headers TEXT NOT NULL, -- JSON object
+    request_body MEDIUMTEXT NOT NULL,
+    response_status SMALLINT NULL,
+    response_headers TEXT NULL, -- JSON object
+    response_body MEDIUMTEXT NULL,
+    error VARCHAR(500) NULL, -- Why the last attempt got no response
+    duration_ms INT NULL,
+    attempts INT DEFAULT 0 NOT NULL,
+    status ENUM('pending', 'delivered', 'failed') DEFAULT 'pending' NOT NULL,
+    next_attempt_at TIMESTAMP NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    delivered_at TIMESTAMP NULL, -- When the last attempt finished
+    FOREIGN KEY (webhook_id) REFERENCES webhooks(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_webhook_deliveries_webhook ON webhook_deliveries(webhook_id, created_at);
+CREATE INDEX idx_webhook_deliveries_retry ON webhook_deliveries(status, next_attempt_at);
@@ -0,0 +1,64 @@
+-- Commit statuses and check runs reported by CI, and the head commit status cached on pull requests
+
+-- Every report is kept; the latest one for a sha and context is its current state
+CREATE TABLE IF NOT EXISTS commit_statuses (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('cs_', REPLACE(UUID(), '-', ''))),
+    repository_id VARCHAR(30) NOT NULL,
+    sha CHAR(40) NOT NULL,
+    state ENUM('error', 'failure', 'pending', 'success') NOT NULL,
+    context VARCHAR(255) DEFAULT 'default' NOT NULL,
+    description VARCHAR(1000) NULL,
+    target_url VARCHAR(500) NULL,
+    creator_id VARCHAR(30) NULL,
+    created_at TIMESTAMP(3) DEFAULT CURRENT_TIMESTAMP(3),
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE,
+    FOREIGN KEY (creator_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_commit_statuses_sha ON commit_statuses(repository_id, sha, context, created_at);
+
+CREATE TABLE IF NOT EXISTS check_runs (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('cr_', REPLACE(UUID(), '-', ''))),
+    repository_id VARCHAR(30) NOT NULL,
+    head_sha CHAR(40) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    status ENUM('queued', 'in_progress', 'completed') DEFAULT 'queued' NOT NULL,
+    conclusion ENUM('success', 'failure', 'neutral', 'cancelled', 'skipped', 'timed_out', 'action_required') NULL,
+    details_url VARCHAR(500) NULL,
+    external_id VARCHAR(255) NULL, -- The reporting system's own id for the run
+    output_title VARCHAR(255) NULL,
+    output_summary TEXT NULL,
+    output_text MEDIUMTEXT NULL,
+    started_at TIMESTAMP NULL,
+    completed_at TIMESTAMP NULL,
+    creator_id VARCHAR(30) NULL,
+    created_at TIMESTAMP(3) DEFAULT CURRENT_TIMESTAMP(3),
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE,
+    FOREIGN KEY (creator_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_check_runs_sha ON check_runs(repository_id, head_sha, name, created_at);
+
+CREATE TABLE IF NOT EXISTS check_run_annotations (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('cra_', REPLACE(UUID(), '-', ''))),
+    check_run_id VARCHAR(30) NOT NULL,
+    path VARCHAR(1000) NOT NULL,
+    start_line INTEGER NOT NULL,
+    end_line INTEGER NOT NULL,
+    annotation_level ENUM('notice', 'warning', 'failure') NOT NULL,
+    title VARCHAR(255) NULL,
+    message TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (check_run_id) REFERENCES check_runs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_check_run_annotations_run ON check_run_annotations(check_run_id);
+
+-- Refreshed with mergeability when the head branch moves, and whenever a status for head_sha is reported
+-- head_status is one of pending, success, failure, error; NULL until anything has been reported
+ALTER TABLE pull_requests
+    ADD COLUMN head_sha CHAR(40) NULL AFTER mergeable_state,
+    ADD COLUMN head_status VARCHAR(20) NULL AFTER head_sha;
+
+CREATE INDEX idx_pull_requests_head_sha ON pull_requests(repository_id, head_sha);
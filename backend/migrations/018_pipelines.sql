@@ -0,0 +1,118 @@
+-- Built-in CI: pipeline runs from .devit/pipelines/*.yml, their jobs and steps, and the runners that execute them
+
+-- Runners belong to either a repository or an organization, whose repositories they take jobs from
+CREATE TABLE IF NOT EXISTS ci_runners (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('rnr_', REPLACE(UUID(), '-', ''))),
+    repository_id VARCHAR(30) NULL,
+    organization_id VARCHAR(30) NULL,
+    name VARCHAR(255) NOT NULL,
+    labels VARCHAR(1000) NOT NULL, -- Comma separated, matched against a job's runs-on
+    token_hash CHAR(64) NOT NULL UNIQUE, -- SHA-256 of the runner token, which is only shown once
+    token_last_eight CHAR(8) NOT NULL,
+    active BOOLEAN DEFAULT TRUE NOT NULL,
+    created_by VARCHAR(30) NULL,
+    last_seen_at TIMESTAMP NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_ci_runners_repository ON ci_runners(repository_id);
+CREATE INDEX idx_ci_runners_organization ON ci_runners(organization_id);
+
+-- One run per pipeline file per triggering event; a file that fails to parse gets a failed run with the error
+CREATE TABLE IF NOT EXISTS pipeline_runs (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('plr_', REPLACE(UUID(), '-', ''))),
+    repository_id VARCHAR(30) NOT NULL,
+    number INTEGER NOT NULL,
+    pipeline_path VARCHAR(500) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    event ENUM('push', 'pull_request') NOT NULL,
+    ref_name VARCHAR(255) NOT NULL,
+    sha CHAR(40) NOT NULL,
+    pull_request_id VARCHAR(30) NULL,
+    status ENUM('queued', 'in_progress', 'completed') DEFAULT 'queued' NOT NULL,
+    conclusion ENUM('success', 'failure', 'cancelled', 'skipped') NULL,
+    error VARCHAR(1000) NULL,
+    triggered_by VARCHAR(30) NULL,
+    created_at TIMESTAMP(3) DEFAULT CURRENT_TIMESTAMP(3),
+    started_at TIMESTAMP NULL,
+    completed_at TIMESTAMP NULL,
+    UNIQUE KEY unique_pipeline_run_number (repository_id, number),
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE,
+    FOREIGN KEY (pull_request_id) REFERENCES pull_requests(id) ON DELETE SET NULL,
+    FOREIGN KEY (triggered_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_pipeline_runs_sha ON pipeline_runs(repository_id, sha);
+
+-- Jobs with unfinished needs wait; they are queued once every job they need has succeeded
+CREATE TABLE IF NOT EXISTS pipeline_jobs (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('plj_', REPLACE(UUID(), '-', ''))),
+    run_id VARCHAR(30) NOT NULL,
+    repository_id VARCHAR(30) NOT NULL,
+    job_key VARCHAR(100) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    runs_on VARCHAR(1000) DEFAULT '' NOT NULL, -- Comma separated labels a runner must all have
+    needs VARCHAR(1000) DEFAULT '' NOT NULL, -- Comma separated job keys
+    spec MEDIUMTEXT NOT NULL, -- The job definition as JSON
+    status ENUM('waiting', 'queued', 'in_progress', 'completed') NOT NULL,
+    conclusion ENUM('success', 'failure', 'cancelled', 'skipped') NULL,
+    cancel_requested BOOLEAN DEFAULT FALSE NOT NULL,
+    runner_id VARCHAR(30) NULL,
+    check_run_id VARCHAR(30) NULL,
+    lease_expires_at TIMESTAMP NULL,
+    log_bytes BIGINT DEFAULT 0 NOT NULL,
+    created_at TIMESTAMP(3) DEFAULT CURRENT_TIMESTAMP(3),
+    started_at TIMESTAMP NULL,
+    completed_at TIMESTAMP NULL,
+    UNIQUE KEY unique_pipeline_job_key (run_id, job_key),
+    FOREIGN KEY (run_id) REFERENCES pipeline_runs(id) ON DELETE CASCADE,
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE,
+    FOREIGN KEY (runner_id) REFERENCES ci_runners(id) ON DELETE SET NULL,
+    FOREIGN KEY (check_run_id) REFERENCES check_runs(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_pipeline_jobs_queue ON pipeline_jobs(status, created_at);
+
+CREATE TABLE IF NOT EXISTS pipeline_steps (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('pls_', REPLACE(UUID(), '-', ''))),
+    job_id VARCHAR(30) NOT NULL,
+    number INTEGER NOT NULL, -- 1-based, in the order the job defines them
+    name VARCHAR(255) NOT NULL,
+    status ENUM('queued', 'in_progress', 'completed') DEFAULT 'queued' NOT NULL,
+    conclusion ENUM('success', 'failure', 'cancelled', 'skipped') NULL,
+    exit_code INTEGER NULL,
+    started_at TIMESTAMP NULL,
+    completed_at TIMESTAMP NULL,
+    UNIQUE KEY unique_pipeline_step_number (job_id, number),
+    FOREIGN KEY (job_id) REFERENCES pipeline_jobs(id) ON DELETE CASCADE
+);
+
+-- Log output arrives from runners in chunks while a step runs
+CREATE TABLE IF NOT EXISTS pipeline_log_chunks (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    job_id VARCHAR(30) NOT NULL,
+    step_number INTEGER NOT NULL,
+    content MEDIUMTEXT NOT NULL,
+    created_at TIMESTAMP(3) DEFAULT CURRENT_TIMESTAMP(3),
+    FOREIGN KEY (job_id) REFERENCES pipeline_jobs(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_pipeline_log_chunks_job ON pipeline_log_chunks(job_id, step_number, id);
+
+-- Artifact contents live in object storage under storage_key
+CREATE TABLE IF NOT EXISTS pipeline_artifacts (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('pla_', REPLACE(UUID(), '-', ''))),
+    run_id VARCHAR(30) NOT NULL,
+    job_id VARCHAR(30) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    storage_key VARCHAR(500) NOT NULL,
+    content_type VARCHAR(255) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE KEY unique_pipeline_artifact_name (job_id, name),
+    FOREIGN KEY (run_id) REFERENCES pipeline_runs(id) ON DELETE CASCADE,
+    FOREIGN KEY (job_id) REFERENCES pipeline_jobs(id) ON DELETE CASCADE
+);
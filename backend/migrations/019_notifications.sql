@@ -0,0 +1,63 @@
+-- Repository watching, issue and pull request subscriptions, and the notifications inbox
+
+CREATE TABLE IF NOT EXISTS repository_watches (
+    user_id VARCHAR(30) NOT NULL,
+    repository_id VARCHAR(30) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, repository_id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_repository_watches_repository ON repository_watches(repository_id);
+
+-- Participants are subscribed automatically; an unsubscribed row keeps them from being subscribed again
+CREATE TABLE IF NOT EXISTS thread_subscriptions (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('tsub_', REPLACE(UUID(), '-', ''))),
+    user_id VARCHAR(30) NOT NULL,
+    repository_id VARCHAR(30) NOT NULL,
+    issue_id VARCHAR(30) NULL, -- Exactly one of issue_id and pull_request_id is set
+    pull_request_id VARCHAR(30) NULL,
+    subscribed BOOLEAN DEFAULT TRUE NOT NULL,
+    reason ENUM('assign', 'author', 'comment', 'mention', 'manual') NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    UNIQUE KEY uq_thread_subscriptions_issue (user_id, issue_id),
+    UNIQUE KEY uq_thread_subscriptions_pull_request (user_id, pull_request_id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE,
+    FOREIGN KEY (issue_id) REFERENCES issues(id) ON DELETE CASCADE,
+    FOREIGN KEY (pull_request_id) REFERENCES pull_requests(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_thread_subscriptions_issue ON thread_subscriptions(issue_id);
+CREATE INDEX idx_thread_subscriptions_pull_request ON thread_subscriptions(pull_request_id);
+
+-- One row per user and thread; new activity on the thread marks it unread again
+CREATE TABLE IF NOT EXISTS notifications (
+    id VARCHAR(30) PRIMARY KEY DEFAULT (CONCAT('ntf_', REPLACE(UUID(), '-', ''))),
+    user_id VARCHAR(30) NOT NULL,
+    repository_id VARCHAR(30) NOT NULL,
+    issue_id VARCHAR(30) NULL, -- Exactly one of issue_id and pull_request_id is set
+    pull_request_id VARCHAR(30) NULL,
+    reason ENUM('assign', 'author', 'comment', 'mention', 'manual', 'subscribed') NOT NULL,
+    state ENUM('unread', 'read', 'done') DEFAULT 'unread' NOT NULL,
+    last_actor_id VARCHAR(30) NULL,
+    last_read_at TIMESTAMP NULL,
+    emailed_at TIMESTAMP NULL, -- When the thread was last included in a digest
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, -- Time of the latest activity, not of state changes
+    UNIQUE KEY uq_notifications_issue (user_id, issue_id),
+    UNIQUE KEY uq_notifications_pull_request (user_id, pull_request_id),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE,
+    FOREIGN KEY (issue_id) REFERENCES issues(id) ON DELETE CASCADE,
+    FOREIGN KEY (pull_request_id) REFERENCES pull_requests(id) ON DELETE CASCADE,
+    FOREIGN KEY (last_actor_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_notifications_inbox ON notifications(user_id, state, updated_at);
+CREATE INDEX idx_notifications_digest ON notifications(state, emailed_at);
+
+ALTER TABLE users
+    ADD COLUMN notification_digest BOOLEAN DEFAULT TRUE NOT NULL;
@@ -0,0 +1,7 @@
+-- Digest runs claim the notifications they are about to email, so backend instances
+-- running the digest at the same time never send the same items twice
+
+ALTER TABLE notifications
+    ADD COLUMN digest_claim VARCHAR(32) NULL AFTER emailed_at;
+
+CREATE INDEX idx_notifications_digest_claim ON notifications(digest_claim);
@@ -0,0 +1,38 @@
+-- Issues and pull requests share one number space per repository. Numbers were picked as one past
+-- the highest in either table, so an issue and a pull request created together could get the same
+-- number, leaving /issues/{number} ambiguous.
+
+-- The later-numbered side of a collision is always the pull request here: it moves past the highest
+-- number in use in its repository, in creation order
+UPDATE pull_requests
+JOIN (
+    SELECT
+        pr.id,
+        highest.number + ROW_NUMBER() OVER (PARTITION BY pr.repository_id ORDER BY pr.created_at, pr.id) AS number
+    FROM pull_requests pr
+    JOIN issues ON issues.repository_id = pr.repository_id AND issues.number = pr.number
+    JOIN (
+        SELECT repository_id, MAX(number) AS number FROM (
+            SELECT repository_id, number FROM issues
+            UNION ALL
+            SELECT repository_id, number FROM pull_requests
+        ) AS numbers
+        GROUP BY repository_id
+    ) AS highest ON highest.repository_id = pr.repository_id
+) AS renumbered ON renumbered.id = pull_requests.id
+SET pull_requests.number = renumbered.number;
+
+-- Every issue and pull request claims its number here first, so the primary key rejects a second
+-- claim across both tables. Claims outlive the issue or pull request, so numbers are never reused.
+CREATE TABLE IF NOT EXISTS issue_numbers (
+    repository_id VARCHAR(30) NOT NULL,
+    number INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (repository_id, number),
+    FOREIGN KEY (repository_id) REFERENCES repositories(id) ON DELETE CASCADE
+);
+
+INSERT INTO issue_numbers (repository_id, number, created_at)
+SELECT repository_id, number, created_at FROM issues
+UNION ALL
+SELECT repository_id, number, created_at FROM pull_requests;
@@ -0,0 +1,502 @@
+// DevIT pipeline runner: polls the server for jobs and executes their steps as local processes.
+//
+//   DEVIT_URL=http://localhost:8000 DEVIT_RUNNER_TOKEN=dvn_... cargo run --bin devit-runner
+//
+// Each job runs in a fresh directory under DEVIT_RUNNER_WORKDIR holding the commit's sources.
+// Steps run with `sh -c` one after another; output is streamed back while they run.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const LOG_FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const LOG_FLUSH_BYTES: usize = 64 * 1024;
+// Background processes a step left behind may hold its output open
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    data: Option<T>,
+    message: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Step {
+    number: i32,
+    name: String,
+    run: String,
+    env: BTreeMap<String, String>,
+    working_directory: Option<String>,
+    continue_on_error: bool,
+}
+
+#[derive(Deserialize)]
+struct Job {
+    id: String,
+    repository: String,
+    sha: String,
+    name: String,
+    env: BTreeMap<String, String>,
+    steps: Vec<Step>,
+    artifacts: Vec<String>,
+    timeout_minutes: u64,
+    heartbeat_interval_seconds: u64,
+}
+
+#[derive(Deserialize)]
+struct Heartbeat {
+    cancel_requested: bool,
+}
+
+#[derive(Serialize)]
+struct StepUpdate<'a> {
+    status: &'a str,
+    conclusion: Option<&'a str>,
+    exit_code: Option<i32>,
+}
+
+#[derive(Clone)]
+struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+impl Client {
+    fn url(&self, path: &str) -> String {
+        format!("{}/runner/v1/jobs{}", self.base_url, path)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, String> {
+        let response = request
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let message = response
+                .json::<ApiResponse<serde_json::Value>>()
+                .await
+                .ok()
+                .and_then(|body| body.message)
+                .unwrap_or_default();
+            return Err(format!("Server returned {}: {}", status, message));
+        }
+
+        Ok(response)
+    }
+
+    async fn request_job(&self) -> Result<Option<Job>, String> {
+        let response = self.send(self.http.post(self.url("/request"))).await?;
+        if response.status() == reqwest::StatusCode::NO_CONTENT {
+            return Ok(None);
+        }
+
+        let body: ApiResponse<Job> = response.json().await.map_err(|e| format!("Invalid job: {}", e))?;
+        Ok(body.data)
+    }
+
+    async fn heartbeat(&self, job_id: &str) -> Result<bool, String> {
+        let response = self.send(self.http.post(self.url(&format!("/{}/heartbeat", job_id)))).await?;
+        let body: ApiResponse<Heartbeat> = response.json().await.map_err(|e| format!("Invalid heartbeat: {}", e))?;
+        Ok(body.data.is_some_and(|heartbeat| heartbeat.cancel_requested))
+    }
+
+    async fn source(&self, job_id: &str) -> Result<Vec<u8>, String> {
+        let response = self.send(self.http.get(self.url(&format!("/{}/source", job_id)))).await?;
+        response.bytes().await.map(|bytes| bytes.to_vec()).map_err(|e| format!("Download failed: {}", e))
+    }
+
+    async fn update_step(&self, job_id: &str, number: i32, update: &StepUpdate<'_>) -> Result<(), String> {
+        let url = self.url(&format!("/{}/steps/{}", job_id, number));
+        self.send(self.http.patch(url).json(update)).await.map(|_| ())
+    }
+
+    async fn append_log(&self, job_id: &str, number: i32, content: String) -> Result<(), String> {
+        let url = self.url(&format!("/{}/steps/{}/logs", job_id, number));
+        self.send(self.http.post(url).header("content-type", "text/plain; charset=utf-8").body(content))
+            .await
+            .map(|_| ())
+    }
+
+    async fn upload_artifact(&self, job_id: &str, name: &str, content_type: &str, data: Vec<u8>) -> Result<(), String> {
+        let url = self.url(&format!("/{}/artifacts/{}", job_id, name));
+        self.send(self.http.put(url).header("content-type", content_type).body(data)).await.map(|_| ())
+    }
+
+    async fn complete(&self, job_id: &str, conclusion: &str) -> Result<(), String> {
+        let url = self.url(&format!("/{}/complete", job_id));
+        self.send(self.http.post(url).json(&serde_json::json!({ "conclusion": conclusion }))).await.map(|_| ())
+    }
+}
+
+// How a step or the job as a whole ended
+#[derive(Clone, Copy, PartialEq)]
+enum Outcome {
+    Success,
+    Failure,
+    Cancelled,
+}
+
+impl Outcome {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Outcome::Success => "success",
+            Outcome::Failure => "failure",
+            Outcome::Cancelled => "cancelled",
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
+
+    let base_url = std::env::var("DEVIT_URL").unwrap_or_else(|_| "http://localhost:8000".to_string());
+    let token = std::env::var("DEVIT_RUNNER_TOKEN").expect("DEVIT_RUNNER_TOKEN must be set");
+    let workdir = PathBuf::from(std::env::var("DEVIT_RUNNER_WORKDIR").unwrap_or_else(|_| "./data/runner".to_string()));
+
+    let client = Client {
+        http: reqwest::Client::new(),
+        base_url: base_url.trim_end_matches('/').to_string(),
+        token,
+    };
+
+    log::info!("Runner polling {} for jobs", client.base_url);
+
+    loop {
+        match client.request_job().await {
+            Ok(Some(job)) => {
+                log::info!("Running job {} ({}) for {}@{}", job.name, job.id, job.repository, job.sha);
+
+                let conclusion = run_job(&client, &workdir, &job).await;
+                log::info!("Job {} finished: {}", job.id, conclusion.as_str());
+
+                if let Err(err) = client.complete(&job.id, conclusion.as_str()).await {
+                    log::error!("Failed to report job {}: {}", job.id, err);
+                }
+            }
+            Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+            Err(err) => {
+                log::error!("Failed to request a job: {}", err);
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+}
+
+async fn run_job(client: &Client, workdir: &Path, job: &Job) -> Outcome {
+    let workspace = workdir.join(&job.id);
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    // Heartbeats keep the job leased to this runner and carry cancellation requests
+    let heartbeat = {
+        let (client, job_id, cancelled) = (client.clone(), job.id.clone(), cancelled.clone());
+        let interval = Duration::from_secs(job.heartbeat_interval_seconds.max(1));
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                match client.heartbeat(&job_id).await {
+                    Ok(true) => cancelled.store(true, Ordering::SeqCst),
+                    Ok(false) => {}
+                    Err(err) => log::warn!("Heartbeat for job {} failed: {}", job_id, err),
+                }
+            }
+        })
+    };
+
+    let outcome = match prepare_workspace(client, &workspace, job).await {
+        Ok(()) => run_steps(client, &workspace, job, &cancelled).await,
+        Err(err) => {
+            log::error!("Failed to prepare job {}: {}", job.id, err);
+            if let Some(step) = job.steps.first() {
+                let _ = client.append_log(&job.id, step.number, format!("Failed to prepare the workspace: {}\n", err)).await;
+            }
+            Outcome::Failure
+        }
+    };
+
+    heartbeat.abort();
+
+    if let Err(err) = tokio::fs::remove_dir_all(&workspace).await {
+        log::warn!("Failed to clean up {}: {}", workspace.display(), err);
+    }
+
+    outcome
+}
+
+async fn prepare_workspace(client: &Client, workspace: &Path, job: &Job) -> Result<(), String> {
+    if workspace.exists() {
+        tokio::fs::remove_dir_all(workspace).await.map_err(|e| e.to_string())?;
+    }
+    tokio::fs::create_dir_all(workspace).await.map_err(|e| e.to_string())?;
+
+    let archive = client.source(&job.id).await?;
+    let archive_path = workspace.join(".devit-source.tar.gz");
+    tokio::fs::write(&archive_path, archive).await.map_err(|e| e.to_string())?;
+
+    let status = Command::new("tar")
+        .arg("-xzf")
+        .arg(&archive_path)
+        .arg("-C")
+        .arg(workspace)
+        .status()
+        .await
+        .map_err(|e| format!("Failed to run tar: {}", e))?;
+
+    tokio::fs::remove_file(&archive_path).await.map_err(|e| e.to_string())?;
+
+    if !status.success() {
+        return Err(format!("tar exited with {}", status));
+    }
+
+    Ok(())
+}
+
+async fn run_steps(client: &Client, workspace: &Path, job: &Job, cancelled: &AtomicBool) -> Outcome {
+    let deadline = Instant::now() + Duration::from_secs(job.timeout_minutes * 60);
+    let mut outcome = Outcome::Success;
+
+    for step in &job.steps {
+        let (step_outcome, exit_code) = run_step(client, workspace, job, step, cancelled, deadline).await;
+
+        let update = StepUpdate { status: "completed", conclusion: Some(step_outcome.as_str()), exit_code };
+        if let Err(err) = client.update_step(&job.id, step.number, &update).await {
+            log::error!("Failed to report step {} of job {}: {}", step.number, job.id, err);
+        }
+
+        match step_outcome {
+            Outcome::Success => {}
+            Outcome::Failure if step.continue_on_error => {}
+            _ => {
+                // Remaining steps are left for the server to mark as skipped
+                outcome = step_outcome;
+                break;
+            }
+        }
+    }
+
+    if outcome == Outcome::Success {
+        if let Some(step) = job.steps.last() {
+            upload_artifacts(client, workspace, job, step.number).await;
+        }
+    }
+
+    outcome
+}
+
+async fn run_step(
+    client: &Client,
+    workspace: &Path,
+    job: &Job,
+    step: &Step,
+    cancelled: &AtomicBool,
+    deadline: Instant,
+) -> (Outcome, Option<i32>) {
+    let in_progress = StepUpdate { status: "in_progress", conclusion: None, exit_code: None };
+    if let Err(err) = client.update_step(&job.id, step.number, &in_progress).await {
+        log::error!("Failed to report step {} of job {}: {}", step.number, job.id, err);
+    }
+
+    let mut step_log = StepLog::new(client, &job.id, step.number);
+    step_log.push(format!("==> {}\n", step.name));
+
+    let directory = match &step.working_directory {
+        Some(directory) => workspace.join(directory),
+        None => workspace.to_path_buf(),
+    };
+
+    let mut child = match Command::new("sh")
+        .arg("-c")
+        .arg(&step.run)
+        .current_dir(&directory)
+        .envs(&job.env)
+        .envs(&step.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(err) => {
+            step_log.push(format!("Failed to start step: {}\n", err));
+            step_log.flush().await;
+            return (Outcome::Failure, None);
+        }
+    };
+
+    let (sender, mut output) = mpsc::unbounded_channel();
+    if let Some(stdout) = child.stdout.take() {
+        tokio::spawn(forward_lines(stdout, sender.clone()));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        tokio::spawn(forward_lines(stderr, sender.clone()));
+    }
+    drop(sender);
+
+    let mut ticker = tokio::time::interval(LOG_FLUSH_INTERVAL);
+    let result = loop {
+        tokio::select! {
+            status = child.wait() => break Ok(status),
+            Some(line) = output.recv() => {
+                step_log.push(line);
+                if step_log.buffered() >= LOG_FLUSH_BYTES {
+                    step_log.flush().await;
+                }
+            }
+            _ = ticker.tick() => {
+                step_log.flush().await;
+                if cancelled.load(Ordering::SeqCst) {
+                    break Err(Outcome::Cancelled);
+                }
+            }
+            _ = tokio::time::sleep_until(deadline) => break Err(Outcome::Failure),
+        }
+    };
+
+    let (outcome, exit_code) = match result {
+        Ok(Ok(status)) => {
+            let outcome = if status.success() { Outcome::Success } else { Outcome::Failure };
+            (outcome, status.code())
+        }
+        Ok(Err(err)) => {
+            step_log.push(format!("Failed to wait for step: {}\n", err));
+            (Outcome::Failure, None)
+        }
+        Err(outcome) => {
+            let _ = child.kill().await;
+            match outcome {
+                Outcome::Cancelled => step_log.push("The job was cancelled\n".to_string()),
+                _ => step_log.push(format!("The job exceeded its timeout of {} minutes\n", job.timeout_minutes)),
+            }
+            (outcome, None)
+        }
+    };
+
+    let _ = tokio::time::timeout(OUTPUT_DRAIN_TIMEOUT, async {
+        while let Some(line) = output.recv().await {
+            step_log.push(line);
+        }
+    })
+    .await;
+
+    if let Some(code) = exit_code.filter(|code| *code != 0) {
+        step_log.push(format!("Process exited with code {}\n", code));
+    }
+    step_log.flush().await;
+
+    (outcome, exit_code)
+}
+
+async fn forward_lines(stream: impl AsyncRead + Unpin, sender: mpsc::UnboundedSender<String>) {
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                if sender.send(String::from_utf8_lossy(&line).into_owned()).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// Buffers step output and sends it to the server in chunks
+struct StepLog<'a> {
+    client: &'a Client,
+    job_id: &'a str,
+    step_number: i32,
+    buffer: String,
+}
+
+impl<'a> StepLog<'a> {
+    fn new(client: &'a Client, job_id: &'a str, step_number: i32) -> Self {
+        Self { client, job_id, step_number, buffer: String::new() }
+    }
+
+    fn push(&mut self, content: String) {
+        print!("{}", content);
+        self.buffer.push_str(&content);
+    }
+
+    fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    async fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let content = std::mem::take(&mut self.buffer);
+        if let Err(err) = self.client.append_log(self.job_id, self.step_number, content).await {
+            log::warn!("Failed to send log for job {}: {}", self.job_id, err);
+        }
+    }
+}
+
+// Files are uploaded as they are; directories as a .tar.gz of their contents
+async fn upload_artifacts(client: &Client, workspace: &Path, job: &Job, step_number: i32) {
+    let mut step_log = StepLog::new(client, &job.id, step_number);
+
+    for artifact in &job.artifacts {
+        let path = workspace.join(artifact);
+        let base_name = path.file_name().and_then(|name| name.to_str()).unwrap_or("artifact").to_string();
+
+        let upload = if path.is_dir() {
+            let archive = workspace.join(format!(".devit-artifact-{}.tar.gz", base_name));
+            let status = Command::new("tar")
+                .arg("-czf")
+                .arg(&archive)
+                .arg("-C")
+                .arg(&path)
+                .arg(".")
+                .status()
+                .await;
+
+            let data = match status {
+                Ok(status) if status.success() => tokio::fs::read(&archive).await.map_err(|e| e.to_string()),
+                Ok(status) => Err(format!("tar exited with {}", status)),
+                Err(err) => Err(format!("Failed to run tar: {}", err)),
+            };
+            let _ = tokio::fs::remove_file(&archive).await;
+
+            data.map(|data| (format!("{}.tar.gz", base_name), "application/gzip", data))
+        } else {
+            tokio::fs::read(&path)
+                .await
+                .map(|data| (base_name, "application/octet-stream", data))
+                .map_err(|e| e.to_string())
+        };
+
+        let result = match upload {
+            Ok((name, content_type, data)) => client.upload_artifact(&job.id, &name, content_type, data).await,
+            Err(err) => Err(err),
+        };
+
+        match result {
+            Ok(()) => step_log.push(format!("Uploaded artifact {}\n", artifact)),
+            Err(err) => step_log.push(format!("Failed to upload artifact {}: {}\n", artifact, err)),
+        }
+    }
+
+    step_log.flush().await;
+}
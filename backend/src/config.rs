@@ -1,3 +1,40 @@
+// An external identity provider users can sign in with
+#[derive(Clone)]
+pub struct OidcProviderConfig {
+    pub name: String, // Used in URLs, e.g. /auth/oidc/{name}/authorize
+    pub display_name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scopes: String,
+    // Set these for providers without discovery (GitHub); otherwise they come from the issuer
+    pub authorization_url: Option<String>,
+    pub token_url: Option<String>,
+    pub userinfo_url: Option<String>,
+}
+
+impl OidcProviderConfig {
+    // Each name in OIDC_PROVIDERS is configured through OIDC_<NAME>_* variables
+    fn from_env(name: &str) -> Self {
+        let var = |key: &str| std::env::var(format!("OIDC_{}_{}", name.to_uppercase(), key)).ok();
+
+        Self {
+            name: name.to_lowercase(),
+            display_name: var("DISPLAY_NAME").unwrap_or_else(|| name.to_string()),
+            issuer: var("ISSUER")
+                .unwrap_or_else(|| panic!("OIDC_{}_ISSUER must be set", name.to_uppercase())),
+            client_id: var("CLIENT_ID")
+                .unwrap_or_else(|| panic!("OIDC_{}_CLIENT_ID must be set", name.to_uppercase())),
+            client_secret: var("CLIENT_SECRET")
+                .unwrap_or_else(|| panic!("OIDC_{}_CLIENT_SECRET must be set", name.to_uppercase())),
+            scopes: var("SCOPES").unwrap_or_else(|| "openid email profile".to_string()),
+            authorization_url: var("AUTHORIZATION_URL"),
+            token_url: var("TOKEN_URL"),
+            userinfo_url: var("USERINFO_URL"),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct AppConfig {
     pub host: String,
@@ -8,6 +45,32 @@ pub struct AppConfig {
     pub minio_endpoint: String,
     pub minio_access_key: String,
     pub minio_secret_key: String,
+    pub git_storage_path: String,
+    // Largest push or fetch request a git client may send, in megabytes after decompression
+    pub git_max_request_mb: usize,
+    pub ssh_port: u16,
+    pub ssh_host_key_path: String,
+    // Base URL of the web frontend, used for links in outgoing mail
+    pub public_url: String,
+    // Outgoing mail: "smtp", "file" or "log"
+    pub mail_transport: String,
+    pub mail_from: String,
+    pub mail_dir: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: Option<String>,
+    pub smtp_password: Option<String>,
+    pub smtp_tls: bool,
+    // Minutes between notification digest emails; 0 turns digests off
+    pub notification_digest_minutes: u64,
+    // Webhook hosts that may resolve to internal addresses, from a comma separated WEBHOOK_ALLOWED_HOSTS list
+    pub webhook_allowed_hosts: Vec<String>,
+    // CI artifacts: "s3" uploads to the MinIO bucket, "file" keeps them under artifact_dir
+    pub artifact_storage: String,
+    pub artifact_bucket: String,
+    pub artifact_dir: String,
+    // External sign-in providers, from a comma separated OIDC_PROVIDERS list
+    pub oidc_providers: Vec<OidcProviderConfig>,
     // AlloyDB specific configurations
     pub alloydb_instance_id: String,
     pub alloydb_cluster_id: String,
@@ -36,6 +99,61 @@ impl AppConfig {
                 .unwrap_or_else(|_| "devit".to_string()),
             minio_secret_key: std::env::var("MINIO_SECRET_KEY")
                 .unwrap_or_else(|_| "devit_password".to_string()),
+            git_storage_path: std::env::var("GIT_STORAGE_PATH")
+                .unwrap_or_else(|_| "./data/repositories".to_string()),
+            git_max_request_mb: std::env::var("GIT_MAX_REQUEST_MB")
+                .unwrap_or_else(|_| "1024".to_string())
+                .parse()
+                .expect("GIT_MAX_REQUEST_MB must be a valid number"),
+            ssh_port: std::env::var("SSH_PORT")
+                .unwrap_or_else(|_| "2222".to_string())
+                .parse()
+                .expect("SSH_PORT must be a valid number"),
+            ssh_host_key_path: std::env::var("SSH_HOST_KEY_PATH")
+                .unwrap_or_else(|_| "./data/ssh_host_ed25519_key".to_string()),
+            public_url: std::env::var("PUBLIC_URL")
+                .unwrap_or_else(|_| "http://localhost:3000".to_string()),
+            mail_transport: std::env::var("MAIL_TRANSPORT")
+                .unwrap_or_else(|_| "log".to_string()),
+            mail_from: std::env::var("MAIL_FROM")
+                .unwrap_or_else(|_| "DevIT <noreply@localhost>".to_string()),
+            mail_dir: std::env::var("MAIL_DIR")
+                .unwrap_or_else(|_| "./data/mail".to_string()),
+            smtp_host: std::env::var("SMTP_HOST")
+                .unwrap_or_else(|_| "localhost".to_string()),
+            smtp_port: std::env::var("SMTP_PORT")
+                .unwrap_or_else(|_| "587".to_string())
+                .parse()
+                .expect("SMTP_PORT must be a valid number"),
+            smtp_username: std::env::var("SMTP_USERNAME").ok(),
+            smtp_password: std::env::var("SMTP_PASSWORD").ok(),
+            smtp_tls: std::env::var("SMTP_TLS")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            notification_digest_minutes: std::env::var("NOTIFICATION_DIGEST_MINUTES")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .expect("NOTIFICATION_DIGEST_MINUTES must be a valid number"),
+            webhook_allowed_hosts: std::env::var("WEBHOOK_ALLOWED_HOSTS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|host| host.trim().to_string())
+                .filter(|host| !host.is_empty())
+                .collect(),
+            artifact_storage: std::env::var("ARTIFACT_STORAGE")
+                .unwrap_or_else(|_| "file".to_string()),
+            artifact_bucket: std::env::var("ARTIFACT_BUCKET")
+                .unwrap_or_else(|_| "devit-artifacts".to_string()),
+            artifact_dir: std::env::var("ARTIFACT_DIR")
+                .unwrap_or_else(|_| "./data/artifacts".to_string()),
+            oidc_providers: std::env::var("OIDC_PROVIDERS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|name| name.trim())
+                .filter(|name| !name.is_empty())
+                .map(OidcProviderConfig::from_env)
+                .collect(),
             // AlloyDB configurations for GCP
             alloydb_instance_id: std::env::var("ALLOYDB_INSTANCE_ID")
                 .unwrap_or_else(|_| "devit-instance".to_string()),
@@ -0,0 +1,71 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use crate::services::AccessTokenService;
+use crate::models::{CreateAccessTokenRequest, User};
+use crate::utils::jwt::{extract_user_from_token, token_authentication};
+use crate::utils::response::{success_response, error_response};
+
+// Credentials are managed from a signed-in session only, so a leaked token cannot mint more.
+// The refusal is boxed to keep the Result small for the many handlers that call this.
+pub fn session_user(req: &HttpRequest) -> Result<User, Box<HttpResponse>> {
+    if token_authentication(req).is_some() {
+        return Err(Box::new(error_response("Credentials cannot be managed with an access token", 403)));
+    }
+
+    extract_user_from_token(req).map_err(|err| Box::new(error_response(&err, 401)))
+}
+
+pub async fn list_tokens(
+    req: HttpRequest,
+    token_service: web::Data<AccessTokenService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match token_service.list_tokens(&current_user.id).await {
+        Ok(tokens) => Ok(success_response(tokens)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn create_token(
+    req: HttpRequest,
+    json: web::Json<CreateAccessTokenRequest>,
+    token_service: web::Data<AccessTokenService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match token_service.create_token(&current_user.id, &json.into_inner()).await {
+        Ok(token) => Ok(success_response(token)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn revoke_token(
+    req: HttpRequest,
+    path: web::Path<String>,
+    token_service: web::Data<AccessTokenService>,
+) -> Result<HttpResponse> {
+    let token_id = path.into_inner();
+
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match token_service.revoke_token(&current_user.id, &token_id).await {
+        Ok(_) => Ok(success_response("Access token revoked")),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub fn access_token_routes() -> actix_web::Scope {
+    web::scope("/user/tokens")
+        .route("", web::get().to(list_tokens))
+        .route("", web::post().to(create_token))
+        .route("/{token_id}", web::delete().to(revoke_token))
+}
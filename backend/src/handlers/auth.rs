@@ -1,10 +1,12 @@
+use actix_web::cookie::{time::Duration as CookieDuration, Cookie, SameSite};
 use actix_web::{web, HttpResponse, Result, HttpRequest};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use sqlx::MySqlPool;
-use crate::services::AuthService;
-use crate::models::{CreateUserRequest, UserResponse};
-use crate::config::AppConfig;
+use crate::services::{AccountService, AuthService, OidcService, SessionService, TwoFactorService};
+use crate::models::{CreateUserRequest, ExternalIdentity, OidcAuthorization, OidcCallbackRequest, RefreshTokenRequest, TwoFactorLoginRequest, UserResponse, UserWithPassword};
+use crate::handlers::access_tokens::session_user;
+use crate::utils::jwt::{extract_user_from_token, session_authentication};
+use crate::utils::response::{success_response, error_response};
 
 #[derive(Deserialize)]
 pub struct LoginRequest {
@@ -12,173 +14,611 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    pub password: String,
+}
+
 #[derive(Serialize)]
 pub struct AuthResponse {
     pub success: bool,
     pub message: String,
     pub token: Option<String>,
+    pub refresh_token: Option<String>,
+    pub expires_in: Option<i64>,
     pub user: Option<UserResponse>,
 }
 
+// User agent and client address, shown when listing sessions
+fn device_info(req: &HttpRequest) -> (Option<String>, Option<String>) {
+    let user_agent = req
+        .headers()
+        .get("User-Agent")
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.chars().take(255).collect());
+    let ip_address = req.connection_info().realip_remote_addr().map(|addr| addr.to_string());
+
+    (user_agent, ip_address)
+}
+
 pub async fn register(
-    pool: web::Data<MySqlPool>,
-    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    auth_service: web::Data<AuthService>,
+    session_service: web::Data<SessionService>,
+    account_service: web::Data<AccountService>,
     request: web::Json<CreateUserRequest>
 ) -> Result<HttpResponse> {
-    let auth_service = AuthService::new(pool.get_ref().clone(), config.jwt_secret.clone());
-    let req = request.into_inner();
+    let body = request.into_inner();
     
-    match auth_service.register(&req.username, &req.email, &req.password, req.full_name.as_deref()).await {
-        Ok(token) => {
-            // Get user details after registration
-            match auth_service.validate_token(&token).await {
-                Ok(user) => Ok(HttpResponse::Created().json(AuthResponse {
+    match auth_service.register(&body.username, &body.email, &body.password, body.full_name.as_deref()).await {
+        Ok(user) => {
+            // A failed send is recoverable through /auth/verify-email/resend
+            if let Err(error) = account_service.send_verification_email(&user).await {
+                log::error!("Failed to send verification email to {}: {}", user.username, error);
+            }
+
+            let (user_agent, ip_address) = device_info(&req);
+
+            // The account exists either way; without a session the client simply logs in
+            match session_service.create_session(&user, user_agent.as_deref(), ip_address.as_deref()).await {
+                Ok(tokens) => Ok(HttpResponse::Created().json(AuthResponse {
                     success: true,
                     message: "User registered successfully".to_string(),
-                    token: Some(token),
+                    token: Some(tokens.access_token),
+                    refresh_token: Some(tokens.refresh_token),
+                    expires_in: Some(tokens.expires_in),
                     user: Some(user.into()),
                 })),
-                Err(_) => Ok(HttpResponse::Created().json(AuthResponse {
-                    success: true,
-                    message: "User registered successfully".to_string(),
-                    token: Some(token),
-                    user: None,
-                }))
+                Err(error) => {
+                    log::error!("Failed to create session after registration: {}", error);
+                    Ok(HttpResponse::Created().json(AuthResponse {
+                        success: true,
+                        message: "User registered successfully".to_string(),
+                        token: None,
+                        refresh_token: None,
+                        expires_in: None,
+                        user: Some(user.into()),
+                    }))
+                }
             }
         },
         Err(error) => Ok(HttpResponse::BadRequest().json(AuthResponse {
             success: false,
             message: error,
             token: None,
+            refresh_token: None,
+            expires_in: None,
+            user: None,
+        }))
+    }
+}
+
+async fn start_session(req: &HttpRequest, session_service: &SessionService, user: UserWithPassword) -> HttpResponse {
+    let (user_agent, ip_address) = device_info(req);
+
+    match session_service.create_session(&user, user_agent.as_deref(), ip_address.as_deref()).await {
+        Ok(tokens) => HttpResponse::Ok().json(AuthResponse {
+            success: true,
+            message: "Login successful".to_string(),
+            token: Some(tokens.access_token),
+            refresh_token: Some(tokens.refresh_token),
+            expires_in: Some(tokens.expires_in),
+            user: Some(user.into()),
+        }),
+        Err(error) => HttpResponse::InternalServerError().json(AuthResponse {
+            success: false,
+            message: error,
+            token: None,
+            refresh_token: None,
+            expires_in: None,
             user: None,
+        })
+    }
+}
+
+// With 2FA on, the first factor only earns a partial token for /auth/2fa/verify
+async fn finish_login(
+    req: &HttpRequest,
+    session_service: &SessionService,
+    two_factor_service: &TwoFactorService,
+    user: UserWithPassword
+) -> HttpResponse {
+    match two_factor_service.is_enabled(&user.id).await {
+        Ok(true) => match two_factor_service.create_login_challenge(&user.id) {
+            Ok(challenge) => HttpResponse::Ok().json(json!({
+                "success": true,
+                "message": "Two-factor authentication required",
+                "two_factor_required": true,
+                "two_factor_token": challenge
+            })),
+            Err(error) => HttpResponse::InternalServerError().json(json!({
+                "success": false,
+                "message": error
+            }))
+        },
+        Ok(false) => start_session(req, session_service, user).await,
+        Err(error) => HttpResponse::InternalServerError().json(json!({
+            "success": false,
+            "message": error
         }))
     }
 }
 
 pub async fn login(
-    pool: web::Data<MySqlPool>,
-    config: web::Data<AppConfig>,
+    req: HttpRequest,
+    auth_service: web::Data<AuthService>,
+    session_service: web::Data<SessionService>,
+    two_factor_service: web::Data<TwoFactorService>,
     request: web::Json<LoginRequest>
 ) -> Result<HttpResponse> {
-    let auth_service = AuthService::new(pool.get_ref().clone(), config.jwt_secret.clone());
-    
-    match auth_service.authenticate(&request.username_or_email, &request.password).await {
-        Ok(token) => {
-            // Get user details for response
-            match auth_service.validate_token(&token).await {
-                Ok(user) => Ok(HttpResponse::Ok().json(AuthResponse {
-                    success: true,
-                    message: "Login successful".to_string(),
-                    token: Some(token),
-                    user: Some(user.into()),
-                })),
-                Err(_) => Ok(HttpResponse::InternalServerError().json(AuthResponse {
-                    success: false,
-                    message: "Authentication successful but failed to retrieve user details".to_string(),
-                    token: Some(token),
-                    user: None,
-                }))
-            }
-        },
-        Err(error) => Ok(HttpResponse::Unauthorized().json(AuthResponse {
+    let user = match auth_service.authenticate(&request.username_or_email, &request.password).await {
+        Ok(user) => user,
+        Err(error) => return Ok(HttpResponse::Unauthorized().json(AuthResponse {
             success: false,
             message: error,
             token: None,
+            refresh_token: None,
+            expires_in: None,
             user: None,
         }))
+    };
+
+    Ok(finish_login(&req, &session_service, &two_factor_service, user).await)
+}
+
+pub async fn verify_two_factor(
+    req: HttpRequest,
+    auth_service: web::Data<AuthService>,
+    session_service: web::Data<SessionService>,
+    two_factor_service: web::Data<TwoFactorService>,
+    request: web::Json<TwoFactorLoginRequest>
+) -> Result<HttpResponse> {
+    let user_id = match two_factor_service.complete_login(&request.two_factor_token, &request.code).await {
+        Ok(user_id) => user_id,
+        Err(error) => return Ok(HttpResponse::Unauthorized().json(json!({
+            "success": false,
+            "message": error
+        })))
+    };
+
+    match auth_service.find_user(&user_id).await {
+        Ok(user) => Ok(start_session(&req, &session_service, user).await),
+        Err(error) => Ok(HttpResponse::Unauthorized().json(json!({
+            "success": false,
+            "message": error
+        })))
     }
 }
 
-pub async fn logout() -> Result<HttpResponse> {
-    // Since we're using stateless JWT tokens, logout is handled client-side
+pub async fn list_oidc_providers(
+    oidc_service: web::Data<OidcService>,
+) -> Result<HttpResponse> {
     Ok(HttpResponse::Ok().json(json!({
         "success": true,
-        "message": "Logged out successfully"
+        "providers": oidc_service.list_providers()
     })))
 }
 
+// Ties an OIDC state to the browser that started the flow. Only sent to the callback.
+const OIDC_BINDING_COOKIE: &str = "devit_oidc_binding";
+const OIDC_BINDING_PATH: &str = "/api/v1/auth/oidc";
+const OIDC_BINDING_MAX_AGE_SECONDS: i64 = 10 * 60;
+
+fn oidc_binding_cookie(req: &HttpRequest, binding: &str) -> Cookie<'static> {
+    Cookie::build(OIDC_BINDING_COOKIE, binding.to_string())
+        .path(OIDC_BINDING_PATH)
+        .http_only(true)
+        .secure(req.connection_info().scheme() == "https")
+        .same_site(SameSite::Lax)
+        .max_age(CookieDuration::seconds(OIDC_BINDING_MAX_AGE_SECONDS))
+        .finish()
+}
+
+fn clear_oidc_binding(response: &mut HttpResponse) {
+    let mut cookie = Cookie::build(OIDC_BINDING_COOKIE, "").path(OIDC_BINDING_PATH).finish();
+    cookie.make_removal();
+    let _ = response.add_cookie(&cookie);
+}
+
+pub async fn oidc_authorize(
+    req: HttpRequest,
+    path: web::Path<String>,
+    oidc_service: web::Data<OidcService>,
+) -> Result<HttpResponse> {
+    let provider = path.into_inner();
+
+    match oidc_service.authorization_url(&provider, None).await {
+        Ok((authorization_url, binding)) => Ok(HttpResponse::Ok()
+            .cookie(oidc_binding_cookie(&req, &binding))
+            .json(json!({
+                "success": true,
+                "authorization_url": authorization_url
+            }))),
+        Err(error) if error == "Identity provider not found" => Ok(HttpResponse::NotFound().json(json!({
+            "success": false,
+            "message": error
+        }))),
+        Err(error) => Ok(HttpResponse::InternalServerError().json(json!({
+            "success": false,
+            "message": error
+        })))
+    }
+}
+
+// The frontend posts the code and state the provider redirected back with. The binding cookie
+// set when the flow started must come along, so a state cannot be finished from another browser.
+pub async fn oidc_callback(
+    req: HttpRequest,
+    path: web::Path<String>,
+    auth_service: web::Data<AuthService>,
+    session_service: web::Data<SessionService>,
+    two_factor_service: web::Data<TwoFactorService>,
+    oidc_service: web::Data<OidcService>,
+    request: web::Json<OidcCallbackRequest>
+) -> Result<HttpResponse> {
+    let provider = path.into_inner();
+    let binding = req.cookie(OIDC_BINDING_COOKIE).map(|cookie| cookie.value().to_string());
+
+    let mut response = match oidc_service.complete(&provider, &request.code, &request.state, binding.as_deref()).await {
+        Ok((identity, Some(link_user_id))) => link_callback(&req, &auth_service, &identity, &link_user_id).await,
+        Ok((identity, None)) => match auth_service.authenticate_external(&identity).await {
+            Ok(user) => finish_login(&req, &session_service, &two_factor_service, user).await,
+            Err(error) => HttpResponse::Unauthorized().json(json!({
+                "success": false,
+                "message": error
+            })),
+        },
+        Err(error) => HttpResponse::Unauthorized().json(json!({
+            "success": false,
+            "message": error
+        })),
+    };
+
+    clear_oidc_binding(&mut response);
+    Ok(response)
+}
+
+// Started from account settings: attach the identity rather than signing in, but only for the
+// user who started linking
+async fn link_callback(req: &HttpRequest, auth_service: &AuthService, identity: &ExternalIdentity, link_user_id: &str) -> HttpResponse {
+    match session_user(req) {
+        Ok(user) if user.id == link_user_id => {}
+        Ok(_) => return error_response("Linking was started by a different user", 403),
+        Err(response) => return *response,
+    }
+
+    match auth_service.link_external_identity(link_user_id, identity).await {
+        Ok(_) => HttpResponse::Ok().json(json!({
+            "success": true,
+            "message": "Account linked successfully"
+        })),
+        Err(error) => HttpResponse::BadRequest().json(json!({
+            "success": false,
+            "message": error
+        })),
+    }
+}
+
+// Ends the session the request was made from; its refresh token stops working immediately
+pub async fn logout(
+    req: HttpRequest,
+    session_service: web::Data<SessionService>,
+) -> Result<HttpResponse> {
+    let session = match session_authentication(&req) {
+        Some(session) => session,
+        None => return Ok(HttpResponse::Unauthorized().json(json!({
+            "success": false,
+            "message": "Not signed in with a session token"
+        })))
+    };
+
+    let revoked = match session_service.deny_token(&session.jti, session.expires_at).await {
+        Ok(_) => session_service.revoke_session(&session.session_id).await,
+        Err(error) => Err(error),
+    };
+
+    match revoked {
+        Ok(_) => Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "message": "Logged out successfully"
+        }))),
+        Err(error) => Ok(HttpResponse::InternalServerError().json(json!({
+            "success": false,
+            "message": error
+        })))
+    }
+}
+
 pub async fn me(
-    pool: web::Data<MySqlPool>,
-    config: web::Data<AppConfig>,
-    req: HttpRequest
+    req: HttpRequest,
+    auth_service: web::Data<AuthService>,
 ) -> Result<HttpResponse> {
-    // Extract token from Authorization header
-    let auth_header = req.headers().get("Authorization");
-    
-    if let Some(auth_value) = auth_header {
-        if let Ok(auth_str) = auth_value.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = &auth_str[7..];
-                let auth_service = AuthService::new(pool.get_ref().clone(), config.jwt_secret.clone());
-                
-                match auth_service.validate_token(token).await {
-                    Ok(user) => return Ok(HttpResponse::Ok().json(json!({
-                        "success": true,
-                        "user": UserResponse::from(user)
-                    }))),
-                    Err(error) => return Ok(HttpResponse::Unauthorized().json(json!({
-                        "success": false,
-                        "message": error
-                    })))
-                }
-            }
-        }
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(error) => return Ok(HttpResponse::Unauthorized().json(json!({
+            "success": false,
+            "message": error
+        })))
+    };
+
+    match auth_service.find_user(&current_user.id).await {
+        Ok(user) => Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "user": UserResponse::from(user)
+        }))),
+        Err(error) => Ok(HttpResponse::Unauthorized().json(json!({
+            "success": false,
+            "message": error
+        })))
     }
-    
-    Ok(HttpResponse::Unauthorized().json(json!({
-        "success": false,
-        "message": "Authorization header missing or invalid"
-    })))
 }
 
+// Trades a refresh token for a new access token and a new refresh token
 pub async fn refresh_token(
-    pool: web::Data<MySqlPool>,
-    config: web::Data<AppConfig>,
-    req: HttpRequest
+    auth_service: web::Data<AuthService>,
+    session_service: web::Data<SessionService>,
+    request: web::Json<RefreshTokenRequest>
 ) -> Result<HttpResponse> {
-    // Extract current token and generate new one
-    let auth_header = req.headers().get("Authorization");
-    
-    if let Some(auth_value) = auth_header {
-        if let Ok(auth_str) = auth_value.to_str() {
-            if auth_str.starts_with("Bearer ") {
-                let token = &auth_str[7..];
-                let auth_service = AuthService::new(pool.get_ref().clone(), config.jwt_secret.clone());
-                
-                match auth_service.validate_token(token).await {
-                    Ok(user) => {
-                        match auth_service.refresh_token(&user.id).await {
-                            Ok(new_token) => return Ok(HttpResponse::Ok().json(json!({
-                                "success": true,
-                                "token": new_token,
-                                "message": "Token refreshed successfully"
-                            }))),
-                            Err(error) => return Ok(HttpResponse::InternalServerError().json(json!({
-                                "success": false,
-                                "message": error
-                            })))
-                        }
-                    },
-                    Err(error) => return Ok(HttpResponse::Unauthorized().json(json!({
-                        "success": false,
-                        "message": error
-                    })))
-                }
+    let (session_id, user_id, refresh_token) = match session_service.rotate_refresh_token(&request.refresh_token).await {
+        Ok(rotated) => rotated,
+        Err(error) => return Ok(HttpResponse::Unauthorized().json(json!({
+            "success": false,
+            "message": error
+        })))
+    };
+
+    let user = match auth_service.find_user(&user_id).await {
+        Ok(user) => user,
+        Err(error) => {
+            // The account is gone, so the session goes with it
+            if let Err(err) = session_service.revoke_session(&session_id).await {
+                log::error!("Failed to revoke session {}: {}", session_id, err);
             }
+            return Ok(HttpResponse::Unauthorized().json(json!({
+                "success": false,
+                "message": error
+            })));
         }
+    };
+
+    match session_service.issue_tokens(&user, &session_id, refresh_token) {
+        Ok(tokens) => Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "token": tokens.access_token,
+            "refresh_token": tokens.refresh_token,
+            "expires_in": tokens.expires_in,
+            "message": "Token refreshed successfully"
+        }))),
+        Err(error) => Ok(HttpResponse::InternalServerError().json(json!({
+            "success": false,
+            "message": error
+        })))
     }
-    
-    Ok(HttpResponse::Unauthorized().json(json!({
-        "success": false,
-        "message": "Authorization header missing or invalid"
+}
+
+pub async fn verify_email(
+    account_service: web::Data<AccountService>,
+    request: web::Json<VerifyEmailRequest>
+) -> Result<HttpResponse> {
+    match account_service.verify_email(&request.token).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "message": "Email verified successfully"
+        }))),
+        Err(error) => Ok(HttpResponse::BadRequest().json(json!({
+            "success": false,
+            "message": error
+        })))
+    }
+}
+
+pub async fn resend_verification_email(
+    req: HttpRequest,
+    auth_service: web::Data<AuthService>,
+    account_service: web::Data<AccountService>,
+) -> Result<HttpResponse> {
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(error) => return Ok(HttpResponse::Unauthorized().json(json!({
+            "success": false,
+            "message": error
+        })))
+    };
+
+    let user = match auth_service.find_user(&current_user.id).await {
+        Ok(user) => user,
+        Err(error) => return Ok(HttpResponse::Unauthorized().json(json!({
+            "success": false,
+            "message": error
+        })))
+    };
+
+    match account_service.send_verification_email(&user).await {
+        Ok(_) => Ok(HttpResponse::Ok().json(json!({
+            "success": true,
+            "message": "Verification email sent"
+        }))),
+        Err(error) => Ok(HttpResponse::BadRequest().json(json!({
+            "success": false,
+            "message": error
+        })))
+    }
+}
+
+pub async fn forgot_password(
+    account_service: web::Data<AccountService>,
+    request: web::Json<ForgotPasswordRequest>
+) -> Result<HttpResponse> {
+    // Sent in the background so the response time does not reveal whether the account exists
+    let account_service = account_service.get_ref().clone();
+    let email = request.into_inner().email;
+    tokio::spawn(async move {
+        if let Err(error) = account_service.request_password_reset(&email).await {
+            log::error!("Failed to send password reset email: {}", error);
+        }
+    });
+
+    Ok(HttpResponse::Ok().json(json!({
+        "success": true,
+        "message": "If an account exists for that address, a password reset link has been sent"
     })))
 }
 
+pub async fn reset_password(
+    account_service: web::Data<AccountService>,
+    session_service: web::Data<SessionService>,
+    request: web::Json<ResetPasswordRequest>
+) -> Result<HttpResponse> {
+    let user_id = match account_service.reset_password(&request.token, &request.password).await {
+        Ok(user_id) => user_id,
+        Err(error) => return Ok(HttpResponse::BadRequest().json(json!({
+            "success": false,
+            "message": error
+        })))
+    };
+
+    // Whoever knew the old password may still hold a session
+    if let Err(error) = session_service.revoke_all_sessions(&user_id).await {
+        log::error!("Failed to revoke sessions after password reset for {}: {}", user_id, error);
+    }
+
+    Ok(HttpResponse::Ok().json(json!({
+        "success": true,
+        "message": "Password has been reset"
+    })))
+}
+
+pub async fn list_sessions(
+    req: HttpRequest,
+    session_service: web::Data<SessionService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    let current_session = session_authentication(&req).map(|session| session.session_id);
+
+    match session_service.list_sessions(&current_user.id, current_session.as_deref()).await {
+        Ok(sessions) => Ok(success_response(sessions)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn revoke_session(
+    req: HttpRequest,
+    path: web::Path<String>,
+    session_service: web::Data<SessionService>,
+) -> Result<HttpResponse> {
+    let session_id = path.into_inner();
+
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match session_service.revoke_user_session(&current_user.id, &session_id).await {
+        Ok(_) => Ok(success_response("Session revoked")),
+        Err(err) if err == "Session not found" => Ok(error_response(&err, 404)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn list_identities(
+    req: HttpRequest,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match auth_service.list_identities(&current_user.id).await {
+        Ok(identities) => Ok(success_response(identities)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+// Returns the provider URL to visit; the identity is linked when the callback comes back
+pub async fn link_identity(
+    req: HttpRequest,
+    path: web::Path<String>,
+    oidc_service: web::Data<OidcService>,
+) -> Result<HttpResponse> {
+    let provider = path.into_inner();
+
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match oidc_service.authorization_url(&provider, Some(&current_user.id)).await {
+        Ok((authorization_url, binding)) => {
+            let mut response = success_response(OidcAuthorization { authorization_url });
+            let _ = response.add_cookie(&oidc_binding_cookie(&req, &binding));
+            Ok(response)
+        }
+        Err(err) if err == "Identity provider not found" => Ok(error_response(&err, 404)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn unlink_identity(
+    req: HttpRequest,
+    path: web::Path<String>,
+    auth_service: web::Data<AuthService>,
+) -> Result<HttpResponse> {
+    let identity_id = path.into_inner();
+
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match auth_service.unlink_identity(&current_user.id, &identity_id).await {
+        Ok(_) => Ok(success_response("Identity unlinked")),
+        Err(err) if err == "Identity not found" => Ok(error_response(&err, 404)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
 pub fn auth_routes() -> actix_web::Scope {
     web::scope("/auth")
         .route("/register", web::post().to(register))
         .route("/login", web::post().to(login))
+        .route("/2fa/verify", web::post().to(verify_two_factor))
+        .route("/oidc/providers", web::get().to(list_oidc_providers))
+        .route("/oidc/{provider}/authorize", web::get().to(oidc_authorize))
+        .route("/oidc/{provider}/callback", web::post().to(oidc_callback))
         .route("/logout", web::post().to(logout))
         .route("/me", web::get().to(me))
         .route("/refresh", web::post().to(refresh_token))
+        .route("/verify-email", web::post().to(verify_email))
+        .route("/verify-email/resend", web::post().to(resend_verification_email))
+        .route("/forgot-password", web::post().to(forgot_password))
+        .route("/reset-password", web::post().to(reset_password))
+}
+
+pub fn session_routes() -> actix_web::Scope {
+    web::scope("/user/sessions")
+        .route("", web::get().to(list_sessions))
+        .route("/{session_id}", web::delete().to(revoke_session))
+}
+
+pub fn identity_routes() -> actix_web::Scope {
+    web::scope("/user/identities")
+        .route("", web::get().to(list_identities))
+        .route("/{provider}", web::post().to(link_identity))
+        .route("/{identity_id}", web::delete().to(unlink_identity))
 }
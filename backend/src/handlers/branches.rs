@@ -0,0 +1,324 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use crate::services::{BranchProtectionService, GitService, PermissionService, RepositoryService};
+use crate::models::{Permission, Repository, UpdateBranchProtectionRequest};
+use crate::models::git::{CreateBranchRequest, CreateTagRequest};
+use crate::handlers::repositories::{find_readable_repository, find_repository_for_user};
+use crate::utils::response::{success_response, error_response};
+
+// Resolves a repository whose refs or settings the current user may change
+async fn find_writable_repository(
+    req: &HttpRequest,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+    owner: &str,
+    name: &str,
+    required: Permission,
+) -> Result<(Repository, String, Permission), HttpResponse> {
+    let (repo, current_user, permission) =
+        find_repository_for_user(req, repo_service, permission_service, owner, name).await?;
+
+    if permission < required {
+        return Err(error_response("Unauthorized to modify this repository", 403));
+    }
+
+    if repo.is_archived_bool() {
+        return Err(error_response("Repository is archived", 403));
+    }
+
+    Ok((repo, current_user.id, permission))
+}
+
+pub async fn list_branches(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    match git_service.blocking(move |git| git.list_branches(&repo.id)).await {
+        Ok(branches) => Ok(success_response(branches)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn get_branch(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, branch) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    match git_service.blocking(move |git| git.get_branch(&repo.id, &branch)).await {
+        Ok(branch) => Ok(success_response(branch)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn create_branch(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    json: web::Json<CreateBranchRequest>,
+    repo_service: web::Data<RepositoryService>,
+    git_service: web::Data<GitService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    let (repo, _, _) = match find_writable_repository(&req, &repo_service, &permission_service, &owner, &repo_name, Permission::Write).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let request = json.into_inner();
+
+    match git_service.blocking(move |git| git.create_branch(&repo.id, &request.name, request.sha.as_deref())).await {
+        Ok(branch) => Ok(success_response(branch)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn delete_branch(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    git_service: web::Data<GitService>,
+    protection_service: web::Data<BranchProtectionService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, branch) = path.into_inner();
+
+    let (repo, user_id, permission) = match find_writable_repository(&req, &repo_service, &permission_service, &owner, &repo_name, Permission::Write).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    if branch == repo.default_branch {
+        return Ok(error_response("Cannot delete the default branch", 400));
+    }
+
+    // Deleting is held to the same rules as a delete pushed over git
+    let protection = match protection_service.get_protection(&repo.id, &branch).await {
+        Ok(protection) => protection,
+        Err(err) => return Ok(error_response(&err, 500)),
+    };
+
+    if let Some(protection) = protection {
+        match protection_service.can_push(&protection, &user_id, permission == Permission::Admin).await {
+            Ok(true) => {}
+            Ok(false) => return Ok(error_response("You are not allowed to push to this protected branch", 403)),
+            Err(err) => return Ok(error_response(&err, 500)),
+        }
+
+        if !protection.allow_deletions {
+            return Ok(error_response("Cannot delete a protected branch", 403));
+        }
+    }
+
+    match git_service.blocking(move |git| git.delete_branch(&repo.id, &branch)).await {
+        Ok(_) => Ok(success_response("Branch deleted successfully")),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn get_branch_protection(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    protection_service: web::Data<BranchProtectionService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, branch) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    match protection_service.get_protection(&repo.id, &branch).await {
+        Ok(Some(protection)) => Ok(success_response(protection)),
+        Ok(None) => Ok(error_response("Branch not protected", 404)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn update_branch_protection(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    json: web::Json<UpdateBranchProtectionRequest>,
+    repo_service: web::Data<RepositoryService>,
+    git_service: web::Data<GitService>,
+    protection_service: web::Data<BranchProtectionService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, branch) = path.into_inner();
+
+    let (repo, _, _) = match find_writable_repository(&req, &repo_service, &permission_service, &owner, &repo_name, Permission::Admin).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let (repo_id, name) = (repo.id.clone(), branch.clone());
+    if let Err(err) = git_service.blocking(move |git| git.get_branch(&repo_id, &name)).await {
+        return Ok(error_response(&err, 404));
+    }
+
+    match protection_service.update_protection(&repo.id, &branch, &json.into_inner()).await {
+        Ok(protection) => Ok(success_response(protection)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn delete_branch_protection(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    protection_service: web::Data<BranchProtectionService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, branch) = path.into_inner();
+
+    let (repo, _, _) = match find_writable_repository(&req, &repo_service, &permission_service, &owner, &repo_name, Permission::Admin).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match protection_service.delete_protection(&repo.id, &branch).await {
+        Ok(_) => Ok(success_response("Branch protection removed")),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn list_tags(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    match git_service.blocking(move |git| git.list_tags(&repo.id)).await {
+        Ok(tags) => Ok(success_response(tags)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn get_tag(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, tag) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    match git_service.blocking(move |git| git.get_tag(&repo.id, &tag)).await {
+        Ok(tag) => Ok(success_response(tag)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn create_tag(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    json: web::Json<CreateTagRequest>,
+    repo_service: web::Data<RepositoryService>,
+    git_service: web::Data<GitService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    let (repo, user_id, _) = match find_writable_repository(&req, &repo_service, &permission_service, &owner, &repo_name, Permission::Write).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let (tagger_name, tagger_email) = match repo_service.git_identity(&user_id).await {
+        Ok(identity) => identity,
+        Err(err) => return Ok(error_response(&err, 500)),
+    };
+
+    let request = json.into_inner();
+
+    match git_service
+        .blocking(move |git| {
+            git.create_tag(
+                &repo.id,
+                &request.name,
+                request.sha.as_deref(),
+                request.message.as_deref(),
+                &tagger_name,
+                &tagger_email,
+            )
+        })
+        .await
+    {
+        Ok(tag) => Ok(success_response(tag)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn delete_tag(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    git_service: web::Data<GitService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, tag) = path.into_inner();
+
+    let (repo, _, _) = match find_writable_repository(&req, &repo_service, &permission_service, &owner, &repo_name, Permission::Write).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match git_service.blocking(move |git| git.delete_tag(&repo.id, &tag)).await {
+        Ok(_) => Ok(success_response("Tag deleted successfully")),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+// Branch and tag names may contain slashes, so the name segment matches the rest of the path
+pub fn branches_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/branches")
+        .route("", web::get().to(list_branches))
+        .route("", web::post().to(create_branch))
+        // Branch names may contain slashes, so the protection suffix is matched first
+        .route("/{branch:.*}/protection", web::get().to(get_branch_protection))
+        .route("/{branch:.*}/protection", web::put().to(update_branch_protection))
+        .route("/{branch:.*}/protection", web::delete().to(delete_branch_protection))
+        .route("/{branch:.*}", web::get().to(get_branch))
+        .route("/{branch:.*}", web::delete().to(delete_branch))
+}
+
+pub fn tags_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/tags")
+        .route("", web::get().to(list_tags))
+        .route("", web::post().to(create_tag))
+        .route("/{tag:.*}", web::get().to(get_tag))
+        .route("/{tag:.*}", web::delete().to(delete_tag))
+}
@@ -0,0 +1,283 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use crate::services::{CollaboratorService, PermissionService, RepositoryService, UserService};
+use crate::models::{AddCollaboratorRequest, Permission, PermissionResponse, Repository};
+use crate::handlers::repositories::find_repository_for_user;
+use crate::utils::jwt::extract_user_from_token;
+use crate::utils::response::{success_response, error_response};
+
+// Resolves a repository whose collaborators the current user may manage
+async fn find_administered_repository(
+    req: &HttpRequest,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+    owner: &str,
+    name: &str,
+) -> Result<(Repository, String), HttpResponse> {
+    let (repo, current_user, permission) =
+        find_repository_for_user(req, repo_service, permission_service, owner, name).await?;
+
+    if permission < Permission::Admin {
+        return Err(error_response("Only repository admins can manage collaborators", 403));
+    }
+
+    Ok((repo, current_user.id))
+}
+
+pub async fn list_collaborators(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    collaborator_service: web::Data<CollaboratorService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    let (repo, _, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    if permission < Permission::Write {
+        return Ok(error_response("Insufficient permissions to list collaborators", 403));
+    }
+
+    match collaborator_service.list_collaborators(&repo.id).await {
+        Ok(collaborators) => Ok(success_response(collaborators)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+// Invites a new collaborator, or changes the permission of an existing one
+pub async fn add_collaborator(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    json: Option<web::Json<AddCollaboratorRequest>>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    collaborator_service: web::Data<CollaboratorService>,
+    user_service: web::Data<UserService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, username) = path.into_inner();
+
+    let (repo, current_user_id) = match find_administered_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    let user = match user_service.get_user_by_username(&username).await {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    // The creator of an organization repository has no standing access, so they can be added
+    if repo.organization_id.is_none() && user.id == repo.owner_id {
+        return Ok(error_response("The repository owner cannot be added as a collaborator", 400));
+    }
+
+    let permission = json
+        .and_then(|json| json.into_inner().permission)
+        .unwrap_or(Permission::Write);
+
+    match collaborator_service.get_permission(&repo.id, &user.id).await {
+        Ok(Some(_)) => {
+            return match collaborator_service.update_permission(&repo.id, &user.id, permission).await {
+                Ok(_) => Ok(success_response("Collaborator permission updated")),
+                Err(err) => Ok(error_response(&err, 500)),
+            };
+        }
+        Ok(None) => {}
+        Err(err) => return Ok(error_response(&err, 500)),
+    }
+
+    match collaborator_service.invite(&repo.id, &current_user_id, &user.id, permission).await {
+        Ok(invitation) => Ok(success_response(invitation)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn remove_collaborator(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    collaborator_service: web::Data<CollaboratorService>,
+    user_service: web::Data<UserService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, username) = path.into_inner();
+
+    let (repo, current_user, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    let user = match user_service.get_user_by_username(&username).await {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    // Collaborators may always leave a repository on their own
+    if user.id != current_user.id && permission < Permission::Admin {
+        return Ok(error_response("Only repository admins can manage collaborators", 403));
+    }
+
+    match collaborator_service.remove_collaborator(&repo.id, &user.id).await {
+        Ok(_) => Ok(success_response("Collaborator removed")),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn get_collaborator_permission(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    user_service: web::Data<UserService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, username) = path.into_inner();
+
+    let (repo, _, _) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    let user = match user_service.get_user_by_username(&username).await {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match permission_service.effective_permission(Some(&user.id), &repo).await {
+        Ok(permission) => Ok(success_response(PermissionResponse { username: user.username, permission })),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn list_repository_invitations(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    collaborator_service: web::Data<CollaboratorService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    let (repo, _) = match find_administered_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    match collaborator_service.list_repository_invitations(&repo.id).await {
+        Ok(invitations) => Ok(success_response(invitations)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn cancel_repository_invitation(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    collaborator_service: web::Data<CollaboratorService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, invitation_id) = path.into_inner();
+
+    let (repo, _) = match find_administered_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    match collaborator_service.get_invitation(&invitation_id).await {
+        Ok(invitation) if invitation.repository_id == repo.id => {}
+        Ok(_) => return Ok(error_response("Invitation not found", 404)),
+        Err(err) => return Ok(error_response(&err, 404)),
+    }
+
+    match collaborator_service.delete_invitation(&invitation_id).await {
+        Ok(_) => Ok(success_response("Invitation cancelled")),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn list_my_repository_invitations(
+    req: HttpRequest,
+    collaborator_service: web::Data<CollaboratorService>,
+) -> Result<HttpResponse> {
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match collaborator_service.list_user_invitations(&current_user.id).await {
+        Ok(invitations) => Ok(success_response(invitations)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn accept_repository_invitation(
+    req: HttpRequest,
+    path: web::Path<String>,
+    collaborator_service: web::Data<CollaboratorService>,
+) -> Result<HttpResponse> {
+    let invitation_id = path.into_inner();
+
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    let invitation = match collaborator_service.get_invitation(&invitation_id).await {
+        Ok(invitation) if invitation.invitee_id == current_user.id => invitation,
+        Ok(_) => return Ok(error_response("Invitation not found", 404)),
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match collaborator_service.accept_invitation(&invitation).await {
+        Ok(_) => Ok(success_response("Invitation accepted")),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn decline_repository_invitation(
+    req: HttpRequest,
+    path: web::Path<String>,
+    collaborator_service: web::Data<CollaboratorService>,
+) -> Result<HttpResponse> {
+    let invitation_id = path.into_inner();
+
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match collaborator_service.get_invitation(&invitation_id).await {
+        Ok(invitation) if invitation.invitee_id == current_user.id => {}
+        Ok(_) => return Ok(error_response("Invitation not found", 404)),
+        Err(err) => return Ok(error_response(&err, 404)),
+    }
+
+    match collaborator_service.delete_invitation(&invitation_id).await {
+        Ok(_) => Ok(success_response("Invitation declined")),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub fn collaborator_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/collaborators")
+        .route("", web::get().to(list_collaborators))
+        .route("/{username}", web::put().to(add_collaborator))
+        .route("/{username}", web::delete().to(remove_collaborator))
+        .route("/{username}/permission", web::get().to(get_collaborator_permission))
+}
+
+pub fn repository_invitation_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/invitations")
+        .route("", web::get().to(list_repository_invitations))
+        .route("/{invitation_id}", web::delete().to(cancel_repository_invitation))
+}
+
+// Repository invitations received by the authenticated user
+pub fn user_repository_invitation_routes() -> actix_web::Scope {
+    web::scope("/user/repository_invitations")
+        .route("", web::get().to(list_my_repository_invitations))
+        .route("/{invitation_id}/accept", web::post().to(accept_repository_invitation))
+        .route("/{invitation_id}", web::delete().to(decline_repository_invitation))
+}
@@ -0,0 +1,226 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use serde::Deserialize;
+use crate::services::{ActivityService, CommentService, PermissionService, RepositoryService};
+use crate::models::{CommentThread, CreateCommentRequest, Permission, Repository, UpdateCommentRequest};
+use crate::handlers::repositories::find_readable_repository;
+use crate::utils::jwt::extract_user_from_token;
+use crate::utils::pagination::page_bounds;
+use crate::utils::response::{success_response, error_response};
+
+#[derive(Deserialize)]
+pub struct CommentsQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+// Looks up the repository and the issue or pull request a comment request refers to
+async fn find_thread(
+    req: &HttpRequest,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+    comment_service: &CommentService,
+    owner: &str,
+    repo_name: &str,
+    number: i32,
+) -> Result<(Repository, CommentThread), HttpResponse> {
+    let repo = find_readable_repository(req, repo_service, permission_service, owner, repo_name).await?;
+
+    let thread = comment_service
+        .find_thread(&repo.id, number)
+        .await
+        .map_err(|err| error_response(&err, 404))?;
+
+    Ok((repo, thread))
+}
+
+pub async fn list_comments(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    query: web::Query<CommentsQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    comment_service: web::Data<CommentService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, number) = path.into_inner();
+
+    let (_, thread) = match find_thread(&req, &repo_service, &permission_service, &comment_service, &owner, &repo_name, number).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    let (limit, offset) = page_bounds(query.page, query.per_page);
+
+    match comment_service.list_comments(&thread, limit, offset).await {
+        Ok(comments) => Ok(success_response(comments)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn get_comment(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    comment_service: web::Data<CommentService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, number, comment_id) = path.into_inner();
+
+    let (_, thread) = match find_thread(&req, &repo_service, &permission_service, &comment_service, &owner, &repo_name, number).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    match comment_service.get_comment(&thread, &comment_id).await {
+        Ok(comment) => Ok(success_response(comment)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn create_comment(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    json: web::Json<CreateCommentRequest>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    comment_service: web::Data<CommentService>,
+    activity_service: web::Data<ActivityService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, number) = path.into_inner();
+
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    let (repo, thread) = match find_thread(&req, &repo_service, &permission_service, &comment_service, &owner, &repo_name, number).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    match comment_service.create_comment(&thread, &current_user.id, &json.body).await {
+        Ok(comment) => {
+            activity_service.comment(&repo, &current_user.id, "created", &thread, number, &comment);
+            Ok(success_response(comment))
+        }
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn update_comment(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32, String)>,
+    json: web::Json<UpdateCommentRequest>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    comment_service: web::Data<CommentService>,
+    activity_service: web::Data<ActivityService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, number, comment_id) = path.into_inner();
+
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    let (repo, thread) = match find_thread(&req, &repo_service, &permission_service, &comment_service, &owner, &repo_name, number).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    let comment = match comment_service.get_comment(&thread, &comment_id).await {
+        Ok(comment) => comment,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    // Only the author may change what a comment says
+    if comment.author_id != current_user.id {
+        return Ok(error_response("Only the comment author can edit this comment", 403));
+    }
+
+    match comment_service.update_comment(&comment, &current_user.id, &json.body).await {
+        Ok(updated_comment) => {
+            activity_service.comment(&repo, &current_user.id, "edited", &thread, number, &updated_comment);
+            Ok(success_response(updated_comment))
+        }
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn delete_comment(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    comment_service: web::Data<CommentService>,
+    activity_service: web::Data<ActivityService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, number, comment_id) = path.into_inner();
+
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    let (repo, thread) = match find_thread(&req, &repo_service, &permission_service, &comment_service, &owner, &repo_name, number).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    let comment = match comment_service.get_comment(&thread, &comment_id).await {
+        Ok(comment) => comment,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    // Verify user can delete the comment (author or write access)
+    if comment.author_id != current_user.id {
+        match permission_service.has_permission(Some(&current_user.id), &repo, Permission::Write).await {
+            Ok(true) => {}
+            Ok(false) => return Ok(error_response("Unauthorized to delete this comment", 403)),
+            Err(err) => return Ok(error_response(&err, 500)),
+        }
+    }
+
+    match comment_service.delete_comment(&comment.id).await {
+        Ok(_) => {
+            activity_service.comment(&repo, &current_user.id, "deleted", &thread, number, &comment);
+            Ok(success_response("Comment deleted successfully"))
+        }
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn list_comment_history(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    comment_service: web::Data<CommentService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, number, comment_id) = path.into_inner();
+
+    let (_, thread) = match find_thread(&req, &repo_service, &permission_service, &comment_service, &owner, &repo_name, number).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    let comment = match comment_service.get_comment(&thread, &comment_id).await {
+        Ok(comment) => comment,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match comment_service.list_comment_edits(&comment.id).await {
+        Ok(edits) => Ok(success_response(edits)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+// Serves conversation comments for both issues and pull requests, which share numbers
+pub fn comment_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/issues/{number}/comments")
+        .route("", web::get().to(list_comments))
+        .route("", web::post().to(create_comment))
+        .route("/{comment_id}", web::get().to(get_comment))
+        .route("/{comment_id}", web::patch().to(update_comment))
+        .route("/{comment_id}", web::delete().to(delete_comment))
+        .route("/{comment_id}/history", web::get().to(list_comment_history))
+}
@@ -0,0 +1,83 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use crate::services::{GitService, PermissionService, RepositoryService};
+use crate::services::git_service::CommitFilter;
+use crate::handlers::repositories::find_readable_repository;
+use crate::handlers::statuses;
+use crate::utils::pagination::page_bounds;
+use crate::utils::response::{success_response, error_response};
+
+#[derive(Deserialize)]
+pub struct CommitsQuery {
+    pub sha: Option<String>, // branch, tag or commit sha to start from; defaults to HEAD
+    pub path: Option<String>,
+    pub author: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+pub async fn list_commits(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<CommitsQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let query = query.into_inner();
+    let (limit, offset) = page_bounds(query.page, query.per_page);
+
+    let filter = CommitFilter {
+        sha: query.sha,
+        path: query.path,
+        author: query.author,
+        since: query.since,
+        until: query.until,
+        limit: limit as usize,
+        offset: offset as usize,
+    };
+
+    match git_service.blocking(move |git| git.list_commits(&repo.id, &filter)).await {
+        Ok(commits) => Ok(success_response(commits)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn get_commit(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, sha) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    match git_service.blocking(move |git| git.get_commit(&repo.id, &sha)).await {
+        Ok(commit) => Ok(success_response(commit)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub fn commits_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/commits")
+        .route("", web::get().to(list_commits))
+        .route("/{sha}", web::get().to(get_commit))
+        .route("/{sha}/status", web::get().to(statuses::get_combined_status))
+        .route("/{sha}/statuses", web::get().to(statuses::list_statuses))
+        .route("/{sha}/check-runs", web::get().to(statuses::list_check_runs_for_ref))
+}
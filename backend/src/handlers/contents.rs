@@ -0,0 +1,155 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use actix_web::http::header;
+use serde::Deserialize;
+use crate::services::{GitService, PermissionService, RepositoryService};
+use crate::handlers::repositories::find_readable_repository;
+use crate::utils::response::{success_response, error_response};
+
+#[derive(Deserialize)]
+pub struct ContentsQuery {
+    #[serde(rename = "ref")]
+    pub reference: Option<String>, // branch, tag or commit sha; defaults to HEAD
+}
+
+#[derive(Deserialize)]
+pub struct TreeQuery {
+    pub recursive: Option<String>,
+}
+
+pub async fn get_root_contents(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<ContentsQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let reference = query.into_inner().reference;
+    match git_service.blocking(move |git| git.get_contents(&repo.id, "", reference.as_deref())).await {
+        Ok(contents) => Ok(success_response(contents)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn get_contents(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    query: web::Query<ContentsQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, file_path) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let reference = query.into_inner().reference;
+    match git_service.blocking(move |git| git.get_contents(&repo.id, &file_path, reference.as_deref())).await {
+        Ok(contents) => Ok(success_response(contents)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn get_raw(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    query: web::Query<ContentsQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, file_path) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let (reference, path) = (query.into_inner().reference, file_path.clone());
+    match git_service.blocking(move |git| git.read_file(&repo.id, &path, reference.as_deref())).await {
+        Ok(data) => Ok(HttpResponse::Ok()
+            .content_type(raw_content_type(&file_path, &data))
+            .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+            .insert_header((header::CONTENT_SECURITY_POLICY, "default-src 'none'; style-src 'unsafe-inline'; sandbox"))
+            .body(data)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn get_tree(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    query: web::Query<TreeQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, sha) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    // GitHub-compatible: any value other than 0/false enables recursion
+    let recursive = matches!(query.recursive.as_deref(), Some(value) if value != "0" && value != "false");
+
+    match git_service.blocking(move |git| git.get_tree(&repo.id, &sha, recursive)).await {
+        Ok(tree) => Ok(success_response(tree)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+// Markup and scripts are served as plain text so raw files can never execute in the API origin
+fn raw_content_type(path: &str, data: &[u8]) -> &'static str {
+    let extension = path
+        .rsplit('.')
+        .next()
+        .map(|ext| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "bmp" => "image/bmp",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "wasm" => "application/wasm",
+        "mp3" => "audio/mpeg",
+        "mp4" => "video/mp4",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        _ if data.iter().take(8000).any(|&b| b == 0) => "application/octet-stream",
+        _ => "text/plain; charset=utf-8",
+    }
+}
+
+pub fn contents_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/contents")
+        .route("", web::get().to(get_root_contents))
+        .route("/{path:.*}", web::get().to(get_contents))
+}
+
+pub fn raw_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/raw")
+        .route("/{path:.*}", web::get().to(get_raw))
+}
+
+pub fn git_data_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/git")
+        .route("/trees/{sha}", web::get().to(get_tree))
+}
@@ -0,0 +1,296 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use actix_ws::{CloseCode, CloseReason, Message, MessageStream, Session};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+use crate::services::{AccessTokenService, EventService, OAuthService, PermissionService, RepositoryService, SessionService};
+use crate::services::event_service::{EventChannel, TICKET_TTL_SECONDS};
+use crate::models::{EventStreamMessage, EventStreamRequest, EventStreamTicket, StreamCredential, StreamIdentity};
+use crate::utils::jwt::{extract_user_from_token, session_authentication, token_authentication};
+use crate::utils::response::{error_response, success_response};
+
+#[derive(Deserialize)]
+pub struct EventStreamQuery {
+    pub ticket: Option<String>,
+}
+
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+// Connections that send nothing, not even a pong, for this long are closed
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(90);
+const MAX_SUBSCRIPTIONS: usize = 100;
+
+// Services a stream uses to answer its client and to re-check the client at every heartbeat
+struct StreamServices {
+    repos: web::Data<RepositoryService>,
+    permissions: web::Data<PermissionService>,
+    events: web::Data<EventService>,
+    sessions: web::Data<SessionService>,
+    tokens: web::Data<AccessTokenService>,
+    oauth: web::Data<OAuthService>,
+}
+
+// The credential the request was authenticated with, so an open stream can tell when it is revoked
+fn stream_identity(req: &HttpRequest) -> Result<StreamIdentity, String> {
+    if let Some(token) = token_authentication(req) {
+        return Ok(StreamIdentity { user_id: token.user_id, credential: StreamCredential::Token { token_id: token.token_id } });
+    }
+
+    if let Some(session) = session_authentication(req) {
+        return Ok(StreamIdentity {
+            user_id: session.user_id,
+            credential: StreamCredential::Session { session_id: session.session_id, jti: session.jti },
+        });
+    }
+
+    // Neither was attached, so this reports why the request was not authenticated
+    extract_user_from_token(req).and(Err("Invalid or expired token".to_string()))
+}
+
+// Sessions stay valid across access token refreshes, so only revocation ends a session's stream
+async fn is_still_authorized(credential: &StreamCredential, services: &StreamServices) -> Result<bool, String> {
+    match credential {
+        StreamCredential::Session { session_id, jti } => Ok(!services.sessions.is_denied(jti, session_id).await?),
+        StreamCredential::Token { token_id } if token_id.starts_with("oat_") => services.oauth.is_active(token_id).await,
+        StreamCredential::Token { token_id } => services.tokens.is_active(token_id).await,
+    }
+}
+
+// Checks that the user can read the channel's repository and returns the channel's key
+async fn resolve_channel(
+    channel: &str,
+    user_id: &str,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+) -> Result<String, String> {
+    let invalid = || "Channels are named repo:owner/name, issue:owner/name#number or pull:owner/name#number".to_string();
+
+    let (kind, target) = channel.split_once(':').ok_or_else(invalid)?;
+    let (repo_path, number) = match target.split_once('#') {
+        Some((repo_path, number)) => (repo_path, Some(number.parse::<i32>().map_err(|_| invalid())?)),
+        None => (target, None),
+    };
+    let (owner, name) = repo_path.split_once('/').ok_or_else(invalid)?;
+
+    // Repositories the user cannot see are reported the same as missing ones
+    let repo = repo_service
+        .get_repository(owner, name)
+        .await
+        .map_err(|_| "Repository not found".to_string())?;
+    let permission = permission_service.effective_permission(Some(user_id), &repo).await?;
+    if permission.is_none() {
+        return Err("Repository not found".to_string());
+    }
+
+    let channel = match (kind, number) {
+        ("repo", None) => EventChannel::Repository(repo.id),
+        ("issue", Some(number)) => EventChannel::Issue(repo.id, number),
+        ("pull", Some(number)) => EventChannel::PullRequest(repo.id, number),
+        _ => return Err(invalid()),
+    };
+
+    Ok(channel.key())
+}
+
+// `subscriptions` maps channel keys to the names the client subscribed with
+async fn handle_request(
+    text: &str,
+    user_id: &str,
+    subscriptions: &mut HashMap<String, String>,
+    services: &StreamServices,
+) -> EventStreamMessage {
+    let request = match serde_json::from_str::<EventStreamRequest>(text) {
+        Ok(request) => request,
+        Err(err) => return EventStreamMessage::Error { channel: None, message: format!("Invalid message: {}", err) },
+    };
+
+    match request {
+        EventStreamRequest::Subscribe { channel } => {
+            if subscriptions.len() >= MAX_SUBSCRIPTIONS {
+                let message = format!("At most {} channels can be subscribed to", MAX_SUBSCRIPTIONS);
+                return EventStreamMessage::Error { channel: Some(channel), message };
+            }
+
+            match resolve_channel(&channel, user_id, &services.repos, &services.permissions).await {
+                Ok(key) => {
+                    subscriptions.insert(key, channel.clone());
+                    EventStreamMessage::Subscribed { channel }
+                }
+                Err(message) => EventStreamMessage::Error { channel: Some(channel), message },
+            }
+        }
+        EventStreamRequest::Unsubscribe { channel } => {
+            subscriptions.retain(|_, name| *name != channel);
+            EventStreamMessage::Unsubscribed { channel }
+        }
+    }
+}
+
+async fn send(session: &mut Session, message: &EventStreamMessage) -> Result<(), actix_ws::Closed> {
+    match serde_json::to_string(message) {
+        Ok(text) => session.text(text).await,
+        Err(err) => {
+            log::error!("Failed to encode event stream message: {}", err);
+            Ok(())
+        }
+    }
+}
+
+// Channels whose repository the user can no longer read, found by resolving each one again
+async fn lost_channels(user_id: &str, subscriptions: &HashMap<String, String>, services: &StreamServices) -> Vec<String> {
+    let mut lost = Vec::new();
+    for (key, channel) in subscriptions {
+        match resolve_channel(channel, user_id, &services.repos, &services.permissions).await {
+            Ok(resolved) if resolved == *key => {}
+            _ => lost.push(key.clone()),
+        }
+    }
+    lost
+}
+
+async fn run_event_stream(
+    mut session: Session,
+    mut messages: MessageStream,
+    identity: StreamIdentity,
+    services: StreamServices,
+) {
+    let user_id = identity.user_id.clone();
+    let mut events = services.events.subscribe();
+    let mut subscriptions: HashMap<String, String> = HashMap::new();
+    let mut heartbeat = tokio::time::interval(PING_INTERVAL);
+    let mut last_seen = Instant::now();
+
+    let reason = loop {
+        tokio::select! {
+            message = messages.next() => {
+                last_seen = Instant::now();
+
+                match message {
+                    Some(Ok(Message::Text(text))) => {
+                        let reply = handle_request(&text, &user_id, &mut subscriptions, &services).await;
+                        if send(&mut session, &reply).await.is_err() {
+                            break None;
+                        }
+                    }
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break None;
+                        }
+                    }
+                    Some(Ok(Message::Close(reason))) => break reason,
+                    Some(Ok(_)) => {}
+                    Some(Err(_)) | None => break None,
+                }
+            }
+            event = events.recv() => match event {
+                Ok(relayed) => {
+                    if let Some(channel) = subscriptions.get(&relayed.channel_key) {
+                        let message = EventStreamMessage::Event { channel: channel.clone(), event: relayed.event };
+                        if send(&mut session, &message).await.is_err() {
+                            break None;
+                        }
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    let message = format!("{} events were dropped because the connection fell behind", skipped);
+                    if send(&mut session, &EventStreamMessage::Error { channel: None, message }).await.is_err() {
+                        break None;
+                    }
+                }
+                Err(RecvError::Closed) => break None,
+            },
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > CLIENT_TIMEOUT || session.ping(b"").await.is_err() {
+                    break None;
+                }
+
+                // A revoked session or token, or a failure to check it, ends the stream
+                match is_still_authorized(&identity.credential, &services).await {
+                    Ok(true) => {}
+                    Ok(false) => break Some(CloseReason::from((CloseCode::Policy, "Authorization was revoked"))),
+                    Err(err) => {
+                        log::error!("Failed to check event stream authorization: {}", err);
+                        break Some(CloseReason::from((CloseCode::Error, "Failed to check authorization")));
+                    }
+                }
+
+                let mut closed = false;
+                for key in lost_channels(&user_id, &subscriptions, &services).await {
+                    if let Some(channel) = subscriptions.remove(&key) {
+                        let message = EventStreamMessage::Error { channel: Some(channel), message: "Access to this channel was lost".to_string() };
+                        closed = closed || send(&mut session, &message).await.is_err();
+                    }
+                }
+                if closed {
+                    break None;
+                }
+            }
+        }
+    };
+
+    let _ = session.close(reason).await;
+}
+
+/// Upgrades to a WebSocket that streams events for the channels the client subscribes to.
+/// Clients that cannot send an Authorization header pass `?ticket=` from `create_ticket` instead.
+#[allow(clippy::too_many_arguments)]
+pub async fn event_stream(
+    req: HttpRequest,
+    body: web::Payload,
+    query: web::Query<EventStreamQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    event_service: web::Data<EventService>,
+    session_service: web::Data<SessionService>,
+    token_service: web::Data<AccessTokenService>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let identity = match &query.ticket {
+        Some(ticket) => match event_service.redeem_ticket(ticket).await {
+            Ok(Some(identity)) => identity,
+            Ok(None) => return Ok(error_response("Invalid or expired ticket", 401)),
+            Err(err) => return Ok(error_response(&err, 500)),
+        },
+        None => match stream_identity(&req) {
+            Ok(identity) => identity,
+            Err(err) => return Ok(error_response(&err, 401)),
+        },
+    };
+
+    let (response, session, messages) = actix_ws::handle(&req, body)?;
+
+    let services = StreamServices {
+        repos: repo_service,
+        permissions: permission_service,
+        events: event_service,
+        sessions: session_service,
+        tokens: token_service,
+        oauth: oauth_service,
+    };
+    actix_web::rt::spawn(run_event_stream(session, messages, identity, services));
+
+    Ok(response)
+}
+
+/// Issues a short-lived, single-use ticket for opening the event stream as the current user.
+pub async fn create_ticket(
+    req: HttpRequest,
+    event_service: web::Data<EventService>,
+) -> Result<HttpResponse> {
+    let identity = match stream_identity(&req) {
+        Ok(identity) => identity,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match event_service.create_ticket(&identity).await {
+        Ok(ticket) => Ok(success_response(EventStreamTicket { ticket, expires_in: TICKET_TTL_SECONDS })),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub fn event_routes() -> actix_web::Scope {
+    web::scope("/events")
+        .route("", web::get().to(event_stream))
+        .route("/tickets", web::post().to(create_ticket))
+}
@@ -0,0 +1,367 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use actix_web::dev::Decompress;
+use actix_web::http::header::{self, Header};
+use actix_web_httpauth::headers::authorization::{Authorization, Basic};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use std::collections::HashMap;
+use crate::services::{ActivityService, AuthService, BranchProtectionService, GitService, PermissionService, PullRequestService, RepositoryService};
+use crate::services::git_service::{parse_push_request, push_rejection_report, GitRpc, PushRequest};
+use crate::models::{Permission, Repository, UserWithPassword};
+
+#[derive(Deserialize)]
+pub struct InfoRefsQuery {
+    pub service: Option<String>,
+}
+
+fn unauthorized() -> HttpResponse {
+    unauthorized_with("Authentication required")
+}
+
+fn unauthorized_with(message: &str) -> HttpResponse {
+    HttpResponse::Unauthorized()
+        .insert_header((header::WWW_AUTHENTICATE, "Basic realm=\"DevIT\""))
+        .body(message.to_string())
+}
+
+fn payload_too_large(max_request_bytes: usize) -> HttpResponse {
+    HttpResponse::PayloadTooLarge().body(format!("Request exceeds the {} MB limit", max_request_bytes / (1024 * 1024)))
+}
+
+// Anonymous requests are allowed through; bad credentials are not
+async fn authenticate(req: &HttpRequest, auth_service: &AuthService) -> Result<Option<UserWithPassword>, HttpResponse> {
+    if req.headers().get(header::AUTHORIZATION).is_none() {
+        return Ok(None);
+    }
+
+    let credentials = Authorization::<Basic>::parse(req).map_err(|_| unauthorized())?;
+    let basic = credentials.as_ref();
+    let password = basic.password().ok_or_else(unauthorized)?;
+
+    auth_service
+        .authenticate_git(basic.user_id(), password)
+        .await
+        .map(Some)
+        .map_err(|err| unauthorized_with(&err))
+}
+
+async fn resolve_repository(
+    req: &HttpRequest,
+    owner: &str,
+    name: &str,
+    rpc: GitRpc,
+    auth_service: &AuthService,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+) -> Result<(Repository, Option<UserWithPassword>, Option<Permission>), HttpResponse> {
+    let user = authenticate(req, auth_service).await?;
+
+    // Clone URLs conventionally end in .git
+    let name = name.strip_suffix(".git").unwrap_or(name);
+
+    let repo = repo_service
+        .get_repository(owner, name)
+        .await
+        .map_err(|_| match user {
+            Some(_) => HttpResponse::NotFound().body("Repository not found"),
+            None => unauthorized(),
+        })?;
+
+    let permission = permission_service
+        .effective_permission(user.as_ref().map(|u| u.id.as_str()), &repo)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to resolve permissions for {}/{}: {}", owner, name, err);
+            HttpResponse::InternalServerError().body("Failed to check permissions")
+        })?;
+
+    if permission.is_none() {
+        return match user {
+            Some(_) => Err(HttpResponse::NotFound().body("Repository not found")),
+            None => Err(unauthorized()),
+        };
+    }
+
+    match rpc {
+        GitRpc::UploadPack => {}
+        GitRpc::ReceivePack if user.is_none() => return Err(unauthorized()),
+        GitRpc::ReceivePack if permission < Some(Permission::Write) => return Err(HttpResponse::Forbidden().body("Permission denied")),
+        GitRpc::ReceivePack if repo.is_archived_bool() => return Err(HttpResponse::Forbidden().body("Repository is archived")),
+        GitRpc::ReceivePack => {}
+    }
+
+    Ok((repo, user, permission))
+}
+
+fn git_protocol(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get("Git-Protocol")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+}
+
+pub async fn info_refs(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<InfoRefsQuery>,
+    auth_service: web::Data<AuthService>,
+    repo_service: web::Data<RepositoryService>,
+    git_service: web::Data<GitService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let (owner, name) = path.into_inner();
+
+    let rpc = match query.service.as_deref().and_then(GitRpc::from_service_name) {
+        Some(rpc) => rpc,
+        None => return Ok(HttpResponse::Forbidden().body("Only the smart HTTP protocol is supported")),
+    };
+
+    let (repo, _user, _permission) = match resolve_repository(&req, &owner, &name, rpc, &auth_service, &repo_service, &permission_service).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match git_service.advertise_refs(&repo.id, rpc, git_protocol(&req).as_deref()).await {
+        Ok(body) => Ok(HttpResponse::Ok()
+            .content_type(format!("application/x-{}-advertisement", rpc.service_name()))
+            .insert_header((header::CACHE_CONTROL, "no-cache"))
+            .body(body)),
+        Err(err) => {
+            log::error!("Failed to advertise refs for {}/{}: {}", owner, name, err);
+            Ok(HttpResponse::InternalServerError().body("Failed to read repository"))
+        }
+    }
+}
+
+// Branches created, moved or deleted between two snapshots of branch tips
+pub fn updated_branches(before: &HashMap<String, String>, after: &HashMap<String, String>) -> Vec<String> {
+    let mut updated: Vec<String> = after
+        .iter()
+        .filter(|(name, sha)| before.get(*name) != Some(*sha))
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    updated.extend(before.keys().filter(|name| !after.contains_key(*name)).cloned());
+    updated
+}
+
+// What branch protection makes of a push before receive-pack runs
+#[derive(Default)]
+pub struct PushCheck {
+    pub violations: Vec<(String, String)>, // Ref updates refused outright, with the reason for each
+    pub fast_forward_refs: Vec<String>, // Protected refs the pre-receive hook only lets fast-forward
+}
+
+// Force pushes can only be told apart once the pack is unpacked, so those are left to the pre-receive hook
+pub async fn check_push(
+    repo: &Repository,
+    user_id: &str,
+    is_admin: bool,
+    push: &PushRequest,
+    protection_service: &BranchProtectionService,
+) -> Result<PushCheck, String> {
+    let mut check = PushCheck::default();
+
+    for update in &push.updates {
+        let branch = match update.branch() {
+            Some(branch) => branch,
+            None => continue,
+        };
+
+        let protection = match protection_service.get_protection(&repo.id, branch).await? {
+            Some(protection) => protection,
+            None => continue,
+        };
+
+        let reason = if !protection_service.can_push(&protection, user_id, is_admin).await? {
+            Some(format!("You are not allowed to push to protected branch {}", branch))
+        } else if update.is_delete() {
+            (!protection.allow_deletions).then(|| format!("Cannot delete protected branch {}", branch))
+        } else if update.is_create() {
+            None
+        } else if protection.required_approving_reviews > 0 || !protection.required_status_checks.is_empty() {
+            Some(format!("Changes to protected branch {} must be made through a pull request", branch))
+        } else {
+            if !protection.allow_force_pushes {
+                check.fast_forward_refs.push(update.name.clone());
+            }
+            None
+        };
+
+        if let Some(reason) = reason {
+            check.violations.push((update.name.clone(), reason));
+        }
+    }
+
+    Ok(check)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn service_rpc(
+    rpc: GitRpc,
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    payload: web::Payload,
+    auth_service: web::Data<AuthService>,
+    repo_service: web::Data<RepositoryService>,
+    git_service: web::Data<GitService>,
+    pr_service: web::Data<PullRequestService>,
+    protection_service: web::Data<BranchProtectionService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
+) -> Result<HttpResponse> {
+    let (owner, name) = path.into_inner();
+
+    let (repo, user, permission) = match resolve_repository(&req, &owner, &name, rpc, &auth_service, &repo_service, &permission_service).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    // Branch tips before a push, compared afterwards to find what it updated
+    let heads_before = match rpc {
+        GitRpc::ReceivePack => {
+            let repo_id = repo.id.clone();
+            git_service.blocking(move |git| git.branch_heads(&repo_id)).await.unwrap_or_default()
+        }
+        GitRpc::UploadPack => HashMap::new(),
+    };
+
+    // Git clients gzip large requests, and packs can exceed the default body limits, so the
+    // request is streamed into git with its own limit
+    let max_request_bytes = git_service.max_request_bytes();
+    let mut payload = Decompress::from_headers(payload, req.headers());
+    let mut head = Vec::new();
+
+    // Receive-pack only runs as an authenticated user, enforced by resolve_repository
+    let mut fast_forward_refs = Vec::new();
+    if let (GitRpc::ReceivePack, Some(user)) = (rpc, user.as_ref()) {
+        // Branch protection is checked against the commands before any of the pack reaches git
+        let push = loop {
+            match parse_push_request(&head) {
+                Ok(Some((push, _))) => break push,
+                Ok(None) => {}
+                Err(err) => return Ok(HttpResponse::BadRequest().body(err)),
+            }
+
+            match payload.next().await {
+                Some(chunk) => head.extend_from_slice(&chunk?),
+                None => return Ok(HttpResponse::BadRequest().body("Truncated pkt-line")),
+            }
+
+            if head.len() > max_request_bytes {
+                return Ok(payload_too_large(max_request_bytes));
+            }
+        };
+
+        match check_push(&repo, &user.id, permission == Some(Permission::Admin), &push, &protection_service).await {
+            Ok(check) if !check.violations.is_empty() => {
+                return Ok(HttpResponse::Ok()
+                    .content_type(format!("application/x-{}-result", rpc.service_name()))
+                    .insert_header((header::CACHE_CONTROL, "no-cache"))
+                    .body(push_rejection_report(&push, &check.violations)));
+            }
+            Ok(check) => fast_forward_refs = check.fast_forward_refs,
+            Err(err) => {
+                log::error!("Failed to check branch protection for {}/{}: {}", owner, name, err);
+                return Ok(HttpResponse::InternalServerError().body("Failed to check branch protection"));
+            }
+        }
+    }
+
+    let mut process = match git_service.spawn_stateless_rpc(&repo.id, rpc, git_protocol(&req).as_deref(), &fast_forward_refs) {
+        Ok(process) => process,
+        Err(err) => {
+            log::error!("git {} failed for {}/{}: {}", rpc.service_name(), owner, name, err);
+            return Ok(HttpResponse::InternalServerError().body("Git operation failed"));
+        }
+    };
+
+    // Dropping the process on an early return kills git before receive-pack stores anything
+    let mut received = head.len();
+    let mut written = process.write(&head).await;
+    while written.is_ok() {
+        let chunk = match payload.next().await {
+            Some(chunk) => chunk?,
+            None => break,
+        };
+
+        received += chunk.len();
+        if received > max_request_bytes {
+            return Ok(payload_too_large(max_request_bytes));
+        }
+
+        written = process.write(&chunk).await;
+    }
+
+    // A failed write means git stopped reading early; its own error explains why
+    match process.finish().await {
+        Ok(output) => {
+            if rpc == GitRpc::ReceivePack {
+                if let Err(err) = repo_service.record_push(&repo.id).await {
+                    log::error!("Failed to record push for {}/{}: {}", owner, name, err);
+                }
+
+                let repo_id = repo.id.clone();
+                let heads_after = git_service.blocking(move |git| git.branch_heads(&repo_id)).await.unwrap_or_default();
+                let updated = updated_branches(&heads_before, &heads_after);
+
+                if !updated.is_empty() {
+                    pr_service.schedule_branch_refresh(&repo.id, updated);
+
+                    if let Some(user) = &user {
+                        activity_service.push(&repo, &user.id, heads_before, heads_after);
+                    }
+                }
+            }
+
+            Ok(HttpResponse::Ok()
+                .content_type(format!("application/x-{}-result", rpc.service_name()))
+                .insert_header((header::CACHE_CONTROL, "no-cache"))
+                .body(output))
+        }
+        Err(err) => {
+            log::error!("git {} failed for {}/{}: {}", rpc.service_name(), owner, name, err);
+            Ok(HttpResponse::InternalServerError().body("Git operation failed"))
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_pack(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    payload: web::Payload,
+    auth_service: web::Data<AuthService>,
+    repo_service: web::Data<RepositoryService>,
+    git_service: web::Data<GitService>,
+    pr_service: web::Data<PullRequestService>,
+    protection_service: web::Data<BranchProtectionService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
+) -> Result<HttpResponse> {
+    service_rpc(GitRpc::UploadPack, req, path, payload, auth_service, repo_service, git_service, pr_service, protection_service, permission_service, activity_service).await
+}
+
+#[allow(clippy::too_many_arguments)]
+pub async fn receive_pack(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    payload: web::Payload,
+    auth_service: web::Data<AuthService>,
+    repo_service: web::Data<RepositoryService>,
+    git_service: web::Data<GitService>,
+    pr_service: web::Data<PullRequestService>,
+    protection_service: web::Data<BranchProtectionService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
+) -> Result<HttpResponse> {
+    service_rpc(GitRpc::ReceivePack, req, path, payload, auth_service, repo_service, git_service, pr_service, protection_service, permission_service, activity_service).await
+}
+
+// Mounted at the server root so clone URLs look like http://host/{owner}/{repo}.git
+pub fn git_http_routes() -> actix_web::Scope {
+    web::scope("/{owner}/{repo}")
+        .route("/info/refs", web::get().to(info_refs))
+        .route("/git-upload-pack", web::post().to(upload_pack))
+        .route("/git-receive-pack", web::post().to(receive_pack))
+}
@@ -1,8 +1,8 @@
 use actix_web::{web, HttpResponse, Result, HttpRequest};
 use serde::Deserialize;
-use crate::services::{IssueService, RepositoryService, UserService};
-use crate::models::{CreateIssueRequest, UpdateIssueRequest};
-use crate::utils::jwt::extract_user_from_token;
+use crate::services::{ActivityService, IssueService, PermissionService, RepositoryService};
+use crate::models::{CreateIssueRequest, Permission, UpdateIssueRequest};
+use crate::handlers::repositories::{find_readable_repository, find_repository_for_user};
 use crate::utils::response::{success_response, error_response};
 
 #[derive(Deserialize)]
@@ -15,17 +15,18 @@ pub struct IssueQuery {
 }
 
 pub async fn list_issues(
+    req: HttpRequest,
     path: web::Path<(String, String)>,
     _query: web::Query<IssueQuery>,
     issue_service: web::Data<IssueService>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name) = path.into_inner();
     
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
         Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+        Err(response) => return Ok(response),
     };
     
     // Use repository ID as String (MySQL VARCHAR)
@@ -38,16 +39,17 @@ pub async fn list_issues(
 }
 
 pub async fn get_issue(
+    req: HttpRequest,
     path: web::Path<(String, String, i32)>,
     issue_service: web::Data<IssueService>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name, issue_number) = path.into_inner();
     
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
         Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+        Err(response) => return Ok(response),
     };
     
     let repo_id = repo.id.clone();
@@ -64,19 +66,14 @@ pub async fn create_issue(
     json: web::Json<CreateIssueRequest>,
     issue_service: web::Data<IssueService>,
     repo_service: web::Data<RepositoryService>,
-    _user_service: web::Data<UserService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, current_user, _) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
     let repo_id = repo.id.clone();
@@ -92,7 +89,10 @@ pub async fn create_issue(
         &request.title,
         request.body.as_deref()
     ).await {
-        Ok(issue) => Ok(success_response(issue)),
+        Ok(issue) => {
+            activity_service.issue(&repo, &current_user_id, "opened", &issue);
+            Ok(success_response(issue))
+        }
         Err(err) => Ok(error_response(&err, 400)),
     }
 }
@@ -103,18 +103,14 @@ pub async fn update_issue(
     json: web::Json<UpdateIssueRequest>,
     issue_service: web::Data<IssueService>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name, issue_number) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, current_user, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
     let repo_id = repo.id.clone();
@@ -125,6 +121,11 @@ pub async fn update_issue(
         Err(err) => return Ok(error_response(&err, 404)),
     };
     
+    // Authors edit their own issues; triagers and above edit any
+    if issue.author_id != current_user.id && permission < Permission::Triage {
+        return Ok(error_response("Insufficient permissions to update this issue", 403));
+    }
+    
     let request = json.into_inner();
     
     match issue_service.update_issue(
@@ -133,7 +134,16 @@ pub async fn update_issue(
         request.body.as_deref(),
         request.status.as_deref()
     ).await {
-        Ok(updated_issue) => Ok(success_response(updated_issue)),
+        Ok(updated_issue) => {
+            // Statuses are stored as uppercase ENUM values
+            let action = match (issue.status.to_lowercase().as_str(), updated_issue.status.to_lowercase().as_str()) {
+                ("open", "closed") => "closed",
+                ("closed", "open") => "reopened",
+                _ => "edited",
+            };
+            activity_service.issue(&repo, &current_user.id, action, &updated_issue);
+            Ok(success_response(updated_issue))
+        }
         Err(err) => Ok(error_response(&err, 400)),
     }
 }
@@ -144,18 +154,14 @@ pub async fn assign_issue(
     json: web::Json<serde_json::Value>,
     issue_service: web::Data<IssueService>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name, issue_number) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, current_user, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
     let repo_id = repo.id.clone();
@@ -166,10 +172,18 @@ pub async fn assign_issue(
         Err(err) => return Ok(error_response(&err, 404)),
     };
     
+    // Assigning is a triage action
+    if permission < Permission::Triage {
+        return Ok(error_response("Insufficient permissions to assign this issue", 403));
+    }
+    
     let assignee_id = json.get("assignee_id").and_then(|v| v.as_str());
     
     match issue_service.assign_issue(&issue.id, assignee_id).await {
-        Ok(updated_issue) => Ok(success_response(updated_issue)),
+        Ok(updated_issue) => {
+            activity_service.issue_assigned(&repo, &current_user.id, &updated_issue, assignee_id);
+            Ok(success_response(updated_issue))
+        }
         Err(err) => Ok(error_response(&err, 400)),
     }
 }
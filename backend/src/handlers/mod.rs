@@ -4,3 +4,21 @@ pub mod users;
 pub mod repositories;
 pub mod issues;
 pub mod pull_requests;
+pub mod git_http;
+pub mod templates;
+pub mod contents;
+pub mod commits;
+pub mod branches;
+pub mod comments;
+pub mod organizations;
+pub mod teams;
+pub mod collaborators;
+pub mod access_tokens;
+pub mod ssh_keys;
+pub mod two_factor;
+pub mod oauth;
+pub mod webhooks;
+pub mod statuses;
+pub mod pipelines;
+pub mod events;
+pub mod notifications;
@@ -0,0 +1,300 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use serde::Deserialize;
+use serde_json::json;
+use crate::services::{CommentService, NotificationService, PermissionService, RepositoryService};
+use crate::services::notification_service::NotificationFilter;
+use crate::models::{
+    CommentThread, MarkNotificationsRequest, NotificationReason, NotificationSettings, NotificationState, Repository,
+    UpdateNotificationRequest, UpdateThreadSubscriptionRequest,
+};
+use crate::handlers::repositories::find_repository_for_user;
+use crate::utils::jwt::extract_user_from_token;
+use crate::utils::pagination::page_bounds;
+use crate::utils::response::{success_response, error_response};
+
+#[derive(Deserialize)]
+pub struct NotificationsQuery {
+    pub state: Option<String>, // unread, read, done or all; unread and read when omitted
+    pub reason: Option<String>,
+    pub repository: Option<String>, // owner/name
+    pub participating: Option<bool>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+// Looks up an owner/name repository filter the user can read
+async fn find_filter_repository(
+    req: &HttpRequest,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+    full_name: &str,
+) -> Result<Repository, HttpResponse> {
+    let (owner, name) = full_name
+        .split_once('/')
+        .ok_or_else(|| error_response("Repository must be given as owner/name", 400))?;
+
+    let (repo, _, _) = find_repository_for_user(req, repo_service, permission_service, owner, name).await?;
+
+    Ok(repo)
+}
+
+pub async fn list_notifications(
+    req: HttpRequest,
+    query: web::Query<NotificationsQuery>,
+    notification_service: web::Data<NotificationService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    let states = match query.state.as_deref() {
+        None => vec![NotificationState::Unread, NotificationState::Read],
+        Some("all") => vec![NotificationState::Unread, NotificationState::Read, NotificationState::Done],
+        Some(state) => match NotificationState::parse(state) {
+            Some(state) => vec![state],
+            None => return Ok(error_response("State must be unread, read, done or all", 400)),
+        },
+    };
+
+    let reason = match query.reason.as_deref() {
+        Some(reason) => match NotificationReason::parse(reason) {
+            Some(reason) => Some(reason),
+            None => return Ok(error_response("Unknown notification reason", 400)),
+        },
+        None => None,
+    };
+
+    let repository_id = match query.repository.as_deref() {
+        Some(full_name) => match find_filter_repository(&req, &repo_service, &permission_service, full_name).await {
+            Ok(repo) => Some(repo.id),
+            Err(response) => return Ok(response),
+        },
+        None => None,
+    };
+
+    let filter = NotificationFilter {
+        states,
+        reason,
+        repository_id,
+        participating: query.participating.unwrap_or(false),
+    };
+    let (limit, offset) = page_bounds(query.page, query.per_page);
+
+    match notification_service.list_notifications(&current_user.id, &filter, limit, offset).await {
+        Ok(notifications) => Ok(success_response(notifications)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn unread_count(
+    req: HttpRequest,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match notification_service.unread_count(&current_user.id).await {
+        Ok(count) => Ok(success_response(json!({ "count": count }))),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn mark_notifications(
+    req: HttpRequest,
+    json: web::Json<MarkNotificationsRequest>,
+    notification_service: web::Data<NotificationService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    let request = json.into_inner();
+
+    let repository_id = match request.repository.as_deref() {
+        Some(full_name) => match find_filter_repository(&req, &repo_service, &permission_service, full_name).await {
+            Ok(repo) => Some(repo.id),
+            Err(response) => return Ok(response),
+        },
+        None => None,
+    };
+
+    let state = request.state.unwrap_or(NotificationState::Read);
+
+    match notification_service.mark_all(&current_user.id, state, repository_id.as_deref()).await {
+        Ok(updated) => Ok(success_response(json!({ "updated": updated }))),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn get_notification(
+    req: HttpRequest,
+    path: web::Path<String>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let notification_id = path.into_inner();
+
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match notification_service.get_notification(&current_user.id, &notification_id).await {
+        Ok(notification) => Ok(success_response(notification)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn update_notification(
+    req: HttpRequest,
+    path: web::Path<String>,
+    json: web::Json<UpdateNotificationRequest>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let notification_id = path.into_inner();
+
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match notification_service.set_state(&current_user.id, &notification_id, json.state).await {
+        Ok(notification) => Ok(success_response(notification)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn get_settings(
+    req: HttpRequest,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match notification_service.get_settings(&current_user.id).await {
+        Ok(settings) => Ok(success_response(settings)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn update_settings(
+    req: HttpRequest,
+    json: web::Json<NotificationSettings>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match notification_service.update_settings(&current_user.id, &json.into_inner()).await {
+        Ok(settings) => Ok(success_response(settings)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+// Looks up the repository and the issue or pull request a subscription request refers to
+async fn find_subscription_thread(
+    req: &HttpRequest,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+    comment_service: &CommentService,
+    path: (String, String, i32),
+) -> Result<(Repository, String, CommentThread), HttpResponse> {
+    let (owner, repo_name, number) = path;
+
+    let (repo, current_user, _) = find_repository_for_user(req, repo_service, permission_service, &owner, &repo_name).await?;
+
+    let thread = comment_service
+        .find_thread(&repo.id, number)
+        .await
+        .map_err(|err| error_response(&err, 404))?;
+
+    Ok((repo, current_user.id, thread))
+}
+
+pub async fn get_thread_subscription(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    comment_service: web::Data<CommentService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let (repo, user_id, thread) = match find_subscription_thread(&req, &repo_service, &permission_service, &comment_service, path.into_inner()).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    match notification_service.get_subscription(&user_id, &repo.id, &thread).await {
+        Ok(subscription) => Ok(success_response(subscription)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn set_thread_subscription(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    json: web::Json<UpdateThreadSubscriptionRequest>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    comment_service: web::Data<CommentService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let (repo, user_id, thread) = match find_subscription_thread(&req, &repo_service, &permission_service, &comment_service, path.into_inner()).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    match notification_service.set_subscription(&user_id, &repo.id, &thread, json.subscribed).await {
+        Ok(subscription) => Ok(success_response(subscription)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn delete_thread_subscription(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    comment_service: web::Data<CommentService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let (_, user_id, thread) = match find_subscription_thread(&req, &repo_service, &permission_service, &comment_service, path.into_inner()).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    match notification_service.delete_subscription(&user_id, &thread).await {
+        Ok(_) => Ok(success_response("Subscription removed")),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub fn notification_routes() -> actix_web::Scope {
+    web::scope("/notifications")
+        .route("", web::get().to(list_notifications))
+        .route("", web::put().to(mark_notifications))
+        .route("/unread_count", web::get().to(unread_count))
+        .route("/settings", web::get().to(get_settings))
+        .route("/settings", web::put().to(update_settings))
+        .route("/{id}", web::get().to(get_notification))
+        .route("/{id}", web::patch().to(update_notification))
+}
+
+// Issues and pull requests share numbers, so both are addressed under /issues like comments
+pub fn thread_subscription_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/issues/{number}/subscription")
+        .route("", web::get().to(get_thread_subscription))
+        .route("", web::put().to(set_thread_subscription))
+        .route("", web::delete().to(delete_thread_subscription))
+}
@@ -0,0 +1,303 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use crate::services::OAuthService;
+use crate::models::{
+    AuthorizationRequest, ConsentRedirect, ConsentRequest, CreateOAuthApplicationRequest, OAuthError,
+    OAuthTokenLookupRequest, OAuthTokenRequest, UpdateOAuthApplicationRequest,
+};
+use crate::handlers::access_tokens::session_user;
+use crate::utils::response::{success_response, error_response};
+
+// client_secret_basic, falling back to client_secret_post (RFC 6749 section 2.3.1)
+fn client_credentials(req: &HttpRequest, client_id: Option<&str>, client_secret: Option<&str>) -> Option<(String, String)> {
+    let basic = req
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|value| STANDARD.decode(value).ok())
+        .and_then(|value| String::from_utf8(value).ok())
+        .and_then(|value| value.split_once(':').map(|(id, secret)| (id.to_string(), secret.to_string())));
+
+    basic.or_else(|| Some((client_id?.to_string(), client_secret?.to_string())))
+}
+
+// Token endpoint responses follow RFC 6749 rather than the API's usual envelope
+fn oauth_error_response(error: OAuthError) -> HttpResponse {
+    let mut response = match error.error {
+        "invalid_client" => HttpResponse::Unauthorized(),
+        "server_error" => HttpResponse::InternalServerError(),
+        _ => HttpResponse::BadRequest(),
+    };
+
+    response.insert_header(("Cache-Control", "no-store")).json(error)
+}
+
+fn missing_client() -> HttpResponse {
+    oauth_error_response(OAuthError::new("invalid_client", "Client authentication is required"))
+}
+
+pub async fn list_applications(
+    req: HttpRequest,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match oauth_service.list_applications(&current_user.id).await {
+        Ok(applications) => Ok(success_response(applications)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn create_application(
+    req: HttpRequest,
+    json: web::Json<CreateOAuthApplicationRequest>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match oauth_service.create_application(&current_user.id, &json.into_inner()).await {
+        Ok(application) => Ok(success_response(application)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn get_application(
+    req: HttpRequest,
+    path: web::Path<String>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let application_id = path.into_inner();
+
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match oauth_service.get_application(&current_user.id, &application_id).await {
+        Ok(application) => Ok(success_response(application)),
+        Err(err) if err == "Application not found" => Ok(error_response(&err, 404)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn update_application(
+    req: HttpRequest,
+    path: web::Path<String>,
+    json: web::Json<UpdateOAuthApplicationRequest>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let application_id = path.into_inner();
+
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match oauth_service.update_application(&current_user.id, &application_id, &json.into_inner()).await {
+        Ok(application) => Ok(success_response(application)),
+        Err(err) if err == "Application not found" => Ok(error_response(&err, 404)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn delete_application(
+    req: HttpRequest,
+    path: web::Path<String>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let application_id = path.into_inner();
+
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match oauth_service.delete_application(&current_user.id, &application_id).await {
+        Ok(_) => Ok(success_response("Application deleted")),
+        Err(err) if err == "Application not found" => Ok(error_response(&err, 404)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn reset_client_secret(
+    req: HttpRequest,
+    path: web::Path<String>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let application_id = path.into_inner();
+
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match oauth_service.reset_client_secret(&current_user.id, &application_id).await {
+        Ok(application) => Ok(success_response(application)),
+        Err(err) if err == "Application not found" => Ok(error_response(&err, 404)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn list_authorizations(
+    req: HttpRequest,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match oauth_service.list_authorizations(&current_user.id).await {
+        Ok(authorizations) => Ok(success_response(authorizations)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn revoke_authorization(
+    req: HttpRequest,
+    path: web::Path<String>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let application_id = path.into_inner();
+
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match oauth_service.revoke_authorization(&current_user.id, &application_id).await {
+        Ok(_) => Ok(success_response("Authorization revoked")),
+        Err(err) if err == "Authorization not found" => Ok(error_response(&err, 404)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+// Backs the consent screen: validates the request and describes what is being asked for
+pub async fn get_authorization(
+    req: HttpRequest,
+    query: web::Query<AuthorizationRequest>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match oauth_service.prepare_authorization(&current_user.id, &query).await {
+        Ok(prompt) => Ok(success_response(prompt)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+// The user's decision on the consent screen; either way the browser goes back to the client
+pub async fn submit_authorization(
+    req: HttpRequest,
+    json: web::Json<ConsentRequest>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    let redirect = if json.approve {
+        oauth_service.approve_authorization(&current_user.id, &json.authorization).await
+    } else {
+        oauth_service.deny_authorization(&json.authorization).await
+    };
+
+    match redirect {
+        Ok(redirect_url) => Ok(success_response(ConsentRedirect { redirect_url })),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn token(
+    req: HttpRequest,
+    form: web::Form<OAuthTokenRequest>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let (client_id, client_secret) = match client_credentials(&req, form.client_id.as_deref(), form.client_secret.as_deref()) {
+        Some(credentials) => credentials,
+        None => return Ok(missing_client()),
+    };
+
+    let tokens = match form.grant_type.as_str() {
+        "authorization_code" => match (&form.code, &form.redirect_uri, &form.code_verifier) {
+            (Some(code), Some(redirect_uri), Some(code_verifier)) => {
+                oauth_service.exchange_code(&client_id, &client_secret, code, redirect_uri, code_verifier).await
+            }
+            _ => Err(OAuthError::new("invalid_request", "code, redirect_uri and code_verifier are required")),
+        },
+        "refresh_token" => match &form.refresh_token {
+            Some(refresh_token) => oauth_service.refresh(&client_id, &client_secret, refresh_token).await,
+            None => Err(OAuthError::new("invalid_request", "refresh_token is required")),
+        },
+        _ => Err(OAuthError::new("unsupported_grant_type", "Supported grant types are authorization_code and refresh_token")),
+    };
+
+    match tokens {
+        Ok(tokens) => Ok(HttpResponse::Ok().insert_header(("Cache-Control", "no-store")).json(tokens)),
+        Err(error) => Ok(oauth_error_response(error)),
+    }
+}
+
+pub async fn introspect(
+    req: HttpRequest,
+    form: web::Form<OAuthTokenLookupRequest>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let (client_id, client_secret) = match client_credentials(&req, form.client_id.as_deref(), form.client_secret.as_deref()) {
+        Some(credentials) => credentials,
+        None => return Ok(missing_client()),
+    };
+
+    match oauth_service.introspect(&client_id, &client_secret, &form.token).await {
+        Ok(introspection) => Ok(HttpResponse::Ok().insert_header(("Cache-Control", "no-store")).json(introspection)),
+        Err(error) => Ok(oauth_error_response(error)),
+    }
+}
+
+pub async fn revoke(
+    req: HttpRequest,
+    form: web::Form<OAuthTokenLookupRequest>,
+    oauth_service: web::Data<OAuthService>,
+) -> Result<HttpResponse> {
+    let (client_id, client_secret) = match client_credentials(&req, form.client_id.as_deref(), form.client_secret.as_deref()) {
+        Some(credentials) => credentials,
+        None => return Ok(missing_client()),
+    };
+
+    match oauth_service.revoke(&client_id, &client_secret, &form.token).await {
+        Ok(_) => Ok(HttpResponse::Ok().finish()),
+        Err(error) => Ok(oauth_error_response(error)),
+    }
+}
+
+pub fn oauth_routes() -> actix_web::Scope {
+    web::scope("/oauth")
+        .route("/authorize", web::get().to(get_authorization))
+        .route("/authorize", web::post().to(submit_authorization))
+        .route("/token", web::post().to(token))
+        .route("/introspect", web::post().to(introspect))
+        .route("/revoke", web::post().to(revoke))
+}
+
+pub fn oauth_application_routes() -> actix_web::Scope {
+    web::scope("/user/oauth")
+        .route("/applications", web::get().to(list_applications))
+        .route("/applications", web::post().to(create_application))
+        .route("/applications/{application_id}", web::get().to(get_application))
+        .route("/applications/{application_id}", web::patch().to(update_application))
+        .route("/applications/{application_id}", web::delete().to(delete_application))
+        .route("/applications/{application_id}/secret", web::post().to(reset_client_secret))
+        .route("/authorizations", web::get().to(list_authorizations))
+        .route("/authorizations/{application_id}", web::delete().to(revoke_authorization))
+}
@@ -0,0 +1,421 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use crate::services::{OrganizationService, PermissionService, RepositoryService, TeamService, TwoFactorService, UserService};
+use crate::services::organization_service::TWO_FACTOR_REQUIRED;
+use crate::models::{
+    Organization, OrgRole, CreateOrganizationRequest, UpdateOrganizationRequest, InviteMemberRequest,
+    UpdateMembershipRequest, CreateRepositoryRequest,
+};
+use crate::utils::jwt::extract_user_from_token;
+use crate::utils::response::{success_response, error_response};
+
+// Resolves an organization and the current user's role in it, if the user is a member
+async fn find_organization_as_member(
+    req: &HttpRequest,
+    org_service: &OrganizationService,
+    name: &str,
+) -> Result<(Organization, String, OrgRole), HttpResponse> {
+    let current_user = extract_user_from_token(req).map_err(|err| error_response(&err, 401))?;
+
+    let org = org_service
+        .get_organization(name)
+        .await
+        .map_err(|err| error_response(&err, 404))?;
+
+    let role = org_service
+        .get_active_membership(&org.id, &current_user.id)
+        .await
+        .map_err(|err| error_response(&err, 500))?
+        .ok_or_else(|| error_response("You are not a member of this organization", 403))?;
+
+    Ok((org, current_user.id, role))
+}
+
+pub async fn find_organization_as_owner(
+    req: &HttpRequest,
+    org_service: &OrganizationService,
+    name: &str,
+) -> Result<(Organization, String), HttpResponse> {
+    let (org, user_id, role) = find_organization_as_member(req, org_service, name).await?;
+
+    if role != OrgRole::Owner {
+        return Err(error_response("Only organization owners can do this", 403));
+    }
+
+    Ok((org, user_id))
+}
+
+pub async fn create_organization(
+    req: HttpRequest,
+    json: web::Json<CreateOrganizationRequest>,
+    org_service: web::Data<OrganizationService>,
+) -> Result<HttpResponse> {
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match org_service.create_organization(&current_user.id, &json.into_inner()).await {
+        Ok(org) => Ok(success_response(org)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn get_organization(
+    path: web::Path<String>,
+    org_service: web::Data<OrganizationService>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    match org_service.get_organization(&name).await {
+        Ok(org) => Ok(success_response(org)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn update_organization(
+    req: HttpRequest,
+    path: web::Path<String>,
+    json: web::Json<UpdateOrganizationRequest>,
+    org_service: web::Data<OrganizationService>,
+    two_factor_service: web::Data<TwoFactorService>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    let (org, user_id) = match find_organization_as_owner(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    // Owners must already comply, or requiring 2FA would lock them out of their own org
+    if json.two_factor_required == Some(true) {
+        match two_factor_service.is_enabled(&user_id).await {
+            Ok(true) => {}
+            Ok(false) => return Ok(error_response("Enable two-factor authentication on your account before requiring it", 400)),
+            Err(err) => return Ok(error_response(&err, 500)),
+        }
+    }
+
+    match org_service.update_organization(&org, &json.into_inner()).await {
+        Ok(org) => Ok(success_response(org)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn delete_organization(
+    req: HttpRequest,
+    path: web::Path<String>,
+    org_service: web::Data<OrganizationService>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    let (org, _) = match find_organization_as_owner(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match org_service.delete_organization(&org).await {
+        Ok(_) => Ok(success_response("Organization deleted successfully")),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn list_members(
+    req: HttpRequest,
+    path: web::Path<String>,
+    org_service: web::Data<OrganizationService>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    let (org, _, _) = match find_organization_as_member(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match org_service.list_members(&org.id).await {
+        Ok(members) => Ok(success_response(members)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn update_membership(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    json: web::Json<UpdateMembershipRequest>,
+    org_service: web::Data<OrganizationService>,
+    user_service: web::Data<UserService>,
+) -> Result<HttpResponse> {
+    let (name, username) = path.into_inner();
+
+    let (org, _) = match find_organization_as_owner(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let user = match user_service.get_user_by_username(&username).await {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    if let Err(err) = org_service.update_member_role(&org.id, &user.id, json.role).await {
+        return Ok(error_response(&err, 400));
+    }
+
+    match org_service.list_members(&org.id).await {
+        Ok(members) => Ok(success_response(members.into_iter().find(|member| member.user_id == user.id))),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+// Owners can remove anyone; members can remove themselves
+pub async fn remove_member(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+    user_service: web::Data<UserService>,
+) -> Result<HttpResponse> {
+    let (name, username) = path.into_inner();
+
+    let (org, current_user_id, role) = match find_organization_as_member(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let user = match user_service.get_user_by_username(&username).await {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    if role != OrgRole::Owner && user.id != current_user_id {
+        return Ok(error_response("Only organization owners can remove other members", 403));
+    }
+
+    if let Err(err) = org_service.remove_member(&org.id, &user.id).await {
+        return Ok(error_response(&err, 400));
+    }
+
+    match team_service.remove_member_from_organization(&org.id, &user.id).await {
+        Ok(_) => Ok(success_response("Member removed successfully")),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn list_invitations(
+    req: HttpRequest,
+    path: web::Path<String>,
+    org_service: web::Data<OrganizationService>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    let (org, _) = match find_organization_as_owner(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match org_service.list_invitations(&org.id).await {
+        Ok(invitations) => Ok(success_response(invitations)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn invite_member(
+    req: HttpRequest,
+    path: web::Path<String>,
+    json: web::Json<InviteMemberRequest>,
+    org_service: web::Data<OrganizationService>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    let (org, current_user_id) = match find_organization_as_owner(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let request = json.into_inner();
+
+    match org_service.invite_member(&org.id, &current_user_id, &request.username, request.role).await {
+        Ok(invitation) => Ok(success_response(invitation)),
+        Err(err) if err == "User not found" => Ok(error_response(&err, 404)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn cancel_invitation(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+) -> Result<HttpResponse> {
+    let (name, invitation_id) = path.into_inner();
+
+    let (org, _) = match find_organization_as_owner(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match org_service.get_invitation(&invitation_id).await {
+        Ok(invitation) if invitation.organization_id == org.id => {}
+        Ok(_) => return Ok(error_response("Invitation not found", 404)),
+        Err(err) => return Ok(error_response(&err, 404)),
+    }
+
+    match org_service.delete_invitation(&invitation_id).await {
+        Ok(_) => Ok(success_response("Invitation cancelled")),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn list_org_repos(
+    req: HttpRequest,
+    path: web::Path<String>,
+    org_service: web::Data<OrganizationService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    let org = match org_service.get_organization(&name).await {
+        Ok(org) => org,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    let repos = match repo_service.list_organization_repositories(&org.id).await {
+        Ok(repos) => repos,
+        Err(err) => return Ok(error_response(&err, 500)),
+    };
+
+    // Private repositories are listed only to users with access to them
+    let user_id = extract_user_from_token(&req).ok().map(|user| user.id);
+
+    match permission_service.filter_visible(user_id.as_deref(), repos).await {
+        Ok(repos) => Ok(success_response(repos)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn create_org_repo(
+    req: HttpRequest,
+    path: web::Path<String>,
+    json: web::Json<CreateRepositoryRequest>,
+    org_service: web::Data<OrganizationService>,
+    repo_service: web::Data<RepositoryService>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    let (org, current_user_id, role) = match find_organization_as_member(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    if role == OrgRole::BillingManager {
+        return Ok(error_response("Billing managers cannot create repositories", 403));
+    }
+
+    match repo_service.create_repository(&current_user_id, Some(&org.id), &json.into_inner()).await {
+        Ok(repo) => Ok(success_response(repo)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn list_my_organizations(
+    req: HttpRequest,
+    org_service: web::Data<OrganizationService>,
+) -> Result<HttpResponse> {
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match org_service.list_user_organizations(&current_user.id).await {
+        Ok(orgs) => Ok(success_response(orgs)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn list_my_invitations(
+    req: HttpRequest,
+    org_service: web::Data<OrganizationService>,
+) -> Result<HttpResponse> {
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match org_service.list_user_invitations(&current_user.id).await {
+        Ok(invitations) => Ok(success_response(invitations)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn accept_invitation(
+    req: HttpRequest,
+    path: web::Path<String>,
+    org_service: web::Data<OrganizationService>,
+) -> Result<HttpResponse> {
+    let invitation_id = path.into_inner();
+
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    let invitation = match org_service.get_invitation(&invitation_id).await {
+        Ok(invitation) if invitation.invitee_id == current_user.id => invitation,
+        Ok(_) => return Ok(error_response("Invitation not found", 404)),
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match org_service.accept_invitation(&invitation).await {
+        Ok(_) => Ok(success_response("Invitation accepted")),
+        Err(err) if err == TWO_FACTOR_REQUIRED => Ok(error_response(&err, 403)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn decline_invitation(
+    req: HttpRequest,
+    path: web::Path<String>,
+    org_service: web::Data<OrganizationService>,
+) -> Result<HttpResponse> {
+    let invitation_id = path.into_inner();
+
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    match org_service.get_invitation(&invitation_id).await {
+        Ok(invitation) if invitation.invitee_id == current_user.id => {}
+        Ok(_) => return Ok(error_response("Invitation not found", 404)),
+        Err(err) => return Ok(error_response(&err, 404)),
+    }
+
+    match org_service.delete_invitation(&invitation_id).await {
+        Ok(_) => Ok(success_response("Invitation declined")),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub fn organization_routes() -> actix_web::Scope {
+    web::scope("/orgs")
+        .route("", web::post().to(create_organization))
+        .route("/{org}", web::get().to(get_organization))
+        .route("/{org}", web::patch().to(update_organization))
+        .route("/{org}", web::delete().to(delete_organization))
+        .route("/{org}/members", web::get().to(list_members))
+        .route("/{org}/members/{username}", web::put().to(update_membership))
+        .route("/{org}/members/{username}", web::delete().to(remove_member))
+        .route("/{org}/invitations", web::get().to(list_invitations))
+        .route("/{org}/invitations", web::post().to(invite_member))
+        .route("/{org}/invitations/{invitation_id}", web::delete().to(cancel_invitation))
+        .route("/{org}/repos", web::get().to(list_org_repos))
+        .route("/{org}/repos", web::post().to(create_org_repo))
+}
+
+// Organization memberships and invitations of the authenticated user
+pub fn user_organization_routes() -> actix_web::Scope {
+    web::scope("/user")
+        .route("/orgs", web::get().to(list_my_organizations))
+        .route("/invitations", web::get().to(list_my_invitations))
+        .route("/invitations/{invitation_id}/accept", web::post().to(accept_invitation))
+        .route("/invitations/{invitation_id}", web::delete().to(decline_invitation))
+}
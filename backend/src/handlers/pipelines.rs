@@ -0,0 +1,502 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use actix_web::http::header::{self, Header};
+use actix_web_httpauth::headers::authorization::{Authorization, Bearer};
+use serde::Deserialize;
+use crate::services::{OrganizationService, PermissionService, PipelineService, RepositoryService};
+use crate::services::pipeline_service::RunnerOwner;
+use crate::models::{CreateRunnerRequest, Permission, Runner, RunnerJobCompletion, RunnerStepUpdate};
+use crate::handlers::organizations::find_organization_as_owner;
+use crate::handlers::repositories::{find_readable_repository, find_repository_for_user};
+use crate::utils::pagination::page_bounds;
+use crate::utils::response::{success_response, error_response};
+
+// Artifacts are uploaded as a single request body
+const MAX_ARTIFACT_BYTES: usize = 100 * 1024 * 1024;
+
+fn pipeline_error_response(err: &str) -> HttpResponse {
+    match err {
+        "Pipeline run not found" | "Job not found" | "Step not found" | "Runner not found" | "Artifact not found" => {
+            error_response(err, 404)
+        }
+        "Pipeline run is already completed" => error_response(err, 409),
+        err if err.starts_with("Database error") || err.starts_with("Transaction") || err.starts_with("Storage error") => {
+            error_response(err, 500)
+        }
+        err => error_response(err, 400),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RunsQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct LogQuery {
+    pub step: Option<i32>,
+}
+
+pub async fn list_runs(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    query: web::Query<RunsQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let (limit, offset) = page_bounds(query.page, query.per_page);
+
+    match pipeline_service.list_runs(&repo.id, limit, offset).await {
+        Ok(runs) => Ok(success_response(runs)),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+pub async fn get_run(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, number) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    match pipeline_service.get_run(&repo.id, number).await {
+        Ok(run) => Ok(success_response(run)),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+pub async fn cancel_run(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, number) = path.into_inner();
+
+    let (repo, _, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    if permission < Permission::Write {
+        return Ok(error_response("Insufficient permissions to cancel pipeline runs", 403));
+    }
+
+    match pipeline_service.cancel_run(&repo.id, number).await {
+        Ok(_) => Ok(success_response("Pipeline run cancelled")),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+pub async fn list_run_artifacts(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, number) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    match pipeline_service.list_artifacts(&repo.id, number).await {
+        Ok(artifacts) => Ok(success_response(artifacts)),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+pub async fn get_job_log(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    query: web::Query<LogQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, job_id) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    match pipeline_service.job_log(&repo.id, &job_id, query.step).await {
+        Ok(log) => Ok(HttpResponse::Ok()
+            .content_type("text/plain; charset=utf-8")
+            .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+            .body(log)),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+pub async fn download_artifact(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, artifact_id) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    // Always served as a download so uploaded HTML can never render in the API origin
+    match pipeline_service.download_artifact(&repo.id, &artifact_id).await {
+        Ok((artifact, data)) => Ok(HttpResponse::Ok()
+            .content_type("application/octet-stream")
+            .insert_header((header::X_CONTENT_TYPE_OPTIONS, "nosniff"))
+            .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", artifact.name.replace('"', ""))))
+            .body(data)),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+// Repository runners are managed by repository admins
+async fn repository_runners(
+    req: &HttpRequest,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+    owner: &str,
+    name: &str,
+) -> Result<(RunnerOwner, String), HttpResponse> {
+    let (repo, current_user, permission) =
+        find_repository_for_user(req, repo_service, permission_service, owner, name).await?;
+
+    if permission < Permission::Admin {
+        return Err(error_response("Only repository admins can manage runners", 403));
+    }
+
+    Ok((RunnerOwner::Repository(repo.id), current_user.id))
+}
+
+// Organization runners take jobs from every repository in the organization and are managed by its owners
+async fn organization_runners(
+    req: &HttpRequest,
+    org_service: &OrganizationService,
+    name: &str,
+) -> Result<(RunnerOwner, String), HttpResponse> {
+    let (org, user_id) = find_organization_as_owner(req, org_service, name).await?;
+
+    Ok((RunnerOwner::Organization(org.id), user_id))
+}
+
+async fn list_runners(owner: RunnerOwner, pipeline_service: &PipelineService) -> HttpResponse {
+    match pipeline_service.list_runners(&owner).await {
+        Ok(runners) => success_response(runners),
+        Err(err) => pipeline_error_response(&err),
+    }
+}
+
+async fn create_runner(owner: RunnerOwner, user_id: &str, request: &CreateRunnerRequest, pipeline_service: &PipelineService) -> HttpResponse {
+    match pipeline_service.create_runner(&owner, user_id, request).await {
+        Ok(runner) => success_response(runner),
+        Err(err) => pipeline_error_response(&err),
+    }
+}
+
+async fn delete_runner(owner: RunnerOwner, runner_id: &str, pipeline_service: &PipelineService) -> HttpResponse {
+    match pipeline_service.delete_runner(&owner, runner_id).await {
+        Ok(_) => success_response("Runner deleted"),
+        Err(err) => pipeline_error_response(&err),
+    }
+}
+
+pub async fn list_repo_runners(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    match repository_runners(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((runners, _)) => Ok(list_runners(runners, &pipeline_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn create_repo_runner(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    json: web::Json<CreateRunnerRequest>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    match repository_runners(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((runners, user_id)) => Ok(create_runner(runners, &user_id, &json, &pipeline_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn delete_repo_runner(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, runner_id) = path.into_inner();
+
+    match repository_runners(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((runners, _)) => Ok(delete_runner(runners, &runner_id, &pipeline_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn list_org_runners(
+    req: HttpRequest,
+    path: web::Path<String>,
+    org_service: web::Data<OrganizationService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let org_name = path.into_inner();
+
+    match organization_runners(&req, &org_service, &org_name).await {
+        Ok((runners, _)) => Ok(list_runners(runners, &pipeline_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn create_org_runner(
+    req: HttpRequest,
+    path: web::Path<String>,
+    json: web::Json<CreateRunnerRequest>,
+    org_service: web::Data<OrganizationService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let org_name = path.into_inner();
+
+    match organization_runners(&req, &org_service, &org_name).await {
+        Ok((runners, user_id)) => Ok(create_runner(runners, &user_id, &json, &pipeline_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn delete_org_runner(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (org_name, runner_id) = path.into_inner();
+
+    match organization_runners(&req, &org_service, &org_name).await {
+        Ok((runners, _)) => Ok(delete_runner(runners, &runner_id, &pipeline_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+// Runner agents authenticate with the `dvn_` token they were registered with, not a user token
+async fn authenticate_runner(req: &HttpRequest, pipeline_service: &PipelineService) -> Result<Runner, HttpResponse> {
+    let credentials = Authorization::<Bearer>::parse(req).map_err(|_| error_response("Runner token required", 401))?;
+
+    pipeline_service
+        .authenticate_runner(credentials.as_ref().token())
+        .await
+        .map_err(|err| match err.as_str() {
+            "Invalid runner token" => error_response(&err, 401),
+            _ => error_response(&err, 500),
+        })
+}
+
+pub async fn request_job(
+    req: HttpRequest,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let runner = match authenticate_runner(&req, &pipeline_service).await {
+        Ok(runner) => runner,
+        Err(response) => return Ok(response),
+    };
+
+    match pipeline_service.claim_job(&runner).await {
+        Ok(Some(job)) => Ok(success_response(job)),
+        Ok(None) => Ok(HttpResponse::NoContent().finish()),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+pub async fn job_heartbeat(
+    req: HttpRequest,
+    path: web::Path<String>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let job_id = path.into_inner();
+
+    let runner = match authenticate_runner(&req, &pipeline_service).await {
+        Ok(runner) => runner,
+        Err(response) => return Ok(response),
+    };
+
+    match pipeline_service.heartbeat(&runner, &job_id).await {
+        Ok(heartbeat) => Ok(success_response(heartbeat)),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+pub async fn job_source(
+    req: HttpRequest,
+    path: web::Path<String>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let job_id = path.into_inner();
+
+    let runner = match authenticate_runner(&req, &pipeline_service).await {
+        Ok(runner) => runner,
+        Err(response) => return Ok(response),
+    };
+
+    match pipeline_service.job_source(&runner, &job_id).await {
+        Ok(archive) => Ok(HttpResponse::Ok().content_type("application/gzip").body(archive)),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+pub async fn update_job_step(
+    req: HttpRequest,
+    path: web::Path<(String, i32)>,
+    json: web::Json<RunnerStepUpdate>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (job_id, number) = path.into_inner();
+
+    let runner = match authenticate_runner(&req, &pipeline_service).await {
+        Ok(runner) => runner,
+        Err(response) => return Ok(response),
+    };
+
+    match pipeline_service.update_step(&runner, &job_id, number, &json).await {
+        Ok(_) => Ok(success_response("Step updated")),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+// The body is raw step output; invalid UTF-8 is replaced rather than rejected
+pub async fn append_job_log(
+    req: HttpRequest,
+    path: web::Path<(String, i32)>,
+    body: web::Bytes,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (job_id, number) = path.into_inner();
+
+    let runner = match authenticate_runner(&req, &pipeline_service).await {
+        Ok(runner) => runner,
+        Err(response) => return Ok(response),
+    };
+
+    match pipeline_service.append_log(&runner, &job_id, number, &String::from_utf8_lossy(&body)).await {
+        Ok(_) => Ok(success_response("Log appended")),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+pub async fn upload_job_artifact(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    body: web::Bytes,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let (job_id, name) = path.into_inner();
+
+    let runner = match authenticate_runner(&req, &pipeline_service).await {
+        Ok(runner) => runner,
+        Err(response) => return Ok(response),
+    };
+
+    let content_type = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+
+    match pipeline_service.upload_artifact(&runner, &job_id, &name, &content_type, body.to_vec()).await {
+        Ok(artifact) => Ok(success_response(artifact)),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+pub async fn complete_job(
+    req: HttpRequest,
+    path: web::Path<String>,
+    json: web::Json<RunnerJobCompletion>,
+    pipeline_service: web::Data<PipelineService>,
+) -> Result<HttpResponse> {
+    let job_id = path.into_inner();
+
+    let runner = match authenticate_runner(&req, &pipeline_service).await {
+        Ok(runner) => runner,
+        Err(response) => return Ok(response),
+    };
+
+    match pipeline_service.complete_job(&runner, &job_id, json.conclusion).await {
+        Ok(_) => Ok(success_response("Job completed")),
+        Err(err) => Ok(pipeline_error_response(&err)),
+    }
+}
+
+pub fn pipeline_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/pipelines")
+        .route("/runs", web::get().to(list_runs))
+        .route("/runs/{number}", web::get().to(get_run))
+        .route("/runs/{number}/cancel", web::post().to(cancel_run))
+        .route("/runs/{number}/artifacts", web::get().to(list_run_artifacts))
+        .route("/jobs/{job_id}/logs", web::get().to(get_job_log))
+        .route("/artifacts/{artifact_id}", web::get().to(download_artifact))
+}
+
+pub fn repository_runner_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/runners")
+        .route("", web::get().to(list_repo_runners))
+        .route("", web::post().to(create_repo_runner))
+        .route("/{runner_id}", web::delete().to(delete_repo_runner))
+}
+
+pub fn organization_runner_routes() -> actix_web::Scope {
+    web::scope("/orgs/{org}/runners")
+        .route("", web::get().to(list_org_runners))
+        .route("", web::post().to(create_org_runner))
+        .route("/{runner_id}", web::delete().to(delete_org_runner))
+}
+
+// The protocol runner agents speak; mounted outside /api/v1 since it uses runner tokens
+pub fn runner_protocol_routes() -> actix_web::Scope {
+    web::scope("/runner/v1/jobs")
+        .route("/request", web::post().to(request_job))
+        .route("/{job_id}/heartbeat", web::post().to(job_heartbeat))
+        .route("/{job_id}/source", web::get().to(job_source))
+        .route("/{job_id}/steps/{number}", web::patch().to(update_job_step))
+        .route("/{job_id}/steps/{number}/logs", web::post().to(append_job_log))
+        .service(
+            web::resource("/{job_id}/artifacts/{name}")
+                .app_data(web::PayloadConfig::new(MAX_ARTIFACT_BYTES))
+                .route(web::put().to(upload_job_artifact)),
+        )
+        .route("/{job_id}/complete", web::post().to(complete_job))
+}
@@ -1,8 +1,12 @@
 use actix_web::{web, HttpResponse, Result, HttpRequest};
 use serde::Deserialize;
-use crate::services::{PullRequestService, RepositoryService};
-use crate::models::{CreatePullRequestRequest, UpdatePullRequestRequest};
+use crate::services::{ActivityService, GitService, PermissionService, PullRequestService, RepositoryService};
+use crate::services::git_service::MergeOutcome;
+use crate::models::{CreatePullRequestRequest, UpdatePullRequestRequest, MergePullRequestRequest, MergeMethod, Permission};
+use crate::models::{PullRequestReviewDetail, ReviewState, SubmitReviewRequest, CreateReviewCommentRequest};
+use crate::handlers::repositories::{find_readable_repository, find_repository_for_user};
 use crate::utils::jwt::extract_user_from_token;
+use crate::utils::pagination::page_bounds;
 use crate::utils::response::{success_response, error_response};
 
 #[derive(Deserialize)]
@@ -15,17 +19,19 @@ pub struct PullRequestQuery {
 }
 
 pub async fn list_pull_requests(
+    req: HttpRequest,
     path: web::Path<(String, String)>,
     _query: web::Query<PullRequestQuery>,
     pr_service: web::Data<PullRequestService>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name) = path.into_inner();
     
     // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
         Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+        Err(response) => return Ok(response),
     };
     
     match pr_service.list_repository_pull_requests(&repo.id).await {
@@ -34,17 +40,109 @@ pub async fn list_pull_requests(
     }
 }
 
+#[derive(Deserialize)]
+pub struct PullRequestFilesQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+pub async fn list_pull_request_files(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    query: web::Query<PullRequestFilesQuery>,
+    pr_service: web::Data<PullRequestService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, pr_number) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let pr = match pr_service.get_pull_request(&repo.id, pr_number).await {
+        Ok(pr) => pr,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    let (limit, offset) = page_bounds(query.page, query.per_page);
+
+    match git_service.blocking(move |git| git.compare_files(&repo.id, &pr.base_branch, &pr.head_branch)).await {
+        Ok((files, _)) => {
+            let files: Vec<_> = files.into_iter().skip(offset as usize).take(limit as usize).collect();
+            Ok(success_response(files))
+        }
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn get_pull_request_diff(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    pr_service: web::Data<PullRequestService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, pr_number) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let pr = match pr_service.get_pull_request(&repo.id, pr_number).await {
+        Ok(pr) => pr,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match git_service.blocking(move |git| git.compare_diff(&repo.id, &pr.base_branch, &pr.head_branch)).await {
+        Ok(diff) => Ok(HttpResponse::Ok().content_type("text/x-diff; charset=utf-8").body(diff)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn get_pull_request_patch(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    pr_service: web::Data<PullRequestService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, pr_number) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let pr = match pr_service.get_pull_request(&repo.id, pr_number).await {
+        Ok(pr) => pr,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match git_service.blocking(move |git| git.compare_patch(&repo.id, &pr.base_branch, &pr.head_branch)).await {
+        Ok(patch) => Ok(HttpResponse::Ok().content_type("text/x-patch; charset=utf-8").body(patch)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
 pub async fn get_pull_request(
+    req: HttpRequest,
     path: web::Path<(String, String, i32)>,
     pr_service: web::Data<PullRequestService>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name, pr_number) = path.into_inner();
     
     // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
         Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+        Err(response) => return Ok(response),
     };
     
     match pr_service.get_pull_request(&repo.id, pr_number).await {
@@ -59,21 +157,21 @@ pub async fn create_pull_request(
     json: web::Json<CreatePullRequestRequest>,
     pr_service: web::Data<PullRequestService>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, current_user, _) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
     let request = json.into_inner();
+
+    if request.base_branch == request.head_branch {
+        return Ok(error_response("Head and base branches must differ", 400));
+    }
     
     match pr_service.create_pull_request(
         &repo.id,
@@ -83,7 +181,11 @@ pub async fn create_pull_request(
         &request.base_branch,
         &request.head_branch
     ).await {
-        Ok(pr) => Ok(success_response(pr)),
+        Ok(pr) => {
+            pr_service.schedule_branch_refresh(&repo.id, vec![pr.head_branch.clone()]);
+            activity_service.pull_request(&repo, &current_user.id, "opened", &pr);
+            Ok(success_response(pr))
+        }
         Err(err) => Ok(error_response(&err, 400)),
     }
 }
@@ -94,18 +196,14 @@ pub async fn update_pull_request(
     json: web::Json<UpdatePullRequestRequest>,
     pr_service: web::Data<PullRequestService>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name, pr_number) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, current_user, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
     // Get the pull request to verify it exists
@@ -114,7 +212,23 @@ pub async fn update_pull_request(
         Err(err) => return Ok(error_response(&err, 404)),
     };
     
+    // Authors edit their own pull requests; triagers and above edit any
+    if pr.author_id != current_user.id && permission < Permission::Triage {
+        return Ok(error_response("Insufficient permissions to update", 403));
+    }
+    
     let request = json.into_inner();
+
+    // Only merging marks a pull request merged, and a merged one keeps that status
+    if let Some(status) = request.status.as_deref() {
+        if !status.eq_ignore_ascii_case("open") && !status.eq_ignore_ascii_case("closed") {
+            return Ok(error_response("Status must be open or closed", 400));
+        }
+
+        if pr.is_merged {
+            return Ok(error_response("Pull request is already merged", 400));
+        }
+    }
     
     match pr_service.update_pull_request(
         &pr.id,
@@ -122,7 +236,16 @@ pub async fn update_pull_request(
         request.body.as_deref(),
         request.status.as_deref()
     ).await {
-        Ok(updated_pr) => Ok(success_response(updated_pr)),
+        Ok(updated_pr) => {
+            // Statuses are stored as uppercase ENUM values
+            let action = match (pr.status.to_lowercase().as_str(), updated_pr.status.to_lowercase().as_str()) {
+                ("open", "closed") => "closed",
+                ("closed", "open") => "reopened",
+                _ => "edited",
+            };
+            activity_service.pull_request(&repo, &current_user.id, action, &updated_pr);
+            Ok(success_response(updated_pr))
+        }
         Err(err) => Ok(error_response(&err, 400)),
     }
 }
@@ -130,21 +253,17 @@ pub async fn update_pull_request(
 pub async fn merge_pull_request(
     req: HttpRequest,
     path: web::Path<(String, String, i32)>,
-    json: web::Json<serde_json::Value>,
+    json: web::Json<MergePullRequestRequest>,
     pr_service: web::Data<PullRequestService>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name, pr_number) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, current_user, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
     // Get the pull request
@@ -153,19 +272,72 @@ pub async fn merge_pull_request(
         Err(err) => return Ok(error_response(&err, 404)),
     };
     
-    // Verify user has permission to merge (owner or admin)
-    if repo.owner_id != current_user.id {
+    // Merging needs write access; admins additionally bypass push restrictions
+    if permission < Permission::Write {
         return Ok(error_response("Insufficient permissions to merge", 403));
     }
+
+    // Merging writes to the base branch, which archived repositories refuse like any push
+    if repo.is_archived_bool() {
+        return Ok(error_response("Repository is archived", 403));
+    }
+
+    if pr.is_merged {
+        return Ok(error_response("Pull request is already merged", 400));
+    }
+
+    if !pr.status.eq_ignore_ascii_case("open") {
+        return Ok(error_response("Pull request is not open", 400));
+    }
+
+    // The merge is held to this commit, so a push after the checks below cannot slip in
+    let head_sha = match pr_service.head_sha(&pr).await {
+        Ok(sha) => sha,
+        Err(err) => return Ok(error_response(&err, 400)),
+    };
+
+    match pr_service.check_merge(&pr, &head_sha, &current_user.id, permission == Permission::Admin).await {
+        Ok(Some(reason)) => return Ok(error_response(reason, 403)),
+        Ok(None) => {}
+        Err(err) => return Ok(error_response(&err, 500)),
+    }
     
-    let merge_message = json.get("commit_message")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| format!("Merge pull request #{} from {}", pr.number, pr.head_branch));
+    let request = json.into_inner();
+    let merge_method = request.merge_method.unwrap_or_default();
 
-    match pr_service.merge_pull_request(&pr.id, &current_user.id, &merge_message).await {
-        Ok(merged_pr) => Ok(success_response(merged_pr)),
-        Err(err) => Ok(error_response(&err, 400)),
+    let merge_message = request.commit_message.unwrap_or_else(|| match merge_method {
+        MergeMethod::Squash => format!("{} (#{})", pr.title, pr.number),
+        _ => format!("Merge pull request #{} from {}", pr.number, pr.head_branch),
+    });
+
+    let (committer_name, committer_email) = match repo_service.git_identity(&current_user.id).await {
+        Ok(identity) => identity,
+        Err(err) => return Ok(error_response(&err, 500)),
+    };
+
+    let merge = match pr_service.merge_branches(&pr, &head_sha, merge_method, &merge_message, &committer_name, &committer_email).await {
+        Ok(merge) => merge,
+        Err(err) => return Ok(error_response(&err, 400)),
+    };
+
+    let merge_commit_sha = match merge.outcome {
+        MergeOutcome::Merged(sha) => sha,
+        MergeOutcome::Conflict(paths) => {
+            return Ok(error_response(format!("Merge conflict in: {}", paths.join(", ")), 409));
+        }
+        MergeOutcome::HeadMoved => {
+            return Ok(error_response("Head branch was modified; review the changes and try the merge again", 409));
+        }
+    };
+
+    match pr_service.merge_pull_request(&pr.id, &current_user.id, &merge_commit_sha).await {
+        Ok(merged_pr) => {
+            // Other pull requests into the same base may no longer merge cleanly
+            pr_service.schedule_branch_refresh(&repo.id, vec![merged_pr.base_branch.clone()]);
+            activity_service.pull_request_merged(&repo, &current_user.id, &merged_pr, merge.heads_before, merge.heads_after);
+            Ok(success_response(merged_pr))
+        }
+        Err(err) => Ok(error_response(&err, 500)),
     }
 }
 
@@ -174,18 +346,14 @@ pub async fn close_pull_request(
     path: web::Path<(String, String, i32)>,
     pr_service: web::Data<PullRequestService>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name, pr_number) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, current_user, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
     // Get the pull request
@@ -194,13 +362,20 @@ pub async fn close_pull_request(
         Err(err) => return Ok(error_response(&err, 404)),
     };
     
-    // Verify user can close PR (author or repo owner)
-    if pr.author_id != current_user.id && repo.owner_id != current_user.id {
+    // Verify user can close PR (author or triage access)
+    if pr.author_id != current_user.id && permission < Permission::Triage {
         return Ok(error_response("Insufficient permissions to close", 403));
     }
+
+    if pr.is_merged {
+        return Ok(error_response("Pull request is already merged", 400));
+    }
     
     match pr_service.update_pull_request(&pr.id, None, None, Some("closed")).await {
-        Ok(closed_pr) => Ok(success_response(closed_pr)),
+        Ok(closed_pr) => {
+            activity_service.pull_request(&repo, &current_user.id, "closed", &closed_pr);
+            Ok(success_response(closed_pr))
+        }
         Err(err) => Ok(error_response(&err, 400)),
     }
 }
@@ -210,18 +385,14 @@ pub async fn reopen_pull_request(
     path: web::Path<(String, String, i32)>,
     pr_service: web::Data<PullRequestService>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
 ) -> Result<HttpResponse> {
     let (owner, repo_name, pr_number) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &repo_name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, current_user, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
     // Get the pull request
@@ -230,23 +401,276 @@ pub async fn reopen_pull_request(
         Err(err) => return Ok(error_response(&err, 404)),
     };
     
-    // Verify user can reopen PR (author or repo owner)
-    if pr.author_id != current_user.id && repo.owner_id != current_user.id {
+    // Verify user can reopen PR (author or triage access)
+    if pr.author_id != current_user.id && permission < Permission::Triage {
         return Ok(error_response("Insufficient permissions to reopen", 403));
     }
+
+    if pr.is_merged {
+        return Ok(error_response("A merged pull request cannot be reopened", 400));
+    }
     
     match pr_service.update_pull_request(&pr.id, None, None, Some("open")).await {
-        Ok(reopened_pr) => Ok(success_response(reopened_pr)),
+        Ok(reopened_pr) => {
+            activity_service.pull_request(&repo, &current_user.id, "reopened", &reopened_pr);
+            Ok(success_response(reopened_pr))
+        }
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn list_reviews(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    pr_service: web::Data<PullRequestService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, pr_number) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let pr = match pr_service.get_pull_request(&repo.id, pr_number).await {
+        Ok(pr) => pr,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match pr_service.list_reviews(&pr.id).await {
+        Ok(reviews) => Ok(success_response(reviews)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn get_review(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32, String)>,
+    pr_service: web::Data<PullRequestService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, pr_number, review_id) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let pr = match pr_service.get_pull_request(&repo.id, pr_number).await {
+        Ok(pr) => pr,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    let review = match pr_service.get_review(&pr.id, &review_id).await {
+        Ok(review) => review,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match pr_service.list_review_comments_for_review(&review.id).await {
+        Ok(comments) => Ok(success_response(PullRequestReviewDetail { review, comments })),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn submit_review(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    json: web::Json<SubmitReviewRequest>,
+    pr_service: web::Data<PullRequestService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, pr_number) = path.into_inner();
+
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let pr = match pr_service.get_pull_request(&repo.id, pr_number).await {
+        Ok(pr) => pr,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    let request = json.into_inner();
+
+    if !pr.status.eq_ignore_ascii_case("open") {
+        return Ok(error_response("Pull request is not open", 400));
+    }
+
+    // Authors may comment on their own pull request but not approve or block it
+    if pr.author_id == current_user.id && request.event != ReviewState::Comment {
+        return Ok(error_response("Cannot approve or request changes on your own pull request", 400));
+    }
+
+    match pr_service.submit_review(&pr, &current_user.id, &request).await {
+        Ok(review) => {
+            activity_service.pull_request_review(&repo, &current_user.id, &pr, review.body.as_deref());
+            Ok(success_response(review))
+        }
         Err(err) => Ok(error_response(&err, 400)),
     }
 }
 
+#[derive(Deserialize)]
+pub struct ReviewCommentsQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+pub async fn list_review_comments(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    query: web::Query<ReviewCommentsQuery>,
+    pr_service: web::Data<PullRequestService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, pr_number) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let pr = match pr_service.get_pull_request(&repo.id, pr_number).await {
+        Ok(pr) => pr,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    let (limit, offset) = page_bounds(query.page, query.per_page);
+
+    match pr_service.list_review_comments(&pr.id, limit, offset).await {
+        Ok(comments) => Ok(success_response(comments)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn create_review_comment(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32)>,
+    json: web::Json<CreateReviewCommentRequest>,
+    pr_service: web::Data<PullRequestService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    activity_service: web::Data<ActivityService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, pr_number) = path.into_inner();
+
+    let current_user = match extract_user_from_token(&req) {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 401)),
+    };
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let pr = match pr_service.get_pull_request(&repo.id, pr_number).await {
+        Ok(pr) => pr,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match pr_service.create_review_comment(&pr, &current_user.id, &json.into_inner()).await {
+        Ok(comment) => {
+            activity_service.pull_request_review(&repo, &current_user.id, &pr, Some(&comment.body));
+            Ok(success_response(comment))
+        }
+        Err(err) if err == "Review comment not found" => Ok(error_response(&err, 404)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+async fn set_thread_resolved(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32, String)>,
+    pr_service: web::Data<PullRequestService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    resolved: bool,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, pr_number, comment_id) = path.into_inner();
+
+    let (repo, current_user, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    let pr = match pr_service.get_pull_request(&repo.id, pr_number).await {
+        Ok(pr) => pr,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    let comment = match pr_service.get_review_comment(&pr.id, &comment_id).await {
+        Ok(comment) => comment,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    if comment.in_reply_to_id.is_some() {
+        return Ok(error_response("Only the first comment of a thread can be resolved", 400));
+    }
+
+    // Thread author, pull request author or anyone with write access
+    if comment.author_id != current_user.id && pr.author_id != current_user.id && permission < Permission::Write {
+        return Ok(error_response("Insufficient permissions to resolve this thread", 403));
+    }
+
+    if let Err(err) = pr_service.set_thread_resolved(&pr.id, &comment.id, resolved, &current_user.id).await {
+        return Ok(error_response(&err, 500));
+    }
+
+    match pr_service.get_review_comment(&pr.id, &comment.id).await {
+        Ok(comment) => Ok(success_response(comment)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn resolve_review_thread(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32, String)>,
+    pr_service: web::Data<PullRequestService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    set_thread_resolved(req, path, pr_service, repo_service, permission_service, true).await
+}
+
+pub async fn unresolve_review_thread(
+    req: HttpRequest,
+    path: web::Path<(String, String, i32, String)>,
+    pr_service: web::Data<PullRequestService>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+) -> Result<HttpResponse> {
+    set_thread_resolved(req, path, pr_service, repo_service, permission_service, false).await
+}
+
 pub fn pull_request_routes() -> actix_web::Scope {
     web::scope("/repos/{owner}/{repo}/pulls")
         .route("", web::get().to(list_pull_requests))
         .route("", web::post().to(create_pull_request))
+        // The .diff and .patch suffixes must be matched before the plain number route
+        .route("/{number}.diff", web::get().to(get_pull_request_diff))
+        .route("/{number}.patch", web::get().to(get_pull_request_patch))
         .route("/{number}", web::get().to(get_pull_request))
         .route("/{number}", web::patch().to(update_pull_request))
+        .route("/{number}/files", web::get().to(list_pull_request_files))
+        .route("/{number}/reviews", web::get().to(list_reviews))
+        .route("/{number}/reviews", web::post().to(submit_review))
+        .route("/{number}/reviews/{review_id}", web::get().to(get_review))
+        .route("/{number}/comments", web::get().to(list_review_comments))
+        .route("/{number}/comments", web::post().to(create_review_comment))
+        .route("/{number}/comments/{comment_id}/resolve", web::put().to(resolve_review_thread))
+        .route("/{number}/comments/{comment_id}/resolve", web::delete().to(unresolve_review_thread))
         .route("/{number}/merge", web::put().to(merge_pull_request))
         .route("/{number}/close", web::patch().to(close_pull_request))
         .route("/{number}/reopen", web::patch().to(reopen_pull_request))
@@ -1,7 +1,8 @@
 use actix_web::{web, HttpResponse, Result, HttpRequest};
 use serde::Deserialize;
-use crate::services::RepositoryService;
-use crate::models::{CreateRepositoryRequest, UpdateRepositoryRequest};
+use serde_json::json;
+use crate::services::{NotificationService, PermissionService, RepositoryService, WebhookService};
+use crate::models::{Permission, Repository, User, CreateRepositoryRequest, UpdateRepositoryRequest, WebhookEvent};
 use crate::utils::jwt::extract_user_from_token;
 use crate::utils::response::{success_response, error_response};
 
@@ -14,30 +15,94 @@ pub struct ListReposQuery {
     pub direction: Option<String>, // asc, desc
 }
 
+// Looks up a repository for a read endpoint; private repositories are reported
+// as missing to anyone without access so their existence is not leaked
+pub async fn find_readable_repository(
+    req: &HttpRequest,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+    owner: &str,
+    name: &str,
+) -> Result<Repository, HttpResponse> {
+    let repo = repo_service
+        .get_repository(owner, name)
+        .await
+        .map_err(|err| error_response(&err, 404))?;
+
+    let user_id = extract_user_from_token(req).ok().map(|user| user.id);
+
+    let permission = permission_service
+        .effective_permission(user_id.as_deref(), &repo)
+        .await
+        .map_err(|err| error_response(&err, 500))?;
+
+    if permission.is_none() {
+        return Err(error_response("Repository not found", 404));
+    }
+
+    Ok(repo)
+}
+
+// Like find_readable_repository, but requires a signed-in user and also
+// returns the user together with their permission on the repository
+pub async fn find_repository_for_user(
+    req: &HttpRequest,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+    owner: &str,
+    name: &str,
+) -> Result<(Repository, User, Permission), HttpResponse> {
+    let current_user = extract_user_from_token(req).map_err(|err| error_response(&err, 401))?;
+
+    let repo = repo_service
+        .get_repository(owner, name)
+        .await
+        .map_err(|err| error_response(&err, 404))?;
+
+    let permission = permission_service
+        .effective_permission(Some(&current_user.id), &repo)
+        .await
+        .map_err(|err| error_response(&err, 500))?
+        .ok_or_else(|| error_response("Repository not found", 404))?;
+
+    Ok((repo, current_user, permission))
+}
+
 pub async fn list_repos(
+    req: HttpRequest,
     query: web::Query<ListReposQuery>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
 ) -> Result<HttpResponse> {
     let username = match &query.username {
         Some(user) => user,
         None => return Ok(error_response("Username parameter is required", 400)),
     };
     
-    match repo_service.list_user_repositories(username).await {
+    let repos = match repo_service.list_user_repositories(username).await {
+        Ok(repos) => repos,
+        Err(err) => return Ok(error_response(&err, 500)),
+    };
+    
+    let user_id = extract_user_from_token(&req).ok().map(|user| user.id);
+    
+    match permission_service.filter_visible(user_id.as_deref(), repos).await {
         Ok(repos) => Ok(success_response(repos)),
         Err(err) => Ok(error_response(&err, 500)),
     }
 }
 
 pub async fn get_repo(
+    req: HttpRequest,
     path: web::Path<(String, String)>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
 ) -> Result<HttpResponse> {
     let (owner, name) = path.into_inner();
     
-    match repo_service.get_repository(&owner, &name).await {
+    match find_readable_repository(&req, &repo_service, &permission_service, &owner, &name).await {
         Ok(repo) => Ok(success_response(repo)),
-        Err(err) => Ok(error_response(&err, 404)),
+        Err(response) => Ok(response),
     }
 }
 
@@ -53,12 +118,7 @@ pub async fn create_repo(
     
     let request = json.into_inner();
     
-    match repo_service.create_repository(
-        &current_user.id,
-        &request.name,
-        request.description.as_deref(),
-        request.is_private
-    ).await {
+    match repo_service.create_repository(&current_user.id, None, &request).await {
         Ok(repo) => Ok(success_response(repo)),
         Err(err) => Ok(error_response(&err, 400)),
     }
@@ -69,21 +129,17 @@ pub async fn update_repo(
     path: web::Path<(String, String)>,
     json: web::Json<UpdateRepositoryRequest>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
 ) -> Result<HttpResponse> {
     let (owner, name) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Check if user owns the repository
-    let repo = match repo_service.get_repository(&owner, &name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, _, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
-    if repo.owner_id != current_user.id {
+    // Check if user administers the repository
+    if permission < Permission::Admin {
         return Ok(error_response("Unauthorized to update this repository", 403));
     }
     
@@ -93,7 +149,8 @@ pub async fn update_repo(
         &repo.id,
         request.name.as_deref(),
         request.description.as_deref(),
-        request.is_private
+        request.is_private,
+        request.default_branch.as_deref()
     ).await {
         Ok(updated_repo) => Ok(success_response(updated_repo)),
         Err(err) => Ok(error_response(&err, 400)),
@@ -104,21 +161,17 @@ pub async fn delete_repo(
     req: HttpRequest,
     path: web::Path<(String, String)>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
 ) -> Result<HttpResponse> {
     let (owner, name) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Check if user owns the repository
-    let repo = match repo_service.get_repository(&owner, &name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, _, permission) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
-    if repo.owner_id != current_user.id {
+    // Check if user administers the repository
+    if permission < Permission::Admin {
         return Ok(error_response("Unauthorized to delete this repository", 403));
     }
     
@@ -132,22 +185,23 @@ pub async fn star_repo(
     req: HttpRequest,
     path: web::Path<(String, String)>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    webhook_service: web::Data<WebhookService>,
 ) -> Result<HttpResponse> {
     let (owner, name) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, current_user, _) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
     match repo_service.star_repository(&current_user.id, &repo.id).await {
-        Ok(_) => Ok(success_response("Repository starred successfully")),
+        Ok(changed) => {
+            if changed {
+                webhook_service.dispatch(&repo, &current_user.id, WebhookEvent::Star, json!({ "action": "created" }));
+            }
+            Ok(success_response("Repository starred successfully"))
+        }
         Err(err) => Ok(error_response(&err, 400)),
     }
 }
@@ -156,22 +210,23 @@ pub async fn unstar_repo(
     req: HttpRequest,
     path: web::Path<(String, String)>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    webhook_service: web::Data<WebhookService>,
 ) -> Result<HttpResponse> {
     let (owner, name) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, current_user, _) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
     match repo_service.unstar_repository(&current_user.id, &repo.id).await {
-        Ok(_) => Ok(success_response("Repository unstarred successfully")),
+        Ok(changed) => {
+            if changed {
+                webhook_service.dispatch(&repo, &current_user.id, WebhookEvent::Star, json!({ "action": "deleted" }));
+            }
+            Ok(success_response("Repository unstarred successfully"))
+        }
         Err(err) => Ok(error_response(&err, 400)),
     }
 }
@@ -180,18 +235,13 @@ pub async fn check_star_status(
     req: HttpRequest,
     path: web::Path<(String, String)>,
     repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
 ) -> Result<HttpResponse> {
     let (owner, name) = path.into_inner();
     
-    let current_user = match extract_user_from_token(&req) {
-        Ok(user) => user,
-        Err(err) => return Ok(error_response(&err, 401)),
-    };
-    
-    // Get repository
-    let repo = match repo_service.get_repository(&owner, &name).await {
-        Ok(repo) => repo,
-        Err(err) => return Ok(error_response(&err, 404)),
+    let (repo, current_user, _) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
     };
     
     match repo_service.is_repository_starred(&current_user.id, &repo.id).await {
@@ -200,6 +250,66 @@ pub async fn check_star_status(
     }
 }
 
+pub async fn watch_repo(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let (owner, name) = path.into_inner();
+    
+    let (repo, current_user, _) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+    
+    match notification_service.watch_repository(&current_user.id, &repo.id).await {
+        Ok(_) => Ok(success_response("Repository watched successfully")),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn unwatch_repo(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let (owner, name) = path.into_inner();
+    
+    let (repo, current_user, _) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+    
+    match notification_service.unwatch_repository(&current_user.id, &repo.id).await {
+        Ok(_) => Ok(success_response("Repository unwatched successfully")),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn check_watch_status(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    notification_service: web::Data<NotificationService>,
+) -> Result<HttpResponse> {
+    let (owner, name) = path.into_inner();
+    
+    let (repo, current_user, _) = match find_repository_for_user(&req, &repo_service, &permission_service, &owner, &name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+    
+    match notification_service.is_watching(&current_user.id, &repo.id).await {
+        Ok(is_watching) => Ok(success_response(serde_json::json!({ "watching": is_watching }))),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
 pub fn repo_routes() -> actix_web::Scope {
     web::scope("/repos")
         .route("", web::get().to(list_repos))
@@ -210,4 +320,7 @@ pub fn repo_routes() -> actix_web::Scope {
         .route("/{owner}/{repo}/star", web::put().to(star_repo))
         .route("/{owner}/{repo}/star", web::delete().to(unstar_repo))
         .route("/{owner}/{repo}/star", web::get().to(check_star_status))
+        .route("/{owner}/{repo}/watch", web::put().to(watch_repo))
+        .route("/{owner}/{repo}/watch", web::delete().to(unwatch_repo))
+        .route("/{owner}/{repo}/watch", web::get().to(check_watch_status))
 }
@@ -0,0 +1,81 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use crate::services::SshKeyService;
+use crate::models::CreateSshKeyRequest;
+use crate::handlers::access_tokens::session_user;
+use crate::utils::response::{success_response, error_response};
+
+pub async fn list_keys(
+    req: HttpRequest,
+    ssh_key_service: web::Data<SshKeyService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match ssh_key_service.list_keys(&current_user.id).await {
+        Ok(keys) => Ok(success_response(keys)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn get_key(
+    req: HttpRequest,
+    path: web::Path<String>,
+    ssh_key_service: web::Data<SshKeyService>,
+) -> Result<HttpResponse> {
+    let key_id = path.into_inner();
+
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match ssh_key_service.get_key(&current_user.id, &key_id).await {
+        Ok(key) => Ok(success_response(key)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn add_key(
+    req: HttpRequest,
+    json: web::Json<CreateSshKeyRequest>,
+    ssh_key_service: web::Data<SshKeyService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match ssh_key_service.add_key(&current_user.id, &json.into_inner()).await {
+        Ok(key) => Ok(success_response(key)),
+        Err(err) if err == "Key is already in use" => Ok(error_response(&err, 409)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn delete_key(
+    req: HttpRequest,
+    path: web::Path<String>,
+    ssh_key_service: web::Data<SshKeyService>,
+) -> Result<HttpResponse> {
+    let key_id = path.into_inner();
+
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match ssh_key_service.delete_key(&current_user.id, &key_id).await {
+        Ok(_) => Ok(success_response("SSH key deleted")),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub fn ssh_key_routes() -> actix_web::Scope {
+    web::scope("/user/keys")
+        .route("", web::get().to(list_keys))
+        .route("", web::post().to(add_key))
+        .route("/{key_id}", web::get().to(get_key))
+        .route("/{key_id}", web::delete().to(delete_key))
+}
@@ -0,0 +1,274 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use serde::Deserialize;
+use crate::services::{CommitStatusService, GitService, PermissionService, RepositoryService};
+use crate::models::{CreateCheckRunRequest, CreateCommitStatusRequest, Permission, Repository, UpdateCheckRunRequest};
+use crate::handlers::repositories::{find_readable_repository, find_repository_for_user};
+use crate::utils::pagination::page_bounds;
+use crate::utils::response::{success_response, error_response};
+
+fn status_error_response(err: &str) -> HttpResponse {
+    match err {
+        "Check run not found" => error_response(err, 404),
+        err if err.starts_with("Database error") || err.starts_with("Transaction") => error_response(err, 500),
+        err => error_response(err, 400),
+    }
+}
+
+// Statuses and check runs are reported by anyone with write access, usually a CI bot account
+async fn find_repository_for_reporter(
+    req: &HttpRequest,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+    owner: &str,
+    name: &str,
+) -> Result<(Repository, String), HttpResponse> {
+    let (repo, current_user, permission) =
+        find_repository_for_user(req, repo_service, permission_service, owner, name).await?;
+
+    if permission < Permission::Write {
+        return Err(error_response("Insufficient permissions to report statuses", 403));
+    }
+
+    Ok((repo, current_user.id))
+}
+
+// Branch and tag names are accepted wherever a sha is, and resolved to the commit they point at
+async fn resolve_commit(git_service: &GitService, repo: &Repository, reference: &str) -> Result<String, HttpResponse> {
+    let (repo_id, reference) = (repo.id.clone(), reference.to_string());
+
+    git_service
+        .blocking(move |git| git.resolve_sha(&repo_id, &reference))
+        .await
+        .map_err(|err| error_response(&err, 404))
+}
+
+#[derive(Deserialize)]
+pub struct StatusesQuery {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+#[derive(Deserialize)]
+pub struct CheckRunsQuery {
+    pub filter: Option<String>, // "latest" (default) or "all"
+}
+
+pub async fn create_status(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    json: web::Json<CreateCommitStatusRequest>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+    status_service: web::Data<CommitStatusService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, reference) = path.into_inner();
+
+    let (repo, user_id) = match find_repository_for_reporter(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    let sha = match resolve_commit(&git_service, &repo, &reference).await {
+        Ok(sha) => sha,
+        Err(response) => return Ok(response),
+    };
+
+    match status_service.create_status(&repo.id, &sha, &user_id, &json).await {
+        Ok(status) => Ok(success_response(status)),
+        Err(err) => Ok(status_error_response(&err)),
+    }
+}
+
+pub async fn list_statuses(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    query: web::Query<StatusesQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+    status_service: web::Data<CommitStatusService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, reference) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let sha = match resolve_commit(&git_service, &repo, &reference).await {
+        Ok(sha) => sha,
+        Err(response) => return Ok(response),
+    };
+
+    let (limit, offset) = page_bounds(query.page, query.per_page);
+
+    match status_service.list_statuses(&repo.id, &sha, limit, offset).await {
+        Ok(statuses) => Ok(success_response(statuses)),
+        Err(err) => Ok(status_error_response(&err)),
+    }
+}
+
+pub async fn get_combined_status(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+    status_service: web::Data<CommitStatusService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, reference) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let sha = match resolve_commit(&git_service, &repo, &reference).await {
+        Ok(sha) => sha,
+        Err(response) => return Ok(response),
+    };
+
+    match status_service.combined_status(&repo.id, &sha).await {
+        Ok(combined) => Ok(success_response(combined)),
+        Err(err) => Ok(status_error_response(&err)),
+    }
+}
+
+pub async fn list_check_runs_for_ref(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    query: web::Query<CheckRunsQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+    status_service: web::Data<CommitStatusService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, reference) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let sha = match resolve_commit(&git_service, &repo, &reference).await {
+        Ok(sha) => sha,
+        Err(response) => return Ok(response),
+    };
+
+    let latest_only = match query.filter.as_deref() {
+        None | Some("latest") => true,
+        Some("all") => false,
+        Some(_) => return Ok(error_response("filter must be one of: latest, all", 400)),
+    };
+
+    match status_service.list_check_runs(&repo.id, &sha, latest_only).await {
+        Ok(runs) => Ok(success_response(runs)),
+        Err(err) => Ok(status_error_response(&err)),
+    }
+}
+
+pub async fn create_check_run(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    json: web::Json<CreateCheckRunRequest>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    git_service: web::Data<GitService>,
+    status_service: web::Data<CommitStatusService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    let (repo, user_id) = match find_repository_for_reporter(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    let head_sha = match resolve_commit(&git_service, &repo, &json.head_sha).await {
+        Ok(sha) => sha,
+        Err(response) => return Ok(response),
+    };
+
+    match status_service.create_check_run(&repo.id, &head_sha, &user_id, &json).await {
+        Ok(run) => Ok(success_response(run)),
+        Err(err) => Ok(status_error_response(&err)),
+    }
+}
+
+pub async fn get_check_run(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    status_service: web::Data<CommitStatusService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, check_run_id) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    match status_service.get_check_run(&repo.id, &check_run_id).await {
+        Ok(run) => Ok(success_response(run)),
+        Err(err) => Ok(status_error_response(&err)),
+    }
+}
+
+pub async fn update_check_run(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    json: web::Json<UpdateCheckRunRequest>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    status_service: web::Data<CommitStatusService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, check_run_id) = path.into_inner();
+
+    let (repo, _) = match find_repository_for_reporter(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    match status_service.update_check_run(&repo.id, &check_run_id, &json).await {
+        Ok(run) => Ok(success_response(run)),
+        Err(err) => Ok(status_error_response(&err)),
+    }
+}
+
+pub async fn list_annotations(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    query: web::Query<StatusesQuery>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    status_service: web::Data<CommitStatusService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, check_run_id) = path.into_inner();
+
+    let repo = match find_readable_repository(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok(repo) => repo,
+        Err(response) => return Ok(response),
+    };
+
+    let (limit, offset) = page_bounds(query.page, query.per_page);
+
+    match status_service.list_annotations(&repo.id, &check_run_id, limit, offset).await {
+        Ok(annotations) => Ok(success_response(annotations)),
+        Err(err) => Ok(status_error_response(&err)),
+    }
+}
+
+pub fn statuses_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/statuses")
+        .route("/{sha}", web::post().to(create_status))
+        .route("/{sha}", web::get().to(list_statuses))
+}
+
+pub fn check_runs_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/check-runs")
+        .route("", web::post().to(create_check_run))
+        .route("/{check_run_id}", web::get().to(get_check_run))
+        .route("/{check_run_id}", web::patch().to(update_check_run))
+        .route("/{check_run_id}/annotations", web::get().to(list_annotations))
+}
@@ -0,0 +1,394 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use crate::services::{OrganizationService, RepositoryService, TeamService, UserService};
+use crate::models::{
+    Organization, OrgRole, Team, TeamRole, CreateTeamRequest, UpdateTeamRequest, UpdateTeamMembershipRequest,
+    UpdateTeamRepositoryRequest,
+};
+use crate::utils::jwt::extract_user_from_token;
+use crate::utils::response::{success_response, error_response};
+
+// Resolves an organization for a member of it, along with the member's role
+async fn find_organization(
+    req: &HttpRequest,
+    org_service: &OrganizationService,
+    name: &str,
+) -> Result<(Organization, String, OrgRole), HttpResponse> {
+    let current_user = extract_user_from_token(req).map_err(|err| error_response(&err, 401))?;
+
+    let org = org_service
+        .get_organization(name)
+        .await
+        .map_err(|err| error_response(&err, 404))?;
+
+    let role = org_service
+        .get_active_membership(&org.id, &current_user.id)
+        .await
+        .map_err(|err| error_response(&err, 500))?
+        .ok_or_else(|| error_response("You are not a member of this organization", 403))?;
+
+    Ok((org, current_user.id, role))
+}
+
+// Org owners manage every team; team maintainers manage their own team's members
+async fn find_manageable_team(
+    req: &HttpRequest,
+    org_service: &OrganizationService,
+    team_service: &TeamService,
+    name: &str,
+    slug: &str,
+    allow_maintainers: bool,
+) -> Result<(Organization, Team), HttpResponse> {
+    let (org, user_id, role) = find_organization(req, org_service, name).await?;
+
+    let team = team_service
+        .get_team(&org.id, slug)
+        .await
+        .map_err(|err| error_response(&err, 404))?;
+
+    if role == OrgRole::Owner {
+        return Ok((org, team));
+    }
+
+    let team_role = team_service
+        .get_member_role(&team.id, &user_id)
+        .await
+        .map_err(|err| error_response(&err, 500))?;
+
+    if allow_maintainers && team_role == Some(TeamRole::Maintainer) {
+        return Ok((org, team));
+    }
+
+    Err(error_response("Insufficient permissions to manage this team", 403))
+}
+
+pub async fn list_teams(
+    req: HttpRequest,
+    path: web::Path<String>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    let (org, _, _) = match find_organization(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match team_service.list_teams(&org.id).await {
+        Ok(teams) => Ok(success_response(teams)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn create_team(
+    req: HttpRequest,
+    path: web::Path<String>,
+    json: web::Json<CreateTeamRequest>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    let (org, user_id, role) = match find_organization(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    if role != OrgRole::Owner {
+        return Ok(error_response("Only organization owners can create teams", 403));
+    }
+
+    let team = match team_service.create_team(&org.id, &json.into_inner()).await {
+        Ok(team) => team,
+        Err(err) => return Ok(error_response(&err, 400)),
+    };
+
+    // The creator maintains the new team
+    match team_service.set_member(&team.id, &user_id, TeamRole::Maintainer).await {
+        Ok(_) => Ok(success_response(team)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn get_team(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let (name, slug) = path.into_inner();
+
+    let (org, _, _) = match find_organization(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match team_service.get_team(&org.id, &slug).await {
+        Ok(team) => Ok(success_response(team)),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn update_team(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    json: web::Json<UpdateTeamRequest>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let (name, slug) = path.into_inner();
+
+    let (_, team) = match find_manageable_team(&req, &org_service, &team_service, &name, &slug, false).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match team_service.update_team(&team, &json.into_inner()).await {
+        Ok(team) => Ok(success_response(team)),
+        Err(err) if err == "Team not found" => Ok(error_response(&err, 404)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn delete_team(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let (name, slug) = path.into_inner();
+
+    let (_, team) = match find_manageable_team(&req, &org_service, &team_service, &name, &slug, false).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    match team_service.delete_team(&team).await {
+        Ok(_) => Ok(success_response("Team deleted successfully")),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn list_child_teams(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let (name, slug) = path.into_inner();
+
+    let (org, _, _) = match find_organization(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let team = match team_service.get_team(&org.id, &slug).await {
+        Ok(team) => team,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match team_service.list_child_teams(&team.id).await {
+        Ok(teams) => Ok(success_response(teams)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn list_team_members(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let (name, slug) = path.into_inner();
+
+    let (org, _, _) = match find_organization(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let team = match team_service.get_team(&org.id, &slug).await {
+        Ok(team) => team,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match team_service.list_members(&team.id).await {
+        Ok(members) => Ok(success_response(members)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn set_team_member(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    json: Option<web::Json<UpdateTeamMembershipRequest>>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+    user_service: web::Data<UserService>,
+) -> Result<HttpResponse> {
+    let (name, slug, username) = path.into_inner();
+
+    let (org, team) = match find_manageable_team(&req, &org_service, &team_service, &name, &slug, true).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let user = match user_service.get_user_by_username(&username).await {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match org_service.get_membership(&org.id, &user.id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Ok(error_response("Only organization members can join its teams", 400)),
+        Err(err) => return Ok(error_response(&err, 500)),
+    }
+
+    let role = json.map(|json| json.role).unwrap_or_default();
+
+    if let Err(err) = team_service.set_member(&team.id, &user.id, role).await {
+        return Ok(error_response(&err, 500));
+    }
+
+    match team_service.list_members(&team.id).await {
+        Ok(members) => Ok(success_response(members.into_iter().find(|member| member.user_id == user.id))),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn remove_team_member(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+    user_service: web::Data<UserService>,
+) -> Result<HttpResponse> {
+    let (name, slug, username) = path.into_inner();
+
+    let (_, team) = match find_manageable_team(&req, &org_service, &team_service, &name, &slug, true).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let user = match user_service.get_user_by_username(&username).await {
+        Ok(user) => user,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match team_service.remove_member(&team.id, &user.id).await {
+        Ok(_) => Ok(success_response("Team member removed successfully")),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub async fn list_team_repos(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+) -> Result<HttpResponse> {
+    let (name, slug) = path.into_inner();
+
+    let (org, _, _) = match find_organization(&req, &org_service, &name).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let team = match team_service.get_team(&org.id, &slug).await {
+        Ok(team) => team,
+        Err(err) => return Ok(error_response(&err, 404)),
+    };
+
+    match team_service.list_repositories(&team.id).await {
+        Ok(repos) => Ok(success_response(repos)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+// Only repositories owned by the team's organization can be granted
+async fn find_org_repository(
+    repo_service: &RepositoryService,
+    org: &Organization,
+    repo_name: &str,
+) -> Result<String, HttpResponse> {
+    let repo = repo_service
+        .get_repository(&org.name, repo_name)
+        .await
+        .map_err(|err| error_response(&err, 404))?;
+
+    if repo.organization_id.as_deref() != Some(org.id.as_str()) {
+        return Err(error_response("Repository not found", 404));
+    }
+
+    Ok(repo.id)
+}
+
+pub async fn set_team_repo(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    json: web::Json<UpdateTeamRepositoryRequest>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+    repo_service: web::Data<RepositoryService>,
+) -> Result<HttpResponse> {
+    let (name, slug, repo_name) = path.into_inner();
+
+    let (org, team) = match find_manageable_team(&req, &org_service, &team_service, &name, &slug, false).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let repo_id = match find_org_repository(&repo_service, &org, &repo_name).await {
+        Ok(repo_id) => repo_id,
+        Err(response) => return Ok(response),
+    };
+
+    if let Err(err) = team_service.set_repository_permission(&team.id, &repo_id, json.permission).await {
+        return Ok(error_response(&err, 500));
+    }
+
+    match team_service.list_repositories(&team.id).await {
+        Ok(repos) => Ok(success_response(repos.into_iter().find(|repo| repo.repository_id == repo_id))),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn remove_team_repo(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    org_service: web::Data<OrganizationService>,
+    team_service: web::Data<TeamService>,
+    repo_service: web::Data<RepositoryService>,
+) -> Result<HttpResponse> {
+    let (name, slug, repo_name) = path.into_inner();
+
+    let (org, team) = match find_manageable_team(&req, &org_service, &team_service, &name, &slug, false).await {
+        Ok(resolved) => resolved,
+        Err(response) => return Ok(response),
+    };
+
+    let repo_id = match find_org_repository(&repo_service, &org, &repo_name).await {
+        Ok(repo_id) => repo_id,
+        Err(response) => return Ok(response),
+    };
+
+    match team_service.remove_repository(&team.id, &repo_id).await {
+        Ok(_) => Ok(success_response("Repository removed from team")),
+        Err(err) => Ok(error_response(&err, 404)),
+    }
+}
+
+pub fn team_routes() -> actix_web::Scope {
+    web::scope("/orgs/{org}/teams")
+        .route("", web::get().to(list_teams))
+        .route("", web::post().to(create_team))
+        .route("/{team_slug}", web::get().to(get_team))
+        .route("/{team_slug}", web::patch().to(update_team))
+        .route("/{team_slug}", web::delete().to(delete_team))
+        .route("/{team_slug}/teams", web::get().to(list_child_teams))
+        .route("/{team_slug}/members", web::get().to(list_team_members))
+        .route("/{team_slug}/members/{username}", web::put().to(set_team_member))
+        .route("/{team_slug}/members/{username}", web::delete().to(remove_team_member))
+        .route("/{team_slug}/repos", web::get().to(list_team_repos))
+        .route("/{team_slug}/repos/{repo}", web::put().to(set_team_repo))
+        .route("/{team_slug}/repos/{repo}", web::delete().to(remove_team_repo))
+}
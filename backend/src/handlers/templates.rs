@@ -0,0 +1,63 @@
+use actix_web::{web, HttpResponse, Result};
+use serde_json::json;
+use crate::utils::templates::{
+    find_gitignore_template, find_license_template, GITIGNORE_TEMPLATES, LICENSE_TEMPLATES,
+};
+use crate::utils::response::{success_response, error_response};
+
+pub async fn list_gitignore_templates() -> Result<HttpResponse> {
+    let names: Vec<&str> = GITIGNORE_TEMPLATES.iter().map(|t| t.name).collect();
+
+    Ok(success_response(names))
+}
+
+pub async fn get_gitignore_template(path: web::Path<String>) -> Result<HttpResponse> {
+    let name = path.into_inner();
+
+    match find_gitignore_template(&name) {
+        Some(template) => Ok(success_response(json!({
+            "name": template.name,
+            "source": template.source,
+        }))),
+        None => Ok(error_response("Gitignore template not found", 404)),
+    }
+}
+
+pub async fn list_licenses() -> Result<HttpResponse> {
+    let licenses: Vec<_> = LICENSE_TEMPLATES
+        .iter()
+        .map(|t| json!({
+            "key": t.key,
+            "name": t.name,
+            "spdx_id": t.spdx_id,
+        }))
+        .collect();
+
+    Ok(success_response(licenses))
+}
+
+pub async fn get_license(path: web::Path<String>) -> Result<HttpResponse> {
+    let key = path.into_inner();
+
+    match find_license_template(&key) {
+        Some(template) => Ok(success_response(json!({
+            "key": template.key,
+            "name": template.name,
+            "spdx_id": template.spdx_id,
+            "body": template.body,
+        }))),
+        None => Ok(error_response("License not found", 404)),
+    }
+}
+
+pub fn gitignore_routes() -> actix_web::Scope {
+    web::scope("/gitignore/templates")
+        .route("", web::get().to(list_gitignore_templates))
+        .route("/{name}", web::get().to(get_gitignore_template))
+}
+
+pub fn license_routes() -> actix_web::Scope {
+    web::scope("/licenses")
+        .route("", web::get().to(list_licenses))
+        .route("/{license}", web::get().to(get_license))
+}
@@ -0,0 +1,92 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use crate::services::TwoFactorService;
+use crate::models::TwoFactorCodeRequest;
+use crate::handlers::access_tokens::session_user;
+use crate::utils::response::{success_response, error_response};
+
+pub async fn get_status(
+    req: HttpRequest,
+    two_factor_service: web::Data<TwoFactorService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match two_factor_service.get_status(&current_user.id).await {
+        Ok(status) => Ok(success_response(status)),
+        Err(err) => Ok(error_response(&err, 500)),
+    }
+}
+
+pub async fn begin_enrollment(
+    req: HttpRequest,
+    two_factor_service: web::Data<TwoFactorService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match two_factor_service.begin_enrollment(&current_user.id, &current_user.username).await {
+        Ok(enrollment) => Ok(success_response(enrollment)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn confirm_enrollment(
+    req: HttpRequest,
+    json: web::Json<TwoFactorCodeRequest>,
+    two_factor_service: web::Data<TwoFactorService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match two_factor_service.confirm_enrollment(&current_user.id, &json.code).await {
+        Ok(recovery_codes) => Ok(success_response(recovery_codes)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn disable(
+    req: HttpRequest,
+    json: web::Json<TwoFactorCodeRequest>,
+    two_factor_service: web::Data<TwoFactorService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match two_factor_service.disable(&current_user.id, &json.code).await {
+        Ok(_) => Ok(success_response("Two-factor authentication disabled")),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub async fn regenerate_recovery_codes(
+    req: HttpRequest,
+    json: web::Json<TwoFactorCodeRequest>,
+    two_factor_service: web::Data<TwoFactorService>,
+) -> Result<HttpResponse> {
+    let current_user = match session_user(&req) {
+        Ok(user) => user,
+        Err(response) => return Ok(*response),
+    };
+
+    match two_factor_service.regenerate_recovery_codes(&current_user.id, &json.code).await {
+        Ok(recovery_codes) => Ok(success_response(recovery_codes)),
+        Err(err) => Ok(error_response(&err, 400)),
+    }
+}
+
+pub fn two_factor_routes() -> actix_web::Scope {
+    web::scope("/user/2fa")
+        .route("", web::get().to(get_status))
+        .route("/enroll", web::post().to(begin_enrollment))
+        .route("/confirm", web::post().to(confirm_enrollment))
+        .route("/disable", web::post().to(disable))
+        .route("/recovery_codes", web::post().to(regenerate_recovery_codes))
+}
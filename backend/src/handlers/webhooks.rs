@@ -0,0 +1,398 @@
+use actix_web::{web, HttpResponse, Result, HttpRequest};
+use crate::services::{OrganizationService, PermissionService, RepositoryService, WebhookService};
+use crate::services::webhook_service::WebhookOwner;
+use crate::models::{CreateWebhookRequest, Permission, UpdateWebhookRequest};
+use crate::handlers::organizations::find_organization_as_owner;
+use crate::handlers::repositories::find_repository_for_user;
+use crate::utils::response::{success_response, error_response};
+
+fn webhook_error_response(err: &str) -> HttpResponse {
+    match err {
+        "Webhook not found" | "Delivery not found" => error_response(err, 404),
+        err if err.starts_with("Database error") => error_response(err, 500),
+        err => error_response(err, 400),
+    }
+}
+
+// Repository hooks are managed by repository admins
+async fn repository_hooks(
+    req: &HttpRequest,
+    repo_service: &RepositoryService,
+    permission_service: &PermissionService,
+    owner: &str,
+    name: &str,
+) -> Result<(WebhookOwner, String), HttpResponse> {
+    let (repo, current_user, permission) =
+        find_repository_for_user(req, repo_service, permission_service, owner, name).await?;
+
+    if permission < Permission::Admin {
+        return Err(error_response("Only repository admins can manage webhooks", 403));
+    }
+
+    Ok((WebhookOwner::Repository(repo.id), current_user.id))
+}
+
+// Organization hooks are managed by organization owners
+async fn organization_hooks(
+    req: &HttpRequest,
+    org_service: &OrganizationService,
+    name: &str,
+) -> Result<(WebhookOwner, String), HttpResponse> {
+    let (org, user_id) = find_organization_as_owner(req, org_service, name).await?;
+
+    Ok((WebhookOwner::Organization(org.id), user_id))
+}
+
+async fn list_hooks(owner: WebhookOwner, webhook_service: &WebhookService) -> HttpResponse {
+    match webhook_service.list_webhooks(&owner).await {
+        Ok(hooks) => success_response(hooks),
+        Err(err) => webhook_error_response(&err),
+    }
+}
+
+async fn create_hook(owner: WebhookOwner, user_id: &str, request: &CreateWebhookRequest, webhook_service: &WebhookService) -> HttpResponse {
+    match webhook_service.create_webhook(&owner, user_id, request).await {
+        Ok(hook) => success_response(hook),
+        Err(err) => webhook_error_response(&err),
+    }
+}
+
+async fn get_hook(owner: WebhookOwner, hook_id: &str, webhook_service: &WebhookService) -> HttpResponse {
+    match webhook_service.get_webhook(&owner, hook_id).await {
+        Ok(hook) => success_response(hook),
+        Err(err) => webhook_error_response(&err),
+    }
+}
+
+async fn update_hook(owner: WebhookOwner, hook_id: &str, request: &UpdateWebhookRequest, webhook_service: &WebhookService) -> HttpResponse {
+    match webhook_service.update_webhook(&owner, hook_id, request).await {
+        Ok(hook) => success_response(hook),
+        Err(err) => webhook_error_response(&err),
+    }
+}
+
+async fn delete_hook(owner: WebhookOwner, hook_id: &str, webhook_service: &WebhookService) -> HttpResponse {
+    match webhook_service.delete_webhook(&owner, hook_id).await {
+        Ok(_) => success_response("Webhook deleted"),
+        Err(err) => webhook_error_response(&err),
+    }
+}
+
+async fn ping_hook(owner: WebhookOwner, hook_id: &str, user_id: &str, webhook_service: &WebhookService) -> HttpResponse {
+    match webhook_service.ping(&owner, hook_id, user_id).await {
+        Ok(delivery) => success_response(delivery),
+        Err(err) => webhook_error_response(&err),
+    }
+}
+
+async fn list_hook_deliveries(owner: WebhookOwner, hook_id: &str, webhook_service: &WebhookService) -> HttpResponse {
+    match webhook_service.list_deliveries(&owner, hook_id).await {
+        Ok(deliveries) => success_response(deliveries),
+        Err(err) => webhook_error_response(&err),
+    }
+}
+
+async fn get_hook_delivery(owner: WebhookOwner, hook_id: &str, delivery_id: &str, webhook_service: &WebhookService) -> HttpResponse {
+    match webhook_service.get_delivery(&owner, hook_id, delivery_id).await {
+        Ok(delivery) => success_response(delivery),
+        Err(err) => webhook_error_response(&err),
+    }
+}
+
+async fn redeliver_hook_delivery(owner: WebhookOwner, hook_id: &str, delivery_id: &str, webhook_service: &WebhookService) -> HttpResponse {
+    match webhook_service.redeliver(&owner, hook_id, delivery_id).await {
+        Ok(delivery) => success_response(delivery),
+        Err(err) => webhook_error_response(&err),
+    }
+}
+
+pub async fn list_repo_hooks(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    match repository_hooks(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((hooks, _)) => Ok(list_hooks(hooks, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn create_repo_hook(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    json: web::Json<CreateWebhookRequest>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name) = path.into_inner();
+
+    match repository_hooks(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((hooks, user_id)) => Ok(create_hook(hooks, &user_id, &json, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn get_repo_hook(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, hook_id) = path.into_inner();
+
+    match repository_hooks(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((hooks, _)) => Ok(get_hook(hooks, &hook_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn update_repo_hook(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    json: web::Json<UpdateWebhookRequest>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, hook_id) = path.into_inner();
+
+    match repository_hooks(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((hooks, _)) => Ok(update_hook(hooks, &hook_id, &json, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn delete_repo_hook(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, hook_id) = path.into_inner();
+
+    match repository_hooks(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((hooks, _)) => Ok(delete_hook(hooks, &hook_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn ping_repo_hook(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, hook_id) = path.into_inner();
+
+    match repository_hooks(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((hooks, user_id)) => Ok(ping_hook(hooks, &hook_id, &user_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn list_repo_hook_deliveries(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, hook_id) = path.into_inner();
+
+    match repository_hooks(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((hooks, _)) => Ok(list_hook_deliveries(hooks, &hook_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn get_repo_hook_delivery(
+    req: HttpRequest,
+    path: web::Path<(String, String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, hook_id, delivery_id) = path.into_inner();
+
+    match repository_hooks(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((hooks, _)) => Ok(get_hook_delivery(hooks, &hook_id, &delivery_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn redeliver_repo_hook_delivery(
+    req: HttpRequest,
+    path: web::Path<(String, String, String, String)>,
+    repo_service: web::Data<RepositoryService>,
+    permission_service: web::Data<PermissionService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (owner, repo_name, hook_id, delivery_id) = path.into_inner();
+
+    match repository_hooks(&req, &repo_service, &permission_service, &owner, &repo_name).await {
+        Ok((hooks, _)) => Ok(redeliver_hook_delivery(hooks, &hook_id, &delivery_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn list_org_hooks(
+    req: HttpRequest,
+    path: web::Path<String>,
+    org_service: web::Data<OrganizationService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let org_name = path.into_inner();
+
+    match organization_hooks(&req, &org_service, &org_name).await {
+        Ok((hooks, _)) => Ok(list_hooks(hooks, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn create_org_hook(
+    req: HttpRequest,
+    path: web::Path<String>,
+    json: web::Json<CreateWebhookRequest>,
+    org_service: web::Data<OrganizationService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let org_name = path.into_inner();
+
+    match organization_hooks(&req, &org_service, &org_name).await {
+        Ok((hooks, user_id)) => Ok(create_hook(hooks, &user_id, &json, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn get_org_hook(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (org_name, hook_id) = path.into_inner();
+
+    match organization_hooks(&req, &org_service, &org_name).await {
+        Ok((hooks, _)) => Ok(get_hook(hooks, &hook_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn update_org_hook(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    json: web::Json<UpdateWebhookRequest>,
+    org_service: web::Data<OrganizationService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (org_name, hook_id) = path.into_inner();
+
+    match organization_hooks(&req, &org_service, &org_name).await {
+        Ok((hooks, _)) => Ok(update_hook(hooks, &hook_id, &json, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn delete_org_hook(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (org_name, hook_id) = path.into_inner();
+
+    match organization_hooks(&req, &org_service, &org_name).await {
+        Ok((hooks, _)) => Ok(delete_hook(hooks, &hook_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn ping_org_hook(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (org_name, hook_id) = path.into_inner();
+
+    match organization_hooks(&req, &org_service, &org_name).await {
+        Ok((hooks, user_id)) => Ok(ping_hook(hooks, &hook_id, &user_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn list_org_hook_deliveries(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    org_service: web::Data<OrganizationService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (org_name, hook_id) = path.into_inner();
+
+    match organization_hooks(&req, &org_service, &org_name).await {
+        Ok((hooks, _)) => Ok(list_hook_deliveries(hooks, &hook_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn get_org_hook_delivery(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    org_service: web::Data<OrganizationService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (org_name, hook_id, delivery_id) = path.into_inner();
+
+    match organization_hooks(&req, &org_service, &org_name).await {
+        Ok((hooks, _)) => Ok(get_hook_delivery(hooks, &hook_id, &delivery_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub async fn redeliver_org_hook_delivery(
+    req: HttpRequest,
+    path: web::Path<(String, String, String)>,
+    org_service: web::Data<OrganizationService>,
+    webhook_service: web::Data<WebhookService>,
+) -> Result<HttpResponse> {
+    let (org_name, hook_id, delivery_id) = path.into_inner();
+
+    match organization_hooks(&req, &org_service, &org_name).await {
+        Ok((hooks, _)) => Ok(redeliver_hook_delivery(hooks, &hook_id, &delivery_id, &webhook_service).await),
+        Err(response) => Ok(response),
+    }
+}
+
+pub fn repository_webhook_routes() -> actix_web::Scope {
+    web::scope("/repos/{owner}/{repo}/hooks")
+        .route("", web::get().to(list_repo_hooks))
+        .route("", web::post().to(create_repo_hook))
+        .route("/{hook_id}", web::get().to(get_repo_hook))
+        .route("/{hook_id}", web::patch().to(update_repo_hook))
+        .route("/{hook_id}", web::delete().to(delete_repo_hook))
+        .route("/{hook_id}/pings", web::post().to(ping_repo_hook))
+        .route("/{hook_id}/deliveries", web::get().to(list_repo_hook_deliveries))
+        .route("/{hook_id}/deliveries/{delivery_id}", web::get().to(get_repo_hook_delivery))
+        .route("/{hook_id}/deliveries/{delivery_id}/attempts", web::post().to(redeliver_repo_hook_delivery))
+}
+
+pub fn organization_webhook_routes() -> actix_web::Scope {
+    web::scope("/orgs/{org}/hooks")
+        .route("", web::get().to(list_org_hooks))
+        .route("", web::post().to(create_org_hook))
+        .route("/{hook_id}", web::get().to(get_org_hook))
+        .route("/{hook_id}", web::patch().to(update_org_hook))
+        .route("/{hook_id}", web::delete().to(delete_org_hook))
+        .route("/{hook_id}/pings", web::post().to(ping_org_hook))
+        .route("/{hook_id}/deliveries", web::get().to(list_org_hook_deliveries))
+        .route("/{hook_id}/deliveries/{delivery_id}", web::get().to(get_org_hook_delivery))
+        .route("/{hook_id}/deliveries/{delivery_id}/attempts", web::post().to(redeliver_org_hook_delivery))
+}
@@ -9,9 +9,12 @@ mod handlers;
 mod models;
 mod services;
 mod middleware;
+mod ssh_server;
 mod utils;
 
 use config::AppConfig;
+use middleware::auth::{BearerTokenAuth, RequireScope};
+use models::TokenScope;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -48,7 +51,75 @@ async fn main() -> std::io::Result<()> {
     let bind_address = format!("{}:{}", config.host, config.port);
 
     // Initialize services (start with minimal working set)
-    let auth_service = services::auth_service::AuthService::new(pool.clone(), config.jwt_secret.clone());
+    let token_service = services::access_token_service::AccessTokenService::new(pool.clone());
+    let oauth_service = services::oauth_service::OAuthService::new(pool.clone());
+    let auth_service = services::auth_service::AuthService::new(pool.clone(), token_service.clone(), oauth_service.clone());
+    let git_service = services::git_service::GitService::new(config.git_storage_path.clone(), config.git_max_request_mb * 1024 * 1024);
+    git_service.install_hooks().expect("Failed to install git hooks");
+    let repo_service = services::repository_service::RepositoryService::new(pool.clone(), git_service.clone());
+    let status_service = services::commit_status_service::CommitStatusService::new(pool.clone());
+    let user_service = services::user_service::UserService::new(pool.clone());
+    let issue_service = services::issue_service::IssueService::new(pool.clone());
+    let comment_service = services::comment_service::CommentService::new(pool.clone());
+    let protection_service = services::branch_protection_service::BranchProtectionService::new(pool.clone(), status_service.clone());
+    let pr_service = services::pull_requests_service::PullRequestService::new(pool.clone(), git_service.clone(), status_service.clone(), protection_service.clone());
+    let org_service = services::organization_service::OrganizationService::new(pool.clone(), git_service.clone());
+    let team_service = services::team_service::TeamService::new(pool.clone());
+    let collaborator_service = services::collaborator_service::CollaboratorService::new(pool.clone());
+    let permission_service = services::permission_service::PermissionService::new(org_service.clone(), team_service.clone(), collaborator_service.clone());
+    let ssh_key_service = services::ssh_key_service::SshKeyService::new(pool.clone());
+
+    // Webhook deliveries that failed are retried in the background
+    let webhook_service = services::webhook_service::WebhookService::new(pool.clone(), git_service.clone(), config.webhook_allowed_hosts.clone());
+    webhook_service.spawn_retry_worker();
+
+    // Pipeline jobs whose runner stopped sending heartbeats are failed in the background
+    let artifact_store = services::object_storage::object_store_from_config(&config).expect("Invalid artifact storage configuration");
+    let pipeline_service = services::pipeline_service::PipelineService::new(pool.clone(), git_service.clone(), status_service.clone(), artifact_store);
+    pipeline_service.spawn_lease_worker();
+
+    // Sessions, refresh tokens and the access token denylist live in Redis
+    let redis_client = redis::Client::open(config.redis_url.clone()).expect("Invalid REDIS_URL");
+    let redis_connection = redis::aio::ConnectionManager::new(redis_client.clone())
+        .await
+        .expect("Failed to connect to Redis");
+    let session_service = services::session_service::SessionService::new(redis_connection.clone(), config.jwt_secret.clone());
+
+    // Live events fan out through Redis pub/sub so clients on any instance receive them
+    let event_service = services::event_service::EventService::new(redis_connection.clone(), redis_client.clone());
+    event_service.spawn_relay();
+
+    let mail_service = services::mail_service::MailService::from_config(&config).expect("Invalid mail configuration");
+    let account_service = services::account_service::AccountService::new(pool.clone(), auth_service.clone(), mail_service.clone(), config.jwt_secret.clone(), config.public_url.clone());
+
+    // Unread notifications are also summarized by email every NOTIFICATION_DIGEST_MINUTES
+    let notification_service = services::notification_service::NotificationService::new(pool.clone(), permission_service.clone(), mail_service, config.public_url.clone());
+    if config.notification_digest_minutes > 0 {
+        notification_service.spawn_digest_worker(std::time::Duration::from_secs(config.notification_digest_minutes * 60));
+    }
+
+    // Handlers report each action once; this fans it out to events, webhooks, pipelines and notifications
+    let activity_service = services::activity_service::ActivityService::new(event_service.clone(), webhook_service.clone(), pipeline_service.clone(), notification_service.clone());
+
+    let two_factor_service = services::two_factor_service::TwoFactorService::new(pool.clone(), config.jwt_secret.clone());
+    let oidc_service = services::oidc_service::OidcService::new(config.oidc_providers.clone(), redis_connection.clone(), config.public_url.clone());
+
+    // Git over SSH runs alongside the HTTP server
+    let ssh_server = ssh_server::GitSshServer::new(
+        ssh_key_service.clone(),
+        repo_service.clone(),
+        git_service.clone(),
+        permission_service.clone(),
+        protection_service.clone(),
+        pr_service.clone(),
+        activity_service.clone(),
+    );
+    let (ssh_host, ssh_port, ssh_host_key_path) = (config.host.clone(), config.ssh_port, config.ssh_host_key_path.clone());
+    tokio::spawn(async move {
+        if let Err(err) = ssh_server::run(ssh_server, &ssh_host, ssh_port, &ssh_host_key_path).await {
+            log::error!("{}", err);
+        }
+    });
 
     HttpServer::new(move || {
         let cors = Cors::default()
@@ -61,13 +132,81 @@ async fn main() -> std::io::Result<()> {
             .app_data(web::Data::new(config.clone()))
             .app_data(web::Data::new(pool.clone()))
             .app_data(web::Data::new(auth_service.clone()))
+            .app_data(web::Data::new(git_service.clone()))
+            .app_data(web::Data::new(repo_service.clone()))
+            .app_data(web::Data::new(pr_service.clone()))
+            .app_data(web::Data::new(user_service.clone()))
+            .app_data(web::Data::new(issue_service.clone()))
+            .app_data(web::Data::new(comment_service.clone()))
+            .app_data(web::Data::new(protection_service.clone()))
+            .app_data(web::Data::new(org_service.clone()))
+            .app_data(web::Data::new(team_service.clone()))
+            .app_data(web::Data::new(collaborator_service.clone()))
+            .app_data(web::Data::new(permission_service.clone()))
+            .app_data(web::Data::new(token_service.clone()))
+            .app_data(web::Data::new(ssh_key_service.clone()))
+            .app_data(web::Data::new(oauth_service.clone()))
+            .app_data(web::Data::new(webhook_service.clone()))
+            .app_data(web::Data::new(status_service.clone()))
+            .app_data(web::Data::new(pipeline_service.clone()))
+            .app_data(web::Data::new(event_service.clone()))
+            .app_data(web::Data::new(notification_service.clone()))
+            .app_data(web::Data::new(activity_service.clone()))
+            .app_data(web::Data::new(session_service.clone()))
+            .app_data(web::Data::new(account_service.clone()))
+            .app_data(web::Data::new(two_factor_service.clone()))
+            .app_data(web::Data::new(oidc_service.clone()))
             .wrap(cors)
             .wrap(Logger::default())
             .service(
                 web::scope("/api/v1")
-                    .service(handlers::auth::auth_routes())
+                    // Resolves access tokens and sessions; each scope below declares the token scope it needs
+                    .wrap(BearerTokenAuth)
+                    .service(handlers::auth::auth_routes().wrap(RequireScope::new(TokenScope::ReadUser)))
+                    .service(handlers::access_tokens::access_token_routes())
+                    .service(handlers::ssh_keys::ssh_key_routes())
+                    .service(handlers::auth::session_routes())
+                    .service(handlers::two_factor::two_factor_routes())
+                    .service(handlers::auth::identity_routes())
+                    .service(handlers::oauth::oauth_application_routes())
+                    .service(handlers::oauth::oauth_routes())
+                    .service(handlers::events::event_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::notifications::notification_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    // Teams, hooks and runners are nested under /orgs/{org}, so they precede the organizations scope
+                    .service(handlers::teams::team_routes().wrap(RequireScope::new(TokenScope::AdminOrg)))
+                    .service(handlers::webhooks::organization_webhook_routes().wrap(RequireScope::new(TokenScope::AdminOrg)))
+                    .service(handlers::pipelines::organization_runner_routes().wrap(RequireScope::new(TokenScope::AdminOrg)))
+                    .service(handlers::organizations::organization_routes().wrap(RequireScope::new(TokenScope::AdminOrg)))
+                    .service(handlers::collaborators::user_repository_invitation_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::organizations::user_organization_routes().wrap(RequireScope::new(TokenScope::AdminOrg)))
+                    // Repository sub-resource scopes must precede the catch-all /repos scope
+                    .service(handlers::contents::contents_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::contents::raw_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::contents::git_data_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::commits::commits_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::branches::branches_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::branches::tags_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::pull_requests::pull_request_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::collaborators::collaborator_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::collaborators::repository_invitation_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::webhooks::repository_webhook_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::statuses::statuses_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::statuses::check_runs_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::pipelines::pipeline_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::pipelines::repository_runner_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    // Comment scopes are nested under /issues/{number}, so they precede the issues scope
+                    .service(handlers::comments::comment_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::notifications::thread_subscription_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::issues::issue_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::repositories::repo_routes().wrap(RequireScope::new(TokenScope::Repo)))
+                    .service(handlers::templates::gitignore_routes())
+                    .service(handlers::templates::license_routes())
             )
             .service(handlers::health::health_check)
+            // Runner agents authenticate with runner tokens, so the protocol sits outside /api/v1
+            .service(handlers::pipelines::runner_protocol_routes())
+            // Git smart HTTP must come last: its /{owner}/{repo} scope matches any two segments
+            .service(handlers::git_http::git_http_routes())
     })
     .bind(bind_address)?
     .run()
@@ -1,23 +1,194 @@
 use actix_web::{dev::ServiceRequest, Error, HttpMessage, web};
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceResponse, Transform};
 use actix_web_httpauth::extractors::bearer::{BearerAuth, Config};
 use actix_web_httpauth::extractors::AuthenticationError;
-use crate::services::AuthService;
-use crate::config::AppConfig;
-use sqlx::MySqlPool;
+use futures_util::future::LocalBoxFuture;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use crate::models::{SessionAuthentication, TokenAuthentication, TokenScope};
+use crate::services::{AccessTokenService, OAuthService, SessionService};
+use crate::services::access_token_service::is_access_token;
+use crate::services::oauth_service::is_oauth_access_token;
+use crate::utils::response::error_response;
 
+// Session access tokens must be validly signed, unexpired, and not on the Redis denylist
 pub async fn validator(req: ServiceRequest, credentials: BearerAuth) -> Result<ServiceRequest, (Error, ServiceRequest)> {
-    let pool = req.app_data::<web::Data<MySqlPool>>().unwrap();
-    let config = req.app_data::<web::Data<AppConfig>>().unwrap();
-    let auth_service = AuthService::new(pool.get_ref().clone(), config.jwt_secret.clone());
-    
-    match auth_service.validate_token(credentials.token()).await {
-        Ok(user) => {
-            req.extensions_mut().insert(user);
-            Ok(req)
+    let session_service = match req.app_data::<web::Data<SessionService>>().cloned() {
+        Some(session_service) => session_service,
+        None => return Err(unauthorized(req)),
+    };
+
+    let claims = match session_service.decode_access_token(credentials.token()) {
+        Ok(claims) => claims,
+        Err(_) => return Err(unauthorized(req)),
+    };
+
+    // Logged-out tokens and revoked sessions stay denied until the token expires; fail closed if Redis is down
+    match session_service.is_denied(&claims.jti, &claims.sid).await {
+        Ok(false) => {}
+        Ok(true) => return Err(unauthorized(req)),
+        Err(err) => {
+            log::error!("Failed to check the session denylist: {}", err);
+            return Err(unauthorized(req));
         }
-        Err(_) => {
-            let config = req.app_data::<Config>().cloned().unwrap_or_default();
-            Err((AuthenticationError::from(config).into(), req))
+    }
+
+    req.extensions_mut().insert(SessionAuthentication {
+        session_id: claims.sid,
+        jti: claims.jti,
+        user_id: claims.sub,
+        username: claims.username,
+        expires_at: claims.exp as i64,
+    });
+
+    Ok(req)
+}
+
+fn unauthorized(req: ServiceRequest) -> (Error, ServiceRequest) {
+    let config = req.app_data::<Config>().cloned().unwrap_or_default();
+    (AuthenticationError::from(config).into(), req)
+}
+
+fn bearer_access_token(req: &ServiceRequest) -> Option<String> {
+    let header = req.headers().get("Authorization")?.to_str().ok()?;
+    let token = header.strip_prefix("Bearer ")?;
+
+    (is_access_token(token) || is_oauth_access_token(token)).then(|| token.to_string())
+}
+
+/// Resolves `Authorization: Bearer` credentials. Personal access tokens and OAuth
+/// access tokens attach a `TokenAuthentication` and are rejected when invalid; session JWTs go through
+/// `validator` and attach a `SessionAuthentication`. A stale session token is not
+/// rejected here so that public endpoints such as login keep working; handlers
+/// that need a user report it through `extract_user_from_token`.
+pub struct BearerTokenAuth;
+
+impl<S, B> Transform<S, ServiceRequest> for BearerTokenAuth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = BearerTokenAuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(BearerTokenAuthMiddleware { service: Rc::new(service) }))
+    }
+}
+
+pub struct BearerTokenAuthMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for BearerTokenAuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            if let Some(token) = bearer_access_token(&req) {
+                let authentication = if is_oauth_access_token(&token) {
+                    match req.app_data::<web::Data<OAuthService>>().cloned() {
+                        Some(oauth_service) => oauth_service.authenticate(&token).await,
+                        None => Err("Access tokens are not supported".to_string()),
+                    }
+                } else {
+                    match req.app_data::<web::Data<AccessTokenService>>().cloned() {
+                        Some(token_service) => token_service.authenticate(&token).await,
+                        None => Err("Access tokens are not supported".to_string()),
+                    }
+                };
+
+                match authentication {
+                    Ok(authentication) => {
+                        req.extensions_mut().insert(authentication);
+                    }
+                    Err(err) => {
+                        return Ok(req.into_response(error_response(&err, 401)).map_into_right_body());
+                    }
+                }
+            } else if let Ok(credentials) = req.extract::<BearerAuth>().await {
+                req = match validator(req, credentials).await {
+                    Ok(req) => req,
+                    Err((_, req)) => req,
+                };
+            }
+
+            service.call(req).await.map(ServiceResponse::map_into_left_body)
+        })
+    }
+}
+
+/// Rejects requests made with a personal or OAuth access token that lacks `scope`.
+/// Session JWTs are not scoped and always pass.
+pub struct RequireScope {
+    scope: TokenScope,
+}
+
+impl RequireScope {
+    pub fn new(scope: TokenScope) -> Self {
+        Self { scope }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RequireScope
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RequireScopeMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequireScopeMiddleware { service, scope: self.scope }))
+    }
+}
+
+pub struct RequireScopeMiddleware<S> {
+    service: S,
+    scope: TokenScope,
+}
+
+impl<S, B> Service<ServiceRequest> for RequireScopeMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let missing_scope = req
+            .extensions()
+            .get::<TokenAuthentication>()
+            .is_some_and(|token| !token.has_scope(self.scope));
+
+        if missing_scope {
+            let message = format!("Access token is missing the '{}' scope", self.scope.as_str());
+            let response = req.into_response(error_response(message, 403)).map_into_right_body();
+            return Box::pin(ready(Ok(response)));
         }
+
+        let response = self.service.call(req);
+        Box::pin(async move { response.await.map(ServiceResponse::map_into_left_body) })
     }
 }
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// What a personal or OAuth access token may be used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TokenScope {
+    #[serde(rename = "repo")]
+    Repo,
+    #[serde(rename = "read:user")]
+    ReadUser,
+    #[serde(rename = "admin:org")]
+    AdminOrg,
+}
+
+impl TokenScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::Repo => "repo",
+            TokenScope::ReadUser => "read:user",
+            TokenScope::AdminOrg => "admin:org",
+        }
+    }
+
+    pub fn parse(scope: &str) -> Option<Self> {
+        match scope {
+            "repo" => Some(TokenScope::Repo),
+            "read:user" => Some(TokenScope::ReadUser),
+            "admin:org" => Some(TokenScope::AdminOrg),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccessToken {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    pub token_last_eight: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_used: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+// Returned once on creation; the plain token cannot be retrieved afterwards
+#[derive(Debug, Serialize)]
+pub struct CreatedAccessToken {
+    #[serde(flatten)]
+    pub access_token: AccessToken,
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateAccessTokenRequest {
+    pub name: String,
+    pub scopes: Vec<TokenScope>,
+    pub expires_in_days: Option<u32>, // None for a token that never expires
+}
+
+/// Attached to a request authenticated with a personal access token or an OAuth access token.
+#[derive(Debug, Clone)]
+pub struct TokenAuthentication {
+    pub token_id: String,
+    pub user_id: String,
+    pub username: String,
+    pub scopes: Vec<TokenScope>,
+}
+
+impl TokenAuthentication {
+    pub fn has_scope(&self, scope: TokenScope) -> bool {
+        self.scopes.contains(&scope)
+    }
+}
@@ -0,0 +1,27 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct BranchProtection {
+    pub id: String,
+    pub repository_id: String,
+    pub branch: String,
+    pub required_approving_reviews: i32,
+    pub allow_force_pushes: bool,
+    pub allow_deletions: bool,
+    pub required_status_checks: Vec<String>, // Status contexts that must succeed before merging
+    pub restrict_pushes: bool,
+    pub push_allowances: Vec<String>, // Usernames allowed to push when restrict_pushes is set
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateBranchProtectionRequest {
+    pub required_approving_reviews: Option<i32>,
+    pub allow_force_pushes: Option<bool>,
+    pub allow_deletions: Option<bool>,
+    pub required_status_checks: Option<Vec<String>>,
+    pub restrict_pushes: Option<bool>,
+    pub push_allowances: Option<Vec<String>>, // Usernames; replaces the existing list
+}
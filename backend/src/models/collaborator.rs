@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::models::Permission;
+
+#[derive(Debug, Serialize)]
+pub struct Collaborator {
+    pub user_id: String,
+    pub username: String,
+    pub permission: String, // read, triage, write, maintain, admin
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepositoryInvitation {
+    pub id: String,
+    pub repository_id: String,
+    pub repository_name: String,
+    pub invitee_id: String,
+    pub invitee_username: String,
+    pub inviter_id: String,
+    pub permission: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddCollaboratorRequest {
+    pub permission: Option<Permission>, // Defaults to write
+}
+
+#[derive(Debug, Serialize)]
+pub struct PermissionResponse {
+    pub username: String,
+    pub permission: Option<Permission>, // None when the user cannot see the repository
+}
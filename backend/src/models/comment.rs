@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Comment {
+    pub id: String,
+    pub body: String,
+    pub author_id: String,
+    pub issue_id: Option<String>, // Exactly one of issue_id and pull_request_id is set
+    pub pull_request_id: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommentEdit {
+    pub id: String,
+    pub comment_id: String,
+    pub previous_body: String,
+    pub edited_by_id: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+// The conversation a comment belongs to; issues and pull requests share one number sequence per repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommentThread {
+    Issue(String),
+    PullRequest(String),
+}
+
+impl CommentThread {
+    pub fn issue_id(&self) -> Option<&str> {
+        match self {
+            CommentThread::Issue(id) => Some(id),
+            CommentThread::PullRequest(_) => None,
+        }
+    }
+
+    pub fn pull_request_id(&self) -> Option<&str> {
+        match self {
+            CommentThread::Issue(_) => None,
+            CommentThread::PullRequest(id) => Some(id),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCommentRequest {
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCommentRequest {
+    pub body: String,
+}
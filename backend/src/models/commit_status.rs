@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitState {
+    Error,
+    Failure,
+    Pending,
+    Success,
+}
+
+impl CommitState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommitState::Error => "error",
+            CommitState::Failure => "failure",
+            CommitState::Pending => "pending",
+            CommitState::Success => "success",
+        }
+    }
+
+    pub fn parse(state: &str) -> Option<Self> {
+        match state {
+            "error" => Some(CommitState::Error),
+            "failure" => Some(CommitState::Failure),
+            "pending" => Some(CommitState::Pending),
+            "success" => Some(CommitState::Success),
+            _ => None,
+        }
+    }
+}
+
+/// A state reported by an external system for one context on a commit.
+#[derive(Debug, Serialize)]
+pub struct CommitStatus {
+    pub id: String,
+    pub sha: String,
+    pub state: CommitState,
+    pub context: String,
+    pub description: Option<String>,
+    pub target_url: Option<String>,
+    pub creator_id: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCommitStatusRequest {
+    pub state: CommitState,
+    pub target_url: Option<String>,
+    pub description: Option<String>,
+    pub context: Option<String>, // Defaults to "default"
+}
+
+/// The overall state of a commit from the latest status of every context and the latest run of every check.
+#[derive(Debug, Serialize)]
+pub struct CombinedStatus {
+    pub sha: String,
+    pub state: CommitState, // Pending when nothing has been reported
+    pub total_count: usize,
+    pub statuses: Vec<CommitStatus>,
+    pub check_runs: Vec<CheckRun>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckRunStatus {
+    Queued,
+    InProgress,
+    Completed,
+}
+
+impl CheckRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckRunStatus::Queued => "queued",
+            CheckRunStatus::InProgress => "in_progress",
+            CheckRunStatus::Completed => "completed",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "queued" => Some(CheckRunStatus::Queued),
+            "in_progress" => Some(CheckRunStatus::InProgress),
+            "completed" => Some(CheckRunStatus::Completed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckRunConclusion {
+    Success,
+    Failure,
+    Neutral,
+    Cancelled,
+    Skipped,
+    TimedOut,
+    ActionRequired,
+}
+
+impl CheckRunConclusion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckRunConclusion::Success => "success",
+            CheckRunConclusion::Failure => "failure",
+            CheckRunConclusion::Neutral => "neutral",
+            CheckRunConclusion::Cancelled => "cancelled",
+            CheckRunConclusion::Skipped => "skipped",
+            CheckRunConclusion::TimedOut => "timed_out",
+            CheckRunConclusion::ActionRequired => "action_required",
+        }
+    }
+
+    pub fn parse(conclusion: &str) -> Option<Self> {
+        match conclusion {
+            "success" => Some(CheckRunConclusion::Success),
+            "failure" => Some(CheckRunConclusion::Failure),
+            "neutral" => Some(CheckRunConclusion::Neutral),
+            "cancelled" => Some(CheckRunConclusion::Cancelled),
+            "skipped" => Some(CheckRunConclusion::Skipped),
+            "timed_out" => Some(CheckRunConclusion::TimedOut),
+            "action_required" => Some(CheckRunConclusion::ActionRequired),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckRunOutput {
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub text: Option<String>,
+    pub annotations_count: i64,
+}
+
+/// A named check on a commit that moves from queued through in_progress to a conclusion.
+#[derive(Debug, Serialize)]
+pub struct CheckRun {
+    pub id: String,
+    pub head_sha: String,
+    pub name: String,
+    pub status: CheckRunStatus,
+    pub conclusion: Option<CheckRunConclusion>, // Set once completed
+    pub details_url: Option<String>,
+    pub external_id: Option<String>,
+    pub output: CheckRunOutput,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationLevel {
+    Notice,
+    Warning,
+    Failure,
+}
+
+impl AnnotationLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnotationLevel::Notice => "notice",
+            AnnotationLevel::Warning => "warning",
+            AnnotationLevel::Failure => "failure",
+        }
+    }
+
+    pub fn parse(level: &str) -> Self {
+        match level {
+            "warning" => AnnotationLevel::Warning,
+            "failure" => AnnotationLevel::Failure,
+            _ => AnnotationLevel::Notice,
+        }
+    }
+}
+
+/// A message attached to a range of lines in a file by a check run.
+#[derive(Debug, Serialize)]
+pub struct CheckRunAnnotation {
+    pub id: String,
+    pub path: String,
+    pub start_line: i32,
+    pub end_line: i32,
+    pub annotation_level: AnnotationLevel,
+    pub title: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckRunAnnotationRequest {
+    pub path: String,
+    pub start_line: i32,
+    pub end_line: Option<i32>, // Defaults to start_line
+    pub annotation_level: AnnotationLevel,
+    pub title: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CheckRunOutputRequest {
+    pub title: Option<String>,
+    pub summary: Option<String>,
+    pub text: Option<String>,
+    pub annotations: Option<Vec<CheckRunAnnotationRequest>>, // Added to the run's existing annotations
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCheckRunRequest {
+    pub name: String,
+    pub head_sha: String,
+    pub status: Option<CheckRunStatus>, // Defaults to queued, or completed when a conclusion is given
+    pub conclusion: Option<CheckRunConclusion>,
+    pub details_url: Option<String>,
+    pub external_id: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub output: Option<CheckRunOutputRequest>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCheckRunRequest {
+    pub name: Option<String>,
+    pub status: Option<CheckRunStatus>,
+    pub conclusion: Option<CheckRunConclusion>, // Completes the run
+    pub details_url: Option<String>,
+    pub external_id: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub output: Option<CheckRunOutputRequest>,
+}
@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Something that happened on a repository, as published to event stream subscribers.
+/// `event` and `data` match the webhook event name and payload.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RealtimeEvent {
+    pub event: String,
+    pub actor_id: String,
+    pub data: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Messages clients send over the event stream. Channels are named `repo:owner/name`,
+/// `issue:owner/name#number` or `pull:owner/name#number`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum EventStreamRequest {
+    Subscribe { channel: String },
+    Unsubscribe { channel: String },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventStreamMessage {
+    Subscribed { channel: String },
+    Unsubscribed { channel: String },
+    Event {
+        channel: String,
+        #[serde(flatten)]
+        event: RealtimeEvent,
+    },
+    Error {
+        channel: Option<String>,
+        message: String,
+    },
+}
+
+/// The credential an event stream was opened with, checked again while the stream stays open.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamCredential {
+    Session { session_id: String, jti: String },
+    Token { token_id: String }, // A personal or OAuth access token
+}
+
+/// Who an event stream ticket was issued to.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StreamIdentity {
+    pub user_id: String,
+    pub credential: StreamCredential,
+}
+
+/// A single-use ticket for opening an event stream where an Authorization header cannot be sent,
+/// such as from a browser's WebSocket API.
+#[derive(Debug, Serialize)]
+pub struct EventStreamTicket {
+    pub ticket: String,
+    pub expires_in: i64,
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize)]
+pub struct ContentEntry {
+    #[serde(rename = "type")]
+    pub entry_type: String, // file, dir, symlink, submodule
+    pub name: String,
+    pub path: String,
+    pub sha: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileContent {
+    #[serde(rename = "type")]
+    pub entry_type: String,
+    pub name: String,
+    pub path: String,
+    pub sha: String,
+    pub size: u64,
+    pub encoding: String,
+    pub content: Option<String>, // Omitted for files too large to inline
+    pub is_binary: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum RepositoryContents {
+    File(FileContent),
+    Directory(Vec<ContentEntry>),
+}
+
+#[derive(Debug, Serialize)]
+pub struct GitTreeEntry {
+    pub path: String,
+    pub mode: String,
+    #[serde(rename = "type")]
+    pub entry_type: String, // blob, tree, commit
+    pub sha: String,
+    pub size: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GitTree {
+    pub sha: String,
+    pub tree: Vec<GitTreeEntry>,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GitSignature {
+    pub name: String,
+    pub email: String,
+    pub date: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommitSummary {
+    pub sha: String,
+    pub message: String,
+    pub author: GitSignature,
+    pub committer: GitSignature,
+    pub parents: Vec<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct DiffStats {
+    pub additions: usize,
+    pub deletions: usize,
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileDiff {
+    pub filename: String,
+    pub previous_filename: Option<String>,
+    pub status: String, // added, removed, modified, renamed, copied
+    pub additions: usize,
+    pub deletions: usize,
+    pub changes: usize,
+    pub patch: Option<String>, // Omitted for binary or oversized files
+}
+
+#[derive(Debug, Serialize)]
+pub struct CommitDetail {
+    #[serde(flatten)]
+    pub commit: CommitSummary,
+    pub stats: DiffStats,
+    pub files: Vec<FileDiff>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Branch {
+    pub name: String,
+    pub commit: CommitSummary,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Tag {
+    pub name: String,
+    pub commit: CommitSummary,
+    pub tag_sha: Option<String>, // Tag object id; only set for annotated tags
+    pub message: Option<String>,
+    pub tagger: Option<GitSignature>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBranchRequest {
+    pub name: String,
+    pub sha: Option<String>, // branch, tag or commit to start from; defaults to HEAD
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTagRequest {
+    pub name: String,
+    pub sha: Option<String>, // commit to tag; defaults to HEAD
+    pub message: Option<String>, // creates an annotated tag when present
+}
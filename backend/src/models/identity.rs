@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// A configured sign-in provider, as listed on the login page.
+#[derive(Debug, Serialize)]
+pub struct OidcProvider {
+    pub name: String,
+    pub display_name: String,
+}
+
+// Where the client should send the browser to sign in at the provider
+#[derive(Debug, Serialize)]
+pub struct OidcAuthorization {
+    pub authorization_url: String,
+}
+
+// Posted by the frontend once the provider redirects back with a code
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackRequest {
+    pub code: String,
+    pub state: String,
+}
+
+/// Who the provider says the user is, once the authorization code has been exchanged.
+#[derive(Debug, Clone)]
+pub struct ExternalIdentity {
+    pub provider: String,
+    pub subject: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub username: Option<String>,
+    pub full_name: Option<String>,
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct LinkedIdentity {
+    pub id: String,
+    pub provider: String,
+    pub email: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_used: Option<DateTime<Utc>>,
+}
@@ -4,9 +4,46 @@ pub mod user;
 pub mod repository;
 pub mod issue;
 pub mod pull_request;
+pub mod git;
+pub mod comment;
+pub mod review;
+pub mod branch_protection;
+pub mod organization;
+pub mod permission;
+pub mod team;
+pub mod collaborator;
+pub mod access_token;
+pub mod ssh_key;
+pub mod session;
+pub mod two_factor;
+pub mod identity;
+pub mod oauth;
+pub mod webhook;
+pub mod commit_status;
+pub mod pipeline;
+pub mod event;
+pub mod notification;
 
 // Re-export the MySQL models as the main models
 pub use user::{User, UserWithPassword, UserResponse, CreateUserRequest, UpdateUserRequest};
 pub use repository::{Repository, CreateRepositoryRequest, UpdateRepositoryRequest};
 pub use issue::{Issue, CreateIssueRequest, UpdateIssueRequest};
-pub use pull_request::{PullRequest, CreatePullRequestRequest, UpdatePullRequestRequest};
+pub use pull_request::{PullRequest, CreatePullRequestRequest, UpdatePullRequestRequest, MergePullRequestRequest, MergeMethod};
+pub use comment::{Comment, CommentEdit, CommentThread, CreateCommentRequest, UpdateCommentRequest};
+pub use review::{PullRequestReview, PullRequestReviewDetail, ReviewComment, ReviewState, SubmitReviewRequest, CreateReviewCommentRequest};
+pub use branch_protection::{BranchProtection, UpdateBranchProtectionRequest};
+pub use organization::{Organization, OrgRole, OrganizationMember, OrganizationInvitation, CreateOrganizationRequest, UpdateOrganizationRequest, InviteMemberRequest, UpdateMembershipRequest};
+pub use permission::Permission;
+pub use team::{Team, TeamRole, TeamMember, TeamRepository, CreateTeamRequest, UpdateTeamRequest, UpdateTeamMembershipRequest, UpdateTeamRepositoryRequest};
+pub use collaborator::{Collaborator, RepositoryInvitation, AddCollaboratorRequest, PermissionResponse};
+pub use access_token::{AccessToken, CreatedAccessToken, CreateAccessTokenRequest, TokenAuthentication, TokenScope};
+pub use ssh_key::{SshKey, CreateSshKeyRequest};
+pub use session::{Session, SessionTokens, RefreshTokenRequest, SessionAuthentication};
+pub use two_factor::{TwoFactorEnrollment, TwoFactorStatus, RecoveryCodes, TwoFactorCodeRequest, TwoFactorLoginRequest};
+pub use identity::{OidcProvider, OidcAuthorization, OidcCallbackRequest, ExternalIdentity, LinkedIdentity};
+pub use oauth::{OAuthApplication, CreatedOAuthApplication, CreateOAuthApplicationRequest, UpdateOAuthApplicationRequest, AuthorizationRequest, ConsentRequest, ConsentPrompt, ConsentRedirect, OAuthTokenRequest, OAuthTokenResponse, OAuthTokenLookupRequest, IntrospectionResponse, AuthorizedApplication, OAuthError};
+pub use webhook::{WebhookEvent, Webhook, CreateWebhookRequest, UpdateWebhookRequest, DeliveryStatus, WebhookDelivery, WebhookDeliverySummary, WebhookDeliveryRequest, WebhookDeliveryResponse};
+pub use commit_status::{CommitState, CommitStatus, CreateCommitStatusRequest, CombinedStatus, CheckRunStatus, CheckRunConclusion, CheckRunOutput, CheckRun, AnnotationLevel, CheckRunAnnotation, CheckRunAnnotationRequest, CheckRunOutputRequest, CreateCheckRunRequest, UpdateCheckRunRequest};
+pub use pipeline::{PipelineEvent, PipelineStatus, PipelineConclusion, PipelineRun, PipelineRunDetail, PipelineJob, PipelineStep, PipelineArtifact, Runner, CreatedRunner, CreateRunnerRequest, RunnerStep, RunnerJob, RunnerStepUpdate, RunnerJobCompletion, RunnerHeartbeat};
+pub use event::{RealtimeEvent, EventStreamRequest, EventStreamMessage, EventStreamTicket, StreamCredential, StreamIdentity};
+pub use notification::{NotificationReason, NotificationState, NotificationSubject, Notification, UpdateNotificationRequest, MarkNotificationsRequest, ThreadSubscription, UpdateThreadSubscriptionRequest, NotificationSettings};
@@ -0,0 +1,134 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Why a user is subscribed to a thread or was notified about it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationReason {
+    Assign,
+    Author,
+    Comment,
+    Mention,
+    Manual, // Subscribed through the API
+    Subscribed, // Watching the repository
+}
+
+impl NotificationReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationReason::Assign => "assign",
+            NotificationReason::Author => "author",
+            NotificationReason::Comment => "comment",
+            NotificationReason::Mention => "mention",
+            NotificationReason::Manual => "manual",
+            NotificationReason::Subscribed => "subscribed",
+        }
+    }
+
+    pub fn parse(reason: &str) -> Option<Self> {
+        match reason {
+            "assign" => Some(NotificationReason::Assign),
+            "author" => Some(NotificationReason::Author),
+            "comment" => Some(NotificationReason::Comment),
+            "mention" => Some(NotificationReason::Mention),
+            "manual" => Some(NotificationReason::Manual),
+            "subscribed" => Some(NotificationReason::Subscribed),
+            _ => None,
+        }
+    }
+
+    // A user with several reasons is notified with the most personal one
+    pub fn rank(&self) -> u8 {
+        match self {
+            NotificationReason::Mention => 5,
+            NotificationReason::Assign => 4,
+            NotificationReason::Author => 3,
+            NotificationReason::Comment => 2,
+            NotificationReason::Manual => 1,
+            NotificationReason::Subscribed => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotificationState {
+    Unread,
+    Read,
+    Done, // Hidden from the inbox until there is new activity
+}
+
+impl NotificationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationState::Unread => "unread",
+            NotificationState::Read => "read",
+            NotificationState::Done => "done",
+        }
+    }
+
+    pub fn parse(state: &str) -> Option<Self> {
+        match state {
+            "unread" => Some(NotificationState::Unread),
+            "read" => Some(NotificationState::Read),
+            "done" => Some(NotificationState::Done),
+            _ => None,
+        }
+    }
+}
+
+/// The issue or pull request a notification is about, as it is now.
+#[derive(Debug, Serialize)]
+pub struct NotificationSubject {
+    #[serde(rename = "type")]
+    pub kind: String, // "issue" or "pull_request"
+    pub number: i32,
+    pub title: String,
+    pub status: String,
+}
+
+/// Activity on one thread for one user. Later activity updates the same notification.
+#[derive(Debug, Serialize)]
+pub struct Notification {
+    pub id: String,
+    pub repository_id: String,
+    pub repository: String, // owner/name
+    pub subject: NotificationSubject,
+    pub reason: NotificationReason,
+    pub state: NotificationState,
+    pub last_actor_id: Option<String>,
+    pub last_read_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateNotificationRequest {
+    pub state: NotificationState,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MarkNotificationsRequest {
+    pub state: Option<NotificationState>, // Defaults to read
+    pub repository: Option<String>, // owner/name; every repository when omitted
+}
+
+/// A user's subscription to an issue or pull request. Without one, only repository watchers and
+/// mentioned users are notified.
+#[derive(Debug, Serialize)]
+pub struct ThreadSubscription {
+    pub subscribed: bool,
+    pub reason: Option<NotificationReason>, // None when the user has no subscription row
+    pub watching_repository: bool,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateThreadSubscriptionRequest {
+    pub subscribed: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NotificationSettings {
+    pub email_digest: bool,
+}
@@ -0,0 +1,158 @@
+use crate::models::TokenScope;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A third-party application that can ask users for access to their account.
+#[derive(Debug, Serialize)]
+pub struct OAuthApplication {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub homepage_url: Option<String>,
+    pub client_id: String,
+    pub redirect_uris: Vec<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+// Returned on creation and when the secret is reset; the secret cannot be retrieved afterwards
+#[derive(Debug, Serialize)]
+pub struct CreatedOAuthApplication {
+    #[serde(flatten)]
+    pub application: OAuthApplication,
+    pub client_secret: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateOAuthApplicationRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub homepage_url: Option<String>,
+    pub redirect_uris: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateOAuthApplicationRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub homepage_url: Option<String>,
+    pub redirect_uris: Option<Vec<String>>,
+}
+
+/// The authorization request parameters from RFC 6749 section 4.1.1, with the PKCE challenge.
+#[derive(Debug, Deserialize)]
+pub struct AuthorizationRequest {
+    pub response_type: String,
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub scope: String, // Space-separated, e.g. "repo read:user"
+    pub state: Option<String>,
+    pub code_challenge: Option<String>,
+    pub code_challenge_method: Option<String>,
+}
+
+// Submitted from the consent screen
+#[derive(Debug, Deserialize)]
+pub struct ConsentRequest {
+    #[serde(flatten)]
+    pub authorization: AuthorizationRequest,
+    pub approve: bool,
+}
+
+// What the consent screen shows the user
+#[derive(Debug, Serialize)]
+pub struct ConsentPrompt {
+    pub application_name: String,
+    pub application_description: Option<String>,
+    pub homepage_url: Option<String>,
+    pub owner: String,
+    pub scopes: Vec<TokenScope>,
+    pub already_authorized: bool, // The user has granted every requested scope before
+}
+
+// Where to send the browser after the user decides
+#[derive(Debug, Serialize)]
+pub struct ConsentRedirect {
+    pub redirect_url: String,
+}
+
+/// Token endpoint parameters (RFC 6749 sections 4.1.3 and 6). Client credentials may come
+/// from here or from HTTP Basic authentication.
+#[derive(Debug, Deserialize)]
+pub struct OAuthTokenRequest {
+    pub grant_type: String,
+    pub code: Option<String>,
+    pub redirect_uri: Option<String>,
+    pub code_verifier: Option<String>,
+    pub refresh_token: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OAuthTokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+    pub refresh_token: String,
+    pub scope: String,
+}
+
+// Introspection (RFC 7662) and revocation (RFC 7009) both identify a token this way. Any
+// token_type_hint is ignored: the token's prefix already says what kind it is.
+#[derive(Debug, Deserialize)]
+pub struct OAuthTokenLookupRequest {
+    pub token: String,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct IntrospectionResponse {
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iat: Option<i64>,
+}
+
+/// An application the user has authorized, listed so access can be withdrawn.
+#[derive(Debug, Serialize)]
+pub struct AuthorizedApplication {
+    pub application_id: String,
+    pub name: String,
+    pub homepage_url: Option<String>,
+    pub scopes: Vec<TokenScope>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Error body from RFC 6749 section 5.2, used by the token, introspection and revocation endpoints.
+#[derive(Debug, Serialize)]
+pub struct OAuthError {
+    pub error: &'static str,
+    pub error_description: String,
+}
+
+impl OAuthError {
+    pub fn new(error: &'static str, description: &str) -> Self {
+        Self { error, error_description: description.to_string() }
+    }
+}
+
+// Database and other internal failures surface as server_error
+impl From<String> for OAuthError {
+    fn from(description: String) -> Self {
+        Self { error: "server_error", error_description: description }
+    }
+}
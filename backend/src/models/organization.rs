@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Organization {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub avatar_url: Option<String>,
+    pub website: Option<String>,
+    pub location: Option<String>,
+    pub email: Option<String>,
+    pub owner_id: String, // Creator; ownership itself is tracked through member roles
+    pub two_factor_required: bool, // Members without 2FA lose their org access while set
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrgRole {
+    Owner,
+    #[default]
+    Member,
+    BillingManager,
+}
+
+impl OrgRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrgRole::Owner => "owner",
+            OrgRole::Member => "member",
+            OrgRole::BillingManager => "billing_manager",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "owner" => Some(OrgRole::Owner),
+            "member" => Some(OrgRole::Member),
+            "billing_manager" => Some(OrgRole::BillingManager),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrganizationMember {
+    pub user_id: String,
+    pub username: String,
+    pub role: String, // owner, member, billing_manager
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OrganizationInvitation {
+    pub id: String,
+    pub organization_id: String,
+    pub organization_name: String,
+    pub invitee_id: String,
+    pub invitee_username: String,
+    pub inviter_id: String,
+    pub role: String,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateOrganizationRequest {
+    pub name: String,
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub website: Option<String>,
+    pub location: Option<String>,
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateOrganizationRequest {
+    pub display_name: Option<String>,
+    pub description: Option<String>,
+    pub avatar_url: Option<String>,
+    pub website: Option<String>,
+    pub location: Option<String>,
+    pub email: Option<String>,
+    pub two_factor_required: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InviteMemberRequest {
+    pub username: String,
+    #[serde(default)]
+    pub role: OrgRole,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateMembershipRequest {
+    pub role: OrgRole,
+}
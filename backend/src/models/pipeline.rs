@@ -0,0 +1,230 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Repository activity that starts pipeline runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineEvent {
+    Push,
+    PullRequest,
+}
+
+impl PipelineEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PipelineEvent::Push => "push",
+            PipelineEvent::PullRequest => "pull_request",
+        }
+    }
+
+    pub fn parse(event: &str) -> Option<Self> {
+        match event {
+            "push" => Some(PipelineEvent::Push),
+            "pull_request" => Some(PipelineEvent::PullRequest),
+            _ => None,
+        }
+    }
+}
+
+// Runs and steps are never waiting; only jobs with unfinished needs are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStatus {
+    Waiting,
+    Queued,
+    InProgress,
+    Completed,
+}
+
+impl PipelineStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PipelineStatus::Waiting => "waiting",
+            PipelineStatus::Queued => "queued",
+            PipelineStatus::InProgress => "in_progress",
+            PipelineStatus::Completed => "completed",
+        }
+    }
+
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "waiting" => Some(PipelineStatus::Waiting),
+            "queued" => Some(PipelineStatus::Queued),
+            "in_progress" => Some(PipelineStatus::InProgress),
+            "completed" => Some(PipelineStatus::Completed),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PipelineConclusion {
+    Success,
+    Failure,
+    Cancelled,
+    Skipped,
+}
+
+impl PipelineConclusion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PipelineConclusion::Success => "success",
+            PipelineConclusion::Failure => "failure",
+            PipelineConclusion::Cancelled => "cancelled",
+            PipelineConclusion::Skipped => "skipped",
+        }
+    }
+
+    pub fn parse(conclusion: &str) -> Option<Self> {
+        match conclusion {
+            "success" => Some(PipelineConclusion::Success),
+            "failure" => Some(PipelineConclusion::Failure),
+            "cancelled" => Some(PipelineConclusion::Cancelled),
+            "skipped" => Some(PipelineConclusion::Skipped),
+            _ => None,
+        }
+    }
+}
+
+/// One execution of a pipeline file for a push or pull request.
+#[derive(Debug, Serialize)]
+pub struct PipelineRun {
+    pub id: String,
+    pub number: i32,
+    pub pipeline_path: String,
+    pub name: String,
+    pub event: PipelineEvent,
+    #[serde(rename = "ref")]
+    pub ref_name: String,
+    pub sha: String,
+    pub pull_request_number: Option<i32>,
+    pub status: PipelineStatus,
+    pub conclusion: Option<PipelineConclusion>,
+    pub error: Option<String>, // Why the pipeline file could not be run
+    pub triggered_by: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PipelineStep {
+    pub number: i32,
+    pub name: String,
+    pub status: PipelineStatus,
+    pub conclusion: Option<PipelineConclusion>,
+    pub exit_code: Option<i32>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PipelineJob {
+    pub id: String,
+    pub key: String,
+    pub name: String,
+    pub runs_on: Vec<String>,
+    pub needs: Vec<String>,
+    pub status: PipelineStatus,
+    pub conclusion: Option<PipelineConclusion>,
+    pub runner_id: Option<String>,
+    pub check_run_id: Option<String>,
+    pub steps: Vec<PipelineStep>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PipelineRunDetail {
+    #[serde(flatten)]
+    pub run: PipelineRun,
+    pub jobs: Vec<PipelineJob>,
+}
+
+/// A file a job uploaded to object storage.
+#[derive(Debug, Serialize)]
+pub struct PipelineArtifact {
+    pub id: String,
+    pub job_id: String,
+    pub name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// An agent that polls for jobs and executes them. Runners take jobs from the repository or the
+/// organization they are registered with, as long as they have every label in the job's runs-on.
+#[derive(Debug, Serialize)]
+pub struct Runner {
+    pub id: String,
+    pub repository_id: Option<String>,
+    pub organization_id: Option<String>,
+    pub name: String,
+    pub labels: Vec<String>,
+    pub token_last_eight: String,
+    pub active: bool,
+    pub last_seen_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedRunner {
+    #[serde(flatten)]
+    pub runner: Runner,
+    pub token: String, // Only returned once
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateRunnerRequest {
+    pub name: String,
+    pub labels: Option<Vec<String>>, // Defaults to ["local"]
+}
+
+/// A step as the runner executes it.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RunnerStep {
+    pub number: i32,
+    pub name: String,
+    pub run: String,
+    pub env: BTreeMap<String, String>,
+    pub working_directory: Option<String>,
+    pub continue_on_error: bool,
+}
+
+/// Everything a runner needs to execute a claimed job. The source is fetched separately as a tarball.
+#[derive(Debug, Serialize)]
+pub struct RunnerJob {
+    pub id: String,
+    pub run_id: String,
+    pub repository: String, // owner/name
+    pub event: PipelineEvent,
+    #[serde(rename = "ref")]
+    pub ref_name: String,
+    pub sha: String,
+    pub name: String,
+    pub env: BTreeMap<String, String>,
+    pub steps: Vec<RunnerStep>,
+    pub artifacts: Vec<String>, // Workspace paths to upload once the steps finish
+    pub timeout_minutes: u32,
+    pub heartbeat_interval_seconds: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunnerStepUpdate {
+    pub status: PipelineStatus,
+    pub conclusion: Option<PipelineConclusion>, // Required once completed
+    pub exit_code: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunnerJobCompletion {
+    pub conclusion: PipelineConclusion,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunnerHeartbeat {
+    pub cancel_requested: bool,
+}
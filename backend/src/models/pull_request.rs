@@ -13,6 +13,12 @@ pub struct PullRequest {
     pub head_branch: String,
     pub base_branch: String,
     pub is_merged: bool,
+    pub mergeable: Option<bool>, // None until the background check has run
+    pub mergeable_state: String, // unknown, clean, dirty
+    pub head_sha: Option<String>, // Tip of the head branch as of the last refresh
+    pub head_status: Option<String>, // Combined status of head_sha: pending, success, failure, error
+    pub merge_commit_sha: Option<String>,
+    pub merged_by_id: Option<String>,
     pub created_at: Option<DateTime<Utc>>,
     pub updated_at: Option<DateTime<Utc>>,
     pub merged_at: Option<DateTime<Utc>>,
@@ -33,3 +39,18 @@ pub struct UpdatePullRequestRequest {
     pub body: Option<String>,
     pub status: Option<String>,
 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeMethod {
+    #[default]
+    Merge,
+    Squash,
+    Rebase,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergePullRequestRequest {
+    pub commit_message: Option<String>,
+    pub merge_method: Option<MergeMethod>,
+}
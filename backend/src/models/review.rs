@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ReviewState {
+    Approve,
+    RequestChanges,
+    Comment,
+}
+
+impl ReviewState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewState::Approve => "APPROVE",
+            ReviewState::RequestChanges => "REQUEST_CHANGES",
+            ReviewState::Comment => "COMMENT",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PullRequestReview {
+    pub id: String,
+    pub pull_request_id: String,
+    pub author_id: String,
+    pub state: String, // APPROVE, REQUEST_CHANGES, COMMENT
+    pub body: Option<String>,
+    pub commit_id: String,
+    pub submitted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PullRequestReviewDetail {
+    #[serde(flatten)]
+    pub review: PullRequestReview,
+    pub comments: Vec<ReviewComment>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReviewComment {
+    pub id: String,
+    pub pull_request_id: String,
+    pub review_id: Option<String>,
+    pub author_id: String,
+    pub in_reply_to_id: Option<String>, // Root comment of the thread for replies
+    pub path: String,
+    pub line: i32, // Line in the file at commit_id; follows the code as the head branch moves
+    pub commit_id: String,
+    pub original_line: i32,
+    pub original_commit_id: String,
+    pub body: String,
+    pub is_resolved: bool,
+    pub resolved_by_id: Option<String>,
+    pub is_outdated: bool, // The commented line itself changed after the comment was made
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DraftReviewComment {
+    pub path: String,
+    pub line: i32,
+    pub body: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubmitReviewRequest {
+    pub event: ReviewState,
+    pub body: Option<String>,
+    pub commit_id: Option<String>, // Defaults to the current head of the pull request
+    #[serde(default)]
+    pub comments: Vec<DraftReviewComment>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReviewCommentRequest {
+    pub body: String,
+    pub path: Option<String>,
+    pub line: Option<i32>,
+    pub commit_id: Option<String>,
+    pub in_reply_to: Option<String>, // Replies inherit the position of the thread they answer
+}
@@ -0,0 +1,37 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A signed-in device, backed by a rotating refresh token held in Redis.
+#[derive(Debug, Serialize)]
+pub struct Session {
+    pub id: String,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_used: DateTime<Utc>,
+    pub current: bool, // The session the listing request was made from
+}
+
+// Issued on login and on every refresh; the refresh token is single-use
+#[derive(Debug, Serialize)]
+pub struct SessionTokens {
+    pub session_id: String,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64, // Seconds until the access token expires
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+/// Attached to a request authenticated with a session access token.
+#[derive(Debug, Clone)]
+pub struct SessionAuthentication {
+    pub session_id: String,
+    pub jti: String,
+    pub user_id: String,
+    pub username: String,
+    pub expires_at: i64,
+}
@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize)]
+pub struct SshKey {
+    pub id: String,
+    pub title: String,
+    pub key_type: String,
+    pub key_data: String,
+    pub fingerprint: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSshKeyRequest {
+    pub title: Option<String>, // Defaults to the key's comment
+    pub key: String,
+}
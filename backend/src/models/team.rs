@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::models::Permission;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Team {
+    pub id: String,
+    pub organization_id: String,
+    pub name: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub parent_id: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TeamRole {
+    #[default]
+    Member,
+    Maintainer,
+}
+
+impl TeamRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TeamRole::Member => "member",
+            TeamRole::Maintainer => "maintainer",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TeamMember {
+    pub user_id: String,
+    pub username: String,
+    pub role: String, // member, maintainer
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TeamRepository {
+    pub repository_id: String,
+    pub name: String,
+    pub permission: String, // read, triage, write, maintain, admin
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTeamRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub parent_team: Option<String>, // Slug of the parent team
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTeamRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub parent_team: Option<String>, // Slug of the new parent; an empty string detaches the team
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTeamMembershipRequest {
+    #[serde(default)]
+    pub role: TeamRole,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTeamRepositoryRequest {
+    pub permission: Permission,
+}
@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+// Returned when enrollment starts; 2FA is not active until a code from the app is confirmed
+#[derive(Debug, Serialize)]
+pub struct TwoFactorEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+    pub qr_code_svg: String, // The otpauth URI rendered as a QR code
+}
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    pub recovery_codes_remaining: i64,
+}
+
+// Shown once; only hashes are stored
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+// Second login step: the partial token from the password step plus a TOTP or recovery code
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorLoginRequest {
+    pub two_factor_token: String,
+    pub code: String,
+}
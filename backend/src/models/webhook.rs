@@ -0,0 +1,128 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Activity a webhook can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Push,
+    Issues,
+    PullRequest,
+    Star,
+    Comment,
+    Ping, // Sent when a hook is created or pinged; hooks cannot subscribe to it
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::Push => "push",
+            WebhookEvent::Issues => "issues",
+            WebhookEvent::PullRequest => "pull_request",
+            WebhookEvent::Star => "star",
+            WebhookEvent::Comment => "comment",
+            WebhookEvent::Ping => "ping",
+        }
+    }
+
+    pub fn parse(event: &str) -> Option<Self> {
+        match event {
+            "push" => Some(WebhookEvent::Push),
+            "issues" => Some(WebhookEvent::Issues),
+            "pull_request" => Some(WebhookEvent::PullRequest),
+            "star" => Some(WebhookEvent::Star),
+            "comment" => Some(WebhookEvent::Comment),
+            "ping" => Some(WebhookEvent::Ping),
+            _ => None,
+        }
+    }
+}
+
+/// A URL that is sent a signed POST for every subscribed event on a repository or organization.
+#[derive(Debug, Serialize)]
+pub struct Webhook {
+    pub id: String,
+    pub repository_id: Option<String>,
+    pub organization_id: Option<String>,
+    pub url: String,
+    pub has_secret: bool, // The secret itself is never returned
+    pub events: Vec<WebhookEvent>,
+    pub active: bool,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub secret: Option<String>,
+    pub events: Option<Vec<WebhookEvent>>, // Defaults to push
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub secret: Option<String>, // An empty string removes the secret
+    pub events: Option<Vec<WebhookEvent>>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeliveryStatus {
+    Pending, // Waiting for its first attempt or a retry
+    Delivered,
+    Failed, // Out of attempts
+}
+
+impl DeliveryStatus {
+    pub fn parse(status: &str) -> Self {
+        match status {
+            "delivered" => DeliveryStatus::Delivered,
+            "failed" => DeliveryStatus::Failed,
+            _ => DeliveryStatus::Pending,
+        }
+    }
+}
+
+// Listed without the request and response, which can be large
+#[derive(Debug, Serialize)]
+pub struct WebhookDeliverySummary {
+    pub id: String,
+    pub guid: String,
+    pub event: String,
+    pub action: Option<String>,
+    pub redelivery: bool,
+    pub status: DeliveryStatus,
+    pub response_status: Option<u16>,
+    pub duration_ms: Option<u32>,
+    pub attempts: u32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookDeliveryRequest {
+    pub headers: HashMap<String, String>,
+    pub payload: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookDeliveryResponse {
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// A delivery with what was sent and, once attempted, what came back.
+#[derive(Debug, Serialize)]
+pub struct WebhookDelivery {
+    #[serde(flatten)]
+    pub summary: WebhookDeliverySummary,
+    pub url: String,
+    pub error: Option<String>,
+    pub request: WebhookDeliveryRequest,
+    pub response: Option<WebhookDeliveryResponse>,
+}
@@ -0,0 +1,213 @@
+use crate::models::{AccessToken, CreateAccessTokenRequest, CreatedAccessToken, TokenAuthentication, TokenScope};
+use chrono::{DateTime, Duration, Utc};
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+// Lets access tokens be told apart from JWTs without a database round trip
+pub const ACCESS_TOKEN_PREFIX: &str = "dvt_";
+
+pub fn is_access_token(token: &str) -> bool {
+    token.starts_with(ACCESS_TOKEN_PREFIX)
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn hash_token(token: &str) -> String {
+    to_hex(digest(&SHA256, token.as_bytes()).as_ref())
+}
+
+pub fn parse_scopes(scopes: &str) -> Vec<TokenScope> {
+    scopes.split(',').filter_map(TokenScope::parse).collect()
+}
+
+#[derive(Clone)]
+pub struct AccessTokenService {
+    pool: MySqlPool,
+}
+
+impl AccessTokenService {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_token(&self, user_id: &str, request: &CreateAccessTokenRequest) -> Result<CreatedAccessToken, String> {
+        let name = request.name.trim();
+        if name.is_empty() || name.len() > 255 {
+            return Err("Token name must be between 1 and 255 characters".to_string());
+        }
+
+        if request.scopes.is_empty() {
+            return Err("At least one scope is required".to_string());
+        }
+
+        if request.expires_in_days == Some(0) {
+            return Err("Expiration must be at least one day".to_string());
+        }
+
+        let mut scopes: Vec<TokenScope> = Vec::new();
+        for scope in &request.scopes {
+            if !scopes.contains(scope) {
+                scopes.push(*scope);
+            }
+        }
+        let scope_list = scopes.iter().map(|scope| scope.as_str()).collect::<Vec<_>>().join(",");
+
+        let mut secret = [0u8; 20];
+        SystemRandom::new()
+            .fill(&mut secret)
+            .map_err(|_| "Failed to generate token".to_string())?;
+        let token = format!("{}{}", ACCESS_TOKEN_PREFIX, to_hex(&secret));
+        let token_last_eight = token[token.len() - 8..].to_string();
+
+        let token_id = format!("pat_{}", Uuid::new_v4().to_string().replace("-", ""));
+        let expires_at = request
+            .expires_in_days
+            .map(|days| Utc::now() + Duration::days(i64::from(days)));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO access_tokens (id, user_id, name, token_hash, token_last_eight, scopes, created_at, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?, NOW(), ?)
+            "#,
+            token_id, user_id, name, hash_token(&token), token_last_eight, scope_list, expires_at
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let access_token = self.get_token(user_id, &token_id).await?;
+
+        Ok(CreatedAccessToken { access_token, token })
+    }
+
+    pub async fn get_token(&self, user_id: &str, token_id: &str) -> Result<AccessToken, String> {
+        let token = sqlx::query!(
+            r#"
+            SELECT id, name, scopes, token_last_eight, created_at, last_used, expires_at
+            FROM access_tokens
+            WHERE id = ? AND user_id = ?
+            "#,
+            token_id, user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Access token not found".to_string())?;
+
+        Ok(AccessToken {
+            id: token.id,
+            name: token.name,
+            scopes: parse_scopes(&token.scopes),
+            token_last_eight: token.token_last_eight,
+            created_at: token.created_at,
+            last_used: token.last_used,
+            expires_at: token.expires_at,
+        })
+    }
+
+    pub async fn list_tokens(&self, user_id: &str) -> Result<Vec<AccessToken>, String> {
+        let tokens = sqlx::query!(
+            r#"
+            SELECT id, name, scopes, token_last_eight, created_at, last_used, expires_at
+            FROM access_tokens
+            WHERE user_id = ?
+            ORDER BY created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(tokens
+            .into_iter()
+            .map(|token| AccessToken {
+                id: token.id,
+                name: token.name,
+                scopes: parse_scopes(&token.scopes),
+                token_last_eight: token.token_last_eight,
+                created_at: token.created_at,
+                last_used: token.last_used,
+                expires_at: token.expires_at,
+            })
+            .collect())
+    }
+
+    pub async fn revoke_token(&self, user_id: &str, token_id: &str) -> Result<(), String> {
+        let result = sqlx::query!(
+            "DELETE FROM access_tokens WHERE id = ? AND user_id = ?",
+            token_id, user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Access token not found".to_string());
+        }
+
+        Ok(())
+    }
+
+    // False once the token has been revoked or has expired
+    pub async fn is_active(&self, token_id: &str) -> Result<bool, String> {
+        let record = sqlx::query!("SELECT expires_at FROM access_tokens WHERE id = ?", token_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(record.is_some_and(|record| {
+            let expires_at: Option<DateTime<Utc>> = record.expires_at;
+            expires_at.is_none_or(|expires_at| expires_at > Utc::now())
+        }))
+    }
+
+    // Resolves a presented token to its owner and scopes, recording the use
+    pub async fn authenticate(&self, token: &str) -> Result<TokenAuthentication, String> {
+        if !is_access_token(token) {
+            return Err("Invalid access token".to_string());
+        }
+
+        let record = sqlx::query!(
+            r#"
+            SELECT t.id, t.user_id, u.username, t.scopes, t.expires_at
+            FROM access_tokens t
+            INNER JOIN users u ON t.user_id = u.id
+            WHERE t.token_hash = ?
+            "#,
+            hash_token(token)
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Invalid access token".to_string())?;
+
+        let expires_at: Option<DateTime<Utc>> = record.expires_at;
+        if expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+            return Err("Access token has expired".to_string());
+        }
+
+        // Only written once a minute so busy tokens do not hammer the row
+        sqlx::query!(
+            r#"
+            UPDATE access_tokens SET last_used = NOW()
+            WHERE id = ? AND (last_used IS NULL OR last_used < NOW() - INTERVAL 1 MINUTE)
+            "#,
+            record.id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(TokenAuthentication {
+            token_id: record.id,
+            user_id: record.user_id,
+            username: record.username,
+            scopes: parse_scopes(&record.scopes),
+        })
+    }
+}
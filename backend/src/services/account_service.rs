@@ -0,0 +1,179 @@
+use crate::models::UserWithPassword;
+use crate::services::{AuthService, MailService};
+use crate::services::access_token_service::hash_token;
+use bcrypt::{hash, DEFAULT_COST};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+
+// The audience keeps account tokens from being accepted anywhere else a JWT is
+const VERIFY_EMAIL_AUDIENCE: &str = "verify-email";
+const RESET_PASSWORD_AUDIENCE: &str = "reset-password";
+
+const VERIFY_EMAIL_TTL_HOURS: i64 = 24;
+const RESET_PASSWORD_TTL_HOURS: i64 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AccountTokenClaims {
+    sub: String,
+    aud: String,
+    // Ties the token to the state it was issued for: the email address being verified,
+    // or the password hash being replaced, which makes reset tokens single-use
+    fingerprint: String,
+    exp: usize,
+    iat: usize,
+}
+
+fn fingerprint(value: &str) -> String {
+    hash_token(value)[..16].to_string()
+}
+
+#[derive(Clone)]
+pub struct AccountService {
+    pool: MySqlPool,
+    auth: AuthService,
+    mail: MailService,
+    token_secret: String,
+    public_url: String,
+}
+
+impl AccountService {
+    pub fn new(pool: MySqlPool, auth: AuthService, mail: MailService, token_secret: String, public_url: String) -> Self {
+        Self { pool, auth, mail, token_secret, public_url }
+    }
+
+    fn create_token(&self, user_id: &str, audience: &str, fingerprint: String, ttl: Duration) -> Result<String, String> {
+        let now = Utc::now();
+        let claims = AccountTokenClaims {
+            sub: user_id.to_string(),
+            aud: audience.to_string(),
+            fingerprint,
+            exp: (now + ttl).timestamp() as usize,
+            iat: now.timestamp() as usize,
+        };
+
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.token_secret.as_ref())
+        )
+        .map_err(|e| format!("Token generation error: {}", e))
+    }
+
+    fn decode_token(&self, token: &str, audience: &str) -> Result<AccountTokenClaims, String> {
+        let mut validation = Validation::default();
+        validation.set_audience(&[audience]);
+
+        decode::<AccountTokenClaims>(
+            token,
+            &DecodingKey::from_secret(self.token_secret.as_ref()),
+            &validation
+        )
+        .map(|data| data.claims)
+        .map_err(|_| "Invalid or expired token".to_string())
+    }
+
+    pub async fn send_verification_email(&self, user: &UserWithPassword) -> Result<(), String> {
+        if user.is_verified_bool() {
+            return Err("Email is already verified".to_string());
+        }
+
+        let token = self.create_token(
+            &user.id,
+            VERIFY_EMAIL_AUDIENCE,
+            fingerprint(&user.email),
+            Duration::hours(VERIFY_EMAIL_TTL_HOURS),
+        )?;
+
+        let body = format!(
+            "Hi {},\n\nConfirm your email address by opening the link below:\n\n{}/verify-email?token={}\n\nThe link expires in {} hours.\n",
+            user.username, self.public_url, token, VERIFY_EMAIL_TTL_HOURS
+        );
+
+        self.mail.send(&user.email, "Verify your email address", &body).await
+    }
+
+    pub async fn verify_email(&self, token: &str) -> Result<(), String> {
+        let claims = self.decode_token(token, VERIFY_EMAIL_AUDIENCE)?;
+        let user = self.auth.find_user(&claims.sub).await?;
+
+        if fingerprint(&user.email) != claims.fingerprint {
+            return Err("Invalid or expired token".to_string());
+        }
+
+        sqlx::query!("UPDATE users SET is_verified = 1 WHERE id = ?", user.id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    // Succeeds whether or not the address belongs to an account, so it cannot be used to probe for users
+    pub async fn request_password_reset(&self, email: &str) -> Result<(), String> {
+        let user = sqlx::query_as!(
+            UserWithPassword,
+            r#"
+            SELECT
+                id, username, email, full_name, password_hash,
+                avatar_url, bio, website_url, location, company,
+                is_admin, is_verified, created_at, updated_at
+            FROM users
+            WHERE email = ?
+            "#,
+            email
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let user = match user {
+            Some(user) => user,
+            None => return Ok(()),
+        };
+
+        let token = self.create_token(
+            &user.id,
+            RESET_PASSWORD_AUDIENCE,
+            fingerprint(&user.password_hash),
+            Duration::hours(RESET_PASSWORD_TTL_HOURS),
+        )?;
+
+        let body = format!(
+            "Hi {},\n\nSomeone asked to reset the password for your account. To choose a new password, open the link below:\n\n{}/reset-password?token={}\n\nThe link expires in {} hour. If you did not ask for this, you can ignore this email.\n",
+            user.username, self.public_url, token, RESET_PASSWORD_TTL_HOURS
+        );
+
+        self.mail.send(&user.email, "Reset your password", &body).await
+    }
+
+    /// Sets a new password from a reset token and returns the user id.
+    pub async fn reset_password(&self, token: &str, password: &str) -> Result<String, String> {
+        let claims = self.decode_token(token, RESET_PASSWORD_AUDIENCE)?;
+        let user = self.auth.find_user(&claims.sub).await?;
+
+        if fingerprint(&user.password_hash) != claims.fingerprint {
+            return Err("Invalid or expired token".to_string());
+        }
+
+        if password.len() < 8 {
+            return Err("Password must be at least 8 characters long".to_string());
+        }
+
+        let password_hash = hash(password, DEFAULT_COST)
+            .map_err(|e| format!("Password hashing error: {}", e))?;
+
+        // Following the link proves control of the address
+        sqlx::query!(
+            "UPDATE users SET password_hash = ?, is_verified = 1 WHERE id = ?",
+            password_hash,
+            user.id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(user.id)
+    }
+}
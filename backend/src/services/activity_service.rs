@@ -0,0 +1,113 @@
+use crate::models::{Comment, CommentThread, Issue, NotificationReason, PullRequest, Repository, WebhookEvent};
+use crate::services::{EventService, NotificationService, PipelineService, WebhookService};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Announces repository activity to everything that reacts to it: live events, webhooks, pipelines
+/// and notifications. Handlers call one method per action; all delivery happens in the background.
+#[derive(Clone)]
+pub struct ActivityService {
+    events: EventService,
+    webhooks: WebhookService,
+    pipelines: PipelineService,
+    notifications: NotificationService,
+}
+
+impl ActivityService {
+    pub fn new(events: EventService, webhooks: WebhookService, pipelines: PipelineService, notifications: NotificationService) -> Self {
+        Self { events, webhooks, pipelines, notifications }
+    }
+
+    /// An issue was opened, edited, closed or reopened. Subscribers hear about everything but edits.
+    pub fn issue(&self, repo: &Repository, actor_id: &str, action: &str, issue: &Issue) {
+        let payload = json!({ "action": action, "issue": issue });
+        self.events.publish_issue(repo, issue.number, WebhookEvent::Issues, actor_id, payload.clone());
+        self.webhooks.dispatch(repo, actor_id, WebhookEvent::Issues, payload);
+
+        let thread = CommentThread::Issue(issue.id.clone());
+        match action {
+            "opened" => self.notifications.record_activity(repo, thread, actor_id, Some(NotificationReason::Author), issue.body.as_deref()),
+            "edited" => {}
+            _ => self.notifications.record_activity(repo, thread, actor_id, None, None),
+        }
+    }
+
+    /// An issue was assigned to `assignee_id`, or unassigned when it is None.
+    pub fn issue_assigned(&self, repo: &Repository, actor_id: &str, issue: &Issue, assignee_id: Option<&str>) {
+        let action = if assignee_id.is_some() { "assigned" } else { "unassigned" };
+        self.webhooks.dispatch(repo, actor_id, WebhookEvent::Issues, json!({ "action": action, "issue": issue }));
+
+        if let Some(assignee_id) = assignee_id {
+            self.notifications.record_assignment(repo, CommentThread::Issue(issue.id.clone()), actor_id, assignee_id);
+        }
+    }
+
+    /// A pull request was opened, edited, closed or reopened. Opening and reopening also run its pipelines.
+    pub fn pull_request(&self, repo: &Repository, actor_id: &str, action: &str, pr: &PullRequest) {
+        if matches!(action, "opened" | "reopened") {
+            self.pipelines.schedule_pull_request(repo, pr, actor_id);
+        }
+
+        let payload = json!({ "action": action, "pull_request": pr });
+        self.events.publish_pull_request(repo, pr.number, WebhookEvent::PullRequest, actor_id, payload.clone());
+        self.webhooks.dispatch(repo, actor_id, WebhookEvent::PullRequest, payload);
+
+        let thread = CommentThread::PullRequest(pr.id.clone());
+        match action {
+            "opened" => self.notifications.record_activity(repo, thread, actor_id, Some(NotificationReason::Author), pr.body.as_deref()),
+            "edited" => {}
+            _ => self.notifications.record_activity(repo, thread, actor_id, None, None),
+        }
+    }
+
+    /// A pull request was merged. The merge moved the base branch, which receivers see as a push
+    /// before the pull request closes.
+    pub fn pull_request_merged(
+        &self,
+        repo: &Repository,
+        actor_id: &str,
+        pr: &PullRequest,
+        heads_before: HashMap<String, String>,
+        heads_after: HashMap<String, String>,
+    ) {
+        self.push(repo, actor_id, heads_before, heads_after);
+        self.pull_request(repo, actor_id, "closed", pr);
+    }
+
+    /// Someone reviewed a pull request or commented on one of its lines.
+    pub fn pull_request_review(&self, repo: &Repository, actor_id: &str, pr: &PullRequest, body: Option<&str>) {
+        self.notifications.record_activity(repo, CommentThread::PullRequest(pr.id.clone()), actor_id, Some(NotificationReason::Comment), body);
+    }
+
+    /// A comment on issue or pull request `number` was created, edited or deleted. Subscribers only
+    /// hear about new comments.
+    pub fn comment(&self, repo: &Repository, actor_id: &str, action: &str, thread: &CommentThread, number: i32, comment: &Comment) {
+        let payload = comment_payload(action, thread, number, comment);
+
+        // Comment events go to the repository and to the issue or pull request the comment is on
+        if thread.issue_id().is_some() {
+            self.events.publish_issue(repo, number, WebhookEvent::Comment, actor_id, payload.clone());
+        } else {
+            self.events.publish_pull_request(repo, number, WebhookEvent::Comment, actor_id, payload.clone());
+        }
+        self.webhooks.dispatch(repo, actor_id, WebhookEvent::Comment, payload);
+
+        if action == "created" {
+            self.notifications.record_activity(repo, thread.clone(), actor_id, Some(NotificationReason::Comment), Some(&comment.body));
+        }
+    }
+
+    /// Branches moved between two snapshots of branch tips, through a push or a merge.
+    pub fn push(&self, repo: &Repository, pusher_id: &str, heads_before: HashMap<String, String>, heads_after: HashMap<String, String>) {
+        self.pipelines.schedule_push(repo, pusher_id, &heads_before, &heads_after);
+        self.events.publish_push(repo, pusher_id, &heads_before, &heads_after);
+        self.webhooks.dispatch_push(repo, pusher_id, heads_before, heads_after);
+    }
+}
+
+// Comment webhook payloads name the issue or pull request the comment is on
+fn comment_payload(action: &str, thread: &CommentThread, number: i32, comment: &Comment) -> Value {
+    let parent = if thread.issue_id().is_some() { "issue" } else { "pull_request" };
+
+    json!({ "action": action, "comment": comment, parent: { "number": number } })
+}
@@ -1,32 +1,26 @@
-use crate::models::UserWithPassword;
+use crate::models::{ExternalIdentity, LinkedIdentity, TokenScope, UserWithPassword};
+use crate::services::{AccessTokenService, OAuthService};
+use crate::services::access_token_service::{is_access_token, to_hex};
+use crate::services::oauth_service::is_oauth_access_token;
 use bcrypt::{hash, verify, DEFAULT_COST};
-use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
-use serde::{Deserialize, Serialize};
+use ring::rand::{SecureRandom, SystemRandom};
 use sqlx::MySqlPool;
 use uuid::Uuid;
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct Claims {
-    pub sub: String,
-    pub username: String,
-    pub email: String,
-    pub exp: usize,
-    pub iat: usize,
-}
-
+// Checks credentials; tokens for the resulting session are issued by SessionService
 #[derive(Clone)]
 pub struct AuthService {
     pool: MySqlPool,
-    jwt_secret: String,
+    tokens: AccessTokenService,
+    oauth: OAuthService,
 }
 
 impl AuthService {
-    pub fn new(pool: MySqlPool, jwt_secret: String) -> Self {
-        Self { pool, jwt_secret }
+    pub fn new(pool: MySqlPool, tokens: AccessTokenService, oauth: OAuthService) -> Self {
+        Self { pool, tokens, oauth }
     }
 
-    pub async fn register(&self, username: &str, email: &str, password: &str, full_name: Option<&str>) -> Result<String, String> {
+    pub async fn register(&self, username: &str, email: &str, password: &str, full_name: Option<&str>) -> Result<UserWithPassword, String> {
         // Validate input
         if username.is_empty() || email.is_empty() || password.is_empty() {
             return Err("Username, email, and password are required".to_string());
@@ -54,6 +48,16 @@ impl AuthService {
             return Err("User with this username or email already exists".to_string());
         }
 
+        // Usernames share the repository owner namespace with organizations
+        let existing_org = sqlx::query!("SELECT id FROM organizations WHERE name = ?", username)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        if existing_org.is_some() {
+            return Err("User with this username or email already exists".to_string());
+        }
+
         // Hash password
         let password_hash = hash(password, DEFAULT_COST)
             .map_err(|e| format!("Password hashing error: {}", e))?;
@@ -98,69 +102,239 @@ impl AuthService {
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-        self.generate_token(&user)
+        Ok(user)
     }
 
-    pub async fn authenticate(&self, username_or_email: &str, password: &str) -> Result<String, String> {
-        // Validate input
-        if username_or_email.is_empty() || password.is_empty() {
-            return Err("Username/email and password are required".to_string());
+    pub async fn authenticate(&self, username_or_email: &str, password: &str) -> Result<UserWithPassword, String> {
+        let user = self.verify_credentials(username_or_email, password).await?;
+
+        // Update last login timestamp
+        sqlx::query!(
+            "UPDATE users SET updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+            user.id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(user)
+    }
+
+    /// Signs in with an identity from an external provider. A known identity signs in its user;
+    /// otherwise it is linked to the account with the same email when both sides have verified
+    /// that address, and a new account is created when no account uses it.
+    pub async fn authenticate_external(&self, identity: &ExternalIdentity) -> Result<UserWithPassword, String> {
+        let linked = sqlx::query!(
+            "SELECT id, user_id FROM user_identities WHERE provider = ? AND subject = ?",
+            identity.provider,
+            identity.subject
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if let Some(linked) = linked {
+            sqlx::query!("UPDATE user_identities SET last_used = NOW() WHERE id = ?", linked.id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+
+            return self.find_user(&linked.user_id).await;
         }
 
-        // Start transaction
-        let mut transaction = self.pool.begin().await
-            .map_err(|e| format!("Transaction error: {}", e))?;
+        let email = identity.email.as_deref()
+            .filter(|email| email.contains('@'))
+            .ok_or("The identity provider did not share an email address".to_string())?;
 
-        // Find user by username or email
-        let user = sqlx::query_as!(
-            UserWithPassword,
-            r#"
-            SELECT 
-                id, username, email, full_name, password_hash, 
-                avatar_url, bio, website_url, location, company, 
-                is_admin, is_verified, created_at, updated_at
-            FROM users 
-            WHERE username = ? OR email = ?
-            "#,
-            username_or_email,
-            username_or_email
+        let existing_user = sqlx::query!("SELECT id, is_verified FROM users WHERE email = ?", email)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        let user_id = match existing_user {
+            // Anyone can register an address or claim one at a provider, so an email match
+            // only proves ownership when both the provider and this account have verified it
+            Some(user) if identity.email_verified && user.is_verified.unwrap_or(0) != 0 => {
+                self.insert_identity(&user.id, identity).await?;
+                user.id
+            }
+            Some(_) => {
+                return Err("An account with this email already exists; sign in and link the provider from your account settings".to_string());
+            }
+            None => self.create_external_user(identity, email).await?,
+        };
+
+        self.find_user(&user_id).await
+    }
+
+    /// Links an external identity to a user who is already signed in.
+    pub async fn link_external_identity(&self, user_id: &str, identity: &ExternalIdentity) -> Result<(), String> {
+        let linked = sqlx::query!(
+            "SELECT user_id FROM user_identities WHERE provider = ? AND subject = ?",
+            identity.provider,
+            identity.subject
         )
-        .fetch_optional(&mut *transaction)
+        .fetch_optional(&self.pool)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-        let user = user.ok_or("Invalid credentials".to_string())?;
+        match linked {
+            Some(linked) if linked.user_id == user_id => Ok(()),
+            Some(_) => Err("This account is already linked to another user".to_string()),
+            None => self.insert_identity(user_id, identity).await,
+        }
+    }
 
-        // Verify password
-        if !verify(password, &user.password_hash)
-            .map_err(|e| format!("Password verification error: {}", e))? {
-            return Err("Invalid credentials".to_string());
+    async fn insert_identity(&self, user_id: &str, identity: &ExternalIdentity) -> Result<(), String> {
+        let existing = sqlx::query!(
+            "SELECT id FROM user_identities WHERE user_id = ? AND provider = ?",
+            user_id,
+            identity.provider
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if existing.is_some() {
+            return Err(format!("Another {} account is already linked", identity.provider));
         }
 
-        // Update last login timestamp
+        let identity_id = format!("ident_{}", Uuid::new_v4().to_string().replace("-", ""));
+
         sqlx::query!(
-            "UPDATE users SET updated_at = CURRENT_TIMESTAMP WHERE id = ?",
-            user.id
+            "INSERT INTO user_identities (id, user_id, provider, subject, email, created_at, last_used) VALUES (?, ?, ?, ?, ?, NOW(), NOW())",
+            identity_id,
+            user_id,
+            identity.provider,
+            identity.subject,
+            identity.email
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    // New accounts get a random password nobody knows; a password can be set later through a reset
+    async fn create_external_user(&self, identity: &ExternalIdentity, email: &str) -> Result<String, String> {
+        let username = self.available_username(identity, email).await?;
+
+        let mut secret = [0u8; 32];
+        SystemRandom::new()
+            .fill(&mut secret)
+            .map_err(|_| "Failed to generate password".to_string())?;
+        let password_hash = hash(to_hex(&secret), DEFAULT_COST)
+            .map_err(|e| format!("Password hashing error: {}", e))?;
+
+        let user_id = format!("user_{}", Uuid::new_v4().to_string().replace("-", ""));
+        let identity_id = format!("ident_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        sqlx::query!(
+            "INSERT INTO users (id, username, email, full_name, avatar_url, password_hash, is_admin, is_verified) VALUES (?, ?, ?, ?, ?, ?, 0, ?)",
+            user_id,
+            username,
+            email,
+            identity.full_name,
+            identity.avatar_url,
+            password_hash,
+            identity.email_verified
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!(
+            "INSERT INTO user_identities (id, user_id, provider, subject, email, created_at, last_used) VALUES (?, ?, ?, ?, ?, NOW(), NOW())",
+            identity_id,
+            user_id,
+            identity.provider,
+            identity.subject,
+            identity.email
         )
         .execute(&mut *transaction)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-        // Commit transaction
         transaction.commit().await
             .map_err(|e| format!("Transaction commit error: {}", e))?;
 
-        self.generate_token(&user)
+        Ok(user_id)
+    }
+
+    // The provider's username when it is free, otherwise the same name with a numeric suffix
+    async fn available_username(&self, identity: &ExternalIdentity, email: &str) -> Result<String, String> {
+        let preferred = identity.username.as_deref()
+            .unwrap_or_else(|| email.split('@').next().unwrap_or_default());
+
+        let mut base: String = preferred
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .take(32)
+            .collect();
+        if base.is_empty() {
+            base = "user".to_string();
+        }
+
+        for attempt in 1..=100 {
+            let candidate = if attempt == 1 { base.clone() } else { format!("{}-{}", base, attempt) };
+
+            // Usernames share the repository owner namespace with organizations
+            let taken = sqlx::query!(
+                "SELECT id FROM users WHERE username = ? UNION SELECT id FROM organizations WHERE name = ?",
+                candidate,
+                candidate
+            )
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+            if taken.is_none() {
+                return Ok(candidate);
+            }
+        }
+
+        Err("Could not find an available username".to_string())
     }
 
-    pub async fn validate_token(&self, token: &str) -> Result<UserWithPassword, String> {
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(self.jwt_secret.as_ref()),
-            &Validation::default()
+    pub async fn list_identities(&self, user_id: &str) -> Result<Vec<LinkedIdentity>, String> {
+        sqlx::query_as!(
+            LinkedIdentity,
+            "SELECT id, provider, email, created_at, last_used FROM user_identities WHERE user_id = ? ORDER BY created_at ASC",
+            user_id
         )
-        .map_err(|e| format!("Invalid token: {}", e))?;
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))
+    }
 
+    pub async fn unlink_identity(&self, user_id: &str, identity_id: &str) -> Result<(), String> {
+        let result = sqlx::query!(
+            "DELETE FROM user_identities WHERE id = ? AND user_id = ?",
+            identity_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Identity not found".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub async fn verify_credentials(&self, username_or_email: &str, password: &str) -> Result<UserWithPassword, String> {
+        // Validate input
+        if username_or_email.is_empty() || password.is_empty() {
+            return Err("Username/email and password are required".to_string());
+        }
+
+        // Find user by username or email
         let user = sqlx::query_as!(
             UserWithPassword,
             r#"
@@ -169,18 +343,62 @@ impl AuthService {
                 avatar_url, bio, website_url, location, company, 
                 is_admin, is_verified, created_at, updated_at
             FROM users 
-            WHERE id = ?
+            WHERE username = ? OR email = ?
             "#,
-            token_data.claims.sub
+            username_or_email,
+            username_or_email
         )
         .fetch_optional(&self.pool)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-        user.ok_or("User not found".to_string())
+        let user = user.ok_or("Invalid credentials".to_string())?;
+
+        // Verify password
+        if !verify(password, &user.password_hash)
+            .map_err(|e| format!("Password verification error: {}", e))? {
+            return Err("Invalid credentials".to_string());
+        }
+
+        Ok(user)
     }
 
-    pub async fn refresh_token(&self, user_id: &str) -> Result<String, String> {
+    pub async fn authenticate_git(&self, username: &str, secret: &str) -> Result<UserWithPassword, String> {
+        // Personal and OAuth access tokens only work for git when they carry the repo scope
+        if is_access_token(secret) || is_oauth_access_token(secret) {
+            let token = if is_oauth_access_token(secret) {
+                self.oauth.authenticate(secret).await?
+            } else {
+                self.tokens.authenticate(secret).await?
+            };
+
+            if !token.has_scope(TokenScope::Repo) {
+                return Err("Access token is missing the 'repo' scope".to_string());
+            }
+
+            return self.find_user(&token.user_id).await;
+        }
+
+        let user = self.verify_credentials(username, secret).await?;
+
+        // A password alone would skip the second factor
+        let two_factor_enabled = sqlx::query!(
+            r#"SELECT two_factor_enabled as "two_factor_enabled: bool" FROM users WHERE id = ?"#,
+            user.id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .two_factor_enabled;
+
+        if two_factor_enabled {
+            return Err("Two-factor authentication is enabled; use a personal access token or OAuth token instead of your password".to_string());
+        }
+
+        Ok(user)
+    }
+
+    pub async fn find_user(&self, user_id: &str) -> Result<UserWithPassword, String> {
         let user = sqlx::query_as!(
             UserWithPassword,
             r#"
@@ -197,28 +415,6 @@ impl AuthService {
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-        let user = user.ok_or("User not found".to_string())?;
-        self.generate_token(&user)
-    }
-
-    fn generate_token(&self, user: &UserWithPassword) -> Result<String, String> {
-        let now = Utc::now();
-        let exp = (now + Duration::hours(24)).timestamp() as usize;
-        let iat = now.timestamp() as usize;
-
-        let claims = Claims {
-            sub: user.id.clone(),
-            username: user.username.clone(),
-            email: user.email.clone(),
-            exp,
-            iat,
-        };
-
-        encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(self.jwt_secret.as_ref())
-        )
-        .map_err(|e| format!("Token generation error: {}", e))
+        user.ok_or("User not found".to_string())
     }
 }
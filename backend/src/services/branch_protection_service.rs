@@ -0,0 +1,268 @@
+use crate::models::{BranchProtection, CommitState, UpdateBranchProtectionRequest};
+use crate::services::CommitStatusService;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct BranchProtectionService {
+    pool: MySqlPool,
+    statuses: CommitStatusService,
+}
+
+impl BranchProtectionService {
+    pub fn new(pool: MySqlPool, statuses: CommitStatusService) -> Self {
+        Self { pool, statuses }
+    }
+
+    // None when the branch is not protected
+    pub async fn get_protection(&self, repo_id: &str, branch: &str) -> Result<Option<BranchProtection>, String> {
+        let row = sqlx::query!(
+            r#"
+            SELECT
+                id, repository_id, branch, required_approving_reviews,
+                allow_force_pushes as "allow_force_pushes: bool",
+                allow_deletions as "allow_deletions: bool",
+                restrict_pushes as "restrict_pushes: bool",
+                created_at, updated_at
+            FROM branch_protections
+            WHERE repository_id = ? AND branch = ?
+            "#,
+            repo_id, branch
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let checks = sqlx::query!(
+            "SELECT context FROM branch_protection_status_checks WHERE protection_id = ? ORDER BY context ASC",
+            row.id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let allowances = sqlx::query!(
+            r#"
+            SELECT u.username
+            FROM branch_protection_push_allowances a
+            INNER JOIN users u ON a.user_id = u.id
+            WHERE a.protection_id = ?
+            ORDER BY u.username ASC
+            "#,
+            row.id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(Some(BranchProtection {
+            id: row.id,
+            repository_id: row.repository_id,
+            branch: row.branch,
+            required_approving_reviews: row.required_approving_reviews,
+            allow_force_pushes: row.allow_force_pushes,
+            allow_deletions: row.allow_deletions,
+            required_status_checks: checks.into_iter().map(|check| check.context).collect(),
+            restrict_pushes: row.restrict_pushes,
+            push_allowances: allowances.into_iter().map(|allowance| allowance.username).collect(),
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }))
+    }
+
+    // Creates the rule on first use; fields left out of the request keep their current value
+    pub async fn update_protection(&self, repo_id: &str, branch: &str, request: &UpdateBranchProtectionRequest) -> Result<BranchProtection, String> {
+        if request.required_approving_reviews.is_some_and(|count| !(0..=6).contains(&count)) {
+            return Err("required_approving_reviews must be between 0 and 6".to_string());
+        }
+
+        // Resolve usernames before touching anything so a typo leaves the rule unchanged
+        let allowed_user_ids = match &request.push_allowances {
+            Some(usernames) => {
+                let mut ids = Vec::with_capacity(usernames.len());
+                for username in usernames {
+                    let user = sqlx::query!("SELECT id FROM users WHERE username = ?", username)
+                        .fetch_optional(&self.pool)
+                        .await
+                        .map_err(|e| format!("Database error: {}", e))?
+                        .ok_or_else(|| format!("Unknown user: {}", username))?;
+                    ids.push(user.id);
+                }
+                Some(ids)
+            }
+            None => None,
+        };
+
+        let protection_id = match self.get_protection(repo_id, branch).await? {
+            Some(protection) => protection.id,
+            None => format!("bp_{}", Uuid::new_v4().to_string().replace("-", "")),
+        };
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO branch_protections (
+                id, repository_id, branch, required_approving_reviews,
+                allow_force_pushes, allow_deletions, restrict_pushes, created_at, updated_at
+            )
+            VALUES (?, ?, ?, COALESCE(?, 0), COALESCE(?, FALSE), COALESCE(?, FALSE), COALESCE(?, FALSE), NOW(), NOW())
+            ON DUPLICATE KEY UPDATE
+                required_approving_reviews = COALESCE(?, required_approving_reviews),
+                allow_force_pushes = COALESCE(?, allow_force_pushes),
+                allow_deletions = COALESCE(?, allow_deletions),
+                restrict_pushes = COALESCE(?, restrict_pushes),
+                updated_at = NOW()
+            "#,
+            protection_id, repo_id, branch,
+            request.required_approving_reviews, request.allow_force_pushes,
+            request.allow_deletions, request.restrict_pushes,
+            request.required_approving_reviews, request.allow_force_pushes,
+            request.allow_deletions, request.restrict_pushes
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if let Some(contexts) = &request.required_status_checks {
+            sqlx::query!("DELETE FROM branch_protection_status_checks WHERE protection_id = ?", protection_id)
+                .execute(&mut *transaction)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+
+            for context in contexts {
+                sqlx::query!(
+                    "INSERT IGNORE INTO branch_protection_status_checks (protection_id, context) VALUES (?, ?)",
+                    protection_id, context
+                )
+                .execute(&mut *transaction)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+            }
+        }
+
+        if let Some(user_ids) = &allowed_user_ids {
+            sqlx::query!("DELETE FROM branch_protection_push_allowances WHERE protection_id = ?", protection_id)
+                .execute(&mut *transaction)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+
+            for user_id in user_ids {
+                sqlx::query!(
+                    "INSERT IGNORE INTO branch_protection_push_allowances (protection_id, user_id) VALUES (?, ?)",
+                    protection_id, user_id
+                )
+                .execute(&mut *transaction)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+            }
+        }
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        self.get_protection(repo_id, branch)
+            .await?
+            .ok_or_else(|| "Branch protection not found".to_string())
+    }
+
+    pub async fn delete_protection(&self, repo_id: &str, branch: &str) -> Result<(), String> {
+        let result = sqlx::query!(
+            "DELETE FROM branch_protections WHERE repository_id = ? AND branch = ?",
+            repo_id, branch
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Branch protection not found".to_string());
+        }
+
+        Ok(())
+    }
+
+    // Repository admins may always push; everyone else needs an allowance when pushes are restricted
+    pub async fn can_push(&self, protection: &BranchProtection, user_id: &str, is_admin: bool) -> Result<bool, String> {
+        if !protection.restrict_pushes || is_admin {
+            return Ok(true);
+        }
+
+        let count = sqlx::query!(
+            "SELECT COUNT(*) as count FROM branch_protection_push_allowances WHERE protection_id = ? AND user_id = ?",
+            protection.id, user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(count.count > 0)
+    }
+
+    // Explains why a pull request cannot be merged into the protected branch, if it cannot
+    pub async fn check_merge(&self, protection: &BranchProtection, pr_id: &str, head_sha: &str, user_id: &str, is_admin: bool) -> Result<Option<String>, String> {
+        if !self.can_push(protection, user_id, is_admin).await? {
+            return Ok(Some(format!("You are not allowed to push to {}", protection.branch)));
+        }
+
+        if protection.required_approving_reviews > 0 {
+            // Only each reviewer's latest approving or blocking review counts
+            let reviews = sqlx::query!(
+                r#"
+                SELECT r.state
+                FROM pull_request_reviews r
+                INNER JOIN pull_requests p ON r.pull_request_id = p.id
+                WHERE r.pull_request_id = ? AND r.author_id <> p.author_id
+                    AND r.state IN ('APPROVE', 'REQUEST_CHANGES')
+                    AND NOT EXISTS (
+                        SELECT 1 FROM pull_request_reviews newer
+                        WHERE newer.pull_request_id = r.pull_request_id
+                            AND newer.author_id = r.author_id
+                            AND newer.state IN ('APPROVE', 'REQUEST_CHANGES')
+                            AND (newer.submitted_at > r.submitted_at
+                                OR (newer.submitted_at = r.submitted_at AND newer.id > r.id))
+                    )
+                "#,
+                pr_id
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+            if reviews.iter().any(|review| review.state == "REQUEST_CHANGES") {
+                return Ok(Some("Changes have been requested by a reviewer".to_string()));
+            }
+
+            let approvals = reviews.len() as i32;
+            if approvals < protection.required_approving_reviews {
+                return Ok(Some(format!(
+                    "At least {} approving review(s) required, found {}",
+                    protection.required_approving_reviews, approvals
+                )));
+            }
+        }
+
+        // Required checks are matched by status context or check run name on the head commit
+        if !protection.required_status_checks.is_empty() {
+            let states = self.statuses.context_states(&protection.repository_id, head_sha).await?;
+
+            for context in &protection.required_status_checks {
+                match states.get(context) {
+                    None => return Ok(Some(format!("Required status check '{}' is expected", context))),
+                    Some(CommitState::Success) => {}
+                    Some(state) => {
+                        return Ok(Some(format!("Required status check '{}' is {}", context, state.as_str())));
+                    }
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
@@ -0,0 +1,204 @@
+use crate::models::{Collaborator, Permission, RepositoryInvitation};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct CollaboratorService {
+    pool: MySqlPool,
+}
+
+impl CollaboratorService {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_collaborators(&self, repo_id: &str) -> Result<Vec<Collaborator>, String> {
+        let collaborators = sqlx::query_as!(
+            Collaborator,
+            r#"
+            SELECT c.user_id, u.username, c.permission, c.created_at
+            FROM repository_collaborators c
+            INNER JOIN users u ON c.user_id = u.id
+            WHERE c.repository_id = ?
+            ORDER BY u.username ASC
+            "#,
+            repo_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(collaborators)
+    }
+
+    // None when the user is not a collaborator
+    pub async fn get_permission(&self, repo_id: &str, user_id: &str) -> Result<Option<Permission>, String> {
+        let collaborator = sqlx::query!(
+            "SELECT permission FROM repository_collaborators WHERE repository_id = ? AND user_id = ?",
+            repo_id, user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(collaborator.and_then(|collaborator| Permission::parse(&collaborator.permission)))
+    }
+
+    pub async fn update_permission(&self, repo_id: &str, user_id: &str, permission: Permission) -> Result<(), String> {
+        sqlx::query!(
+            "UPDATE repository_collaborators SET permission = ? WHERE repository_id = ? AND user_id = ?",
+            permission.as_str(), repo_id, user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn remove_collaborator(&self, repo_id: &str, user_id: &str) -> Result<(), String> {
+        let result = sqlx::query!(
+            "DELETE FROM repository_collaborators WHERE repository_id = ? AND user_id = ?",
+            repo_id, user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Collaborator not found".to_string());
+        }
+
+        Ok(())
+    }
+
+    // Inviting a user again replaces the permission of their pending invitation
+    pub async fn invite(&self, repo_id: &str, inviter_id: &str, invitee_id: &str, permission: Permission) -> Result<RepositoryInvitation, String> {
+        let invitation_id = format!("rinv_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO repository_invitations (id, repository_id, invitee_id, inviter_id, permission, created_at)
+            VALUES (?, ?, ?, ?, ?, NOW())
+            ON DUPLICATE KEY UPDATE inviter_id = VALUES(inviter_id), permission = VALUES(permission), created_at = NOW()
+            "#,
+            invitation_id, repo_id, invitee_id, inviter_id, permission.as_str()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let invitation = sqlx::query!(
+            "SELECT id FROM repository_invitations WHERE repository_id = ? AND invitee_id = ?",
+            repo_id, invitee_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_invitation(&invitation.id).await
+    }
+
+    pub async fn get_invitation(&self, invitation_id: &str) -> Result<RepositoryInvitation, String> {
+        let invitation = sqlx::query_as!(
+            RepositoryInvitation,
+            r#"
+            SELECT
+                i.id, i.repository_id, r.name as repository_name,
+                i.invitee_id, u.username as invitee_username, i.inviter_id, i.permission, i.created_at
+            FROM repository_invitations i
+            INNER JOIN repositories r ON i.repository_id = r.id
+            INNER JOIN users u ON i.invitee_id = u.id
+            WHERE i.id = ?
+            "#,
+            invitation_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        invitation.ok_or_else(|| "Invitation not found".to_string())
+    }
+
+    pub async fn list_repository_invitations(&self, repo_id: &str) -> Result<Vec<RepositoryInvitation>, String> {
+        let invitations = sqlx::query_as!(
+            RepositoryInvitation,
+            r#"
+            SELECT
+                i.id, i.repository_id, r.name as repository_name,
+                i.invitee_id, u.username as invitee_username, i.inviter_id, i.permission, i.created_at
+            FROM repository_invitations i
+            INNER JOIN repositories r ON i.repository_id = r.id
+            INNER JOIN users u ON i.invitee_id = u.id
+            WHERE i.repository_id = ?
+            ORDER BY i.created_at DESC
+            "#,
+            repo_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(invitations)
+    }
+
+    pub async fn list_user_invitations(&self, user_id: &str) -> Result<Vec<RepositoryInvitation>, String> {
+        let invitations = sqlx::query_as!(
+            RepositoryInvitation,
+            r#"
+            SELECT
+                i.id, i.repository_id, r.name as repository_name,
+                i.invitee_id, u.username as invitee_username, i.inviter_id, i.permission, i.created_at
+            FROM repository_invitations i
+            INNER JOIN repositories r ON i.repository_id = r.id
+            INNER JOIN users u ON i.invitee_id = u.id
+            WHERE i.invitee_id = ?
+            ORDER BY i.created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(invitations)
+    }
+
+    // Turns the invitation into a collaborator with the invited permission
+    pub async fn accept_invitation(&self, invitation: &RepositoryInvitation) -> Result<(), String> {
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO repository_collaborators (repository_id, user_id, permission, created_at)
+            VALUES (?, ?, ?, NOW())
+            ON DUPLICATE KEY UPDATE permission = VALUES(permission)
+            "#,
+            invitation.repository_id, invitation.invitee_id, invitation.permission
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!("DELETE FROM repository_invitations WHERE id = ?", invitation.id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        Ok(())
+    }
+
+    // Used both when the invitee declines and when an admin cancels
+    pub async fn delete_invitation(&self, invitation_id: &str) -> Result<(), String> {
+        sqlx::query!("DELETE FROM repository_invitations WHERE id = ?", invitation_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use crate::models::{Comment, CommentEdit, CommentThread};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct CommentService {
+    pool: MySqlPool,
+}
+
+impl CommentService {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+
+    // Resolves an issue or pull request number to the thread its comments hang off; issue_numbers
+    // guarantees at most one of the two tables holds a given number
+    pub async fn find_thread(&self, repo_id: &str, number: i32) -> Result<CommentThread, String> {
+        let issue = sqlx::query!(
+            "SELECT id FROM issues WHERE repository_id = ? AND number = ?",
+            repo_id, number
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if let Some(issue) = issue {
+            return Ok(CommentThread::Issue(issue.id));
+        }
+
+        let pr = sqlx::query!(
+            "SELECT id FROM pull_requests WHERE repository_id = ? AND number = ?",
+            repo_id, number
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        pr.map(|pr| CommentThread::PullRequest(pr.id))
+            .ok_or_else(|| "Issue not found".to_string())
+    }
+
+    pub async fn list_comments(&self, thread: &CommentThread, limit: u32, offset: u32) -> Result<Vec<Comment>, String> {
+        let comments = sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT id, body, author_id, issue_id, pull_request_id, created_at, updated_at
+            FROM comments
+            WHERE issue_id = ? OR pull_request_id = ?
+            ORDER BY created_at ASC, id ASC
+            LIMIT ? OFFSET ?
+            "#,
+            thread.issue_id(), thread.pull_request_id(), limit, offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(comments)
+    }
+
+    // Only returns the comment if it belongs to the given thread
+    pub async fn get_comment(&self, thread: &CommentThread, comment_id: &str) -> Result<Comment, String> {
+        let comment = sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT id, body, author_id, issue_id, pull_request_id, created_at, updated_at
+            FROM comments
+            WHERE id = ? AND (issue_id = ? OR pull_request_id = ?)
+            "#,
+            comment_id, thread.issue_id(), thread.pull_request_id()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        comment.ok_or_else(|| "Comment not found".to_string())
+    }
+
+    pub async fn create_comment(&self, thread: &CommentThread, author_id: &str, body: &str) -> Result<Comment, String> {
+        if body.trim().is_empty() {
+            return Err("Comment body cannot be empty".to_string());
+        }
+
+        let comment_id = format!("comment_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO comments (id, body, author_id, issue_id, pull_request_id, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, NOW(), NOW())
+            "#,
+            comment_id, body, author_id, thread.issue_id(), thread.pull_request_id()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_comment(thread, &comment_id).await
+    }
+
+    // Replaces the body and records the previous one in comment_edits
+    pub async fn update_comment(&self, comment: &Comment, editor_id: &str, body: &str) -> Result<Comment, String> {
+        if body.trim().is_empty() {
+            return Err("Comment body cannot be empty".to_string());
+        }
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        let edit_id = format!("edit_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO comment_edits (id, comment_id, previous_body, edited_by_id, created_at)
+            VALUES (?, ?, ?, ?, NOW())
+            "#,
+            edit_id, comment.id, comment.body, editor_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!(
+            "UPDATE comments SET body = ?, updated_at = NOW() WHERE id = ?",
+            body, comment.id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        let comment = sqlx::query_as!(
+            Comment,
+            r#"
+            SELECT id, body, author_id, issue_id, pull_request_id, created_at, updated_at
+            FROM comments
+            WHERE id = ?
+            "#,
+            comment.id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(comment)
+    }
+
+    pub async fn delete_comment(&self, comment_id: &str) -> Result<(), String> {
+        sqlx::query!("DELETE FROM comments WHERE id = ?", comment_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    // Newest edit first
+    pub async fn list_comment_edits(&self, comment_id: &str) -> Result<Vec<CommentEdit>, String> {
+        let edits = sqlx::query_as!(
+            CommentEdit,
+            r#"
+            SELECT id, comment_id, previous_body, edited_by_id, created_at
+            FROM comment_edits
+            WHERE comment_id = ?
+            ORDER BY created_at DESC, id DESC
+            "#,
+            comment_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(edits)
+    }
+}
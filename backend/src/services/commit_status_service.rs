@@ -0,0 +1,536 @@
+use crate::models::{
+    AnnotationLevel, CheckRun, CheckRunAnnotation, CheckRunAnnotationRequest, CheckRunConclusion, CheckRunOutput,
+    CheckRunStatus, CombinedStatus, CommitState, CommitStatus, CreateCheckRunRequest,
+    CreateCommitStatusRequest, UpdateCheckRunRequest,
+};
+use chrono::{DateTime, Utc};
+use sqlx::{MySql, MySqlPool, Transaction};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+const MAX_ANNOTATIONS_PER_REQUEST: usize = 50;
+
+// Failure wins over pending, which wins over success; nothing reported counts as pending
+pub fn combine_states(states: impl IntoIterator<Item = CommitState>) -> CommitState {
+    let mut combined = None;
+
+    for state in states {
+        combined = Some(match (combined, state) {
+            (_, CommitState::Error | CommitState::Failure) | (Some(CommitState::Failure), _) => CommitState::Failure,
+            (_, CommitState::Pending) | (Some(CommitState::Pending), _) => CommitState::Pending,
+            _ => CommitState::Success,
+        });
+    }
+
+    combined.unwrap_or(CommitState::Pending)
+}
+
+// Neutral and skipped runs do not block anything
+fn check_run_state(run: &CheckRun) -> CommitState {
+    match (run.status, run.conclusion) {
+        (CheckRunStatus::Completed, Some(CheckRunConclusion::Success | CheckRunConclusion::Neutral | CheckRunConclusion::Skipped)) => CommitState::Success,
+        (CheckRunStatus::Completed, _) => CommitState::Failure,
+        _ => CommitState::Pending,
+    }
+}
+
+fn validate_url(url: Option<&str>, field: &str) -> Result<(), String> {
+    if let Some(url) = url {
+        let valid = reqwest::Url::parse(url).is_ok_and(|parsed| matches!(parsed.scheme(), "http" | "https"));
+        if !valid || url.len() > 500 {
+            return Err(format!("{} must be an http or https URL", field));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_annotations(annotations: &[CheckRunAnnotationRequest]) -> Result<(), String> {
+    if annotations.len() > MAX_ANNOTATIONS_PER_REQUEST {
+        return Err(format!("At most {} annotations can be added per request", MAX_ANNOTATIONS_PER_REQUEST));
+    }
+
+    for annotation in annotations {
+        let end_line = annotation.end_line.unwrap_or(annotation.start_line);
+        if annotation.path.is_empty() || annotation.start_line < 1 || end_line < annotation.start_line {
+            return Err(format!("Invalid annotation range for '{}'", annotation.path));
+        }
+        if annotation.message.trim().is_empty() {
+            return Err("Annotation message cannot be empty".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+// A conclusion implies the run is complete, and a completed run needs a conclusion
+fn resolve_run_status(status: Option<CheckRunStatus>, conclusion: Option<CheckRunConclusion>) -> Result<Option<CheckRunStatus>, String> {
+    match (status, conclusion) {
+        (Some(CheckRunStatus::Queued | CheckRunStatus::InProgress), Some(_)) => {
+            Err("A conclusion can only be given for a completed check run".to_string())
+        }
+        (_, Some(_)) => Ok(Some(CheckRunStatus::Completed)),
+        (status, None) => Ok(status),
+    }
+}
+
+struct StatusRow {
+    id: String,
+    sha: String,
+    state: String,
+    context: String,
+    description: Option<String>,
+    target_url: Option<String>,
+    creator_id: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl From<StatusRow> for CommitStatus {
+    fn from(row: StatusRow) -> Self {
+        Self {
+            id: row.id,
+            sha: row.sha,
+            state: CommitState::parse(&row.state).unwrap_or(CommitState::Pending),
+            context: row.context,
+            description: row.description,
+            target_url: row.target_url,
+            creator_id: row.creator_id,
+            created_at: row.created_at,
+        }
+    }
+}
+
+struct CheckRunRow {
+    id: String,
+    head_sha: String,
+    name: String,
+    status: String,
+    conclusion: Option<String>,
+    details_url: Option<String>,
+    external_id: Option<String>,
+    output_title: Option<String>,
+    output_summary: Option<String>,
+    output_text: Option<String>,
+    annotations_count: i64,
+    started_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<CheckRunRow> for CheckRun {
+    fn from(row: CheckRunRow) -> Self {
+        Self {
+            id: row.id,
+            head_sha: row.head_sha,
+            name: row.name,
+            status: CheckRunStatus::parse(&row.status).unwrap_or(CheckRunStatus::Queued),
+            conclusion: row.conclusion.as_deref().and_then(CheckRunConclusion::parse),
+            details_url: row.details_url,
+            external_id: row.external_id,
+            output: CheckRunOutput {
+                title: row.output_title,
+                summary: row.output_summary,
+                text: row.output_text,
+                annotations_count: row.annotations_count,
+            },
+            started_at: row.started_at,
+            completed_at: row.completed_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// Commit statuses and check runs reported against commits, typically by CI. Both feed the
+/// combined status of a commit, the head status shown on pull requests and required status checks.
+#[derive(Clone)]
+pub struct CommitStatusService {
+    pool: MySqlPool,
+}
+
+impl CommitStatusService {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_status(&self, repo_id: &str, sha: &str, creator_id: &str, request: &CreateCommitStatusRequest) -> Result<CommitStatus, String> {
+        let context = request.context.as_deref().map(str::trim).filter(|context| !context.is_empty()).unwrap_or("default");
+        if context.len() > 255 {
+            return Err("context must be at most 255 characters".to_string());
+        }
+        if request.description.as_ref().is_some_and(|description| description.len() > 1000) {
+            return Err("description must be at most 1000 characters".to_string());
+        }
+        validate_url(request.target_url.as_deref(), "target_url")?;
+
+        let status_id = format!("cs_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO commit_statuses (id, repository_id, sha, state, context, description, target_url, creator_id, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, NOW(3))
+            "#,
+            status_id, repo_id, sha, request.state.as_str(), context, request.description, request.target_url, creator_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.refresh_pull_requests(repo_id, sha).await?;
+
+        let status = sqlx::query_as!(
+            StatusRow,
+            r#"
+            SELECT id, sha, state, context, description, target_url, creator_id, created_at
+            FROM commit_statuses
+            WHERE id = ?
+            "#,
+            status_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(status.into())
+    }
+
+    // Every status reported for a commit, newest first
+    pub async fn list_statuses(&self, repo_id: &str, sha: &str, limit: u32, offset: u32) -> Result<Vec<CommitStatus>, String> {
+        let statuses = sqlx::query_as!(
+            StatusRow,
+            r#"
+            SELECT id, sha, state, context, description, target_url, creator_id, created_at
+            FROM commit_statuses
+            WHERE repository_id = ? AND sha = ?
+            ORDER BY created_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+            repo_id, sha, limit, offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(statuses.into_iter().map(CommitStatus::from).collect())
+    }
+
+    // The latest status of each context
+    async fn latest_statuses(&self, repo_id: &str, sha: &str) -> Result<Vec<CommitStatus>, String> {
+        let statuses = sqlx::query_as!(
+            StatusRow,
+            r#"
+            SELECT id, sha, state, context, description, target_url, creator_id, created_at
+            FROM commit_statuses
+            WHERE repository_id = ? AND sha = ?
+            ORDER BY created_at DESC
+            "#,
+            repo_id, sha
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let mut seen = HashSet::new();
+        let mut latest: Vec<CommitStatus> = statuses
+            .into_iter()
+            .filter(|status| seen.insert(status.context.clone()))
+            .map(CommitStatus::from)
+            .collect();
+
+        latest.sort_by(|a, b| a.context.cmp(&b.context));
+        Ok(latest)
+    }
+
+    // The latest run of each check, unless all runs are wanted
+    pub async fn list_check_runs(&self, repo_id: &str, sha: &str, latest_only: bool) -> Result<Vec<CheckRun>, String> {
+        let runs = sqlx::query_as!(
+            CheckRunRow,
+            r#"
+            SELECT c.id, c.head_sha, c.name, c.status, c.conclusion, c.details_url, c.external_id,
+                c.output_title, c.output_summary, c.output_text,
+                (SELECT COUNT(*) FROM check_run_annotations a WHERE a.check_run_id = c.id) as "annotations_count!: i64",
+                c.started_at, c.completed_at, c.created_at, c.updated_at
+            FROM check_runs c
+            WHERE c.repository_id = ? AND c.head_sha = ?
+            ORDER BY c.created_at DESC
+            "#,
+            repo_id, sha
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let mut seen = HashSet::new();
+        let mut runs: Vec<CheckRun> = runs
+            .into_iter()
+            .filter(|run| !latest_only || seen.insert(run.name.clone()))
+            .map(CheckRun::from)
+            .collect();
+
+        if latest_only {
+            runs.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+
+        Ok(runs)
+    }
+
+    pub async fn combined_status(&self, repo_id: &str, sha: &str) -> Result<CombinedStatus, String> {
+        let statuses = self.latest_statuses(repo_id, sha).await?;
+        let check_runs = self.list_check_runs(repo_id, sha, true).await?;
+
+        let state = combine_states(
+            statuses.iter().map(|status| status.state).chain(check_runs.iter().map(check_run_state)),
+        );
+
+        Ok(CombinedStatus {
+            sha: sha.to_string(),
+            state,
+            total_count: statuses.len() + check_runs.len(),
+            statuses,
+            check_runs,
+        })
+    }
+
+    /// The state of every status context and check name on a commit. A check run and a status
+    /// sharing a name are combined, so either one failing fails the name.
+    pub async fn context_states(&self, repo_id: &str, sha: &str) -> Result<HashMap<String, CommitState>, String> {
+        let mut states: HashMap<String, Vec<CommitState>> = HashMap::new();
+
+        for status in self.latest_statuses(repo_id, sha).await? {
+            states.entry(status.context).or_default().push(status.state);
+        }
+        for run in self.list_check_runs(repo_id, sha, true).await? {
+            let state = check_run_state(&run);
+            states.entry(run.name).or_default().push(state);
+        }
+
+        Ok(states.into_iter().map(|(name, states)| (name, combine_states(states))).collect())
+    }
+
+    // None when nothing has been reported for the commit
+    pub async fn head_state(&self, repo_id: &str, sha: &str) -> Result<Option<CommitState>, String> {
+        let combined = self.combined_status(repo_id, sha).await?;
+
+        Ok((combined.total_count > 0).then_some(combined.state))
+    }
+
+    // Keeps the head status of pull requests whose head is this commit in step with new reports
+    async fn refresh_pull_requests(&self, repo_id: &str, sha: &str) -> Result<(), String> {
+        let state = self.head_state(repo_id, sha).await?;
+
+        sqlx::query!(
+            "UPDATE pull_requests SET head_status = ?, updated_at = updated_at WHERE repository_id = ? AND head_sha = ?",
+            state.map(|state| state.as_str()), repo_id, sha
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn get_check_run(&self, repo_id: &str, check_run_id: &str) -> Result<CheckRun, String> {
+        let run = sqlx::query_as!(
+            CheckRunRow,
+            r#"
+            SELECT c.id, c.head_sha, c.name, c.status, c.conclusion, c.details_url, c.external_id,
+                c.output_title, c.output_summary, c.output_text,
+                (SELECT COUNT(*) FROM check_run_annotations a WHERE a.check_run_id = c.id) as "annotations_count!: i64",
+                c.started_at, c.completed_at, c.created_at, c.updated_at
+            FROM check_runs c
+            WHERE c.id = ? AND c.repository_id = ?
+            "#,
+            check_run_id, repo_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Check run not found".to_string())?;
+
+        Ok(run.into())
+    }
+
+    /// `head_sha` must already be resolved to a full commit id.
+    pub async fn create_check_run(&self, repo_id: &str, head_sha: &str, creator_id: &str, request: &CreateCheckRunRequest) -> Result<CheckRun, String> {
+        let name = request.name.trim();
+        if name.is_empty() || name.len() > 255 {
+            return Err("name must be between 1 and 255 characters".to_string());
+        }
+        validate_url(request.details_url.as_deref(), "details_url")?;
+
+        let status = resolve_run_status(request.status, request.conclusion)?.unwrap_or(CheckRunStatus::Queued);
+        if status == CheckRunStatus::Completed && request.conclusion.is_none() {
+            return Err("A completed check run needs a conclusion".to_string());
+        }
+
+        let output = request.output.as_ref();
+        let annotations = output.and_then(|output| output.annotations.as_deref()).unwrap_or_default();
+        validate_annotations(annotations)?;
+
+        // Runs that have started get a start time even if the reporter did not send one
+        let now = Utc::now();
+        let started_at = request.started_at.or((status != CheckRunStatus::Queued).then_some(now));
+        let completed_at = request.completed_at.or((status == CheckRunStatus::Completed).then_some(now));
+
+        let check_run_id = format!("cr_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO check_runs (
+                id, repository_id, head_sha, name, status, conclusion, details_url, external_id,
+                output_title, output_summary, output_text, started_at, completed_at, creator_id, created_at, updated_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, NOW(3), NOW())
+            "#,
+            check_run_id, repo_id, head_sha, name, status.as_str(), request.conclusion.map(|conclusion| conclusion.as_str()),
+            request.details_url, request.external_id,
+            output.and_then(|output| output.title.as_deref()),
+            output.and_then(|output| output.summary.as_deref()),
+            output.and_then(|output| output.text.as_deref()),
+            started_at, completed_at, creator_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        insert_annotations(&mut transaction, &check_run_id, annotations).await?;
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        self.refresh_pull_requests(repo_id, head_sha).await?;
+        self.get_check_run(repo_id, &check_run_id).await
+    }
+
+    // Fields left out keep their value; output annotations are added to the existing ones
+    pub async fn update_check_run(&self, repo_id: &str, check_run_id: &str, request: &UpdateCheckRunRequest) -> Result<CheckRun, String> {
+        let run = self.get_check_run(repo_id, check_run_id).await?;
+
+        if let Some(name) = &request.name {
+            if name.trim().is_empty() || name.trim().len() > 255 {
+                return Err("name must be between 1 and 255 characters".to_string());
+            }
+        }
+        validate_url(request.details_url.as_deref(), "details_url")?;
+
+        let status = resolve_run_status(request.status, request.conclusion)?.unwrap_or(run.status);
+        let conclusion = match status {
+            CheckRunStatus::Completed => Some(
+                request.conclusion
+                    .or(run.conclusion)
+                    .ok_or_else(|| "A completed check run needs a conclusion".to_string())?,
+            ),
+            _ => None,
+        };
+
+        let output = request.output.as_ref();
+        let annotations = output.and_then(|output| output.annotations.as_deref()).unwrap_or_default();
+        validate_annotations(annotations)?;
+
+        let now = Utc::now();
+        let started_at = request.started_at.or(run.started_at).or((status != CheckRunStatus::Queued).then_some(now));
+        let completed_at = match status {
+            CheckRunStatus::Completed => request.completed_at.or(run.completed_at).or(Some(now)),
+            _ => None,
+        };
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        sqlx::query!(
+            r#"
+            UPDATE check_runs
+            SET name = COALESCE(?, name),
+                status = ?,
+                conclusion = ?,
+                details_url = COALESCE(?, details_url),
+                external_id = COALESCE(?, external_id),
+                output_title = COALESCE(?, output_title),
+                output_summary = COALESCE(?, output_summary),
+                output_text = COALESCE(?, output_text),
+                started_at = ?,
+                completed_at = ?,
+                updated_at = NOW()
+            WHERE id = ?
+            "#,
+            request.name.as_deref().map(str::trim), status.as_str(), conclusion.map(|conclusion| conclusion.as_str()),
+            request.details_url, request.external_id,
+            output.and_then(|output| output.title.as_deref()),
+            output.and_then(|output| output.summary.as_deref()),
+            output.and_then(|output| output.text.as_deref()),
+            started_at, completed_at, check_run_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        insert_annotations(&mut transaction, check_run_id, annotations).await?;
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        self.refresh_pull_requests(repo_id, &run.head_sha).await?;
+        self.get_check_run(repo_id, check_run_id).await
+    }
+
+    pub async fn list_annotations(&self, repo_id: &str, check_run_id: &str, limit: u32, offset: u32) -> Result<Vec<CheckRunAnnotation>, String> {
+        // Fails with "Check run not found" for runs in other repositories
+        self.get_check_run(repo_id, check_run_id).await?;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, path, start_line, end_line, annotation_level, title, message
+            FROM check_run_annotations
+            WHERE check_run_id = ?
+            ORDER BY created_at ASC, path ASC, start_line ASC
+            LIMIT ? OFFSET ?
+            "#,
+            check_run_id, limit, offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| CheckRunAnnotation {
+                id: row.id,
+                path: row.path,
+                start_line: row.start_line,
+                end_line: row.end_line,
+                annotation_level: AnnotationLevel::parse(&row.annotation_level),
+                title: row.title,
+                message: row.message,
+            })
+            .collect())
+    }
+}
+
+async fn insert_annotations(
+    transaction: &mut Transaction<'_, MySql>,
+    check_run_id: &str,
+    annotations: &[CheckRunAnnotationRequest],
+) -> Result<(), String> {
+    for annotation in annotations {
+        let annotation_id = format!("cra_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO check_run_annotations (id, check_run_id, path, start_line, end_line, annotation_level, title, message, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, NOW())
+            "#,
+            annotation_id, check_run_id, annotation.path, annotation.start_line,
+            annotation.end_line.unwrap_or(annotation.start_line), annotation.annotation_level.as_str(),
+            annotation.title, annotation.message
+        )
+        .execute(&mut **transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+    }
+
+    Ok(())
+}
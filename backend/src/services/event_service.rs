@@ -0,0 +1,225 @@
+use crate::models::{RealtimeEvent, Repository, StreamIdentity, WebhookEvent};
+use crate::services::access_token_service::to_hex;
+use crate::services::webhook_service::ZERO_SHA;
+use chrono::Utc;
+use futures_util::StreamExt;
+use redis::aio::ConnectionManager;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+// Every event channel in Redis starts with this, so one pattern subscription sees them all
+const CHANNEL_PREFIX: &str = "events:";
+// Events buffered per connected client before it is told it fell behind
+const LOCAL_BUFFER: usize = 1024;
+const RELAY_RECONNECT_SECONDS: u64 = 5;
+// Tickets only need to last from fetching one to opening the stream
+pub const TICKET_TTL_SECONDS: i64 = 30;
+
+fn ticket_key(ticket: &str) -> String {
+    format!("event_ticket:{}", ticket)
+}
+
+fn redis_error(e: redis::RedisError) -> String {
+    format!("Redis error: {}", e)
+}
+
+/// Where an event is published. Channels are keyed by repository id so renames do not affect them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventChannel {
+    Repository(String),
+    Issue(String, i32),
+    PullRequest(String, i32),
+}
+
+impl EventChannel {
+    pub fn key(&self) -> String {
+        match self {
+            EventChannel::Repository(repo_id) => format!("{}repo:{}", CHANNEL_PREFIX, repo_id),
+            EventChannel::Issue(repo_id, number) => format!("{}issue:{}:{}", CHANNEL_PREFIX, repo_id, number),
+            EventChannel::PullRequest(repo_id, number) => format!("{}pull:{}:{}", CHANNEL_PREFIX, repo_id, number),
+        }
+    }
+}
+
+/// An event received from Redis, with the channel it was published to.
+#[derive(Debug, Clone)]
+pub struct RelayedEvent {
+    pub channel_key: String,
+    pub event: RealtimeEvent,
+}
+
+/// Live repository activity for event stream clients. Events are published to Redis so every
+/// backend instance sees them; each instance relays them to its own connections.
+#[derive(Clone)]
+pub struct EventService {
+    // Publishing and tickets share the app's connection; the relay needs one of its own for pub/sub
+    redis: ConnectionManager,
+    client: redis::Client,
+    local: broadcast::Sender<RelayedEvent>,
+}
+
+impl EventService {
+    pub fn new(redis: ConnectionManager, client: redis::Client) -> Self {
+        let (local, _) = broadcast::channel(LOCAL_BUFFER);
+        Self { redis, client, local }
+    }
+
+    // Publishing happens in the background; a Redis outage only costs live updates
+    pub fn publish(&self, channels: Vec<EventChannel>, event: WebhookEvent, actor_id: &str, data: Value) {
+        let conn = self.redis.clone();
+        let message = RealtimeEvent {
+            event: event.as_str().to_string(),
+            actor_id: actor_id.to_string(),
+            data,
+            created_at: Utc::now(),
+        };
+
+        tokio::spawn(async move {
+            if let Err(err) = publish_to(conn, &channels, &message).await {
+                log::error!("Failed to publish {} event: {}", message.event, err);
+            }
+        });
+    }
+
+    pub fn publish_issue(&self, repo: &Repository, number: i32, event: WebhookEvent, actor_id: &str, data: Value) {
+        let channels = vec![EventChannel::Repository(repo.id.clone()), EventChannel::Issue(repo.id.clone(), number)];
+        self.publish(channels, event, actor_id, data);
+    }
+
+    pub fn publish_pull_request(&self, repo: &Repository, number: i32, event: WebhookEvent, actor_id: &str, data: Value) {
+        let channels = vec![EventChannel::Repository(repo.id.clone()), EventChannel::PullRequest(repo.id.clone(), number)];
+        self.publish(channels, event, actor_id, data);
+    }
+
+    /// Publishes a push event to the repository channel for every branch that moved.
+    pub fn publish_push(&self, repo: &Repository, pusher_id: &str, before: &HashMap<String, String>, after: &HashMap<String, String>) {
+        let mut branches: Vec<&String> = after.keys().chain(before.keys()).collect();
+        branches.sort();
+        branches.dedup();
+
+        for branch in branches {
+            let old = before.get(branch);
+            let new = after.get(branch);
+            if old == new {
+                continue;
+            }
+
+            let data = json!({
+                "ref": format!("refs/heads/{}", branch),
+                "before": old.map(|sha| sha.as_str()).unwrap_or(ZERO_SHA),
+                "after": new.map(|sha| sha.as_str()).unwrap_or(ZERO_SHA),
+                "created": old.is_none(),
+                "deleted": new.is_none(),
+            });
+
+            self.publish(vec![EventChannel::Repository(repo.id.clone())], WebhookEvent::Push, pusher_id, data);
+        }
+    }
+
+    /// Issues a single-use ticket that opens an event stream as `identity`.
+    pub async fn create_ticket(&self, identity: &StreamIdentity) -> Result<String, String> {
+        let mut bytes = [0u8; 32];
+        SystemRandom::new()
+            .fill(&mut bytes)
+            .map_err(|_| "Failed to generate ticket".to_string())?;
+        let ticket = to_hex(&bytes);
+
+        let identity = serde_json::to_string(identity).map_err(|e| format!("Serialization error: {}", e))?;
+        let mut conn = self.redis.clone();
+        redis::cmd("SET")
+            .arg(ticket_key(&ticket))
+            .arg(identity)
+            .arg("EX")
+            .arg(TICKET_TTL_SECONDS)
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(redis_error)?;
+
+        Ok(ticket)
+    }
+
+    /// Returns who a ticket was issued to. Each ticket can be redeemed once.
+    pub async fn redeem_ticket(&self, ticket: &str) -> Result<Option<StreamIdentity>, String> {
+        let mut conn = self.redis.clone();
+        let identity: Option<String> = redis::cmd("GETDEL")
+            .arg(ticket_key(ticket))
+            .query_async(&mut conn)
+            .await
+            .map_err(redis_error)?;
+
+        Ok(identity.and_then(|identity| serde_json::from_str(&identity).ok()))
+    }
+
+    /// Every event this instance relays; receivers pick out the channels they subscribed to.
+    pub fn subscribe(&self) -> broadcast::Receiver<RelayedEvent> {
+        self.local.subscribe()
+    }
+
+    // Relays events from Redis to local receivers, reconnecting whenever the subscription drops
+    pub fn spawn_relay(&self) {
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = service.relay().await {
+                    log::error!("Event relay stopped: {}", err);
+                }
+                tokio::time::sleep(Duration::from_secs(RELAY_RECONNECT_SECONDS)).await;
+            }
+        });
+    }
+
+    async fn relay(&self) -> Result<(), String> {
+        let mut pubsub = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(redis_error)?
+            .into_pubsub();
+        pubsub
+            .psubscribe(format!("{}*", CHANNEL_PREFIX))
+            .await
+            .map_err(redis_error)?;
+
+        let mut messages = pubsub.on_message();
+        while let Some(message) = messages.next().await {
+            let payload: String = match message.get_payload() {
+                Ok(payload) => payload,
+                Err(err) => {
+                    log::warn!("Ignoring unreadable event: {}", err);
+                    continue;
+                }
+            };
+
+            let event = match serde_json::from_str::<RealtimeEvent>(&payload) {
+                Ok(event) => event,
+                Err(err) => {
+                    log::warn!("Ignoring malformed event: {}", err);
+                    continue;
+                }
+            };
+
+            // Sending fails only when nobody on this instance is listening
+            let _ = self.local.send(RelayedEvent {
+                channel_key: message.get_channel_name().to_string(),
+                event,
+            });
+        }
+
+        Err("Redis subscription closed".to_string())
+    }
+}
+
+async fn publish_to(mut conn: ConnectionManager, channels: &[EventChannel], message: &RealtimeEvent) -> Result<(), String> {
+    let payload = serde_json::to_string(message).map_err(|e| format!("Failed to encode event: {}", e))?;
+
+    let mut pipe = redis::pipe();
+    for channel in channels {
+        pipe.cmd("PUBLISH").arg(channel.key()).arg(&payload).ignore();
+    }
+
+    pipe.query_async::<_, ()>(&mut conn).await.map_err(redis_error)
+}
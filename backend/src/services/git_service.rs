@@ -0,0 +1,1604 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, TimeZone, Utc};
+use git2::{
+    Blob, BranchType, Commit, Delta, Diff, DiffFormat, DiffOptions, Email, EmailCreateOptions, Index, ObjectType, Odb, Oid, Patch,
+    Reference, Repository as GitRepository, RepositoryInitOptions, Signature, Sort, Tree, TreeEntry,
+    TreeWalkMode, TreeWalkResult,
+};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Output, Stdio};
+use tokio::io::AsyncWriteExt;
+use tokio::process::{Child, ChildStdin, Command};
+use tokio::task::JoinHandle;
+use crate::models::MergeMethod;
+use crate::models::git::{
+    Branch, CommitDetail, CommitSummary, ContentEntry, DiffStats, FileContent, FileDiff, GitSignature,
+    GitTree, GitTreeEntry, RepositoryContents, Tag,
+};
+
+/// Smart HTTP services a git client can request from a hosted repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitRpc {
+    UploadPack,
+    ReceivePack,
+}
+
+impl GitRpc {
+    pub fn from_service_name(name: &str) -> Option<Self> {
+        match name {
+            "git-upload-pack" => Some(GitRpc::UploadPack),
+            "git-receive-pack" => Some(GitRpc::ReceivePack),
+            _ => None,
+        }
+    }
+
+    pub fn service_name(&self) -> &'static str {
+        match self {
+            GitRpc::UploadPack => "git-upload-pack",
+            GitRpc::ReceivePack => "git-receive-pack",
+        }
+    }
+
+    fn subcommand(&self) -> &'static str {
+        match self {
+            GitRpc::UploadPack => "upload-pack",
+            GitRpc::ReceivePack => "receive-pack",
+        }
+    }
+}
+
+/// Result of merging one branch into another.
+#[derive(Debug)]
+pub enum MergeOutcome {
+    Merged(String),        // New head of the base branch
+    Conflict(Vec<String>), // Paths that could not be merged cleanly
+    HeadMoved,             // The head branch no longer points at the commit that was checked
+}
+
+const ZERO_OID: &str = "0000000000000000000000000000000000000000";
+
+/// A single ref update requested by a push.
+#[derive(Debug, Clone)]
+pub struct RefUpdate {
+    pub old: String,
+    pub new: String,
+    pub name: String,
+}
+
+impl RefUpdate {
+    pub fn is_create(&self) -> bool {
+        self.old == ZERO_OID
+    }
+
+    pub fn is_delete(&self) -> bool {
+        self.new == ZERO_OID
+    }
+
+    pub fn branch(&self) -> Option<&str> {
+        self.name.strip_prefix("refs/heads/")
+    }
+}
+
+/// The commands of a receive-pack request.
+#[derive(Debug)]
+pub struct PushRequest {
+    pub updates: Vec<RefUpdate>,
+    pub capabilities: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct CommitFilter {
+    pub sha: Option<String>,
+    pub path: Option<String>,
+    pub author: Option<String>, // Matches the author name or email
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+// Refuses non-fast-forward updates to the refs listed in DEVIT_FAST_FORWARD_REFS. Receive-pack runs it
+// while the pushed objects are still quarantined, so a refused push never reaches the object database.
+const PRE_RECEIVE_HOOK: &str = r#"#!/bin/sh
+zero=0000000000000000000000000000000000000000
+status=0
+while read -r old new ref; do
+    case " $DEVIT_FAST_FORWARD_REFS " in
+        *" $ref "*) ;;
+        *) continue ;;
+    esac
+    if [ "$old" = "$zero" ] || [ "$new" = "$zero" ]; then
+        continue
+    fi
+    if ! git merge-base --is-ancestor "$old" "$new"; then
+        echo "Cannot force-push to protected branch ${ref#refs/heads/}" >&2
+        status=1
+    fi
+done
+exit $status
+"#;
+
+#[derive(Clone)]
+pub struct GitService {
+    storage_root: PathBuf,
+    max_request_bytes: usize,
+}
+
+impl GitService {
+    pub fn new(storage_root: impl Into<PathBuf>, max_request_bytes: usize) -> Self {
+        Self { storage_root: storage_root.into(), max_request_bytes }
+    }
+
+    // Largest request a git client may send, counted after decompression
+    pub fn max_request_bytes(&self) -> usize {
+        self.max_request_bytes
+    }
+
+    // Shared by every repository through core.hooksPath, so hooks inside a repository never run
+    fn hooks_path(&self) -> Result<PathBuf, String> {
+        std::path::absolute(self.storage_root.join("hooks"))
+            .map_err(|e| format!("Failed to resolve hooks directory: {}", e))
+    }
+
+    /// Writes the hooks receive-pack runs for every push. Called once at startup.
+    pub fn install_hooks(&self) -> Result<(), String> {
+        let hooks_path = self.hooks_path()?;
+        std::fs::create_dir_all(&hooks_path)
+            .map_err(|e| format!("Failed to create hooks directory: {}", e))?;
+
+        let hook = hooks_path.join("pre-receive");
+        std::fs::write(&hook, PRE_RECEIVE_HOOK)
+            .map_err(|e| format!("Failed to write pre-receive hook: {}", e))?;
+        std::fs::set_permissions(&hook, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to write pre-receive hook: {}", e))
+    }
+
+    /// Runs git2 work on the blocking thread pool so that walking a large repository never stalls
+    /// the async runtime. Async code calls the synchronous methods below through this.
+    pub async fn blocking<T, F>(&self, work: F) -> Result<T, String>
+    where
+        F: FnOnce(&GitService) -> Result<T, String> + Send + 'static,
+        T: Send + 'static,
+    {
+        let git = self.clone();
+        tokio::task::spawn_blocking(move || work(&git))
+            .await
+            .map_err(|e| format!("Git task failed: {}", e))?
+    }
+
+    // Repositories are stored by id so renames never touch the disk layout
+    pub fn repository_path(&self, repo_id: &str) -> PathBuf {
+        self.storage_root.join(format!("{}.git", repo_id))
+    }
+
+    pub fn init_repository(&self, repo_id: &str, default_branch: &str) -> Result<(), String> {
+        let mut opts = RepositoryInitOptions::new();
+        opts.bare(true).mkpath(true).initial_head(default_branch);
+
+        GitRepository::init_opts(self.repository_path(repo_id), &opts)
+            .map_err(|e| format!("Git error: {}", e))?;
+
+        Ok(())
+    }
+
+    // Writes a root commit containing the given files directly into the bare repository
+    pub fn create_initial_commit(
+        &self,
+        repo_id: &str,
+        branch: &str,
+        author_name: &str,
+        author_email: &str,
+        files: &[(String, String)],
+    ) -> Result<String, String> {
+        let repo = self.open_repository(repo_id)?;
+
+        let mut builder = repo
+            .treebuilder(None)
+            .map_err(|e| format!("Git error: {}", e))?;
+
+        for (path, content) in files {
+            let blob = repo
+                .blob(content.as_bytes())
+                .map_err(|e| format!("Git error: {}", e))?;
+            builder
+                .insert(path, blob, 0o100644)
+                .map_err(|e| format!("Git error: {}", e))?;
+        }
+
+        let tree_id = builder.write().map_err(|e| format!("Git error: {}", e))?;
+        let tree = repo.find_tree(tree_id).map_err(|e| format!("Git error: {}", e))?;
+        let signature = Signature::now(author_name, author_email)
+            .map_err(|e| format!("Git error: {}", e))?;
+
+        let commit_id = repo
+            .commit(
+                Some(&format!("refs/heads/{}", branch)),
+                &signature,
+                &signature,
+                "Initial commit",
+                &tree,
+                &[],
+            )
+            .map_err(|e| format!("Git error: {}", e))?;
+
+        Ok(commit_id.to_string())
+    }
+
+    pub fn open_repository(&self, repo_id: &str) -> Result<GitRepository, String> {
+        GitRepository::open_bare(self.repository_path(repo_id))
+            .map_err(|e| format!("Git error: {}", e))
+    }
+
+    pub fn delete_repository(&self, repo_id: &str) -> Result<(), String> {
+        let path = self.repository_path(repo_id);
+
+        if path.exists() {
+            std::fs::remove_dir_all(&path)
+                .map_err(|e| format!("Storage error: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_contents(&self, repo_id: &str, path: &str, reference: Option<&str>) -> Result<RepositoryContents, String> {
+        let repo = self.open_repository(repo_id)?;
+        let commit = resolve_commit(&repo, reference)?;
+        let root = commit.tree().map_err(|e| format!("Git error: {}", e))?;
+        let path = path.trim_matches('/');
+
+        if path.is_empty() {
+            return Ok(RepositoryContents::Directory(list_directory(&repo, &root, "")?));
+        }
+
+        let entry = root
+            .get_path(Path::new(path))
+            .map_err(|_| "Path not found".to_string())?;
+
+        match entry.kind() {
+            Some(ObjectType::Tree) => {
+                let tree = repo.find_tree(entry.id()).map_err(|e| format!("Git error: {}", e))?;
+                Ok(RepositoryContents::Directory(list_directory(&repo, &tree, path)?))
+            }
+            Some(ObjectType::Blob) => {
+                let blob = repo.find_blob(entry.id()).map_err(|e| format!("Git error: {}", e))?;
+                let content = if blob.size() <= MAX_INLINE_CONTENT_SIZE {
+                    Some(STANDARD.encode(blob.content()))
+                } else {
+                    None
+                };
+
+                Ok(RepositoryContents::File(FileContent {
+                    entry_type: entry_type_name(entry.filemode()).to_string(),
+                    name: entry.name().unwrap_or_default().to_string(),
+                    path: path.to_string(),
+                    sha: entry.id().to_string(),
+                    size: blob.size() as u64,
+                    encoding: "base64".to_string(),
+                    content,
+                    is_binary: blob.is_binary(),
+                }))
+            }
+            _ => Err("Path not found".to_string()),
+        }
+    }
+
+    pub fn read_file(&self, repo_id: &str, path: &str, reference: Option<&str>) -> Result<Vec<u8>, String> {
+        let repo = self.open_repository(repo_id)?;
+        let commit = resolve_commit(&repo, reference)?;
+        let root = commit.tree().map_err(|e| format!("Git error: {}", e))?;
+
+        let entry = root
+            .get_path(Path::new(path.trim_matches('/')))
+            .map_err(|_| "Path not found".to_string())?;
+
+        if entry.kind() != Some(ObjectType::Blob) {
+            return Err("Path is not a file".to_string());
+        }
+
+        let blob = repo.find_blob(entry.id()).map_err(|e| format!("Git error: {}", e))?;
+        Ok(blob.content().to_vec())
+    }
+
+    // Accepts a tree sha or anything that peels to one (commit sha, branch, tag)
+    pub fn get_tree(&self, repo_id: &str, sha: &str, recursive: bool) -> Result<GitTree, String> {
+        let repo = self.open_repository(repo_id)?;
+        let tree = repo
+            .revparse_single(sha)
+            .and_then(|object| object.peel_to_tree())
+            .map_err(|_| "Tree not found".to_string())?;
+        let odb = repo.odb().map_err(|e| format!("Git error: {}", e))?;
+
+        let mut entries = Vec::new();
+        let mut truncated = false;
+
+        if recursive {
+            tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+                if entries.len() >= MAX_TREE_ENTRIES {
+                    truncated = true;
+                    return TreeWalkResult::Abort;
+                }
+                entries.push(tree_entry(&odb, root, entry));
+                TreeWalkResult::Ok
+            })
+            .map_err(|e| format!("Git error: {}", e))?;
+        } else {
+            entries.extend(tree.iter().map(|entry| tree_entry(&odb, "", &entry)));
+        }
+
+        Ok(GitTree {
+            sha: tree.id().to_string(),
+            tree: entries,
+            truncated,
+        })
+    }
+
+    pub fn list_commits(&self, repo_id: &str, filter: &CommitFilter) -> Result<Vec<CommitSummary>, String> {
+        let repo = self.open_repository(repo_id)?;
+
+        if filter.sha.is_none() && repo.is_empty().unwrap_or(false) {
+            return Ok(Vec::new());
+        }
+
+        let start = resolve_commit(&repo, filter.sha.as_deref())?;
+
+        let mut revwalk = repo.revwalk().map_err(|e| format!("Git error: {}", e))?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).map_err(|e| format!("Git error: {}", e))?;
+        revwalk.push(start.id()).map_err(|e| format!("Git error: {}", e))?;
+
+        let path = filter
+            .path
+            .as_deref()
+            .map(|path| path.trim_matches('/'))
+            .filter(|path| !path.is_empty());
+        let author = filter.author.as_deref().map(|author| author.to_lowercase());
+
+        let mut commits = Vec::new();
+        let mut skipped = 0;
+
+        for oid in revwalk {
+            let oid = oid.map_err(|e| format!("Git error: {}", e))?;
+            let commit = repo.find_commit(oid).map_err(|e| format!("Git error: {}", e))?;
+
+            // Date filters apply to the commit date, like git log
+            let committed_at = signature_time(&commit.committer());
+            if filter.since.is_some_and(|since| committed_at < since)
+                || filter.until.is_some_and(|until| committed_at > until)
+            {
+                continue;
+            }
+
+            if let Some(author) = &author {
+                let signature = commit.author();
+                let name = signature.name().unwrap_or_default().to_lowercase();
+                let email = signature.email().unwrap_or_default().to_lowercase();
+                if &name != author && &email != author {
+                    continue;
+                }
+            }
+
+            if let Some(path) = path {
+                if !touches_path(&commit, Path::new(path))? {
+                    continue;
+                }
+            }
+
+            if skipped < filter.offset {
+                skipped += 1;
+                continue;
+            }
+
+            commits.push(commit_summary(&commit));
+            if commits.len() >= filter.limit {
+                break;
+            }
+        }
+
+        Ok(commits)
+    }
+
+    pub fn get_commit(&self, repo_id: &str, sha: &str) -> Result<CommitDetail, String> {
+        let repo = self.open_repository(repo_id)?;
+        let commit = resolve_commit(&repo, Some(sha))?;
+
+        let tree = commit.tree().map_err(|e| format!("Git error: {}", e))?;
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree().map_err(|e| format!("Git error: {}", e))?),
+            Err(_) => None,
+        };
+
+        let mut diff = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+            .map_err(|e| format!("Git error: {}", e))?;
+        diff.find_similar(None).map_err(|e| format!("Git error: {}", e))?;
+
+        let (files, stats) = diff_files(&diff)?;
+
+        Ok(CommitDetail {
+            commit: commit_summary(&commit),
+            stats,
+            files,
+        })
+    }
+
+    pub fn list_branches(&self, repo_id: &str) -> Result<Vec<Branch>, String> {
+        let repo = self.open_repository(repo_id)?;
+        let head = head_branch_name(&repo);
+
+        let mut branches = Vec::new();
+        for branch in repo.branches(Some(BranchType::Local)).map_err(|e| format!("Git error: {}", e))? {
+            let (branch, _) = branch.map_err(|e| format!("Git error: {}", e))?;
+            let name = match branch.name() {
+                Ok(Some(name)) => name.to_string(),
+                _ => continue,
+            };
+            let commit = branch.get().peel_to_commit().map_err(|e| format!("Git error: {}", e))?;
+
+            branches.push(Branch {
+                is_default: head.as_deref() == Some(name.as_str()),
+                name,
+                commit: commit_summary(&commit),
+            });
+        }
+
+        Ok(branches)
+    }
+
+    pub fn get_branch(&self, repo_id: &str, name: &str) -> Result<Branch, String> {
+        let repo = self.open_repository(repo_id)?;
+
+        let branch = repo
+            .find_branch(name, BranchType::Local)
+            .map_err(|_| format!("Branch not found: {}", name))?;
+        let commit = branch.get().peel_to_commit().map_err(|e| format!("Git error: {}", e))?;
+
+        Ok(Branch {
+            name: name.to_string(),
+            commit: commit_summary(&commit),
+            is_default: head_branch_name(&repo).as_deref() == Some(name),
+        })
+    }
+
+    pub fn create_branch(&self, repo_id: &str, name: &str, start: Option<&str>) -> Result<Branch, String> {
+        let repo = self.open_repository(repo_id)?;
+
+        if !Reference::is_valid_name(&format!("refs/heads/{}", name)) {
+            return Err(format!("Invalid branch name: {}", name));
+        }
+        if repo.find_branch(name, BranchType::Local).is_ok() {
+            return Err(format!("Branch already exists: {}", name));
+        }
+
+        let commit = resolve_commit(&repo, start)?;
+        repo.branch(name, &commit, false).map_err(|e| format!("Git error: {}", e))?;
+
+        Ok(Branch {
+            name: name.to_string(),
+            commit: commit_summary(&commit),
+            is_default: false,
+        })
+    }
+
+    pub fn delete_branch(&self, repo_id: &str, name: &str) -> Result<(), String> {
+        let repo = self.open_repository(repo_id)?;
+
+        if head_branch_name(&repo).as_deref() == Some(name) {
+            return Err("Cannot delete the default branch".to_string());
+        }
+
+        let mut branch = repo
+            .find_branch(name, BranchType::Local)
+            .map_err(|_| format!("Branch not found: {}", name))?;
+
+        branch.delete().map_err(|e| format!("Git error: {}", e))
+    }
+
+    // Points HEAD at an existing branch so new clones check it out
+    pub fn set_default_branch(&self, repo_id: &str, name: &str) -> Result<(), String> {
+        let repo = self.open_repository(repo_id)?;
+
+        repo.find_branch(name, BranchType::Local)
+            .map_err(|_| format!("Branch not found: {}", name))?;
+
+        repo.set_head(&format!("refs/heads/{}", name))
+            .map_err(|e| format!("Git error: {}", e))
+    }
+
+    // Merges head into base without a working tree and advances base to the result. head_sha is
+    // the commit the merge was approved for; nothing is merged once head has moved past it.
+    #[allow(clippy::too_many_arguments)]
+    pub fn merge_branches(
+        &self,
+        repo_id: &str,
+        base: &str,
+        head: &str,
+        head_sha: &str,
+        method: MergeMethod,
+        message: &str,
+        committer_name: &str,
+        committer_email: &str,
+    ) -> Result<MergeOutcome, String> {
+        let repo = self.open_repository(repo_id)?;
+
+        let base_commit = branch_tip(&repo, base)?;
+        let head_commit = branch_tip(&repo, head)?;
+
+        if head_commit.id().to_string() != head_sha {
+            return Ok(MergeOutcome::HeadMoved);
+        }
+
+        if base_commit.id() == head_commit.id()
+            || repo
+                .graph_descendant_of(base_commit.id(), head_commit.id())
+                .map_err(|e| format!("Git error: {}", e))?
+        {
+            return Err(format!("Nothing to merge: {} is already contained in {}", head, base));
+        }
+
+        let committer = Signature::now(committer_name, committer_email)
+            .map_err(|e| format!("Git error: {}", e))?;
+
+        let new_head = match method {
+            MergeMethod::Merge | MergeMethod::Squash => {
+                let mut index = repo
+                    .merge_commits(&base_commit, &head_commit, None)
+                    .map_err(|e| format!("Git error: {}", e))?;
+
+                if index.has_conflicts() {
+                    return Ok(MergeOutcome::Conflict(conflict_paths(&index)?));
+                }
+
+                let tree_id = index.write_tree_to(&repo).map_err(|e| format!("Git error: {}", e))?;
+                let tree = repo.find_tree(tree_id).map_err(|e| format!("Git error: {}", e))?;
+
+                // A squash records the combined changes as a single-parent commit
+                let parents = match method {
+                    MergeMethod::Merge => vec![&base_commit, &head_commit],
+                    _ => vec![&base_commit],
+                };
+
+                repo.commit(None, &committer, &committer, message, &tree, &parents)
+                    .map_err(|e| format!("Git error: {}", e))?
+            }
+            MergeMethod::Rebase => {
+                let mut revwalk = repo.revwalk().map_err(|e| format!("Git error: {}", e))?;
+                revwalk
+                    .set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)
+                    .map_err(|e| format!("Git error: {}", e))?;
+                revwalk.push(head_commit.id()).map_err(|e| format!("Git error: {}", e))?;
+                revwalk.hide(base_commit.id()).map_err(|e| format!("Git error: {}", e))?;
+
+                let mut current = base_commit.clone();
+
+                for oid in revwalk {
+                    let oid = oid.map_err(|e| format!("Git error: {}", e))?;
+                    let commit = repo.find_commit(oid).map_err(|e| format!("Git error: {}", e))?;
+
+                    if commit.parent_count() > 1 {
+                        return Err("Cannot rebase merge commits; use merge or squash instead".to_string());
+                    }
+
+                    let mut index = repo
+                        .cherrypick_commit(&commit, &current, 0, None)
+                        .map_err(|e| format!("Git error: {}", e))?;
+
+                    if index.has_conflicts() {
+                        return Ok(MergeOutcome::Conflict(conflict_paths(&index)?));
+                    }
+
+                    let tree_id = index.write_tree_to(&repo).map_err(|e| format!("Git error: {}", e))?;
+                    let tree = repo.find_tree(tree_id).map_err(|e| format!("Git error: {}", e))?;
+
+                    // Replayed commits keep their original author and message
+                    let id = repo
+                        .commit(
+                            None,
+                            &commit.author(),
+                            &committer,
+                            commit.message().unwrap_or_default(),
+                            &tree,
+                            &[&current],
+                        )
+                        .map_err(|e| format!("Git error: {}", e))?;
+
+                    current = repo.find_commit(id).map_err(|e| format!("Git error: {}", e))?;
+                }
+
+                current.id()
+            }
+        };
+
+        // Refuse to move the branch if someone pushed to it while we were merging
+        repo.reference_matching(
+            &format!("refs/heads/{}", base),
+            new_head,
+            true,
+            base_commit.id(),
+            &format!("merge {} into {}", head, base),
+        )
+        .map_err(|_| format!("Branch {} was updated during the merge; try again", base))?;
+
+        Ok(MergeOutcome::Merged(new_head.to_string()))
+    }
+
+    // Dry-run of merge_branches: true when head merges into base without conflicts
+    pub fn is_mergeable(&self, repo_id: &str, base: &str, head: &str) -> Result<bool, String> {
+        let repo = self.open_repository(repo_id)?;
+        let base_commit = branch_tip(&repo, base)?;
+        let head_commit = branch_tip(&repo, head)?;
+
+        let index = repo
+            .merge_commits(&base_commit, &head_commit, None)
+            .map_err(|e| format!("Git error: {}", e))?;
+
+        Ok(!index.has_conflicts())
+    }
+
+    // Current tip of every branch, used to work out which branches a push moved
+    pub fn branch_heads(&self, repo_id: &str) -> Result<HashMap<String, String>, String> {
+        let repo = self.open_repository(repo_id)?;
+
+        let mut heads = HashMap::new();
+        for branch in repo.branches(Some(BranchType::Local)).map_err(|e| format!("Git error: {}", e))? {
+            let (branch, _) = branch.map_err(|e| format!("Git error: {}", e))?;
+            if let (Ok(Some(name)), Some(target)) = (branch.name(), branch.get().target()) {
+                heads.insert(name.to_string(), target.to_string());
+            }
+        }
+
+        Ok(heads)
+    }
+
+    // Commits a push brought onto a branch, newest first. For a new branch these are
+    // the commits not already on any other branch.
+    pub fn pushed_commits(&self, repo_id: &str, branch: &str, before: Option<&str>, after: &str, limit: usize) -> Result<Vec<CommitSummary>, String> {
+        let repo = self.open_repository(repo_id)?;
+
+        let mut revwalk = repo.revwalk().map_err(|e| format!("Git error: {}", e))?;
+        revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).map_err(|e| format!("Git error: {}", e))?;
+        revwalk
+            .push(Oid::from_str(after).map_err(|e| format!("Git error: {}", e))?)
+            .map_err(|e| format!("Git error: {}", e))?;
+
+        match before {
+            Some(before) => {
+                // A force-push may have left the old tip unreachable; that only means nothing to hide
+                if let Ok(oid) = Oid::from_str(before) {
+                    let _ = revwalk.hide(oid);
+                }
+            }
+            None => {
+                for (name, sha) in self.branch_heads(repo_id)? {
+                    if name != branch {
+                        if let Ok(oid) = Oid::from_str(&sha) {
+                            let _ = revwalk.hide(oid);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut commits = Vec::new();
+        for oid in revwalk.take(limit) {
+            let oid = oid.map_err(|e| format!("Git error: {}", e))?;
+            let commit = repo.find_commit(oid).map_err(|e| format!("Git error: {}", e))?;
+            commits.push(commit_summary(&commit));
+        }
+
+        Ok(commits)
+    }
+
+    // Files changed on head since it branched off base
+    pub fn compare_files(&self, repo_id: &str, base: &str, head: &str) -> Result<(Vec<FileDiff>, DiffStats), String> {
+        let repo = self.open_repository(repo_id)?;
+        let diff = branch_diff(&repo, base, head)?;
+
+        diff_files(&diff)
+    }
+
+    // The same comparison as compare_files rendered as a single unified diff
+    pub fn compare_diff(&self, repo_id: &str, base: &str, head: &str) -> Result<String, String> {
+        let repo = self.open_repository(repo_id)?;
+        let diff = branch_diff(&repo, base, head)?;
+
+        let mut text = Vec::new();
+        diff.print(DiffFormat::Patch, |_delta, _hunk, line| {
+            if matches!(line.origin(), '+' | '-' | ' ') {
+                text.push(line.origin() as u8);
+            }
+            text.extend_from_slice(line.content());
+            true
+        })
+        .map_err(|e| format!("Git error: {}", e))?;
+
+        Ok(String::from_utf8_lossy(&text).into_owned())
+    }
+
+    // Commits on head but not on base as a format-patch style mbox; merge commits are skipped like git does
+    pub fn compare_patch(&self, repo_id: &str, base: &str, head: &str) -> Result<String, String> {
+        let repo = self.open_repository(repo_id)?;
+        let base_commit = branch_tip(&repo, base)?;
+        let head_commit = branch_tip(&repo, head)?;
+
+        let mut revwalk = repo.revwalk().map_err(|e| format!("Git error: {}", e))?;
+        revwalk
+            .set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)
+            .map_err(|e| format!("Git error: {}", e))?;
+        revwalk.push(head_commit.id()).map_err(|e| format!("Git error: {}", e))?;
+        revwalk.hide(base_commit.id()).map_err(|e| format!("Git error: {}", e))?;
+
+        let mut commits = Vec::new();
+        for oid in revwalk {
+            let oid = oid.map_err(|e| format!("Git error: {}", e))?;
+            let commit = repo.find_commit(oid).map_err(|e| format!("Git error: {}", e))?;
+            if commit.parent_count() <= 1 {
+                commits.push(commit);
+            }
+        }
+
+        let mut text = Vec::new();
+        for (index, commit) in commits.iter().enumerate() {
+            let tree = commit.tree().map_err(|e| format!("Git error: {}", e))?;
+            let parent_tree = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree().map_err(|e| format!("Git error: {}", e))?),
+                Err(_) => None,
+            };
+            let diff = repo
+                .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+                .map_err(|e| format!("Git error: {}", e))?;
+
+            let email = Email::from_diff(
+                &diff,
+                index + 1,
+                commits.len(),
+                &commit.id(),
+                commit.summary().unwrap_or_default(),
+                commit.body().unwrap_or_default(),
+                &commit.author(),
+                &mut EmailCreateOptions::new(),
+            )
+            .map_err(|e| format!("Git error: {}", e))?;
+
+            text.extend_from_slice(email.as_slice());
+        }
+
+        Ok(String::from_utf8_lossy(&text).into_owned())
+    }
+
+    // Expands a branch, tag or abbreviated sha to the full commit id
+    pub fn resolve_sha(&self, repo_id: &str, reference: &str) -> Result<String, String> {
+        let repo = self.open_repository(repo_id)?;
+        let commit = resolve_commit(&repo, Some(reference))?;
+
+        Ok(commit.id().to_string())
+    }
+
+    // Files directly inside `dir` at a commit with one of the extensions, by path; empty when the directory is missing
+    pub fn files_in_directory(&self, repo_id: &str, sha: &str, dir: &str, extensions: &[&str]) -> Result<Vec<(String, Vec<u8>)>, String> {
+        let repo = self.open_repository(repo_id)?;
+        let root = resolve_commit(&repo, Some(sha))?
+            .tree()
+            .map_err(|e| format!("Git error: {}", e))?;
+
+        let tree = match root.get_path(Path::new(dir)).ok().and_then(|entry| entry.to_object(&repo).ok()).and_then(|object| object.into_tree().ok()) {
+            Some(tree) => tree,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut files = Vec::new();
+        for entry in tree.iter() {
+            let name = match entry.name() {
+                Some(name) => name,
+                None => continue,
+            };
+            let matches_extension = Path::new(name)
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .is_some_and(|extension| extensions.contains(&extension));
+
+            if entry.kind() == Some(ObjectType::Blob) && matches_extension {
+                let blob = repo.find_blob(entry.id()).map_err(|e| format!("Git error: {}", e))?;
+                files.push((format!("{}/{}", dir, name), blob.content().to_vec()));
+            }
+        }
+
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(files)
+    }
+
+    // A gzipped tarball of the tree at a commit, without the .git directory
+    pub async fn archive(&self, repo_id: &str, sha: &str) -> Result<Vec<u8>, String> {
+        let output = Command::new("git")
+            .arg("--git-dir")
+            .arg(self.repository_path(repo_id))
+            .arg("archive")
+            .arg("--format=tar.gz")
+            .arg(sha)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run git archive: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!("git archive failed: {}", String::from_utf8_lossy(&output.stderr)));
+        }
+
+        Ok(output.stdout)
+    }
+
+    // True when commit is the tip of branch or one of its ancestors
+    pub fn branch_contains(&self, repo_id: &str, branch: &str, commit: &str) -> Result<bool, String> {
+        let repo = self.open_repository(repo_id)?;
+        let tip = branch_tip(&repo, branch)?;
+        let commit = resolve_commit(&repo, Some(commit))?;
+
+        if tip.id() == commit.id() {
+            return Ok(true);
+        }
+
+        repo.graph_descendant_of(tip.id(), commit.id())
+            .map_err(|e| format!("Git error: {}", e))
+    }
+
+    pub fn count_lines(&self, repo_id: &str, commit: &str, path: &str) -> Result<usize, String> {
+        let repo = self.open_repository(repo_id)?;
+        let blob = blob_at(&repo, commit, path)?;
+        let content = blob.content();
+
+        let lines = content.iter().filter(|&&b| b == b'\n').count();
+        Ok(if content.last().is_some_and(|&b| b != b'\n') { lines + 1 } else { lines })
+    }
+
+    // Follows a line of a file from one commit to another.
+    // Returns None when the line itself was modified or the file is gone.
+    pub fn remap_line(&self, repo_id: &str, path: &str, from: &str, to: &str, line: u32) -> Result<Option<u32>, String> {
+        let repo = self.open_repository(repo_id)?;
+        let old_blob = blob_at(&repo, from, path)?;
+        let new_blob = match blob_at(&repo, to, path) {
+            Ok(blob) => blob,
+            Err(_) => return Ok(None),
+        };
+
+        let line = line as i64;
+        let mut offset = 0i64;
+        let mut changed = false;
+
+        let mut opts = DiffOptions::new();
+        opts.context_lines(0);
+
+        repo.diff_blobs(
+            Some(&old_blob),
+            Some(path),
+            Some(&new_blob),
+            Some(path),
+            Some(&mut opts),
+            None,
+            None,
+            Some(&mut |_delta, hunk| {
+                let start = hunk.old_start() as i64;
+                let removed = hunk.old_lines() as i64;
+                let added = hunk.new_lines() as i64;
+
+                if removed == 0 {
+                    // Pure insertion after old line `start`
+                    if start < line {
+                        offset += added;
+                    }
+                } else if start + removed <= line {
+                    offset += added - removed;
+                } else if start <= line {
+                    changed = true;
+                }
+                true
+            }),
+            None,
+        )
+        .map_err(|e| format!("Git error: {}", e))?;
+
+        Ok((!changed).then(|| (line + offset) as u32))
+    }
+
+    pub fn list_tags(&self, repo_id: &str) -> Result<Vec<Tag>, String> {
+        let repo = self.open_repository(repo_id)?;
+        let names = repo.tag_names(None).map_err(|e| format!("Git error: {}", e))?;
+
+        let mut tags = Vec::new();
+        for name in names.iter().flatten() {
+            // Tags of trees or blobs are skipped; the API only exposes commit tags
+            if let Some(tag) = tag_info(&repo, name)? {
+                tags.push(tag);
+            }
+        }
+
+        Ok(tags)
+    }
+
+    pub fn get_tag(&self, repo_id: &str, name: &str) -> Result<Tag, String> {
+        let repo = self.open_repository(repo_id)?;
+
+        tag_info(&repo, name)?.ok_or_else(|| format!("Tag not found: {}", name))
+    }
+
+    // Creates an annotated tag when a message is given, otherwise a lightweight one
+    pub fn create_tag(
+        &self,
+        repo_id: &str,
+        name: &str,
+        target: Option<&str>,
+        message: Option<&str>,
+        tagger_name: &str,
+        tagger_email: &str,
+    ) -> Result<Tag, String> {
+        let repo = self.open_repository(repo_id)?;
+
+        if !Reference::is_valid_name(&format!("refs/tags/{}", name)) {
+            return Err(format!("Invalid tag name: {}", name));
+        }
+        if repo.find_reference(&format!("refs/tags/{}", name)).is_ok() {
+            return Err(format!("Tag already exists: {}", name));
+        }
+
+        let commit = resolve_commit(&repo, target)?;
+
+        match message {
+            Some(message) => {
+                let tagger = Signature::now(tagger_name, tagger_email)
+                    .map_err(|e| format!("Git error: {}", e))?;
+                repo.tag(name, commit.as_object(), &tagger, message, false)
+                    .map_err(|e| format!("Git error: {}", e))?;
+            }
+            None => {
+                repo.tag_lightweight(name, commit.as_object(), false)
+                    .map_err(|e| format!("Git error: {}", e))?;
+            }
+        }
+
+        tag_info(&repo, name)?.ok_or_else(|| format!("Tag not found: {}", name))
+    }
+
+    pub fn delete_tag(&self, repo_id: &str, name: &str) -> Result<(), String> {
+        let repo = self.open_repository(repo_id)?;
+
+        repo.find_reference(&format!("refs/tags/{}", name))
+            .map_err(|_| format!("Tag not found: {}", name))?;
+
+        repo.tag_delete(name).map_err(|e| format!("Git error: {}", e))
+    }
+
+    pub async fn advertise_refs(&self, repo_id: &str, rpc: GitRpc, protocol: Option<&str>) -> Result<Vec<u8>, String> {
+        let advertisement = self.ref_advertisement(repo_id, rpc, protocol).await?;
+
+        // Smart HTTP prefixes the advertisement with the service announcement
+        let mut body = pkt_line(&format!("# service={}\n", rpc.service_name()));
+        body.extend_from_slice(b"0000");
+        body.extend_from_slice(&advertisement);
+
+        Ok(body)
+    }
+
+    // The bare ref advertisement, as SSH clients expect it without the HTTP service announcement
+    pub async fn ref_advertisement(&self, repo_id: &str, rpc: GitRpc, protocol: Option<&str>) -> Result<Vec<u8>, String> {
+        let mut command = Command::new("git");
+        command
+            .arg(rpc.subcommand())
+            .arg("--stateless-rpc")
+            .arg("--advertise-refs")
+            .arg(self.repository_path(repo_id));
+
+        if let Some(protocol) = protocol {
+            command.env("GIT_PROTOCOL", protocol);
+        }
+
+        let output = command
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run git {}: {}", rpc.subcommand(), e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "git {} failed: {}",
+                rpc.subcommand(),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(output.stdout)
+    }
+
+    // Starts an interactive upload-pack for transports that keep a connection open, such as SSH
+    pub fn spawn_upload_pack(&self, repo_id: &str, protocol: Option<&str>) -> Result<Child, String> {
+        let mut command = Command::new("git");
+        command
+            .arg(GitRpc::UploadPack.subcommand())
+            .arg(self.repository_path(repo_id))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        if let Some(protocol) = protocol {
+            command.env("GIT_PROTOCOL", protocol);
+        }
+
+        command
+            .spawn()
+            .map_err(|e| format!("Failed to run git {}: {}", GitRpc::UploadPack.subcommand(), e))
+    }
+
+    /// Starts a stateless RPC that takes its request as it arrives. For receive-pack, the
+    /// pre-receive hook refuses updates to `fast_forward_refs` that are not fast-forwards.
+    pub fn spawn_stateless_rpc(&self, repo_id: &str, rpc: GitRpc, protocol: Option<&str>, fast_forward_refs: &[String]) -> Result<StatelessRpc, String> {
+        let mut command = Command::new("git");
+        if rpc == GitRpc::ReceivePack {
+            let mut hooks_path = OsString::from("core.hooksPath=");
+            hooks_path.push(self.hooks_path()?);
+            command
+                .arg("-c")
+                .arg(hooks_path)
+                .env("DEVIT_FAST_FORWARD_REFS", fast_forward_refs.join(" "));
+        }
+        command
+            .arg(rpc.subcommand())
+            .arg("--stateless-rpc")
+            .arg(self.repository_path(repo_id))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        if let Some(protocol) = protocol {
+            command.env("GIT_PROTOCOL", protocol);
+        }
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Failed to run git {}: {}", rpc.subcommand(), e))?;
+        let stdin = child.stdin.take().ok_or("Failed to open git stdin".to_string())?;
+
+        // Collect the response concurrently so a large one cannot fill the pipe while the request is written
+        Ok(StatelessRpc {
+            rpc,
+            stdin: Some(stdin),
+            output: tokio::spawn(child.wait_with_output()),
+        })
+    }
+}
+
+/// A running stateless RPC. Dropping it before `finish` kills git, so an abandoned push stores nothing.
+pub struct StatelessRpc {
+    rpc: GitRpc,
+    stdin: Option<ChildStdin>,
+    output: JoinHandle<std::io::Result<Output>>,
+}
+
+impl StatelessRpc {
+    // Fails once git has stopped reading, in which case finish reports why
+    pub async fn write(&mut self, data: &[u8]) -> Result<(), String> {
+        let stdin = self.stdin.as_mut().ok_or("Git request already finished".to_string())?;
+        stdin
+            .write_all(data)
+            .await
+            .map_err(|e| format!("Failed to write git request: {}", e))
+    }
+
+    /// Closes the request and waits for git's response.
+    pub async fn finish(mut self) -> Result<Vec<u8>, String> {
+        drop(self.stdin.take());
+
+        let output = (&mut self.output)
+            .await
+            .map_err(|e| format!("Failed to run git {}: {}", self.rpc.subcommand(), e))?
+            .map_err(|e| format!("Failed to run git {}: {}", self.rpc.subcommand(), e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "git {} failed: {}",
+                self.rpc.subcommand(),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(output.stdout)
+    }
+}
+
+impl Drop for StatelessRpc {
+    fn drop(&mut self) {
+        self.output.abort();
+    }
+}
+
+fn resolve_commit<'r>(repo: &'r GitRepository, reference: Option<&str>) -> Result<Commit<'r>, String> {
+    let object = match reference {
+        Some(reference) => repo.revparse_single(reference),
+        None => repo.head().and_then(|head| head.peel(ObjectType::Commit)),
+    };
+
+    object
+        .and_then(|object| object.peel_to_commit())
+        .map_err(|_| match reference {
+            Some(reference) => format!("Reference not found: {}", reference),
+            None => "Repository is empty".to_string(),
+        })
+}
+
+fn blob_at<'r>(repo: &'r GitRepository, commit: &str, path: &str) -> Result<Blob<'r>, String> {
+    let tree = resolve_commit(repo, Some(commit))?
+        .tree()
+        .map_err(|e| format!("Git error: {}", e))?;
+
+    tree.get_path(Path::new(path))
+        .and_then(|entry| entry.to_object(repo))
+        .and_then(|object| object.peel_to_blob())
+        .map_err(|_| format!("Path not found at {}: {}", commit, path))
+}
+
+fn branch_tip<'r>(repo: &'r GitRepository, name: &str) -> Result<Commit<'r>, String> {
+    repo.find_branch(name, BranchType::Local)
+        .map_err(|_| format!("Branch not found: {}", name))?
+        .get()
+        .peel_to_commit()
+        .map_err(|e| format!("Git error: {}", e))
+}
+
+// Diff from the merge base of the two branches to head, i.e. base...head
+fn branch_diff<'r>(repo: &'r GitRepository, base: &str, head: &str) -> Result<Diff<'r>, String> {
+    let base_commit = branch_tip(repo, base)?;
+    let head_commit = branch_tip(repo, head)?;
+
+    let merge_base = repo
+        .merge_base(base_commit.id(), head_commit.id())
+        .map_err(|_| format!("{} and {} have no common history", base, head))?;
+
+    let base_tree = repo
+        .find_commit(merge_base)
+        .and_then(|commit| commit.tree())
+        .map_err(|e| format!("Git error: {}", e))?;
+    let head_tree = head_commit.tree().map_err(|e| format!("Git error: {}", e))?;
+
+    let mut diff = repo
+        .diff_tree_to_tree(Some(&base_tree), Some(&head_tree), None)
+        .map_err(|e| format!("Git error: {}", e))?;
+    diff.find_similar(None).map_err(|e| format!("Git error: {}", e))?;
+
+    Ok(diff)
+}
+
+fn conflict_paths(index: &Index) -> Result<Vec<String>, String> {
+    let mut paths = Vec::new();
+
+    for conflict in index.conflicts().map_err(|e| format!("Git error: {}", e))? {
+        let conflict = conflict.map_err(|e| format!("Git error: {}", e))?;
+
+        if let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) {
+            paths.push(String::from_utf8_lossy(&entry.path).into_owned());
+        }
+    }
+
+    Ok(paths)
+}
+
+fn head_branch_name(repo: &GitRepository) -> Option<String> {
+    let head = repo.find_reference("HEAD").ok()?;
+    let target = head.symbolic_target()?;
+
+    target.strip_prefix("refs/heads/").map(|name| name.to_string())
+}
+
+// Returns None when the tag exists but does not point at a commit
+fn tag_info(repo: &GitRepository, name: &str) -> Result<Option<Tag>, String> {
+    let reference = match repo.find_reference(&format!("refs/tags/{}", name)) {
+        Ok(reference) => reference,
+        Err(_) => return Ok(None),
+    };
+
+    let commit = match reference.peel_to_commit() {
+        Ok(commit) => commit,
+        Err(_) => return Ok(None),
+    };
+
+    let annotated = reference.peel_to_tag().ok();
+
+    Ok(Some(Tag {
+        name: name.to_string(),
+        commit: commit_summary(&commit),
+        tag_sha: annotated.as_ref().map(|tag| tag.id().to_string()),
+        message: annotated.as_ref().and_then(|tag| tag.message()).map(|m| m.to_string()),
+        tagger: annotated.as_ref().and_then(|tag| tag.tagger()).map(|sig| git_signature(&sig)),
+    }))
+}
+
+fn signature_time(signature: &Signature) -> DateTime<Utc> {
+    Utc.timestamp_opt(signature.when().seconds(), 0)
+        .single()
+        .unwrap_or_else(Utc::now)
+}
+
+fn git_signature(signature: &Signature) -> GitSignature {
+    GitSignature {
+        name: signature.name().unwrap_or_default().to_string(),
+        email: signature.email().unwrap_or_default().to_string(),
+        date: signature_time(signature),
+    }
+}
+
+fn commit_summary(commit: &Commit) -> CommitSummary {
+    CommitSummary {
+        sha: commit.id().to_string(),
+        message: commit.message().unwrap_or_default().to_string(),
+        author: git_signature(&commit.author()),
+        committer: git_signature(&commit.committer()),
+        parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+    }
+}
+
+// A commit touches a path unless the path is unchanged from one of its parents, as in git log
+fn touches_path(commit: &Commit, path: &Path) -> Result<bool, String> {
+    let entry_id = |tree: Tree| tree.get_path(path).ok().map(|entry| entry.id());
+
+    let current = entry_id(commit.tree().map_err(|e| format!("Git error: {}", e))?);
+
+    if commit.parent_count() == 0 {
+        return Ok(current.is_some());
+    }
+
+    for parent in commit.parents() {
+        let previous = entry_id(parent.tree().map_err(|e| format!("Git error: {}", e))?);
+        if previous == current {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+fn diff_files(diff: &Diff) -> Result<(Vec<FileDiff>, DiffStats), String> {
+    let mut files = Vec::new();
+    let mut stats = DiffStats::default();
+
+    for (index, delta) in diff.deltas().enumerate() {
+        let old_path = delta.old_file().path().map(|p| p.to_string_lossy().into_owned());
+        let new_path = delta.new_file().path().map(|p| p.to_string_lossy().into_owned());
+
+        let status = match delta.status() {
+            Delta::Added => "added",
+            Delta::Deleted => "removed",
+            Delta::Renamed => "renamed",
+            Delta::Copied => "copied",
+            _ => "modified",
+        };
+
+        let filename = match delta.status() {
+            Delta::Deleted => old_path.clone(),
+            _ => new_path.clone(),
+        }
+        .unwrap_or_default();
+
+        let previous_filename = match delta.status() {
+            Delta::Renamed | Delta::Copied => old_path,
+            _ => None,
+        };
+
+        let (additions, deletions, patch) = match Patch::from_diff(diff, index).map_err(|e| format!("Git error: {}", e))? {
+            Some(mut patch) => {
+                let (_, additions, deletions) = patch.line_stats().map_err(|e| format!("Git error: {}", e))?;
+                let text = if patch.delta().flags().is_binary() {
+                    None
+                } else {
+                    Some(hunks_text(&mut patch)?).filter(|text| text.len() <= MAX_PATCH_SIZE)
+                };
+                (additions, deletions, text)
+            }
+            None => (0, 0, None),
+        };
+
+        stats.additions += additions;
+        stats.deletions += deletions;
+        stats.total += additions + deletions;
+
+        files.push(FileDiff {
+            filename,
+            previous_filename,
+            status: status.to_string(),
+            additions,
+            deletions,
+            changes: additions + deletions,
+            patch,
+        });
+    }
+
+    Ok((files, stats))
+}
+
+// Renders a file's hunks without the diff --git header, as shown per file in the API
+fn hunks_text(patch: &mut Patch) -> Result<String, String> {
+    let mut text = String::new();
+
+    patch
+        .print(&mut |_delta, _hunk, line| {
+            match line.origin() {
+                'F' => return true,
+                '+' | '-' | ' ' => text.push(line.origin()),
+                _ => {}
+            }
+            text.push_str(&String::from_utf8_lossy(line.content()));
+            true
+        })
+        .map_err(|e| format!("Git error: {}", e))?;
+
+    Ok(text)
+}
+
+fn entry_type_name(filemode: i32) -> &'static str {
+    match filemode {
+        0o040000 => "dir",
+        0o120000 => "symlink",
+        0o160000 => "submodule",
+        _ => "file",
+    }
+}
+
+fn tree_entry(odb: &Odb, root: &str, entry: &TreeEntry) -> GitTreeEntry {
+    let entry_type = match entry.kind() {
+        Some(ObjectType::Tree) => "tree",
+        Some(ObjectType::Commit) => "commit",
+        _ => "blob",
+    };
+    let size = match entry.kind() {
+        Some(ObjectType::Blob) => odb.read_header(entry.id()).ok().map(|(size, _)| size as u64),
+        _ => None,
+    };
+
+    GitTreeEntry {
+        path: format!("{}{}", root, entry.name().unwrap_or_default()),
+        mode: format!("{:06o}", entry.filemode()),
+        entry_type: entry_type.to_string(),
+        sha: entry.id().to_string(),
+        size,
+    }
+}
+
+fn list_directory(repo: &GitRepository, tree: &Tree, path: &str) -> Result<Vec<ContentEntry>, String> {
+    let odb = repo.odb().map_err(|e| format!("Git error: {}", e))?;
+
+    let mut entries: Vec<ContentEntry> = tree
+        .iter()
+        .map(|entry| {
+            let name = entry.name().unwrap_or_default().to_string();
+            let size = match entry.kind() {
+                Some(ObjectType::Blob) => odb.read_header(entry.id()).map(|(size, _)| size as u64).unwrap_or(0),
+                _ => 0,
+            };
+
+            ContentEntry {
+                entry_type: entry_type_name(entry.filemode()).to_string(),
+                path: if path.is_empty() { name.clone() } else { format!("{}/{}", path, name) },
+                name,
+                sha: entry.id().to_string(),
+                size,
+            }
+        })
+        .collect();
+
+    // Directories first, then files, each alphabetically
+    entries.sort_by(|a, b| (a.entry_type != "dir", &a.name).cmp(&(b.entry_type != "dir", &b.name)));
+
+    Ok(entries)
+}
+
+// Files larger than this are listed without inline content; use the raw endpoint instead
+const MAX_INLINE_CONTENT_SIZE: usize = 1024 * 1024;
+
+// Per-file patches larger than this are omitted from diff responses
+const MAX_PATCH_SIZE: usize = 1024 * 1024;
+
+// Upper bound on entries returned by a recursive tree listing
+const MAX_TREE_ENTRIES: usize = 100_000;
+
+fn pkt_line(data: &str) -> Vec<u8> {
+    pkt_bytes(data.as_bytes())
+}
+
+fn pkt_bytes(data: &[u8]) -> Vec<u8> {
+    let mut line = format!("{:04x}", data.len() + 4).into_bytes();
+    line.extend_from_slice(data);
+    line
+}
+
+// The payload of a pkt-line (None for a flush packet) and the offset after it
+type PktLine<'a> = (Option<&'a [u8]>, usize);
+
+// Reads the pkt-line at pos, or returns None when input ends before it does
+fn read_pkt_line(input: &[u8], pos: usize) -> Result<Option<PktLine<'_>>, String> {
+    let header = match input.get(pos..pos + 4) {
+        Some(header) => header,
+        None => return Ok(None),
+    };
+    let len = std::str::from_utf8(header)
+        .ok()
+        .and_then(|header| usize::from_str_radix(header, 16).ok())
+        .ok_or("Invalid pkt-line length")?;
+
+    match len {
+        0 => Ok(Some((None, pos + 4))),
+        1..=3 => Err("Invalid pkt-line length".to_string()),
+        _ => Ok(input.get(pos + 4..pos + len).map(|data| (Some(data), pos + len))),
+    }
+}
+
+// Commands name objects by their full SHA-1
+fn is_object_id(id: &str) -> bool {
+    id.len() == ZERO_OID.len() && id.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/// Reads the ref update commands at the start of a receive-pack request, along with the offset
+/// where the pack begins. Returns None while the commands have not all arrived.
+pub fn parse_push_request(input: &[u8]) -> Result<Option<(PushRequest, usize)>, String> {
+    let mut updates = Vec::new();
+    let mut capabilities = Vec::new();
+    let mut pos = 0;
+
+    loop {
+        let (line, next) = match read_pkt_line(input, pos)? {
+            Some(read) => read,
+            None => return Ok(None),
+        };
+        pos = next;
+
+        let line = match line {
+            Some(line) => line,
+            None => break,
+        };
+
+        // The first command carries the client's capabilities after a NUL byte
+        let command = match line.iter().position(|&byte| byte == 0) {
+            Some(nul) => {
+                capabilities = String::from_utf8_lossy(&line[nul + 1..])
+                    .split_whitespace()
+                    .map(|capability| capability.to_string())
+                    .collect();
+                &line[..nul]
+            }
+            None => line,
+        };
+
+        let command = String::from_utf8_lossy(command);
+        let mut parts = command.trim_end_matches('\n').splitn(3, ' ');
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(old), Some(new), Some(name)) if is_object_id(old) && is_object_id(new) => updates.push(RefUpdate {
+                old: old.to_string(),
+                new: new.to_string(),
+                name: name.to_string(),
+            }),
+            _ => return Err(format!("Malformed push command: {}", command)),
+        }
+    }
+
+    // Push options come in their own flush-terminated section before the pack
+    if capabilities.iter().any(|capability| capability == "push-options") {
+        loop {
+            let (line, next) = match read_pkt_line(input, pos)? {
+                Some(read) => read,
+                None => return Ok(None),
+            };
+            pos = next;
+            if line.is_none() {
+                break;
+            }
+        }
+    }
+
+    Ok(Some((PushRequest { updates, capabilities }, pos)))
+}
+
+/// Builds the receive-pack response refusing the whole push, with a reason for each rejected ref.
+pub fn push_rejection_report(push: &PushRequest, rejected: &[(String, String)]) -> Vec<u8> {
+    let mut report = pkt_line("unpack ok\n");
+    for update in &push.updates {
+        let reason = rejected
+            .iter()
+            .find(|(name, _)| *name == update.name)
+            .map(|(_, reason)| reason.as_str())
+            .unwrap_or("atomic push failed");
+        report.extend(pkt_line(&format!("ng {} {}\n", update.name, reason)));
+    }
+    report.extend_from_slice(b"0000");
+
+    let max_payload = if push.capabilities.iter().any(|capability| capability == "side-band-64k") {
+        65515
+    } else if push.capabilities.iter().any(|capability| capability == "side-band") {
+        999
+    } else {
+        return report;
+    };
+
+    // Band 2 is shown to the user as "remote:" output, band 1 carries the status report
+    let mut body = Vec::new();
+    for (_, reason) in rejected {
+        let mut message = vec![2u8];
+        message.extend_from_slice(format!("error: {}\n", reason).as_bytes());
+        body.extend(pkt_bytes(&message));
+    }
+    for chunk in report.chunks(max_payload - 1) {
+        let mut data = vec![1u8];
+        data.extend_from_slice(chunk);
+        body.extend(pkt_bytes(&data));
+    }
+    body.extend_from_slice(b"0000");
+    body
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "1111111111111111111111111111111111111111";
+    const NEW: &str = "2222222222222222222222222222222222222222";
+
+    fn push_request(commands: &[&str], pack: &[u8]) -> Vec<u8> {
+        let mut input = Vec::new();
+        for (index, command) in commands.iter().enumerate() {
+            match index {
+                0 => input.extend(pkt_line(&format!("{}\0report-status side-band-64k\n", command))),
+                _ => input.extend(pkt_line(&format!("{}\n", command))),
+            }
+        }
+        input.extend_from_slice(b"0000");
+        input.extend_from_slice(pack);
+        input
+    }
+
+    #[test]
+    fn push_commands_are_parsed_up_to_the_pack() {
+        let update = format!("{} {} refs/heads/main", OLD, NEW);
+        let delete = format!("{} {} refs/heads/old", OLD, ZERO_OID);
+        let input = push_request(&[&update, &delete], b"PACK");
+
+        let (push, pack_offset) = parse_push_request(&input).unwrap().unwrap();
+
+        assert_eq!(&input[pack_offset..], b"PACK");
+        assert_eq!(push.capabilities, vec!["report-status", "side-band-64k"]);
+        assert_eq!(push.updates.len(), 2);
+        assert_eq!(push.updates[0].old, OLD);
+        assert_eq!(push.updates[0].new, NEW);
+        assert_eq!(push.updates[0].branch(), Some("main"));
+        assert!(!push.updates[0].is_delete());
+        assert!(push.updates[1].is_delete());
+    }
+
+    #[test]
+    fn incomplete_push_commands_need_more_input() {
+        let update = format!("{} {} refs/heads/main", OLD, NEW);
+        let input = push_request(&[&update], b"");
+
+        // Cut inside the first length header, inside the command, and before the flush packet
+        for len in [2, 20, input.len() - 4] {
+            assert!(parse_push_request(&input[..len]).unwrap().is_none(), "{} bytes should be incomplete", len);
+        }
+        assert!(parse_push_request(&input).unwrap().is_some());
+    }
+
+    #[test]
+    fn malformed_push_commands_are_rejected() {
+        for command in ["not a command\n", &format!("{} main refs/heads/main\n", OLD), &format!("{} {}\n", OLD, NEW)] {
+            let mut input = pkt_line(command);
+            input.extend_from_slice(b"0000");
+            assert!(parse_push_request(&input).is_err(), "{:?}", command);
+        }
+
+        assert!(parse_push_request(b"zzzz").is_err());
+        assert!(parse_push_request(b"0002").is_err());
+    }
+
+    #[test]
+    fn creates_are_recognized_by_the_zero_id() {
+        let update = RefUpdate { old: ZERO_OID.to_string(), new: NEW.to_string(), name: "refs/tags/v1".to_string() };
+
+        assert!(update.is_create());
+        assert!(!update.is_delete());
+        assert_eq!(update.branch(), None);
+    }
+}
@@ -1,5 +1,5 @@
 use crate::models::Issue;
-use sqlx::MySqlPool;
+use sqlx::{MySql, MySqlPool, Transaction};
 
 #[derive(Clone)]
 pub struct IssueService {
@@ -51,14 +51,10 @@ impl IssueService {
     }
 
     pub async fn create_issue(&self, repo_id: &str, author_id: &str, title: &str, body: Option<&str>) -> Result<Issue, String> {
-        // Get the next issue number for this repository
-        let next_number = sqlx::query!(
-            "SELECT COALESCE(MAX(number), 0) + 1 as next_number FROM issues WHERE repository_id = ?",
-            repo_id
-        )
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        let number = claim_number(&mut transaction, repo_id).await?;
 
         // Insert the issue
         sqlx::query!(
@@ -66,16 +62,19 @@ impl IssueService {
             INSERT INTO issues (number, title, body, status, author_id, repository_id, created_at, updated_at)
             VALUES (?, ?, ?, 'OPEN', ?, ?, NOW(), NOW())
             "#,
-            next_number.next_number,
+            number,
             title,
             body,
             author_id,
             repo_id
         )
-        .execute(&self.pool)
+        .execute(&mut *transaction)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
         // Get the inserted issue by repository_id and number
         let issue = sqlx::query_as!(
             Issue,
@@ -87,7 +86,7 @@ impl IssueService {
             WHERE repository_id = ? AND number = ?
             "#,
             repo_id,
-            next_number.next_number
+            number
         )
         .fetch_one(&self.pool)
         .await
@@ -169,3 +168,24 @@ impl IssueService {
 
 }
 
+/// Claims the next issue number in a repository. Issues and pull requests share one number sequence
+/// so /issues/{number} is unambiguous; the claim locks the repository's numbers until the transaction ends.
+pub async fn claim_number(transaction: &mut Transaction<'_, MySql>, repo_id: &str) -> Result<i64, String> {
+    let next_number = sqlx::query!(
+        r#"SELECT COALESCE(MAX(number), 0) + 1 as "next_number!: i64" FROM issue_numbers WHERE repository_id = ? FOR UPDATE"#,
+        repo_id
+    )
+    .fetch_one(&mut **transaction)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    sqlx::query!(
+        "INSERT INTO issue_numbers (repository_id, number, created_at) VALUES (?, ?, NOW())",
+        repo_id, next_number.next_number
+    )
+    .execute(&mut **transaction)
+    .await
+    .map_err(|e| format!("Database error: {}", e))?;
+
+    Ok(next_number.next_number)
+}
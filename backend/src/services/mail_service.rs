@@ -0,0 +1,154 @@
+use crate::config::AppConfig;
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::path::PathBuf;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug, Clone)]
+pub struct EmailMessage {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub body: String, // Plain text
+}
+
+/// Delivers outgoing mail. The implementation is picked at startup from `MAIL_TRANSPORT`.
+#[async_trait]
+pub trait MailTransport: Send + Sync {
+    async fn send(&self, message: &EmailMessage) -> Result<(), String>;
+}
+
+pub struct SmtpTransport {
+    mailer: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpTransport {
+    pub fn new(host: &str, port: u16, username: Option<String>, password: Option<String>, tls: bool) -> Result<Self, String> {
+        // Local catchers such as MailHog speak plain SMTP
+        let mut builder = if tls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+                .map_err(|e| format!("SMTP error: {}", e))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        };
+
+        builder = builder.port(port);
+
+        if let (Some(username), Some(password)) = (username, password) {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Ok(Self { mailer: builder.build() })
+    }
+}
+
+#[async_trait]
+impl MailTransport for SmtpTransport {
+    async fn send(&self, message: &EmailMessage) -> Result<(), String> {
+        let from: Mailbox = message.from.parse().map_err(|e| format!("Invalid sender address: {}", e))?;
+        let to: Mailbox = message.to.parse().map_err(|e| format!("Invalid recipient address: {}", e))?;
+
+        let email = Message::builder()
+            .from(from)
+            .to(to)
+            .subject(message.subject.clone())
+            .header(ContentType::TEXT_PLAIN)
+            .body(message.body.clone())
+            .map_err(|e| format!("Failed to build message: {}", e))?;
+
+        self.mailer
+            .send(email)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("SMTP error: {}", e))
+    }
+}
+
+// Writes each message to its own file so local setups can read mail without a server
+pub struct FileTransport {
+    dir: PathBuf,
+}
+
+impl FileTransport {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl MailTransport for FileTransport {
+    async fn send(&self, message: &EmailMessage) -> Result<(), String> {
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| format!("Failed to create mail directory: {}", e))?;
+
+        let name = format!("{}_{}.eml", Utc::now().format("%Y%m%d%H%M%S"), Uuid::new_v4().simple());
+        let contents = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\n\r\n{}\r\n",
+            message.from,
+            message.to,
+            message.subject,
+            Utc::now().to_rfc2822(),
+            message.body
+        );
+
+        tokio::fs::write(self.dir.join(name), contents)
+            .await
+            .map_err(|e| format!("Failed to write mail: {}", e))
+    }
+}
+
+pub struct LogTransport;
+
+#[async_trait]
+impl MailTransport for LogTransport {
+    async fn send(&self, message: &EmailMessage) -> Result<(), String> {
+        log::info!("Mail to {} ({}):\n{}", message.to, message.subject, message.body);
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct MailService {
+    transport: Arc<dyn MailTransport>,
+    from: String,
+}
+
+impl MailService {
+    pub fn new(transport: Arc<dyn MailTransport>, from: String) -> Self {
+        Self { transport, from }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Result<Self, String> {
+        let transport: Arc<dyn MailTransport> = match config.mail_transport.as_str() {
+            "smtp" => Arc::new(SmtpTransport::new(
+                &config.smtp_host,
+                config.smtp_port,
+                config.smtp_username.clone(),
+                config.smtp_password.clone(),
+                config.smtp_tls,
+            )?),
+            "file" => Arc::new(FileTransport::new(&config.mail_dir)),
+            "log" => Arc::new(LogTransport),
+            other => return Err(format!("Unknown mail transport: {}", other)),
+        };
+
+        Ok(Self::new(transport, config.mail_from.clone()))
+    }
+
+    pub async fn send(&self, to: &str, subject: &str, body: &str) -> Result<(), String> {
+        self.transport
+            .send(&EmailMessage {
+                from: self.from.clone(),
+                to: to.to_string(),
+                subject: subject.to_string(),
+                body: body.to_string(),
+            })
+            .await
+    }
+}
@@ -5,9 +5,52 @@ pub mod auth_service;
 pub mod repository_service;
 pub mod issue_service;
 pub mod pull_requests_service;
+pub mod git_service;
+pub mod comment_service;
+pub mod branch_protection_service;
+pub mod organization_service;
+pub mod team_service;
+pub mod collaborator_service;
+pub mod permission_service;
+pub mod access_token_service;
+pub mod ssh_key_service;
+pub mod session_service;
+pub mod mail_service;
+pub mod account_service;
+pub mod two_factor_service;
+pub mod oidc_service;
+pub mod oauth_service;
+pub mod webhook_service;
+pub mod commit_status_service;
+pub mod object_storage;
+pub mod pipeline_service;
+pub mod event_service;
+pub mod notification_service;
+pub mod activity_service;
 
 pub use auth_service::AuthService;
 pub use user_service::UserService;
 pub use repository_service::RepositoryService;
 pub use issue_service::IssueService;
 pub use pull_requests_service::PullRequestService;
+pub use git_service::GitService;
+pub use comment_service::CommentService;
+pub use branch_protection_service::BranchProtectionService;
+pub use organization_service::OrganizationService;
+pub use team_service::TeamService;
+pub use collaborator_service::CollaboratorService;
+pub use permission_service::PermissionService;
+pub use access_token_service::AccessTokenService;
+pub use ssh_key_service::SshKeyService;
+pub use session_service::SessionService;
+pub use mail_service::MailService;
+pub use account_service::AccountService;
+pub use two_factor_service::TwoFactorService;
+pub use oidc_service::OidcService;
+pub use oauth_service::OAuthService;
+pub use webhook_service::WebhookService;
+pub use commit_status_service::CommitStatusService;
+pub use pipeline_service::PipelineService;
+pub use event_service::EventService;
+pub use notification_service::NotificationService;
+pub use activity_service::ActivityService;
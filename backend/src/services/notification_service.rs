@@ -0,0 +1,736 @@
+use crate::models::{
+    CommentThread, Notification, NotificationReason, NotificationSettings, NotificationState, NotificationSubject,
+    Repository, ThreadSubscription,
+};
+use crate::services::{MailService, PermissionService};
+use crate::utils::mentions::mentioned_usernames;
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+use std::collections::HashMap;
+use std::time::Duration;
+use uuid::Uuid;
+
+// Items listed in one digest email; the rest are summarized with a count
+const DIGEST_MAX_ITEMS: usize = 20;
+
+struct NotificationRow {
+    id: String,
+    repository_id: String,
+    owner: String,
+    repository_name: String,
+    issue_number: Option<i32>,
+    issue_title: Option<String>,
+    issue_status: Option<String>,
+    pull_request_number: Option<i32>,
+    pull_request_title: Option<String>,
+    pull_request_status: Option<String>,
+    reason: String,
+    state: String,
+    last_actor_id: Option<String>,
+    last_read_at: Option<DateTime<Utc>>,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<NotificationRow> for Notification {
+    fn from(row: NotificationRow) -> Self {
+        let subject = match row.issue_number {
+            Some(number) => NotificationSubject {
+                kind: "issue".to_string(),
+                number,
+                title: row.issue_title.unwrap_or_default(),
+                status: row.issue_status.unwrap_or_default(),
+            },
+            None => NotificationSubject {
+                kind: "pull_request".to_string(),
+                number: row.pull_request_number.unwrap_or_default(),
+                title: row.pull_request_title.unwrap_or_default(),
+                status: row.pull_request_status.unwrap_or_default(),
+            },
+        };
+
+        Self {
+            id: row.id,
+            repository_id: row.repository_id,
+            repository: format!("{}/{}", row.owner, row.repository_name),
+            subject,
+            reason: NotificationReason::parse(&row.reason).unwrap_or(NotificationReason::Subscribed),
+            state: NotificationState::parse(&row.state).unwrap_or(NotificationState::Unread),
+            last_actor_id: row.last_actor_id,
+            last_read_at: row.last_read_at,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+// One user's share of a digest run
+struct Digest {
+    user_id: String,
+    username: String,
+    email: String,
+    lines: Vec<(String, String)>, // (repository_id, line)
+}
+
+/// Which notifications an inbox listing includes.
+pub struct NotificationFilter {
+    pub states: Vec<NotificationState>,
+    pub reason: Option<NotificationReason>,
+    pub repository_id: Option<String>,
+    pub participating: bool, // Leaves out notifications that only come from watching the repository
+}
+
+/// Thread subscriptions, repository watching, the notifications inbox and its email digest.
+#[derive(Clone)]
+pub struct NotificationService {
+    pool: MySqlPool,
+    permissions: PermissionService,
+    mail: MailService,
+    public_url: String,
+}
+
+impl NotificationService {
+    pub fn new(pool: MySqlPool, permissions: PermissionService, mail: MailService, public_url: String) -> Self {
+        Self { pool, permissions, mail, public_url }
+    }
+
+    /// Notifies subscribers, repository watchers and users mentioned in `body` about activity on a
+    /// thread. The actor is subscribed with `participation`, mentioned users as mentioned.
+    pub fn record_activity(
+        &self,
+        repo: &Repository,
+        thread: CommentThread,
+        actor_id: &str,
+        participation: Option<NotificationReason>,
+        body: Option<&str>,
+    ) {
+        let service = self.clone();
+        let repo = repo.clone();
+        let actor_id = actor_id.to_string();
+        let body = body.map(|body| body.to_string());
+
+        tokio::spawn(async move {
+            if let Err(err) = service.deliver_activity(&repo, &thread, &actor_id, participation, body.as_deref()).await {
+                log::error!("Failed to record notifications for {}: {}", repo.id, err);
+            }
+        });
+    }
+
+    /// Subscribes a new assignee to the thread and tells them, unless they assigned themselves.
+    pub fn record_assignment(&self, repo: &Repository, thread: CommentThread, actor_id: &str, assignee_id: &str) {
+        let service = self.clone();
+        let repo = repo.clone();
+        let actor_id = actor_id.to_string();
+        let assignee_id = assignee_id.to_string();
+
+        tokio::spawn(async move {
+            let result = async {
+                if !service.can_read(&assignee_id, &repo).await? {
+                    return Ok(());
+                }
+                service.auto_subscribe(&assignee_id, &repo.id, &thread, NotificationReason::Assign).await?;
+                if assignee_id != actor_id {
+                    service.notify(&assignee_id, &repo.id, &thread, NotificationReason::Assign, &actor_id).await?;
+                }
+                Ok::<(), String>(())
+            }
+            .await;
+
+            if let Err(err) = result {
+                log::error!("Failed to record assignment notification for {}: {}", repo.id, err);
+            }
+        });
+    }
+
+    async fn deliver_activity(
+        &self,
+        repo: &Repository,
+        thread: &CommentThread,
+        actor_id: &str,
+        participation: Option<NotificationReason>,
+        body: Option<&str>,
+    ) -> Result<(), String> {
+        if let Some(reason) = participation {
+            self.auto_subscribe(actor_id, &repo.id, thread, reason).await?;
+        }
+
+        let mut recipients: HashMap<String, NotificationReason> = HashMap::new();
+        let mut add = |user_id: String, reason: NotificationReason| {
+            let current = recipients.entry(user_id).or_insert(reason);
+            if reason.rank() > current.rank() {
+                *current = reason;
+            }
+        };
+
+        let subscribers = sqlx::query!(
+            r#"
+            SELECT user_id, reason FROM thread_subscriptions
+            WHERE issue_id <=> ? AND pull_request_id <=> ? AND subscribed = TRUE
+            "#,
+            thread.issue_id(),
+            thread.pull_request_id()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        for subscriber in subscribers {
+            let reason = NotificationReason::parse(&subscriber.reason).unwrap_or(NotificationReason::Manual);
+            add(subscriber.user_id, reason);
+        }
+
+        // Watchers who unsubscribed from this thread are left out
+        let watchers = sqlx::query!(
+            r#"
+            SELECT w.user_id FROM repository_watches w
+            WHERE w.repository_id = ? AND NOT EXISTS (
+                SELECT 1 FROM thread_subscriptions s
+                WHERE s.user_id = w.user_id AND s.issue_id <=> ? AND s.pull_request_id <=> ? AND s.subscribed = FALSE
+            )
+            "#,
+            repo.id,
+            thread.issue_id(),
+            thread.pull_request_id()
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        for watcher in watchers {
+            add(watcher.user_id, NotificationReason::Subscribed);
+        }
+
+        for user_id in self.resolve_mentions(body.unwrap_or_default()).await? {
+            if user_id != actor_id && self.can_read(&user_id, repo).await? {
+                self.auto_subscribe(&user_id, &repo.id, thread, NotificationReason::Mention).await?;
+                add(user_id, NotificationReason::Mention);
+            }
+        }
+
+        recipients.remove(actor_id);
+
+        for (user_id, reason) in recipients {
+            // Subscribers and watchers may have lost access since they subscribed
+            if reason != NotificationReason::Mention && !self.can_read(&user_id, repo).await? {
+                continue;
+            }
+            self.notify(&user_id, &repo.id, thread, reason, actor_id).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn can_read(&self, user_id: &str, repo: &Repository) -> Result<bool, String> {
+        Ok(self.permissions.effective_permission(Some(user_id), repo).await?.is_some())
+    }
+
+    async fn resolve_mentions(&self, body: &str) -> Result<Vec<String>, String> {
+        let mut user_ids = Vec::new();
+
+        for username in mentioned_usernames(body) {
+            let user = sqlx::query!("SELECT id FROM users WHERE username = ?", username)
+                .fetch_optional(&self.pool)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+
+            if let Some(user) = user {
+                user_ids.push(user.id);
+            }
+        }
+
+        Ok(user_ids)
+    }
+
+    // Users who unsubscribed stay unsubscribed; existing subscriptions keep the most personal reason
+    async fn auto_subscribe(&self, user_id: &str, repo_id: &str, thread: &CommentThread, reason: NotificationReason) -> Result<(), String> {
+        let existing = sqlx::query!(
+            r#"
+            SELECT id, reason, subscribed as "subscribed: bool" FROM thread_subscriptions
+            WHERE user_id = ? AND issue_id <=> ? AND pull_request_id <=> ?
+            "#,
+            user_id,
+            thread.issue_id(),
+            thread.pull_request_id()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        match existing {
+            Some(subscription) => {
+                let current = NotificationReason::parse(&subscription.reason).unwrap_or(NotificationReason::Manual);
+                if subscription.subscribed && reason.rank() > current.rank() {
+                    sqlx::query!("UPDATE thread_subscriptions SET reason = ? WHERE id = ?", reason.as_str(), subscription.id)
+                        .execute(&self.pool)
+                        .await
+                        .map_err(|e| format!("Database error: {}", e))?;
+                }
+            }
+            None => {
+                let subscription_id = format!("tsub_{}", Uuid::new_v4().to_string().replace("-", ""));
+                sqlx::query!(
+                    r#"
+                    INSERT IGNORE INTO thread_subscriptions (id, user_id, repository_id, issue_id, pull_request_id, subscribed, reason, created_at, updated_at)
+                    VALUES (?, ?, ?, ?, ?, TRUE, ?, NOW(), NOW())
+                    "#,
+                    subscription_id,
+                    user_id,
+                    repo_id,
+                    thread.issue_id(),
+                    thread.pull_request_id(),
+                    reason.as_str()
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // New activity brings a notification back to the inbox as unread
+    async fn notify(&self, user_id: &str, repo_id: &str, thread: &CommentThread, reason: NotificationReason, actor_id: &str) -> Result<(), String> {
+        let notification_id = format!("ntf_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO notifications (id, user_id, repository_id, issue_id, pull_request_id, reason, state, last_actor_id, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, 'unread', ?, NOW(), NOW())
+            ON DUPLICATE KEY UPDATE reason = VALUES(reason), state = 'unread', last_actor_id = VALUES(last_actor_id), updated_at = NOW()
+            "#,
+            notification_id,
+            user_id,
+            repo_id,
+            thread.issue_id(),
+            thread.pull_request_id(),
+            reason.as_str(),
+            actor_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn list_notifications(&self, user_id: &str, filter: &NotificationFilter, limit: u32, offset: u32) -> Result<Vec<Notification>, String> {
+        let rows = sqlx::query_as!(
+            NotificationRow,
+            r#"
+            SELECT n.id, n.repository_id, COALESCE(o.name, u.username) as "owner!: String", r.name as repository_name,
+                i.number as "issue_number?: i32", i.title as "issue_title?: String", i.status as "issue_status?: String",
+                p.number as "pull_request_number?: i32", p.title as "pull_request_title?: String", p.status as "pull_request_status?: String",
+                n.reason, n.state, n.last_actor_id, n.last_read_at, n.created_at, n.updated_at
+            FROM notifications n
+            INNER JOIN repositories r ON r.id = n.repository_id
+            LEFT JOIN users u ON u.id = r.owner_id AND r.organization_id IS NULL
+            LEFT JOIN organizations o ON o.id = r.organization_id
+            LEFT JOIN issues i ON i.id = n.issue_id
+            LEFT JOIN pull_requests p ON p.id = n.pull_request_id
+            WHERE n.user_id = ?
+                AND ((n.state = 'unread' AND ?) OR (n.state = 'read' AND ?) OR (n.state = 'done' AND ?))
+                AND (? IS NULL OR n.reason = ?)
+                AND (? IS NULL OR n.repository_id = ?)
+                AND (NOT ? OR n.reason <> 'subscribed')
+            ORDER BY n.updated_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+            user_id,
+            filter.states.contains(&NotificationState::Unread),
+            filter.states.contains(&NotificationState::Read),
+            filter.states.contains(&NotificationState::Done),
+            filter.reason.map(|reason| reason.as_str()),
+            filter.reason.map(|reason| reason.as_str()),
+            filter.repository_id,
+            filter.repository_id,
+            filter.participating,
+            limit,
+            offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(rows.into_iter().map(Notification::from).collect())
+    }
+
+    pub async fn get_notification(&self, user_id: &str, notification_id: &str) -> Result<Notification, String> {
+        sqlx::query_as!(
+            NotificationRow,
+            r#"
+            SELECT n.id, n.repository_id, COALESCE(o.name, u.username) as "owner!: String", r.name as repository_name,
+                i.number as "issue_number?: i32", i.title as "issue_title?: String", i.status as "issue_status?: String",
+                p.number as "pull_request_number?: i32", p.title as "pull_request_title?: String", p.status as "pull_request_status?: String",
+                n.reason, n.state, n.last_actor_id, n.last_read_at, n.created_at, n.updated_at
+            FROM notifications n
+            INNER JOIN repositories r ON r.id = n.repository_id
+            LEFT JOIN users u ON u.id = r.owner_id AND r.organization_id IS NULL
+            LEFT JOIN organizations o ON o.id = r.organization_id
+            LEFT JOIN issues i ON i.id = n.issue_id
+            LEFT JOIN pull_requests p ON p.id = n.pull_request_id
+            WHERE n.id = ? AND n.user_id = ?
+            "#,
+            notification_id,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .map(Notification::from)
+        .ok_or_else(|| "Notification not found".to_string())
+    }
+
+    pub async fn unread_count(&self, user_id: &str) -> Result<i64, String> {
+        let row = sqlx::query!(
+            "SELECT COUNT(*) as count FROM notifications WHERE user_id = ? AND state = 'unread'",
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(row.count)
+    }
+
+    pub async fn set_state(&self, user_id: &str, notification_id: &str, state: NotificationState) -> Result<Notification, String> {
+        sqlx::query!(
+            r#"
+            UPDATE notifications
+            SET state = ?, last_read_at = IF(? = 'unread', last_read_at, NOW())
+            WHERE id = ? AND user_id = ?
+            "#,
+            state.as_str(),
+            state.as_str(),
+            notification_id,
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_notification(user_id, notification_id).await
+    }
+
+    /// Marks every unread notification read, or every unread and read notification done, and
+    /// returns how many changed.
+    pub async fn mark_all(&self, user_id: &str, state: NotificationState, repository_id: Option<&str>) -> Result<u64, String> {
+        if state == NotificationState::Unread {
+            return Err("Notifications can only be marked read or done in bulk".to_string());
+        }
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE notifications
+            SET state = ?, last_read_at = NOW()
+            WHERE user_id = ? AND (state = 'unread' OR (? = 'done' AND state = 'read'))
+                AND (? IS NULL OR repository_id = ?)
+            "#,
+            state.as_str(),
+            user_id,
+            state.as_str(),
+            repository_id,
+            repository_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_subscription(&self, user_id: &str, repo_id: &str, thread: &CommentThread) -> Result<ThreadSubscription, String> {
+        let subscription = sqlx::query!(
+            r#"
+            SELECT reason, subscribed as "subscribed: bool", created_at FROM thread_subscriptions
+            WHERE user_id = ? AND issue_id <=> ? AND pull_request_id <=> ?
+            "#,
+            user_id,
+            thread.issue_id(),
+            thread.pull_request_id()
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let watching_repository = self.is_watching(user_id, repo_id).await?;
+
+        Ok(match subscription {
+            Some(subscription) => ThreadSubscription {
+                subscribed: subscription.subscribed,
+                reason: NotificationReason::parse(&subscription.reason),
+                watching_repository,
+                created_at: subscription.created_at,
+            },
+            None => ThreadSubscription {
+                subscribed: watching_repository,
+                reason: None,
+                watching_repository,
+                created_at: None,
+            },
+        })
+    }
+
+    pub async fn set_subscription(&self, user_id: &str, repo_id: &str, thread: &CommentThread, subscribed: bool) -> Result<ThreadSubscription, String> {
+        let subscription_id = format!("tsub_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO thread_subscriptions (id, user_id, repository_id, issue_id, pull_request_id, subscribed, reason, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, 'manual', NOW(), NOW())
+            ON DUPLICATE KEY UPDATE subscribed = VALUES(subscribed), updated_at = NOW()
+            "#,
+            subscription_id,
+            user_id,
+            repo_id,
+            thread.issue_id(),
+            thread.pull_request_id(),
+            subscribed
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_subscription(user_id, repo_id, thread).await
+    }
+
+    /// Forgets the user's choice for a thread, so they are subscribed again by participating.
+    pub async fn delete_subscription(&self, user_id: &str, thread: &CommentThread) -> Result<(), String> {
+        sqlx::query!(
+            "DELETE FROM thread_subscriptions WHERE user_id = ? AND issue_id <=> ? AND pull_request_id <=> ?",
+            user_id,
+            thread.issue_id(),
+            thread.pull_request_id()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    // Returns whether the user was not already watching the repository
+    pub async fn watch_repository(&self, user_id: &str, repo_id: &str) -> Result<bool, String> {
+        let inserted = sqlx::query!(
+            "INSERT IGNORE INTO repository_watches (user_id, repository_id, created_at) VALUES (?, ?, NOW())",
+            user_id, repo_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .rows_affected() > 0;
+
+        self.refresh_watch_count(repo_id).await?;
+
+        Ok(inserted)
+    }
+
+    // Returns whether the user was watching the repository
+    pub async fn unwatch_repository(&self, user_id: &str, repo_id: &str) -> Result<bool, String> {
+        let deleted = sqlx::query!(
+            "DELETE FROM repository_watches WHERE user_id = ? AND repository_id = ?",
+            user_id, repo_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .rows_affected() > 0;
+
+        self.refresh_watch_count(repo_id).await?;
+
+        Ok(deleted)
+    }
+
+    pub async fn is_watching(&self, user_id: &str, repo_id: &str) -> Result<bool, String> {
+        let count = sqlx::query!(
+            "SELECT COUNT(*) as count FROM repository_watches WHERE user_id = ? AND repository_id = ?",
+            user_id, repo_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(count.count > 0)
+    }
+
+    async fn refresh_watch_count(&self, repo_id: &str) -> Result<(), String> {
+        sqlx::query!(
+            "UPDATE repositories SET watch_count = (SELECT COUNT(*) FROM repository_watches WHERE repository_id = ?) WHERE id = ?",
+            repo_id, repo_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn get_settings(&self, user_id: &str) -> Result<NotificationSettings, String> {
+        let row = sqlx::query!(
+            r#"SELECT notification_digest as "notification_digest: bool" FROM users WHERE id = ?"#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "User not found".to_string())?;
+
+        Ok(NotificationSettings { email_digest: row.notification_digest })
+    }
+
+    pub async fn update_settings(&self, user_id: &str, settings: &NotificationSettings) -> Result<NotificationSettings, String> {
+        sqlx::query!("UPDATE users SET notification_digest = ? WHERE id = ?", settings.email_digest, user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_settings(user_id).await
+    }
+
+    /// Emails every user with a verified address a summary of unread notifications they have not
+    /// been emailed about yet. Runs for the life of the server.
+    pub fn spawn_digest_worker(&self, every: Duration) {
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(every);
+            // The first tick completes immediately; skip it so a restart does not send a digest early
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+
+                if let Err(err) = service.send_digests().await {
+                    log::error!("Failed to send notification digests: {}", err);
+                }
+            }
+        });
+    }
+
+    async fn send_digests(&self) -> Result<(), String> {
+        // Rows are claimed before anything is read or sent, so instances running this at the same
+        // time never email the same items. A run that dies after claiming skips them rather than
+        // sending them twice.
+        let claim = Uuid::new_v4().to_string().replace("-", "");
+
+        sqlx::query!(
+            r#"
+            UPDATE notifications SET digest_claim = ?, emailed_at = NOW()
+            WHERE state = 'unread' AND (emailed_at IS NULL OR emailed_at < updated_at)
+                AND user_id IN (SELECT id FROM users WHERE notification_digest = TRUE AND is_verified = TRUE)
+            "#,
+            claim
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT n.user_id, u.username, u.email, n.repository_id, COALESCE(o.name, ou.username) as "owner!: String", r.name as repository_name,
+                COALESCE(i.number, p.number) as "number!: i32", COALESCE(i.title, p.title) as "title!: String", n.reason
+            FROM notifications n
+            INNER JOIN users u ON u.id = n.user_id
+            INNER JOIN repositories r ON r.id = n.repository_id
+            LEFT JOIN users ou ON ou.id = r.owner_id AND r.organization_id IS NULL
+            LEFT JOIN organizations o ON o.id = r.organization_id
+            LEFT JOIN issues i ON i.id = n.issue_id
+            LEFT JOIN pull_requests p ON p.id = n.pull_request_id
+            WHERE n.digest_claim = ?
+            ORDER BY n.user_id, n.updated_at DESC
+            "#,
+            claim
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let mut digests: Vec<Digest> = Vec::new();
+        for row in rows {
+            let line = format!("- {}/{}#{} {} ({})", row.owner, row.repository_name, row.number, row.title, row.reason);
+            match digests.last_mut() {
+                Some(digest) if digest.user_id == row.user_id => digest.lines.push((row.repository_id, line)),
+                _ => digests.push(Digest {
+                    user_id: row.user_id,
+                    username: row.username,
+                    email: row.email,
+                    lines: vec![(row.repository_id, line)],
+                }),
+            }
+        }
+
+        let mut repositories = HashMap::new();
+        for digest in digests {
+            let user_id = digest.user_id.clone();
+
+            // Failed sends are released, so the next digest includes them again
+            if let Err(err) = self.send_digest(digest, &mut repositories).await {
+                log::error!("Failed to send notification digest to {}: {}", user_id, err);
+
+                sqlx::query!(
+                    "UPDATE notifications SET digest_claim = NULL, emailed_at = NULL WHERE digest_claim = ? AND user_id = ?",
+                    claim,
+                    user_id
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Emails one user's digest, leaving out threads in repositories they can no longer read
+    async fn send_digest(&self, digest: Digest, repositories: &mut HashMap<String, Repository>) -> Result<(), String> {
+        let mut readable = Vec::new();
+        for (repository_id, line) in digest.lines {
+            if !repositories.contains_key(&repository_id) {
+                let repo = self.repository(&repository_id).await?;
+                repositories.insert(repository_id.clone(), repo);
+            }
+
+            if self.can_read(&digest.user_id, &repositories[&repository_id]).await? {
+                readable.push(line);
+            }
+        }
+
+        if readable.is_empty() {
+            return Ok(());
+        }
+
+        let mut items = readable.iter().take(DIGEST_MAX_ITEMS).cloned().collect::<Vec<_>>().join("\n");
+        if readable.len() > DIGEST_MAX_ITEMS {
+            items.push_str(&format!("\n- and {} more", readable.len() - DIGEST_MAX_ITEMS));
+        }
+
+        let body = format!(
+            "Hi {},\n\nThere is new activity on {} thread{} you are subscribed to:\n\n{}\n\nSee your notifications at {}/notifications. To stop these emails, turn off the email digest in your notification settings.\n",
+            digest.username,
+            readable.len(),
+            if readable.len() == 1 { "" } else { "s" },
+            items,
+            self.public_url.trim_end_matches('/')
+        );
+
+        self.mail.send(&digest.email, "Your unread notifications", &body).await
+    }
+
+    async fn repository(&self, repository_id: &str) -> Result<Repository, String> {
+        sqlx::query_as!(
+            Repository,
+            r#"
+            SELECT
+                id, name, description, is_private, is_fork, is_archived,
+                owner_id, organization_id, default_branch, language,
+                star_count, fork_count, watch_count, size,
+                created_at, updated_at, pushed_at
+            FROM repositories
+            WHERE id = ?
+            "#,
+            repository_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))
+    }
+}
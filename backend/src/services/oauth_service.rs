@@ -0,0 +1,787 @@
+use crate::models::{
+    AuthorizationRequest, AuthorizedApplication, ConsentPrompt, CreateOAuthApplicationRequest,
+    CreatedOAuthApplication, IntrospectionResponse, OAuthApplication, OAuthError, OAuthTokenResponse,
+    TokenAuthentication, TokenScope, UpdateOAuthApplicationRequest,
+};
+use crate::services::access_token_service::{hash_token, parse_scopes, to_hex};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Duration, Utc};
+use ring::digest::{digest, SHA256};
+use ring::rand::{SecureRandom, SystemRandom};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+// Prefixes let the bearer middleware route a token without a database round trip
+pub const OAUTH_ACCESS_TOKEN_PREFIX: &str = "dvo_";
+const OAUTH_REFRESH_TOKEN_PREFIX: &str = "dvr_";
+const CLIENT_SECRET_PREFIX: &str = "dvs_";
+
+const ACCESS_TOKEN_TTL_SECONDS: i64 = 60 * 60;
+const REFRESH_TOKEN_TTL_DAYS: i64 = 30;
+const AUTHORIZATION_CODE_TTL_MINUTES: i64 = 10;
+
+const MAX_REDIRECT_URIS: usize = 10;
+
+pub fn is_oauth_access_token(token: &str) -> bool {
+    token.starts_with(OAUTH_ACCESS_TOKEN_PREFIX)
+}
+
+fn random_hex(len: usize) -> Result<String, String> {
+    let mut bytes = vec![0u8; len];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| "Failed to generate token".to_string())?;
+
+    Ok(to_hex(&bytes))
+}
+
+// Scopes arrive space-separated (RFC 6749 section 3.3) and are stored comma-separated
+fn parse_requested_scopes(scope: &str) -> Result<Vec<TokenScope>, String> {
+    let mut scopes: Vec<TokenScope> = Vec::new();
+    for name in scope.split_whitespace() {
+        let scope = TokenScope::parse(name).ok_or_else(|| format!("Unknown scope '{}'", name))?;
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+
+    if scopes.is_empty() {
+        return Err("At least one scope is required".to_string());
+    }
+
+    Ok(scopes)
+}
+
+fn scope_list(scopes: &[TokenScope]) -> String {
+    scopes.iter().map(|scope| scope.as_str()).collect::<Vec<_>>().join(",")
+}
+
+fn scope_string(scope_list: &str) -> String {
+    parse_scopes(scope_list).iter().map(|scope| scope.as_str()).collect::<Vec<_>>().join(" ")
+}
+
+// Plain http is only allowed for apps running on the user's own machine
+fn validate_redirect_uris(redirect_uris: &[String]) -> Result<String, String> {
+    if redirect_uris.is_empty() || redirect_uris.len() > MAX_REDIRECT_URIS {
+        return Err(format!("Between 1 and {} redirect URIs are required", MAX_REDIRECT_URIS));
+    }
+
+    for redirect_uri in redirect_uris {
+        let url = reqwest::Url::parse(redirect_uri)
+            .map_err(|_| format!("Invalid redirect URI '{}'", redirect_uri))?;
+
+        let local = matches!(url.host_str(), Some("localhost") | Some("127.0.0.1") | Some("[::1]"));
+        let allowed = url.scheme() == "https" || (url.scheme() == "http" && local);
+
+        if !allowed || url.fragment().is_some() || redirect_uri.len() > 500 {
+            return Err(format!("Invalid redirect URI '{}'", redirect_uri));
+        }
+    }
+
+    Ok(redirect_uris.join("\n"))
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() || name.len() > 255 {
+        return Err("Application name must be between 1 and 255 characters".to_string());
+    }
+
+    Ok(())
+}
+
+// RFC 7636 S256: the challenge is the unpadded base64url SHA-256 of the verifier
+fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(digest(&SHA256, verifier.as_bytes()).as_ref())
+}
+
+// Only S256 is accepted, and its challenges are always 43 characters
+fn is_valid_code_challenge(challenge: Option<&str>, method: Option<&str>) -> bool {
+    matches!((challenge, method), (Some(challenge), Some("S256")) if challenge.len() == 43)
+}
+
+fn redirect_with(redirect_uri: &str, params: &[(&str, &str)]) -> Result<String, String> {
+    let mut url = reqwest::Url::parse(redirect_uri)
+        .map_err(|_| "Invalid redirect URI".to_string())?;
+    url.query_pairs_mut().extend_pairs(params);
+
+    Ok(url.to_string())
+}
+
+struct ApplicationRow {
+    id: String,
+    name: String,
+    description: Option<String>,
+    homepage_url: Option<String>,
+    client_id: String,
+    redirect_uris: String,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<ApplicationRow> for OAuthApplication {
+    fn from(row: ApplicationRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            homepage_url: row.homepage_url,
+            client_id: row.client_id,
+            redirect_uris: row.redirect_uris.lines().map(|uri| uri.to_string()).collect(),
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+// The application a validated authorization request is for
+struct AuthorizingClient {
+    application_id: String,
+    name: String,
+    description: Option<String>,
+    homepage_url: Option<String>,
+    owner: String,
+}
+
+/// OAuth 2.0 authorization server: the authorization code flow with mandatory PKCE,
+/// refresh token rotation, introspection and revocation. Access tokens resolve to a
+/// `TokenAuthentication`, so they carry the same scopes as personal access tokens.
+#[derive(Clone)]
+pub struct OAuthService {
+    pool: MySqlPool,
+}
+
+impl OAuthService {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_application(&self, owner_id: &str, request: &CreateOAuthApplicationRequest) -> Result<CreatedOAuthApplication, String> {
+        validate_name(&request.name)?;
+        let redirect_uris = validate_redirect_uris(&request.redirect_uris)?;
+
+        let application_id = format!("app_{}", Uuid::new_v4().to_string().replace("-", ""));
+        let client_id = random_hex(10)?;
+        let client_secret = format!("{}{}", CLIENT_SECRET_PREFIX, random_hex(20)?);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO oauth_applications (id, owner_id, name, description, homepage_url, client_id, client_secret_hash, redirect_uris, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, NOW(), NOW())
+            "#,
+            application_id, owner_id, request.name.trim(), request.description, request.homepage_url,
+            client_id, hash_token(&client_secret), redirect_uris
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let application = self.get_application(owner_id, &application_id).await?;
+
+        Ok(CreatedOAuthApplication { application, client_secret })
+    }
+
+    pub async fn list_applications(&self, owner_id: &str) -> Result<Vec<OAuthApplication>, String> {
+        let applications = sqlx::query_as!(
+            ApplicationRow,
+            r#"
+            SELECT id, name, description, homepage_url, client_id, redirect_uris, created_at, updated_at
+            FROM oauth_applications
+            WHERE owner_id = ?
+            ORDER BY created_at DESC
+            "#,
+            owner_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(applications.into_iter().map(OAuthApplication::from).collect())
+    }
+
+    pub async fn get_application(&self, owner_id: &str, application_id: &str) -> Result<OAuthApplication, String> {
+        let application = sqlx::query_as!(
+            ApplicationRow,
+            r#"
+            SELECT id, name, description, homepage_url, client_id, redirect_uris, created_at, updated_at
+            FROM oauth_applications
+            WHERE id = ? AND owner_id = ?
+            "#,
+            application_id, owner_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Application not found".to_string())?;
+
+        Ok(application.into())
+    }
+
+    pub async fn update_application(&self, owner_id: &str, application_id: &str, request: &UpdateOAuthApplicationRequest) -> Result<OAuthApplication, String> {
+        // Fails with "Application not found" for other users' applications
+        self.get_application(owner_id, application_id).await?;
+
+        if let Some(name) = &request.name {
+            validate_name(name)?;
+        }
+
+        let redirect_uris = match &request.redirect_uris {
+            Some(redirect_uris) => Some(validate_redirect_uris(redirect_uris)?),
+            None => None,
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE oauth_applications
+            SET name = COALESCE(?, name),
+                description = COALESCE(?, description),
+                homepage_url = COALESCE(?, homepage_url),
+                redirect_uris = COALESCE(?, redirect_uris),
+                updated_at = NOW()
+            WHERE id = ? AND owner_id = ?
+            "#,
+            request.name.as_deref().map(str::trim), request.description, request.homepage_url,
+            redirect_uris, application_id, owner_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_application(owner_id, application_id).await
+    }
+
+    // Tokens, codes and consent for the application go with it
+    pub async fn delete_application(&self, owner_id: &str, application_id: &str) -> Result<(), String> {
+        let result = sqlx::query!(
+            "DELETE FROM oauth_applications WHERE id = ? AND owner_id = ?",
+            application_id, owner_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Application not found".to_string());
+        }
+
+        Ok(())
+    }
+
+    // Existing tokens keep working; only the client's own credential changes
+    pub async fn reset_client_secret(&self, owner_id: &str, application_id: &str) -> Result<CreatedOAuthApplication, String> {
+        let client_secret = format!("{}{}", CLIENT_SECRET_PREFIX, random_hex(20)?);
+
+        let result = sqlx::query!(
+            "UPDATE oauth_applications SET client_secret_hash = ?, updated_at = NOW() WHERE id = ? AND owner_id = ?",
+            hash_token(&client_secret), application_id, owner_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Application not found".to_string());
+        }
+
+        let application = self.get_application(owner_id, application_id).await?;
+
+        Ok(CreatedOAuthApplication { application, client_secret })
+    }
+
+    // Errors here are shown to the user rather than sent to the redirect URI,
+    // which cannot be trusted until the client and redirect URI have checked out
+    async fn validate_authorization(&self, request: &AuthorizationRequest) -> Result<(AuthorizingClient, Vec<TokenScope>), String> {
+        let application = sqlx::query!(
+            r#"
+            SELECT a.id, a.name, a.description, a.homepage_url, a.redirect_uris, u.username as owner
+            FROM oauth_applications a
+            INNER JOIN users u ON a.owner_id = u.id
+            WHERE a.client_id = ?
+            "#,
+            request.client_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Unknown client_id".to_string())?;
+
+        if !application.redirect_uris.lines().any(|uri| uri == request.redirect_uri) {
+            return Err("redirect_uri is not registered for this application".to_string());
+        }
+
+        if request.response_type != "code" {
+            return Err("Only the 'code' response_type is supported".to_string());
+        }
+
+        // PKCE is required for every client, confidential or not
+        if !is_valid_code_challenge(request.code_challenge.as_deref(), request.code_challenge_method.as_deref()) {
+            return Err("A PKCE code_challenge with code_challenge_method 'S256' is required".to_string());
+        }
+
+        let scopes = parse_requested_scopes(&request.scope)?;
+
+        let client = AuthorizingClient {
+            application_id: application.id,
+            name: application.name,
+            description: application.description,
+            homepage_url: application.homepage_url,
+            owner: application.owner,
+        };
+
+        Ok((client, scopes))
+    }
+
+    pub async fn prepare_authorization(&self, user_id: &str, request: &AuthorizationRequest) -> Result<ConsentPrompt, String> {
+        let (client, scopes) = self.validate_authorization(request).await?;
+
+        let granted = sqlx::query!(
+            "SELECT scopes FROM oauth_authorizations WHERE application_id = ? AND user_id = ?",
+            client.application_id, user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .map(|authorization| parse_scopes(&authorization.scopes))
+        .unwrap_or_default();
+
+        Ok(ConsentPrompt {
+            application_name: client.name,
+            application_description: client.description,
+            homepage_url: client.homepage_url,
+            owner: client.owner,
+            already_authorized: scopes.iter().all(|scope| granted.contains(scope)),
+            scopes,
+        })
+    }
+
+    /// Records the user's consent and returns the redirect carrying a fresh authorization code.
+    pub async fn approve_authorization(&self, user_id: &str, request: &AuthorizationRequest) -> Result<String, String> {
+        let (client, scopes) = self.validate_authorization(request).await?;
+        let scopes = scope_list(&scopes);
+
+        let code = random_hex(32)?;
+        let expires_at = Utc::now() + Duration::minutes(AUTHORIZATION_CODE_TTL_MINUTES);
+        let authorization_id = format!("oauth_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        // Consent is remembered per application; the latest approval replaces it
+        sqlx::query!(
+            r#"
+            INSERT INTO oauth_authorizations (id, application_id, user_id, scopes, created_at, updated_at)
+            VALUES (?, ?, ?, ?, NOW(), NOW())
+            ON DUPLICATE KEY UPDATE scopes = VALUES(scopes), updated_at = NOW()
+            "#,
+            authorization_id, client.application_id, user_id, scopes
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO oauth_authorization_codes (code_hash, application_id, user_id, redirect_uri, scopes, code_challenge, expires_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, NOW())
+            "#,
+            hash_token(&code), client.application_id, user_id, request.redirect_uri, scopes,
+            request.code_challenge, expires_at
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        let mut params = vec![("code", code.as_str())];
+        if let Some(state) = &request.state {
+            params.push(("state", state.as_str()));
+        }
+
+        redirect_with(&request.redirect_uri, &params)
+    }
+
+    pub async fn deny_authorization(&self, request: &AuthorizationRequest) -> Result<String, String> {
+        self.validate_authorization(request).await?;
+
+        let mut params = vec![
+            ("error", "access_denied"),
+            ("error_description", "The user denied the request"),
+        ];
+        if let Some(state) = &request.state {
+            params.push(("state", state.as_str()));
+        }
+
+        redirect_with(&request.redirect_uri, &params)
+    }
+
+    // Returns the application id for a valid client_id and secret
+    async fn authenticate_client(&self, client_id: &str, client_secret: &str) -> Result<String, OAuthError> {
+        let application = sqlx::query!(
+            "SELECT id, client_secret_hash FROM oauth_applications WHERE client_id = ?",
+            client_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        match application {
+            Some(application) if application.client_secret_hash == hash_token(client_secret) => Ok(application.id),
+            _ => Err(OAuthError::new("invalid_client", "Client authentication failed")),
+        }
+    }
+
+    pub async fn exchange_code(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        code: &str,
+        redirect_uri: &str,
+        code_verifier: &str,
+    ) -> Result<OAuthTokenResponse, OAuthError> {
+        let application_id = self.authenticate_client(client_id, client_secret).await?;
+        let invalid_grant = || OAuthError::new("invalid_grant", "The authorization code is invalid or expired");
+
+        // Codes are single-use: the row is claimed and removed in one transaction
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        let grant = sqlx::query!(
+            r#"
+            SELECT application_id, user_id, redirect_uri, scopes, code_challenge, expires_at
+            FROM oauth_authorization_codes
+            WHERE code_hash = ?
+            FOR UPDATE
+            "#,
+            hash_token(code)
+        )
+        .fetch_optional(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!("DELETE FROM oauth_authorization_codes WHERE code_hash = ?", hash_token(code))
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        let grant = grant.ok_or_else(invalid_grant)?;
+        let expires_at: DateTime<Utc> = grant.expires_at;
+
+        if grant.application_id != application_id || expires_at <= Utc::now() || grant.redirect_uri != redirect_uri {
+            return Err(invalid_grant());
+        }
+
+        if code_challenge(code_verifier) != grant.code_challenge {
+            return Err(OAuthError::new("invalid_grant", "The code_verifier does not match the code_challenge"));
+        }
+
+        self.issue_tokens(&application_id, &grant.user_id, &grant.scopes).await
+    }
+
+    // Each refresh token is used once; the pair it belongs to is replaced
+    pub async fn refresh(&self, client_id: &str, client_secret: &str, refresh_token: &str) -> Result<OAuthTokenResponse, OAuthError> {
+        let application_id = self.authenticate_client(client_id, client_secret).await?;
+        let invalid_grant = || OAuthError::new("invalid_grant", "The refresh token is invalid or expired");
+
+        let token = sqlx::query!(
+            "SELECT id, application_id, user_id, scopes, refresh_expires_at FROM oauth_access_tokens WHERE refresh_token_hash = ?",
+            hash_token(refresh_token)
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(invalid_grant)?;
+
+        let refresh_expires_at: DateTime<Utc> = token.refresh_expires_at;
+        if token.application_id != application_id || refresh_expires_at <= Utc::now() {
+            return Err(invalid_grant());
+        }
+
+        // Whichever of two racing refreshes deletes the row wins
+        let deleted = sqlx::query!("DELETE FROM oauth_access_tokens WHERE id = ?", token.id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        if deleted.rows_affected() == 0 {
+            return Err(invalid_grant());
+        }
+
+        self.issue_tokens(&application_id, &token.user_id, &token.scopes).await
+    }
+
+    async fn issue_tokens(&self, application_id: &str, user_id: &str, scopes: &str) -> Result<OAuthTokenResponse, OAuthError> {
+        let access_token = format!("{}{}", OAUTH_ACCESS_TOKEN_PREFIX, random_hex(20)?);
+        let refresh_token = format!("{}{}", OAUTH_REFRESH_TOKEN_PREFIX, random_hex(20)?);
+        let token_id = format!("oat_{}", Uuid::new_v4().to_string().replace("-", ""));
+        let now = Utc::now();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO oauth_access_tokens (id, application_id, user_id, token_hash, refresh_token_hash, scopes, expires_at, refresh_expires_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, NOW())
+            "#,
+            token_id, application_id, user_id, hash_token(&access_token), hash_token(&refresh_token), scopes,
+            now + Duration::seconds(ACCESS_TOKEN_TTL_SECONDS),
+            now + Duration::days(REFRESH_TOKEN_TTL_DAYS)
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(OAuthTokenResponse {
+            access_token,
+            token_type: "bearer".to_string(),
+            expires_in: ACCESS_TOKEN_TTL_SECONDS,
+            refresh_token,
+            scope: scope_string(scopes),
+        })
+    }
+
+    // False once the token has been revoked or has expired
+    pub async fn is_active(&self, token_id: &str) -> Result<bool, String> {
+        let record = sqlx::query!("SELECT expires_at FROM oauth_access_tokens WHERE id = ?", token_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(record.is_some_and(|record| {
+            let expires_at: DateTime<Utc> = record.expires_at;
+            expires_at > Utc::now()
+        }))
+    }
+
+    // Resolves a presented OAuth access token to its user and scopes, recording the use
+    pub async fn authenticate(&self, token: &str) -> Result<TokenAuthentication, String> {
+        if !is_oauth_access_token(token) {
+            return Err("Invalid access token".to_string());
+        }
+
+        let record = sqlx::query!(
+            r#"
+            SELECT t.id, t.user_id, u.username, t.scopes, t.expires_at
+            FROM oauth_access_tokens t
+            INNER JOIN users u ON t.user_id = u.id
+            WHERE t.token_hash = ?
+            "#,
+            hash_token(token)
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Invalid access token".to_string())?;
+
+        let expires_at: DateTime<Utc> = record.expires_at;
+        if expires_at <= Utc::now() {
+            return Err("Access token has expired".to_string());
+        }
+
+        // Only written once a minute so busy tokens do not hammer the row
+        sqlx::query!(
+            r#"
+            UPDATE oauth_access_tokens SET last_used = NOW()
+            WHERE id = ? AND (last_used IS NULL OR last_used < NOW() - INTERVAL 1 MINUTE)
+            "#,
+            record.id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(TokenAuthentication {
+            token_id: record.id,
+            user_id: record.user_id,
+            username: record.username,
+            scopes: parse_scopes(&record.scopes),
+        })
+    }
+
+    /// Describes an access or refresh token issued to the calling client. Tokens belonging
+    /// to other clients are reported as inactive, like unknown ones.
+    pub async fn introspect(&self, client_id: &str, client_secret: &str, token: &str) -> Result<IntrospectionResponse, OAuthError> {
+        let application_id = self.authenticate_client(client_id, client_secret).await?;
+        let token_hash = hash_token(token);
+
+        let record = sqlx::query!(
+            r#"
+            SELECT t.application_id, t.user_id, u.username, t.scopes, t.token_hash,
+                   t.expires_at, t.refresh_expires_at, t.created_at
+            FROM oauth_access_tokens t
+            INNER JOIN users u ON t.user_id = u.id
+            WHERE t.token_hash = ? OR t.refresh_token_hash = ?
+            "#,
+            token_hash, token_hash
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let record = match record {
+            Some(record) if record.application_id == application_id => record,
+            _ => return Ok(IntrospectionResponse::default()),
+        };
+
+        let is_access_token = record.token_hash == token_hash;
+        let expires_at: DateTime<Utc> = if is_access_token { record.expires_at } else { record.refresh_expires_at };
+
+        if expires_at <= Utc::now() {
+            return Ok(IntrospectionResponse::default());
+        }
+
+        Ok(IntrospectionResponse {
+            active: true,
+            scope: Some(scope_string(&record.scopes)),
+            client_id: Some(client_id.to_string()),
+            username: Some(record.username),
+            sub: Some(record.user_id),
+            token_type: Some(if is_access_token { "bearer" } else { "refresh_token" }.to_string()),
+            exp: Some(expires_at.timestamp()),
+            iat: record.created_at.map(|created_at| created_at.timestamp()),
+        })
+    }
+
+    // Revoking either token of a pair ends both. Unknown tokens are not an error (RFC 7009 section 2.2)
+    pub async fn revoke(&self, client_id: &str, client_secret: &str, token: &str) -> Result<(), OAuthError> {
+        let application_id = self.authenticate_client(client_id, client_secret).await?;
+        let token_hash = hash_token(token);
+
+        sqlx::query!(
+            "DELETE FROM oauth_access_tokens WHERE application_id = ? AND (token_hash = ? OR refresh_token_hash = ?)",
+            application_id, token_hash, token_hash
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn list_authorizations(&self, user_id: &str) -> Result<Vec<AuthorizedApplication>, String> {
+        let authorizations = sqlx::query!(
+            r#"
+            SELECT a.id, a.name, a.homepage_url, g.scopes, g.created_at, g.updated_at
+            FROM oauth_authorizations g
+            INNER JOIN oauth_applications a ON g.application_id = a.id
+            WHERE g.user_id = ?
+            ORDER BY g.updated_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(authorizations
+            .into_iter()
+            .map(|authorization| AuthorizedApplication {
+                application_id: authorization.id,
+                name: authorization.name,
+                homepage_url: authorization.homepage_url,
+                scopes: parse_scopes(&authorization.scopes),
+                created_at: authorization.created_at,
+                updated_at: authorization.updated_at,
+            })
+            .collect())
+    }
+
+    // Withdraws consent and signs the application out of the user's account
+    pub async fn revoke_authorization(&self, user_id: &str, application_id: &str) -> Result<(), String> {
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        let result = sqlx::query!(
+            "DELETE FROM oauth_authorizations WHERE application_id = ? AND user_id = ?",
+            application_id, user_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Authorization not found".to_string());
+        }
+
+        sqlx::query!(
+            "DELETE FROM oauth_access_tokens WHERE application_id = ? AND user_id = ?",
+            application_id, user_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!(
+            "DELETE FROM oauth_authorization_codes WHERE application_id = ? AND user_id = ?",
+            application_id, user_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifiers_match_their_rfc_7636_challenge() {
+        let challenge = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+        assert_eq!(code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"), challenge);
+        assert_ne!(code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXj"), challenge);
+        assert_ne!(code_challenge(challenge), challenge);
+    }
+
+    #[test]
+    fn only_s256_challenges_are_accepted() {
+        let challenge = code_challenge("verifier");
+
+        assert!(is_valid_code_challenge(Some(&challenge), Some("S256")));
+        assert!(!is_valid_code_challenge(Some(&challenge), Some("plain")));
+        assert!(!is_valid_code_challenge(Some(&challenge), None));
+        assert!(!is_valid_code_challenge(Some("verifier"), Some("S256")));
+        assert!(!is_valid_code_challenge(None, Some("S256")));
+    }
+
+    #[test]
+    fn requested_scopes_are_space_separated() {
+        assert_eq!(parse_requested_scopes("repo  read:user repo").unwrap(), vec![TokenScope::Repo, TokenScope::ReadUser]);
+        assert!(parse_requested_scopes("repo write:everything").is_err());
+        assert!(parse_requested_scopes(" ").is_err());
+
+        assert_eq!(scope_list(&[TokenScope::Repo, TokenScope::AdminOrg]), "repo,admin:org");
+        assert_eq!(scope_string("repo,admin:org"), "repo admin:org");
+    }
+
+    #[test]
+    fn redirect_uris_must_be_https_unless_local() {
+        let uris = |uris: &[&str]| uris.iter().map(|uri| uri.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            validate_redirect_uris(&uris(&["https://app.example.com/callback", "http://localhost:8080/cb", "http://127.0.0.1/cb"])).unwrap(),
+            "https://app.example.com/callback\nhttp://localhost:8080/cb\nhttp://127.0.0.1/cb"
+        );
+
+        for uri in ["http://app.example.com/callback", "https://app.example.com/cb#fragment", "app://callback", "not a uri"] {
+            assert!(validate_redirect_uris(&uris(&[uri])).is_err(), "{}", uri);
+        }
+        assert!(validate_redirect_uris(&[]).is_err());
+        assert!(validate_redirect_uris(&vec!["https://example.com".to_string(); MAX_REDIRECT_URIS + 1]).is_err());
+    }
+
+    #[test]
+    fn redirects_keep_the_registered_query() {
+        assert_eq!(
+            redirect_with("https://app.example.com/cb?tenant=a", &[("code", "abc"), ("state", "x y")]).unwrap(),
+            "https://app.example.com/cb?tenant=a&code=abc&state=x+y"
+        );
+    }
+}
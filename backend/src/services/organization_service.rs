@@ -0,0 +1,437 @@
+use crate::models::{
+    Organization, OrgRole, OrganizationMember, OrganizationInvitation, CreateOrganizationRequest,
+    UpdateOrganizationRequest,
+};
+use crate::services::GitService;
+use crate::utils::validation::is_valid_username;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+pub const TWO_FACTOR_REQUIRED: &str = "This organization requires two-factor authentication";
+
+#[derive(Clone)]
+pub struct OrganizationService {
+    pool: MySqlPool,
+    git: GitService,
+}
+
+impl OrganizationService {
+    pub fn new(pool: MySqlPool, git: GitService) -> Self {
+        Self { pool, git }
+    }
+
+    pub async fn get_organization(&self, name: &str) -> Result<Organization, String> {
+        let org = sqlx::query_as!(
+            Organization,
+            r#"
+            SELECT
+                id, name, display_name, description, avatar_url, website, location, email, owner_id,
+                two_factor_required as "two_factor_required: bool", created_at, updated_at
+            FROM organizations
+            WHERE name = ?
+            "#,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        org.ok_or_else(|| "Organization not found".to_string())
+    }
+
+    pub async fn list_user_organizations(&self, user_id: &str) -> Result<Vec<Organization>, String> {
+        let orgs = sqlx::query_as!(
+            Organization,
+            r#"
+            SELECT
+                o.id, o.name, o.display_name, o.description, o.avatar_url, o.website,
+                o.location, o.email, o.owner_id,
+                o.two_factor_required as "two_factor_required: bool", o.created_at, o.updated_at
+            FROM organizations o
+            INNER JOIN organization_members m ON m.organization_id = o.id
+            WHERE m.user_id = ?
+            ORDER BY o.name ASC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(orgs)
+    }
+
+    // The creator becomes the first owner
+    pub async fn create_organization(&self, owner_id: &str, request: &CreateOrganizationRequest) -> Result<Organization, String> {
+        if !is_valid_username(&request.name) {
+            return Err("Organization name may only contain letters, digits, '-' and '_' (3-32 characters)".to_string());
+        }
+
+        // Organizations and users share the /{owner} namespace in repository URLs
+        let taken = sqlx::query!(
+            r#"
+            SELECT (SELECT COUNT(*) FROM users WHERE username = ?)
+                + (SELECT COUNT(*) FROM organizations WHERE name = ?) as "count!: i64"
+            "#,
+            request.name, request.name
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if taken.count > 0 {
+            return Err(format!("The name {} is already taken", request.name));
+        }
+
+        let org_id = format!("org_{}", Uuid::new_v4().to_string().replace("-", ""));
+        let display_name = request.display_name.as_deref().unwrap_or(&request.name);
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO organizations (id, name, display_name, description, website, location, email, owner_id, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, NOW(), NOW())
+            "#,
+            org_id, request.name, display_name, request.description,
+            request.website, request.location, request.email, owner_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!(
+            "INSERT INTO organization_members (organization_id, user_id, role, created_at) VALUES (?, ?, 'owner', NOW())",
+            org_id, owner_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        self.get_organization(&request.name).await
+    }
+
+    pub async fn update_organization(&self, org: &Organization, request: &UpdateOrganizationRequest) -> Result<Organization, String> {
+        sqlx::query!(
+            r#"
+            UPDATE organizations
+            SET
+                display_name = COALESCE(?, display_name),
+                description = COALESCE(?, description),
+                avatar_url = COALESCE(?, avatar_url),
+                website = COALESCE(?, website),
+                location = COALESCE(?, location),
+                email = COALESCE(?, email),
+                two_factor_required = COALESCE(?, two_factor_required),
+                updated_at = NOW()
+            WHERE id = ?
+            "#,
+            request.display_name, request.description, request.avatar_url,
+            request.website, request.location, request.email, request.two_factor_required, org.id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_organization(&org.name).await
+    }
+
+    // Deleting the row cascades to the org's repositories, so their git storage is removed too
+    pub async fn delete_organization(&self, org: &Organization) -> Result<(), String> {
+        let repos = sqlx::query!("SELECT id FROM repositories WHERE organization_id = ?", org.id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!("DELETE FROM organizations WHERE id = ?", org.id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        let repo_ids: Vec<String> = repos.into_iter().map(|repo| repo.id).collect();
+        self.git
+            .blocking(move |git| {
+                for repo_id in repo_ids {
+                    if let Err(err) = git.delete_repository(&repo_id) {
+                        log::error!("Failed to remove git storage for {}: {}", repo_id, err);
+                    }
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    // The stored role, for managing memberships. None when the user is not a member
+    pub async fn get_membership(&self, org_id: &str, user_id: &str) -> Result<Option<OrgRole>, String> {
+        let member = sqlx::query!(
+            "SELECT role FROM organization_members WHERE organization_id = ? AND user_id = ?",
+            org_id, user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(member.and_then(|member| OrgRole::parse(&member.role)))
+    }
+
+    // The role the user can act with. Members without 2FA in an org that requires it have none
+    pub async fn get_active_membership(&self, org_id: &str, user_id: &str) -> Result<Option<OrgRole>, String> {
+        let member = sqlx::query!(
+            r#"
+            SELECT m.role
+            FROM organization_members m
+            INNER JOIN organizations o ON o.id = m.organization_id
+            INNER JOIN users u ON u.id = m.user_id
+            WHERE m.organization_id = ? AND m.user_id = ?
+                AND (o.two_factor_required = 0 OR u.two_factor_enabled = 1)
+            "#,
+            org_id, user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(member.and_then(|member| OrgRole::parse(&member.role)))
+    }
+
+    pub async fn list_members(&self, org_id: &str) -> Result<Vec<OrganizationMember>, String> {
+        let members = sqlx::query_as!(
+            OrganizationMember,
+            r#"
+            SELECT m.user_id, u.username, m.role, m.created_at
+            FROM organization_members m
+            INNER JOIN users u ON m.user_id = u.id
+            WHERE m.organization_id = ?
+            ORDER BY u.username ASC
+            "#,
+            org_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(members)
+    }
+
+    pub async fn update_member_role(&self, org_id: &str, user_id: &str, role: OrgRole) -> Result<(), String> {
+        let current = self.get_membership(org_id, user_id).await?
+            .ok_or_else(|| "Membership not found".to_string())?;
+
+        if current == OrgRole::Owner && role != OrgRole::Owner {
+            self.ensure_other_owner(org_id, user_id).await?;
+        }
+
+        sqlx::query!(
+            "UPDATE organization_members SET role = ? WHERE organization_id = ? AND user_id = ?",
+            role.as_str(), org_id, user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn remove_member(&self, org_id: &str, user_id: &str) -> Result<(), String> {
+        let current = self.get_membership(org_id, user_id).await?
+            .ok_or_else(|| "Membership not found".to_string())?;
+
+        if current == OrgRole::Owner {
+            self.ensure_other_owner(org_id, user_id).await?;
+        }
+
+        sqlx::query!(
+            "DELETE FROM organization_members WHERE organization_id = ? AND user_id = ?",
+            org_id, user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    // An organization must never be left without an owner who can act as one
+    async fn ensure_other_owner(&self, org_id: &str, user_id: &str) -> Result<(), String> {
+        let owners = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count
+            FROM organization_members m
+            INNER JOIN organizations o ON o.id = m.organization_id
+            INNER JOIN users u ON u.id = m.user_id
+            WHERE m.organization_id = ? AND m.role = 'owner' AND m.user_id <> ?
+                AND (o.two_factor_required = 0 OR u.two_factor_enabled = 1)
+            "#,
+            org_id, user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if owners.count == 0 {
+            return Err("An organization must have at least one owner".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub async fn invite_member(&self, org_id: &str, inviter_id: &str, username: &str, role: OrgRole) -> Result<OrganizationInvitation, String> {
+        let invitee = sqlx::query!("SELECT id FROM users WHERE username = ?", username)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?
+            .ok_or_else(|| "User not found".to_string())?;
+
+        if self.get_membership(org_id, &invitee.id).await?.is_some() {
+            return Err(format!("{} is already a member", username));
+        }
+
+        let invitation_id = format!("inv_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        // Inviting again replaces the pending invitation's role
+        sqlx::query!(
+            r#"
+            INSERT INTO organization_invitations (id, organization_id, invitee_id, inviter_id, role, created_at)
+            VALUES (?, ?, ?, ?, ?, NOW())
+            ON DUPLICATE KEY UPDATE inviter_id = VALUES(inviter_id), role = VALUES(role), created_at = NOW()
+            "#,
+            invitation_id, org_id, invitee.id, inviter_id, role.as_str()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let invitation = sqlx::query!(
+            "SELECT id FROM organization_invitations WHERE organization_id = ? AND invitee_id = ?",
+            org_id, invitee.id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_invitation(&invitation.id).await
+    }
+
+    pub async fn get_invitation(&self, invitation_id: &str) -> Result<OrganizationInvitation, String> {
+        let invitation = sqlx::query_as!(
+            OrganizationInvitation,
+            r#"
+            SELECT
+                i.id, i.organization_id, o.name as organization_name,
+                i.invitee_id, u.username as invitee_username, i.inviter_id, i.role, i.created_at
+            FROM organization_invitations i
+            INNER JOIN organizations o ON i.organization_id = o.id
+            INNER JOIN users u ON i.invitee_id = u.id
+            WHERE i.id = ?
+            "#,
+            invitation_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        invitation.ok_or_else(|| "Invitation not found".to_string())
+    }
+
+    pub async fn list_invitations(&self, org_id: &str) -> Result<Vec<OrganizationInvitation>, String> {
+        let invitations = sqlx::query_as!(
+            OrganizationInvitation,
+            r#"
+            SELECT
+                i.id, i.organization_id, o.name as organization_name,
+                i.invitee_id, u.username as invitee_username, i.inviter_id, i.role, i.created_at
+            FROM organization_invitations i
+            INNER JOIN organizations o ON i.organization_id = o.id
+            INNER JOIN users u ON i.invitee_id = u.id
+            WHERE i.organization_id = ?
+            ORDER BY i.created_at DESC
+            "#,
+            org_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(invitations)
+    }
+
+    pub async fn list_user_invitations(&self, user_id: &str) -> Result<Vec<OrganizationInvitation>, String> {
+        let invitations = sqlx::query_as!(
+            OrganizationInvitation,
+            r#"
+            SELECT
+                i.id, i.organization_id, o.name as organization_name,
+                i.invitee_id, u.username as invitee_username, i.inviter_id, i.role, i.created_at
+            FROM organization_invitations i
+            INNER JOIN organizations o ON i.organization_id = o.id
+            INNER JOIN users u ON i.invitee_id = u.id
+            WHERE i.invitee_id = ?
+            ORDER BY i.created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(invitations)
+    }
+
+    // Turns the invitation into a membership with the invited role
+    pub async fn accept_invitation(&self, invitation: &OrganizationInvitation) -> Result<(), String> {
+        let allowed = sqlx::query!(
+            r#"
+            SELECT (o.two_factor_required = 0 OR u.two_factor_enabled = 1) as "allowed!: bool"
+            FROM organizations o, users u
+            WHERE o.id = ? AND u.id = ?
+            "#,
+            invitation.organization_id, invitation.invitee_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if !allowed.allowed {
+            return Err(TWO_FACTOR_REQUIRED.to_string());
+        }
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO organization_members (organization_id, user_id, role, created_at)
+            VALUES (?, ?, ?, NOW())
+            ON DUPLICATE KEY UPDATE role = VALUES(role)
+            "#,
+            invitation.organization_id, invitation.invitee_id, invitation.role
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!("DELETE FROM organization_invitations WHERE id = ?", invitation.id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        Ok(())
+    }
+
+    // Used both when the invitee declines and when an owner cancels
+    pub async fn delete_invitation(&self, invitation_id: &str) -> Result<(), String> {
+        sqlx::query!("DELETE FROM organization_invitations WHERE id = ?", invitation_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+}
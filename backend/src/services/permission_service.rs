@@ -0,0 +1,122 @@
+use crate::models::{OrgRole, Permission, Repository};
+use crate::services::{CollaboratorService, OrganizationService, TeamService};
+
+// Admin access that comes from owning the repository. A personal repository belongs to its owner;
+// an organization repository belongs to the organization, so its creator gets nothing from having
+// created it and org owners administer it instead. `org_role` is the user's active membership.
+fn owner_permission(repo: &Repository, user_id: &str, org_role: Option<OrgRole>) -> Option<Permission> {
+    let is_owner = match repo.organization_id {
+        Some(_) => org_role == Some(OrgRole::Owner),
+        None => repo.owner_id == user_id,
+    };
+
+    is_owner.then_some(Permission::Admin)
+}
+
+#[derive(Clone)]
+pub struct PermissionService {
+    orgs: OrganizationService,
+    teams: TeamService,
+    collaborators: CollaboratorService,
+}
+
+impl PermissionService {
+    pub fn new(orgs: OrganizationService, teams: TeamService, collaborators: CollaboratorService) -> Self {
+        Self { orgs, teams, collaborators }
+    }
+
+    // The single source of truth for repository access: the owner of a personal repository and
+    // the owners of an organization administer, otherwise the highest of team grants,
+    // collaborator permission and public read access.
+    // None means the user cannot see the repository at all
+    pub async fn effective_permission(&self, user_id: Option<&str>, repo: &Repository) -> Result<Option<Permission>, String> {
+        let public = (!repo.is_private_bool()).then_some(Permission::Read);
+
+        let user_id = match user_id {
+            Some(user_id) => user_id,
+            None => return Ok(public),
+        };
+
+        let org_role = match repo.organization_id.as_deref() {
+            Some(org_id) => self.orgs.get_active_membership(org_id, user_id).await?,
+            None => None,
+        };
+        if let Some(permission) = owner_permission(repo, user_id, org_role) {
+            return Ok(Some(permission));
+        }
+
+        let team = self.teams.team_permission(user_id, &repo.id).await?;
+        let collaborator = self.collaborators.get_permission(&repo.id, user_id).await?;
+
+        Ok(team.max(collaborator).max(public))
+    }
+
+    // Drops the repositories the user cannot see from a listing
+    pub async fn filter_visible(&self, user_id: Option<&str>, repos: Vec<Repository>) -> Result<Vec<Repository>, String> {
+        let mut visible = Vec::with_capacity(repos.len());
+
+        for repo in repos {
+            if self.effective_permission(user_id, &repo).await?.is_some() {
+                visible.push(repo);
+            }
+        }
+
+        Ok(visible)
+    }
+
+    // Shorthand for handlers that only need a yes/no answer
+    pub async fn has_permission(&self, user_id: Option<&str>, repo: &Repository, required: Permission) -> Result<bool, String> {
+        Ok(self.effective_permission(user_id, repo).await?.is_some_and(|permission| permission >= required))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn repository(owner_id: &str, organization_id: Option<&str>) -> Repository {
+        Repository {
+            id: "repo".to_string(),
+            name: "project".to_string(),
+            description: None,
+            is_private: Some(1),
+            is_fork: Some(0),
+            is_archived: Some(0),
+            owner_id: owner_id.to_string(),
+            organization_id: organization_id.map(str::to_string),
+            default_branch: "main".to_string(),
+            language: None,
+            star_count: 0,
+            fork_count: 0,
+            watch_count: 0,
+            size: 0,
+            created_at: None,
+            updated_at: None,
+            pushed_at: None,
+        }
+    }
+
+    #[test]
+    fn personal_repositories_are_administered_by_their_owner() {
+        let repo = repository("alice", None);
+
+        assert_eq!(owner_permission(&repo, "alice", None), Some(Permission::Admin));
+        assert_eq!(owner_permission(&repo, "bob", None), None);
+    }
+
+    #[test]
+    fn org_repositories_are_administered_by_org_owners() {
+        let repo = repository("alice", Some("acme"));
+
+        assert_eq!(owner_permission(&repo, "bob", Some(OrgRole::Owner)), Some(Permission::Admin));
+        assert_eq!(owner_permission(&repo, "bob", Some(OrgRole::Member)), None);
+    }
+
+    #[test]
+    fn creators_removed_from_the_org_lose_access() {
+        let repo = repository("alice", Some("acme"));
+
+        assert_eq!(owner_permission(&repo, "alice", Some(OrgRole::Member)), None);
+        assert_eq!(owner_permission(&repo, "alice", None), None);
+    }
+}
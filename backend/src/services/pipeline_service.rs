@@ -0,0 +1,1379 @@
+use crate::models::{
+    CheckRunConclusion, CheckRunOutputRequest, CheckRunStatus, CreateCheckRunRequest, CreateRunnerRequest, CreatedRunner,
+    PipelineArtifact, PipelineConclusion, PipelineEvent, PipelineJob, PipelineRun, PipelineRunDetail, PipelineStatus,
+    PipelineStep, PullRequest, Repository, Runner, RunnerHeartbeat, RunnerJob, RunnerStepUpdate, UpdateCheckRunRequest,
+};
+use crate::services::access_token_service::{hash_token, to_hex};
+use crate::services::object_storage::ObjectStore;
+use crate::services::{CommitStatusService, GitService};
+use crate::utils::pipeline::{parse_pipeline, JobSpec, PipelineSpec, PIPELINE_DIR};
+use chrono::{DateTime, Utc};
+use ring::rand::{SecureRandom, SystemRandom};
+use sqlx::MySqlPool;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+// Lets runner tokens be told apart from other credentials
+pub const RUNNER_TOKEN_PREFIX: &str = "dvn_";
+
+// A claimed job goes back to failed if its runner stops sending heartbeats for this long
+const LEASE_SECONDS: i64 = 120;
+const HEARTBEAT_INTERVAL_SECONDS: u64 = 30;
+const LEASE_POLL_SECONDS: u64 = 30;
+const MAX_LOG_BYTES: i64 = 5 * 1024 * 1024;
+const MAX_RUNNERS_PER_OWNER: i64 = 50;
+const DEFAULT_RUNNER_LABEL: &str = "local";
+
+/// Whose repositories a runner takes jobs from.
+#[derive(Debug, Clone)]
+pub enum RunnerOwner {
+    Repository(String),
+    Organization(String),
+}
+
+impl RunnerOwner {
+    fn ids(&self) -> (Option<&str>, Option<&str>) {
+        match self {
+            RunnerOwner::Repository(id) => (Some(id.as_str()), None),
+            RunnerOwner::Organization(id) => (None, Some(id.as_str())),
+        }
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(str::to_string).collect()
+}
+
+fn check_run_conclusion(conclusion: PipelineConclusion) -> CheckRunConclusion {
+    match conclusion {
+        PipelineConclusion::Success => CheckRunConclusion::Success,
+        PipelineConclusion::Failure => CheckRunConclusion::Failure,
+        PipelineConclusion::Cancelled => CheckRunConclusion::Cancelled,
+        PipelineConclusion::Skipped => CheckRunConclusion::Skipped,
+    }
+}
+
+// Failures outrank cancellations; a run whose jobs were all skipped is skipped
+fn run_conclusion(conclusions: &[Option<PipelineConclusion>]) -> PipelineConclusion {
+    if conclusions.contains(&Some(PipelineConclusion::Failure)) {
+        PipelineConclusion::Failure
+    } else if conclusions.contains(&Some(PipelineConclusion::Cancelled)) {
+        PipelineConclusion::Cancelled
+    } else if conclusions.iter().all(|conclusion| *conclusion == Some(PipelineConclusion::Skipped)) {
+        PipelineConclusion::Skipped
+    } else {
+        PipelineConclusion::Success
+    }
+}
+
+type JobStates = HashMap<String, (PipelineStatus, Option<PipelineConclusion>)>;
+
+// Moves waiting jobs on once all their needs completed: to queued when every need succeeded,
+// otherwise to skipped. Skipping a job can settle the jobs that need it, so this repeats until
+// nothing changes. Returns the keys of the jobs to queue and to skip.
+fn settle_waiting_jobs(states: &mut JobStates, needs: &[(String, Vec<String>)]) -> (Vec<String>, Vec<String>) {
+    let mut queued = Vec::new();
+    let mut skipped = Vec::new();
+    loop {
+        let mut changed = false;
+
+        for (key, job_needs) in needs {
+            if states[key].0 != PipelineStatus::Waiting {
+                continue;
+            }
+
+            let needs: Vec<_> = job_needs.iter().map(|need| states.get(need).copied()).collect();
+
+            if needs.iter().any(|need| !matches!(need, Some((PipelineStatus::Completed, _)))) {
+                continue;
+            }
+
+            let succeeded = needs.iter().all(|need| matches!(need, Some((_, Some(PipelineConclusion::Success)))));
+            if succeeded {
+                states.insert(key.clone(), (PipelineStatus::Queued, None));
+                queued.push(key.clone());
+            } else {
+                states.insert(key.clone(), (PipelineStatus::Completed, Some(PipelineConclusion::Skipped)));
+                skipped.push(key.clone());
+            }
+            changed = true;
+        }
+
+        if !changed {
+            return (queued, skipped);
+        }
+    }
+}
+
+// Artifact names become object keys and download file names
+fn validate_artifact_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 255 || name == "." || name == ".." || name.contains('/') || name.contains('\\') {
+        return Err("Invalid artifact name".to_string());
+    }
+
+    Ok(())
+}
+
+struct RunnerRow {
+    id: String,
+    repository_id: Option<String>,
+    organization_id: Option<String>,
+    name: String,
+    labels: String,
+    token_last_eight: String,
+    active: bool,
+    last_seen_at: Option<DateTime<Utc>>,
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl From<RunnerRow> for Runner {
+    fn from(row: RunnerRow) -> Self {
+        Self {
+            id: row.id,
+            repository_id: row.repository_id,
+            organization_id: row.organization_id,
+            name: row.name,
+            labels: split_list(&row.labels),
+            token_last_eight: row.token_last_eight,
+            active: row.active,
+            last_seen_at: row.last_seen_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+struct RunRow {
+    id: String,
+    number: i32,
+    pipeline_path: String,
+    name: String,
+    event: String,
+    ref_name: String,
+    sha: String,
+    pull_request_number: Option<i32>,
+    status: String,
+    conclusion: Option<String>,
+    error: Option<String>,
+    triggered_by: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    started_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl From<RunRow> for PipelineRun {
+    fn from(row: RunRow) -> Self {
+        Self {
+            id: row.id,
+            number: row.number,
+            pipeline_path: row.pipeline_path,
+            name: row.name,
+            event: PipelineEvent::parse(&row.event).unwrap_or(PipelineEvent::Push),
+            ref_name: row.ref_name,
+            sha: row.sha,
+            pull_request_number: row.pull_request_number,
+            status: PipelineStatus::parse(&row.status).unwrap_or(PipelineStatus::Queued),
+            conclusion: row.conclusion.as_deref().and_then(PipelineConclusion::parse),
+            error: row.error,
+            triggered_by: row.triggered_by,
+            created_at: row.created_at,
+            started_at: row.started_at,
+            completed_at: row.completed_at,
+        }
+    }
+}
+
+struct JobRow {
+    id: String,
+    run_id: String,
+    repository_id: String,
+    job_key: String,
+    name: String,
+    runs_on: String,
+    needs: String,
+    spec: String,
+    status: String,
+    conclusion: Option<String>,
+    runner_id: Option<String>,
+    check_run_id: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    started_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl JobRow {
+    fn status(&self) -> PipelineStatus {
+        PipelineStatus::parse(&self.status).unwrap_or(PipelineStatus::Queued)
+    }
+
+    fn conclusion(&self) -> Option<PipelineConclusion> {
+        self.conclusion.as_deref().and_then(PipelineConclusion::parse)
+    }
+}
+
+struct StepRow {
+    number: i32,
+    name: String,
+    status: String,
+    conclusion: Option<String>,
+    exit_code: Option<i32>,
+    started_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+impl From<StepRow> for PipelineStep {
+    fn from(row: StepRow) -> Self {
+        Self {
+            number: row.number,
+            name: row.name,
+            status: PipelineStatus::parse(&row.status).unwrap_or(PipelineStatus::Queued),
+            conclusion: row.conclusion.as_deref().and_then(PipelineConclusion::parse),
+            exit_code: row.exit_code,
+            started_at: row.started_at,
+            completed_at: row.completed_at,
+        }
+    }
+}
+
+struct ArtifactRow {
+    id: String,
+    job_id: String,
+    name: String,
+    storage_key: String,
+    content_type: String,
+    size_bytes: i64,
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl From<ArtifactRow> for PipelineArtifact {
+    fn from(row: ArtifactRow) -> Self {
+        Self {
+            id: row.id,
+            job_id: row.job_id,
+            name: row.name,
+            content_type: row.content_type,
+            size_bytes: row.size_bytes,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Built-in CI. Pushes and pull requests start a run for every matching pipeline file in
+/// `.devit/pipelines`; its jobs are queued for runners, which poll for work, stream logs back and
+/// upload artifacts. Every job is mirrored as a check run on the commit it ran for.
+#[derive(Clone)]
+pub struct PipelineService {
+    pool: MySqlPool,
+    git: GitService,
+    statuses: CommitStatusService,
+    store: Arc<dyn ObjectStore>,
+}
+
+impl PipelineService {
+    pub fn new(pool: MySqlPool, git: GitService, statuses: CommitStatusService, store: Arc<dyn ObjectStore>) -> Self {
+        Self { pool, git, statuses, store }
+    }
+
+    pub async fn list_runners(&self, owner: &RunnerOwner) -> Result<Vec<Runner>, String> {
+        let (repository_id, organization_id) = owner.ids();
+
+        let runners = sqlx::query_as!(
+            RunnerRow,
+            r#"
+            SELECT id, repository_id, organization_id, name, labels, token_last_eight,
+                active as "active: bool", last_seen_at, created_at
+            FROM ci_runners
+            WHERE repository_id <=> ? AND organization_id <=> ?
+            ORDER BY created_at ASC
+            "#,
+            repository_id, organization_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(runners.into_iter().map(Runner::from).collect())
+    }
+
+    pub async fn create_runner(&self, owner: &RunnerOwner, user_id: &str, request: &CreateRunnerRequest) -> Result<CreatedRunner, String> {
+        let name = request.name.trim();
+        if name.is_empty() || name.len() > 255 {
+            return Err("Runner name must be between 1 and 255 characters".to_string());
+        }
+
+        let mut labels: Vec<String> = Vec::new();
+        for label in request.labels.clone().unwrap_or_else(|| vec![DEFAULT_RUNNER_LABEL.to_string()]) {
+            let label = label.trim().to_string();
+            if label.is_empty() || label.len() > 100 || label.contains(',') {
+                return Err(format!("Invalid runner label '{}'", label));
+            }
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+
+        let (repository_id, organization_id) = owner.ids();
+        let count = sqlx::query!(
+            r#"SELECT COUNT(*) as "count!: i64" FROM ci_runners WHERE repository_id <=> ? AND organization_id <=> ?"#,
+            repository_id, organization_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if count.count >= MAX_RUNNERS_PER_OWNER {
+            return Err(format!("At most {} runners can be registered", MAX_RUNNERS_PER_OWNER));
+        }
+
+        let mut secret = [0u8; 20];
+        SystemRandom::new()
+            .fill(&mut secret)
+            .map_err(|_| "Failed to generate token".to_string())?;
+        let token = format!("{}{}", RUNNER_TOKEN_PREFIX, to_hex(&secret));
+        let token_last_eight = token[token.len() - 8..].to_string();
+
+        let runner_id = format!("rnr_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO ci_runners (id, repository_id, organization_id, name, labels, token_hash, token_last_eight, active, created_by, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, TRUE, ?, NOW())
+            "#,
+            runner_id, repository_id, organization_id, name, labels.join(","), hash_token(&token), token_last_eight, user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let runner = self.get_runner(&runner_id).await?;
+        Ok(CreatedRunner { runner, token })
+    }
+
+    async fn get_runner(&self, runner_id: &str) -> Result<Runner, String> {
+        let runner = sqlx::query_as!(
+            RunnerRow,
+            r#"
+            SELECT id, repository_id, organization_id, name, labels, token_last_eight,
+                active as "active: bool", last_seen_at, created_at
+            FROM ci_runners
+            WHERE id = ?
+            "#,
+            runner_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Runner not found".to_string())?;
+
+        Ok(runner.into())
+    }
+
+    // Jobs a deleted runner had claimed fail once their lease runs out
+    pub async fn delete_runner(&self, owner: &RunnerOwner, runner_id: &str) -> Result<(), String> {
+        let (repository_id, organization_id) = owner.ids();
+
+        let result = sqlx::query!(
+            "DELETE FROM ci_runners WHERE id = ? AND repository_id <=> ? AND organization_id <=> ?",
+            runner_id, repository_id, organization_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Runner not found".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub async fn authenticate_runner(&self, token: &str) -> Result<Runner, String> {
+        if !token.starts_with(RUNNER_TOKEN_PREFIX) {
+            return Err("Invalid runner token".to_string());
+        }
+
+        let runner = sqlx::query_as!(
+            RunnerRow,
+            r#"
+            SELECT id, repository_id, organization_id, name, labels, token_last_eight,
+                active as "active: bool", last_seen_at, created_at
+            FROM ci_runners
+            WHERE token_hash = ?
+            "#,
+            hash_token(token)
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .filter(|runner| runner.active)
+        .ok_or_else(|| "Invalid runner token".to_string())?;
+
+        sqlx::query!("UPDATE ci_runners SET last_seen_at = NOW() WHERE id = ?", runner.id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(runner.into())
+    }
+
+    // Starts push runs for updated branches, and pull request runs for open pull requests whose head moved
+    pub fn schedule_push(&self, repo: &Repository, pusher_id: &str, before: &HashMap<String, String>, after: &HashMap<String, String>) {
+        let service = self.clone();
+        let repo = repo.clone();
+        let pusher_id = pusher_id.to_string();
+        let pushed: Vec<(String, String)> = after
+            .iter()
+            .filter(|(branch, sha)| before.get(*branch) != Some(*sha))
+            .map(|(branch, sha)| (branch.clone(), sha.clone()))
+            .collect();
+
+        tokio::spawn(async move {
+            for (branch, sha) in &pushed {
+                if let Err(err) = service.trigger(&repo, PipelineEvent::Push, branch, sha, None, &pusher_id).await {
+                    log::error!("Failed to start pipelines for {} on {}: {}", repo.id, branch, err);
+                }
+            }
+
+            if let Err(err) = service.trigger_pull_requests(&repo, &pushed, &pusher_id).await {
+                log::error!("Failed to start pull request pipelines for {}: {}", repo.id, err);
+            }
+        });
+    }
+
+    pub fn schedule_pull_request(&self, repo: &Repository, pr: &PullRequest, actor_id: &str) {
+        let service = self.clone();
+        let repo = repo.clone();
+        let (pr_id, base_branch, head_branch) = (pr.id.clone(), pr.base_branch.clone(), pr.head_branch.clone());
+        let actor_id = actor_id.to_string();
+
+        tokio::spawn(async move {
+            let (repo_id, branch) = (repo.id.clone(), head_branch.clone());
+            let sha = match service.git.blocking(move |git| git.resolve_sha(&repo_id, &branch)).await {
+                Ok(sha) => sha,
+                Err(err) => {
+                    log::error!("Failed to resolve {} for pipelines in {}: {}", head_branch, repo.id, err);
+                    return;
+                }
+            };
+
+            if let Err(err) = service
+                .trigger(&repo, PipelineEvent::PullRequest, &head_branch, &sha, Some((&pr_id, &base_branch)), &actor_id)
+                .await
+            {
+                log::error!("Failed to start pull request pipelines for {}: {}", repo.id, err);
+            }
+        });
+    }
+
+    async fn trigger_pull_requests(&self, repo: &Repository, pushed: &[(String, String)], actor_id: &str) -> Result<(), String> {
+        for (branch, sha) in pushed {
+            let prs = sqlx::query!(
+                "SELECT id, base_branch FROM pull_requests WHERE repository_id = ? AND head_branch = ? AND status = 'open'",
+                repo.id, branch
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+            for pr in prs {
+                self.trigger(repo, PipelineEvent::PullRequest, branch, sha, Some((&pr.id, &pr.base_branch)), actor_id).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates a run for every pipeline file at `sha` that triggers on the event. Pull request runs are
+    /// filtered on the base branch and given as (pull request id, base branch).
+    async fn trigger(
+        &self,
+        repo: &Repository,
+        event: PipelineEvent,
+        ref_name: &str,
+        sha: &str,
+        pull_request: Option<(&str, &str)>,
+        actor_id: &str,
+    ) -> Result<(), String> {
+        let (repo_id, commit) = (repo.id.clone(), sha.to_string());
+        let files = self.git
+            .blocking(move |git| git.files_in_directory(&repo_id, &commit, PIPELINE_DIR, &["yml", "yaml"]))
+            .await?;
+        let filter_branch = pull_request.map(|(_, base_branch)| base_branch).unwrap_or(ref_name);
+
+        for (path, contents) in files {
+            let default_name = Path::new(&path)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or(&path)
+                .to_string();
+
+            match parse_pipeline(&path, &contents) {
+                Ok(spec) if spec.matches(event, filter_branch) => {
+                    let name = spec.name.clone().unwrap_or(default_name);
+                    self.create_run(repo, &path, &name, Ok(&spec), event, ref_name, sha, pull_request, actor_id).await?;
+                }
+                Ok(_) => {}
+                Err(err) => {
+                    self.create_run(repo, &path, &default_name, Err(err.as_str()), event, ref_name, sha, pull_request, actor_id).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // A pipeline that failed to parse still gets a run, completed as failed, so the error is visible
+    #[allow(clippy::too_many_arguments)]
+    async fn create_run(
+        &self,
+        repo: &Repository,
+        path: &str,
+        name: &str,
+        spec: Result<&PipelineSpec, &str>,
+        event: PipelineEvent,
+        ref_name: &str,
+        sha: &str,
+        pull_request: Option<(&str, &str)>,
+        actor_id: &str,
+    ) -> Result<(), String> {
+        let run_id = format!("plr_{}", Uuid::new_v4().to_string().replace("-", ""));
+        let pull_request_id = pull_request.map(|(id, _)| id);
+        let error = spec.err().map(|err| err.chars().take(1000).collect::<String>());
+        let (status, conclusion) = match error {
+            Some(_) => (PipelineStatus::Completed, Some(PipelineConclusion::Failure)),
+            None => (PipelineStatus::Queued, None),
+        };
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        let next_number = sqlx::query!(
+            r#"SELECT COALESCE(MAX(number), 0) + 1 as "next_number!: i64" FROM pipeline_runs WHERE repository_id = ? FOR UPDATE"#,
+            repo.id
+        )
+        .fetch_one(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO pipeline_runs (
+                id, repository_id, number, pipeline_path, name, event, ref_name, sha, pull_request_id,
+                status, conclusion, error, triggered_by, created_at, completed_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, NOW(3), IF(? IS NULL, NULL, NOW()))
+            "#,
+            run_id, repo.id, next_number.next_number, path, name, event.as_str(), ref_name, sha, pull_request_id,
+            status.as_str(), conclusion.map(|conclusion| conclusion.as_str()), error, actor_id, error
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let mut jobs = Vec::new();
+        if let Ok(spec) = spec {
+            for (key, job) in &spec.jobs {
+                // Pipeline-wide variables apply to every job unless the job sets its own
+                let mut job = job.clone();
+                let mut env = spec.env.clone();
+                env.append(&mut job.env);
+                job.env = env;
+
+                let job_id = format!("plj_{}", Uuid::new_v4().to_string().replace("-", ""));
+                let job_name = job.name.clone().unwrap_or_else(|| key.clone());
+                let needs = job.needs.to_vec();
+                let status = if needs.is_empty() { PipelineStatus::Queued } else { PipelineStatus::Waiting };
+                let spec_json = serde_json::to_string(&job).map_err(|e| format!("Failed to store job: {}", e))?;
+
+                sqlx::query!(
+                    r#"
+                    INSERT INTO pipeline_jobs (id, run_id, repository_id, job_key, name, runs_on, needs, spec, status, created_at)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, NOW(3))
+                    "#,
+                    job_id, run_id, repo.id, key, job_name, job.runs_on.to_vec().join(","), needs.join(","), spec_json, status.as_str()
+                )
+                .execute(&mut *transaction)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+
+                for step in job.runner_steps() {
+                    let step_id = format!("pls_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+                    sqlx::query!(
+                        "INSERT INTO pipeline_steps (id, job_id, number, name, status) VALUES (?, ?, ?, ?, 'queued')",
+                        step_id, job_id, step.number, step.name
+                    )
+                    .execute(&mut *transaction)
+                    .await
+                    .map_err(|e| format!("Database error: {}", e))?;
+                }
+
+                jobs.push((job_id, job_name));
+            }
+        }
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        // Check runs are created once the run exists; a failure to report does not undo the run
+        if let Some(error) = &error {
+            let request = CreateCheckRunRequest {
+                name: name.to_string(),
+                head_sha: sha.to_string(),
+                status: Some(CheckRunStatus::Completed),
+                conclusion: Some(CheckRunConclusion::Failure),
+                details_url: None,
+                external_id: Some(run_id.clone()),
+                started_at: None,
+                completed_at: None,
+                output: Some(CheckRunOutputRequest {
+                    title: Some("Invalid pipeline".to_string()),
+                    summary: Some(error.clone()),
+                    text: None,
+                    annotations: None,
+                }),
+            };
+
+            if let Err(err) = self.statuses.create_check_run(&repo.id, sha, actor_id, &request).await {
+                log::error!("Failed to report pipeline run {}: {}", run_id, err);
+            }
+        }
+
+        for (job_id, job_name) in jobs {
+            let request = CreateCheckRunRequest {
+                name: format!("{} / {}", name, job_name),
+                head_sha: sha.to_string(),
+                status: Some(CheckRunStatus::Queued),
+                conclusion: None,
+                details_url: None,
+                external_id: Some(job_id.clone()),
+                started_at: None,
+                completed_at: None,
+                output: None,
+            };
+
+            match self.statuses.create_check_run(&repo.id, sha, actor_id, &request).await {
+                Ok(check_run) => {
+                    sqlx::query!("UPDATE pipeline_jobs SET check_run_id = ? WHERE id = ?", check_run.id, job_id)
+                        .execute(&self.pool)
+                        .await
+                        .map_err(|e| format!("Database error: {}", e))?;
+                }
+                Err(err) => log::error!("Failed to report pipeline job {}: {}", job_id, err),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn report_job(&self, job: &JobRow, status: CheckRunStatus, conclusion: Option<PipelineConclusion>) {
+        let check_run_id = match &job.check_run_id {
+            Some(check_run_id) => check_run_id,
+            None => return,
+        };
+
+        let request = UpdateCheckRunRequest {
+            name: None,
+            status: Some(status),
+            conclusion: conclusion.map(check_run_conclusion),
+            details_url: None,
+            external_id: None,
+            started_at: None,
+            completed_at: None,
+            output: None,
+        };
+
+        if let Err(err) = self.statuses.update_check_run(&job.repository_id, check_run_id, &request).await {
+            log::error!("Failed to report pipeline job {}: {}", job.id, err);
+        }
+    }
+
+    async fn get_job(&self, job_id: &str) -> Result<JobRow, String> {
+        sqlx::query_as!(
+            JobRow,
+            r#"
+            SELECT id, run_id, repository_id, job_key, name, runs_on, needs, spec, status, conclusion,
+                runner_id, check_run_id, created_at, started_at, completed_at
+            FROM pipeline_jobs
+            WHERE id = ?
+            "#,
+            job_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Job not found".to_string())
+    }
+
+    // Runners may only act on jobs they claimed and have not finished
+    async fn runner_job(&self, runner: &Runner, job_id: &str) -> Result<JobRow, String> {
+        let job = self.get_job(job_id).await?;
+
+        if job.runner_id.as_deref() != Some(runner.id.as_str()) || job.status() != PipelineStatus::InProgress {
+            return Err("Job not found".to_string());
+        }
+
+        Ok(job)
+    }
+
+    /// Claims the oldest queued job the runner may take, if any.
+    pub async fn claim_job(&self, runner: &Runner) -> Result<Option<RunnerJob>, String> {
+        let candidates = sqlx::query!(
+            r#"
+            SELECT j.id, j.runs_on
+            FROM pipeline_jobs j
+            INNER JOIN repositories r ON r.id = j.repository_id
+            WHERE j.status = 'queued' AND (r.id = ? OR r.organization_id = ?)
+            ORDER BY j.created_at ASC
+            LIMIT 100
+            "#,
+            runner.repository_id, runner.organization_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        for candidate in candidates {
+            if !split_list(&candidate.runs_on).iter().all(|label| runner.labels.contains(label)) {
+                continue;
+            }
+
+            // Another runner may have claimed it since it was listed
+            let claimed = sqlx::query!(
+                r#"
+                UPDATE pipeline_jobs
+                SET status = 'in_progress', runner_id = ?, started_at = NOW(), lease_expires_at = NOW() + INTERVAL ? SECOND
+                WHERE id = ? AND status = 'queued'
+                "#,
+                runner.id, LEASE_SECONDS, candidate.id
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+            if claimed.rows_affected() == 0 {
+                continue;
+            }
+
+            let job = self.get_job(&candidate.id).await?;
+
+            sqlx::query!(
+                "UPDATE pipeline_runs SET status = 'in_progress', started_at = COALESCE(started_at, NOW()) WHERE id = ? AND status = 'queued'",
+                job.run_id
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+            self.report_job(&job, CheckRunStatus::InProgress, None).await;
+
+            return self.runner_payload(&job).await.map(Some);
+        }
+
+        Ok(None)
+    }
+
+    async fn runner_payload(&self, job: &JobRow) -> Result<RunnerJob, String> {
+        let spec: JobSpec = serde_json::from_str(&job.spec).map_err(|e| format!("Invalid stored job: {}", e))?;
+
+        let run = sqlx::query!(
+            r#"
+            SELECT p.event, p.ref_name, p.sha, r.name as repo_name, COALESCE(o.name, u.username) as "owner!: String"
+            FROM pipeline_runs p
+            JOIN repositories r ON r.id = p.repository_id
+            JOIN users u ON u.id = r.owner_id
+            LEFT JOIN organizations o ON o.id = r.organization_id
+            WHERE p.id = ?
+            "#,
+            job.run_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let repository = format!("{}/{}", run.owner, run.repo_name);
+        let event = PipelineEvent::parse(&run.event).unwrap_or(PipelineEvent::Push);
+
+        let mut env: BTreeMap<String, String> = BTreeMap::from([
+            ("CI".to_string(), "true".to_string()),
+            ("DEVIT_REPOSITORY".to_string(), repository.clone()),
+            ("DEVIT_EVENT".to_string(), event.as_str().to_string()),
+            ("DEVIT_REF".to_string(), run.ref_name.clone()),
+            ("DEVIT_SHA".to_string(), run.sha.clone()),
+            ("DEVIT_RUN_ID".to_string(), job.run_id.clone()),
+            ("DEVIT_JOB_ID".to_string(), job.id.clone()),
+        ]);
+        env.extend(spec.env.clone());
+
+        Ok(RunnerJob {
+            id: job.id.clone(),
+            run_id: job.run_id.clone(),
+            repository,
+            event,
+            ref_name: run.ref_name,
+            sha: run.sha,
+            name: job.name.clone(),
+            env,
+            steps: spec.runner_steps(),
+            artifacts: spec.artifacts.clone(),
+            timeout_minutes: spec.timeout(),
+            heartbeat_interval_seconds: HEARTBEAT_INTERVAL_SECONDS,
+        })
+    }
+
+    // Extends the runner's lease on the job and tells it whether the run was cancelled
+    pub async fn heartbeat(&self, runner: &Runner, job_id: &str) -> Result<RunnerHeartbeat, String> {
+        self.runner_job(runner, job_id).await?;
+
+        sqlx::query!(
+            "UPDATE pipeline_jobs SET lease_expires_at = NOW() + INTERVAL ? SECOND WHERE id = ?",
+            LEASE_SECONDS, job_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let job = sqlx::query!(
+            r#"SELECT cancel_requested as "cancel_requested: bool" FROM pipeline_jobs WHERE id = ?"#,
+            job_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(RunnerHeartbeat { cancel_requested: job.cancel_requested })
+    }
+
+    pub async fn job_source(&self, runner: &Runner, job_id: &str) -> Result<Vec<u8>, String> {
+        let job = self.runner_job(runner, job_id).await?;
+
+        let run = sqlx::query!("SELECT sha FROM pipeline_runs WHERE id = ?", job.run_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        self.git.archive(&job.repository_id, &run.sha).await
+    }
+
+    pub async fn update_step(&self, runner: &Runner, job_id: &str, number: i32, update: &RunnerStepUpdate) -> Result<(), String> {
+        self.runner_job(runner, job_id).await?;
+
+        let conclusion = match (update.status, update.conclusion) {
+            (PipelineStatus::Completed, Some(conclusion)) => Some(conclusion),
+            (PipelineStatus::Completed, None) => return Err("A completed step needs a conclusion".to_string()),
+            (PipelineStatus::InProgress, _) => None,
+            _ => return Err("Steps can only be marked in_progress or completed".to_string()),
+        };
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE pipeline_steps
+            SET status = ?,
+                conclusion = ?,
+                exit_code = COALESCE(?, exit_code),
+                started_at = COALESCE(started_at, NOW()),
+                completed_at = IF(? = 'completed', NOW(), NULL)
+            WHERE job_id = ? AND number = ?
+            "#,
+            update.status.as_str(), conclusion.map(|conclusion| conclusion.as_str()), update.exit_code,
+            update.status.as_str(), job_id, number
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Step not found".to_string());
+        }
+
+        Ok(())
+    }
+
+    // Output past the per-job limit is dropped, with a note in its place
+    pub async fn append_log(&self, runner: &Runner, job_id: &str, step_number: i32, content: &str) -> Result<(), String> {
+        self.runner_job(runner, job_id).await?;
+
+        let job = sqlx::query!("SELECT log_bytes FROM pipeline_jobs WHERE id = ?", job_id)
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        let remaining = (MAX_LOG_BYTES - job.log_bytes).max(0) as usize;
+        if remaining == 0 || content.is_empty() {
+            return Ok(());
+        }
+
+        let content = if content.len() > remaining {
+            let mut end = remaining;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            format!("{}\n[Log truncated: the job exceeded {} bytes of output]\n", &content[..end], MAX_LOG_BYTES)
+        } else {
+            content.to_string()
+        };
+
+        sqlx::query!(
+            "INSERT INTO pipeline_log_chunks (job_id, step_number, content, created_at) VALUES (?, ?, ?, NOW(3))",
+            job_id, step_number, content
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!(
+            "UPDATE pipeline_jobs SET log_bytes = log_bytes + ? WHERE id = ?",
+            content.len() as i64, job_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    // Uploading an artifact with the same name again replaces it
+    pub async fn upload_artifact(&self, runner: &Runner, job_id: &str, name: &str, content_type: &str, data: Vec<u8>) -> Result<PipelineArtifact, String> {
+        validate_artifact_name(name)?;
+        let job = self.runner_job(runner, job_id).await?;
+
+        let storage_key = format!("pipelines/{}/{}/{}/{}", job.repository_id, job.run_id, job.id, name);
+        let size_bytes = data.len() as i64;
+        self.store.put(&storage_key, content_type, data).await?;
+
+        let artifact_id = format!("pla_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO pipeline_artifacts (id, run_id, job_id, name, storage_key, content_type, size_bytes, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, NOW())
+            ON DUPLICATE KEY UPDATE storage_key = VALUES(storage_key), content_type = VALUES(content_type),
+                size_bytes = VALUES(size_bytes), created_at = NOW()
+            "#,
+            artifact_id, job.run_id, job.id, name, storage_key, content_type, size_bytes
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let artifact = sqlx::query_as!(
+            ArtifactRow,
+            r#"
+            SELECT id, job_id, name, storage_key, content_type, size_bytes, created_at
+            FROM pipeline_artifacts
+            WHERE job_id = ? AND name = ?
+            "#,
+            job.id, name
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(artifact.into())
+    }
+
+    pub async fn complete_job(&self, runner: &Runner, job_id: &str, conclusion: PipelineConclusion) -> Result<(), String> {
+        let job = self.runner_job(runner, job_id).await?;
+
+        self.finish_job(&job, conclusion).await
+    }
+
+    async fn finish_job(&self, job: &JobRow, conclusion: PipelineConclusion) -> Result<(), String> {
+        self.mark_job_completed(job, conclusion).await?;
+        self.advance_run(&job.run_id).await
+    }
+
+    // Steps that never finished take the job's conclusion, or are skipped if they never started
+    async fn mark_job_completed(&self, job: &JobRow, conclusion: PipelineConclusion) -> Result<(), String> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE pipeline_jobs
+            SET status = 'completed', conclusion = ?, completed_at = NOW(), lease_expires_at = NULL
+            WHERE id = ? AND status <> 'completed'
+            "#,
+            conclusion.as_str(), job.id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Ok(());
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE pipeline_steps
+            SET conclusion = IF(status = 'queued', 'skipped', ?), status = 'completed', completed_at = NOW()
+            WHERE job_id = ? AND status <> 'completed'
+            "#,
+            conclusion.as_str(), job.id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.report_job(job, CheckRunStatus::Completed, Some(conclusion)).await;
+        Ok(())
+    }
+
+    /// Queues waiting jobs whose needs all succeeded, skips those with a need that did not, and
+    /// completes the run once every job has finished.
+    async fn advance_run(&self, run_id: &str) -> Result<(), String> {
+        let jobs = sqlx::query_as!(
+            JobRow,
+            r#"
+            SELECT id, run_id, repository_id, job_key, name, runs_on, needs, spec, status, conclusion,
+                runner_id, check_run_id, created_at, started_at, completed_at
+            FROM pipeline_jobs
+            WHERE run_id = ?
+            "#,
+            run_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let mut states: JobStates = jobs
+            .iter()
+            .map(|job| (job.job_key.clone(), (job.status(), job.conclusion())))
+            .collect();
+
+        let needs: Vec<_> = jobs.iter().map(|job| (job.job_key.clone(), split_list(&job.needs))).collect();
+        let (queued, skipped) = settle_waiting_jobs(&mut states, &needs);
+
+        for job in jobs.iter().filter(|job| queued.contains(&job.job_key)) {
+            sqlx::query!("UPDATE pipeline_jobs SET status = 'queued' WHERE id = ? AND status = 'waiting'", job.id)
+                .execute(&self.pool)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+        }
+
+        for job in jobs.iter().filter(|job| skipped.contains(&job.job_key)) {
+            self.mark_job_completed(job, PipelineConclusion::Skipped).await?;
+        }
+
+        if states.values().any(|(status, _)| *status != PipelineStatus::Completed) {
+            return Ok(());
+        }
+
+        let conclusions: Vec<_> = states.values().map(|(_, conclusion)| *conclusion).collect();
+
+        sqlx::query!(
+            r#"
+            UPDATE pipeline_runs
+            SET status = 'completed', conclusion = ?, completed_at = NOW()
+            WHERE id = ? AND status <> 'completed'
+            "#,
+            run_conclusion(&conclusions).as_str(), run_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    // Jobs that have not started are cancelled at once; runners stop running ones on their next heartbeat
+    pub async fn cancel_run(&self, repo_id: &str, number: i32) -> Result<(), String> {
+        let run = self.get_run_row(repo_id, number).await?;
+
+        if run.status == PipelineStatus::Completed.as_str() {
+            return Err("Pipeline run is already completed".to_string());
+        }
+
+        sqlx::query!(
+            "UPDATE pipeline_jobs SET cancel_requested = TRUE WHERE run_id = ? AND status = 'in_progress'",
+            run.id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let pending = sqlx::query_as!(
+            JobRow,
+            r#"
+            SELECT id, run_id, repository_id, job_key, name, runs_on, needs, spec, status, conclusion,
+                runner_id, check_run_id, created_at, started_at, completed_at
+            FROM pipeline_jobs
+            WHERE run_id = ? AND status IN ('waiting', 'queued')
+            "#,
+            run.id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        for job in &pending {
+            self.mark_job_completed(job, PipelineConclusion::Cancelled).await?;
+        }
+
+        self.advance_run(&run.id).await
+    }
+
+    // Fails jobs whose runner stopped sending heartbeats
+    pub fn spawn_lease_worker(&self) {
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(LEASE_POLL_SECONDS));
+            loop {
+                interval.tick().await;
+
+                if let Err(err) = service.expire_leases().await {
+                    log::error!("Failed to expire pipeline job leases: {}", err);
+                }
+            }
+        });
+    }
+
+    async fn expire_leases(&self) -> Result<(), String> {
+        let expired = sqlx::query_as!(
+            JobRow,
+            r#"
+            SELECT id, run_id, repository_id, job_key, name, runs_on, needs, spec, status, conclusion,
+                runner_id, check_run_id, created_at, started_at, completed_at
+            FROM pipeline_jobs
+            WHERE status = 'in_progress' AND lease_expires_at < NOW()
+            LIMIT 100
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        for job in &expired {
+            log::warn!("Runner for pipeline job {} stopped responding", job.id);
+            self.finish_job(job, PipelineConclusion::Failure).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn list_runs(&self, repo_id: &str, limit: u32, offset: u32) -> Result<Vec<PipelineRun>, String> {
+        let runs = sqlx::query_as!(
+            RunRow,
+            r#"
+            SELECT p.id, p.number, p.pipeline_path, p.name, p.event, p.ref_name, p.sha,
+                pr.number as "pull_request_number?: i32", p.status, p.conclusion, p.error, p.triggered_by,
+                p.created_at, p.started_at, p.completed_at
+            FROM pipeline_runs p
+            LEFT JOIN pull_requests pr ON pr.id = p.pull_request_id
+            WHERE p.repository_id = ?
+            ORDER BY p.number DESC
+            LIMIT ? OFFSET ?
+            "#,
+            repo_id, limit, offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(runs.into_iter().map(PipelineRun::from).collect())
+    }
+
+    async fn get_run_row(&self, repo_id: &str, number: i32) -> Result<RunRow, String> {
+        sqlx::query_as!(
+            RunRow,
+            r#"
+            SELECT p.id, p.number, p.pipeline_path, p.name, p.event, p.ref_name, p.sha,
+                pr.number as "pull_request_number?: i32", p.status, p.conclusion, p.error, p.triggered_by,
+                p.created_at, p.started_at, p.completed_at
+            FROM pipeline_runs p
+            LEFT JOIN pull_requests pr ON pr.id = p.pull_request_id
+            WHERE p.repository_id = ? AND p.number = ?
+            "#,
+            repo_id, number
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Pipeline run not found".to_string())
+    }
+
+    pub async fn get_run(&self, repo_id: &str, number: i32) -> Result<PipelineRunDetail, String> {
+        let run = self.get_run_row(repo_id, number).await?;
+
+        let job_rows = sqlx::query_as!(
+            JobRow,
+            r#"
+            SELECT id, run_id, repository_id, job_key, name, runs_on, needs, spec, status, conclusion,
+                runner_id, check_run_id, created_at, started_at, completed_at
+            FROM pipeline_jobs
+            WHERE run_id = ?
+            ORDER BY created_at ASC, job_key ASC
+            "#,
+            run.id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let mut jobs = Vec::with_capacity(job_rows.len());
+        for job in job_rows {
+            let steps = sqlx::query_as!(
+                StepRow,
+                r#"
+                SELECT number, name, status, conclusion, exit_code, started_at, completed_at
+                FROM pipeline_steps
+                WHERE job_id = ?
+                ORDER BY number ASC
+                "#,
+                job.id
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+            jobs.push(PipelineJob {
+                status: job.status(),
+                conclusion: job.conclusion(),
+                runs_on: split_list(&job.runs_on),
+                needs: split_list(&job.needs),
+                id: job.id,
+                key: job.job_key,
+                name: job.name,
+                runner_id: job.runner_id,
+                check_run_id: job.check_run_id,
+                steps: steps.into_iter().map(PipelineStep::from).collect(),
+                created_at: job.created_at,
+                started_at: job.started_at,
+                completed_at: job.completed_at,
+            });
+        }
+
+        Ok(PipelineRunDetail { run: run.into(), jobs })
+    }
+
+    /// The job's output so far, optionally for a single step.
+    pub async fn job_log(&self, repo_id: &str, job_id: &str, step_number: Option<i32>) -> Result<String, String> {
+        let job = self.get_job(job_id).await?;
+        if job.repository_id != repo_id {
+            return Err("Job not found".to_string());
+        }
+
+        let chunks = sqlx::query!(
+            r#"
+            SELECT content FROM pipeline_log_chunks
+            WHERE job_id = ? AND (? IS NULL OR step_number = ?)
+            ORDER BY id ASC
+            "#,
+            job_id, step_number, step_number
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(chunks.into_iter().map(|chunk| chunk.content).collect())
+    }
+
+    pub async fn list_artifacts(&self, repo_id: &str, number: i32) -> Result<Vec<PipelineArtifact>, String> {
+        let run = self.get_run_row(repo_id, number).await?;
+
+        let artifacts = sqlx::query_as!(
+            ArtifactRow,
+            r#"
+            SELECT id, job_id, name, storage_key, content_type, size_bytes, created_at
+            FROM pipeline_artifacts
+            WHERE run_id = ?
+            ORDER BY created_at ASC, name ASC
+            "#,
+            run.id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(artifacts.into_iter().map(PipelineArtifact::from).collect())
+    }
+
+    pub async fn download_artifact(&self, repo_id: &str, artifact_id: &str) -> Result<(PipelineArtifact, Vec<u8>), String> {
+        let artifact = sqlx::query_as!(
+            ArtifactRow,
+            r#"
+            SELECT a.id, a.job_id, a.name, a.storage_key, a.content_type, a.size_bytes, a.created_at
+            FROM pipeline_artifacts a
+            INNER JOIN pipeline_runs p ON p.id = a.run_id
+            WHERE a.id = ? AND p.repository_id = ?
+            "#,
+            artifact_id, repo_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Artifact not found".to_string())?;
+
+        let data = self.store.get(&artifact.storage_key).await.map_err(|err| {
+            if err == "Object not found" { "Artifact not found".to_string() } else { err }
+        })?;
+
+        Ok((artifact.into(), data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn needs(jobs: &[(&str, &[&str])]) -> Vec<(String, Vec<String>)> {
+        jobs.iter()
+            .map(|(key, needs)| (key.to_string(), needs.iter().map(|need| need.to_string()).collect()))
+            .collect()
+    }
+
+    fn states(jobs: &[(&str, PipelineStatus, Option<PipelineConclusion>)]) -> JobStates {
+        jobs.iter().map(|(key, status, conclusion)| (key.to_string(), (*status, *conclusion))).collect()
+    }
+
+    #[test]
+    fn jobs_are_queued_once_their_needs_succeed() {
+        let needs = needs(&[("build", &[]), ("test", &["build"]), ("deploy", &["build", "test"])]);
+        let mut states = states(&[
+            ("build", PipelineStatus::Completed, Some(PipelineConclusion::Success)),
+            ("test", PipelineStatus::Waiting, None),
+            ("deploy", PipelineStatus::Waiting, None),
+        ]);
+
+        assert_eq!(settle_waiting_jobs(&mut states, &needs), (vec!["test".to_string()], vec![]));
+        assert_eq!(states["test"], (PipelineStatus::Queued, None));
+        assert_eq!(states["deploy"], (PipelineStatus::Waiting, None));
+    }
+
+    #[test]
+    fn skipped_needs_skip_everything_after_them() {
+        let needs = needs(&[("build", &[]), ("test", &["build"]), ("deploy", &["test"]), ("lint", &[])]);
+        let mut states = states(&[
+            ("build", PipelineStatus::Completed, Some(PipelineConclusion::Failure)),
+            ("test", PipelineStatus::Waiting, None),
+            ("deploy", PipelineStatus::Waiting, None),
+            ("lint", PipelineStatus::InProgress, None),
+        ]);
+
+        let (queued, skipped) = settle_waiting_jobs(&mut states, &needs);
+        assert!(queued.is_empty());
+        assert_eq!(skipped, vec!["test".to_string(), "deploy".to_string()]);
+        assert_eq!(states["deploy"], (PipelineStatus::Completed, Some(PipelineConclusion::Skipped)));
+        assert_eq!(states["lint"], (PipelineStatus::InProgress, None));
+    }
+
+    #[test]
+    fn runs_conclude_with_their_worst_job() {
+        use PipelineConclusion::*;
+
+        assert_eq!(run_conclusion(&[Some(Success), Some(Cancelled), Some(Failure)]), Failure);
+        assert_eq!(run_conclusion(&[Some(Success), Some(Cancelled), Some(Skipped)]), Cancelled);
+        assert_eq!(run_conclusion(&[Some(Skipped), Some(Skipped)]), Skipped);
+        assert_eq!(run_conclusion(&[Some(Success), Some(Skipped)]), Success);
+    }
+
+    #[test]
+    fn artifact_names_stay_inside_the_run() {
+        assert!(validate_artifact_name("app-linux.tar.gz").is_ok());
+        for name in ["", ".", "..", "dist/app", "dist\\app"] {
+            assert!(validate_artifact_name(name).is_err(), "{:?}", name);
+        }
+        assert_eq!(split_list(" local, gpu ,,"), vec!["local".to_string(), "gpu".to_string()]);
+    }
+}
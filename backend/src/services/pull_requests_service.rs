@@ -1,14 +1,30 @@
-use crate::models::PullRequest;
+use crate::models::{MergeMethod, PullRequest, PullRequestReview, ReviewComment, ReviewState, SubmitReviewRequest, CreateReviewCommentRequest};
+use crate::services::{BranchProtectionService, CommitStatusService, GitService};
+use crate::services::git_service::MergeOutcome;
+use crate::services::issue_service::claim_number;
 use sqlx::MySqlPool;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Result of merging a pull request in git. The merge moves the base branch, so the branch tips on
+/// either side of it are kept to announce it as a push.
+pub struct BranchMerge {
+    pub outcome: MergeOutcome,
+    pub heads_before: HashMap<String, String>,
+    pub heads_after: HashMap<String, String>,
+}
 
 #[derive(Clone)]
 pub struct PullRequestService {
     pool: MySqlPool,
+    git: GitService,
+    statuses: CommitStatusService,
+    protections: BranchProtectionService,
 }
 
 impl PullRequestService {
-    pub fn new(pool: MySqlPool) -> Self {
-        Self { pool }
+    pub fn new(pool: MySqlPool, git: GitService, statuses: CommitStatusService, protections: BranchProtectionService) -> Self {
+        Self { pool, git, statuses, protections }
     }
 
     pub async fn get_pull_request(&self, repo_id: &str, pr_number: i32) -> Result<PullRequest, String> {
@@ -19,6 +35,8 @@ impl PullRequestService {
                 id, number, title, body, status, author_id,
                 repository_id, base_branch, head_branch, 
                 is_merged as "is_merged: bool",
+                mergeable as "mergeable: bool", mergeable_state, head_sha, head_status,
+                merge_commit_sha, merged_by_id,
                 created_at, updated_at, merged_at, closed_at
             FROM pull_requests
             WHERE repository_id = ? AND number = ?
@@ -40,6 +58,8 @@ impl PullRequestService {
                 id, number, title, body, status, author_id,
                 repository_id, base_branch, head_branch, 
                 is_merged as "is_merged: bool",
+                mergeable as "mergeable: bool", mergeable_state, head_sha, head_status,
+                merge_commit_sha, merged_by_id,
                 created_at, updated_at, merged_at, closed_at
             FROM pull_requests
             WHERE repository_id = ?
@@ -55,26 +75,31 @@ impl PullRequestService {
     }
 
     pub async fn create_pull_request(&self, repo_id: &str, author_id: &str, title: &str, body: Option<&str>, base_branch: &str, head_branch: &str) -> Result<PullRequest, String> {
-        // Get the next PR number for this repository
-        let next_number = sqlx::query!(
-            "SELECT COALESCE(MAX(number), 0) + 1 as next_number FROM pull_requests WHERE repository_id = ?",
-            repo_id
-        )
-        .fetch_one(&self.pool)
-        .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        // Both branches must exist for the pull request to ever be mergeable
+        let (repository_id, branches) = (repo_id.to_string(), [base_branch.to_string(), head_branch.to_string()]);
+        self.git
+            .blocking(move |git| branches.iter().try_for_each(|branch| git.get_branch(&repository_id, branch).map(|_| ())))
+            .await?;
+
+        let pr_id = format!("pr_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        let number = claim_number(&mut transaction, repo_id).await?;
 
         // Insert the pull request
-        let result = sqlx::query!(
+        sqlx::query!(
             r#"
             INSERT INTO pull_requests (
-                number, title, body, status, author_id, repository_id,
+                id, number, title, body, status, author_id, repository_id,
                 base_branch, head_branch, is_merged,
                 created_at, updated_at
             )
-            VALUES (?, ?, ?, 'open', ?, ?, ?, ?, false, NOW(), NOW())
+            VALUES (?, ?, ?, ?, 'open', ?, ?, ?, ?, false, NOW(), NOW())
             "#,
-            next_number.next_number,
+            pr_id,
+            number,
             title,
             body,
             author_id,
@@ -82,11 +107,12 @@ impl PullRequestService {
             base_branch,
             head_branch
         )
-        .execute(&self.pool)
+        .execute(&mut *transaction)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-        let pr_id = result.last_insert_id().to_string();
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
 
         // Fetch the created pull request
         let pr = sqlx::query_as!(
@@ -96,6 +122,8 @@ impl PullRequestService {
                 id, number, title, body, status, author_id,
                 repository_id, base_branch, head_branch, 
                 is_merged as "is_merged: bool",
+                mergeable as "mergeable: bool", mergeable_state, head_sha, head_status,
+                merge_commit_sha, merged_by_id,
                 created_at, updated_at, merged_at, closed_at
             FROM pull_requests
             WHERE id = ?
@@ -136,6 +164,8 @@ impl PullRequestService {
                 id, number, title, body, status, author_id,
                 repository_id, base_branch, head_branch, 
                 is_merged as "is_merged: bool",
+                mergeable as "mergeable: bool", mergeable_state, head_sha, head_status,
+                merge_commit_sha, merged_by_id,
                 created_at, updated_at, merged_at, closed_at
             FROM pull_requests
             WHERE id = ?
@@ -149,7 +179,7 @@ impl PullRequestService {
         Ok(pr)
     }
 
-    pub async fn merge_pull_request(&self, pr_id: &str, _merged_by_id: &str, _merge_commit_sha: &str) -> Result<PullRequest, String> {
+    pub async fn merge_pull_request(&self, pr_id: &str, merged_by_id: &str, merge_commit_sha: &str) -> Result<PullRequest, String> {
         // Update the pull request
         sqlx::query!(
             r#"
@@ -157,11 +187,13 @@ impl PullRequestService {
             SET 
                 status = 'merged',
                 is_merged = true,
+                merge_commit_sha = ?,
+                merged_by_id = ?,
                 merged_at = NOW(),
                 updated_at = NOW()
             WHERE id = ?
             "#,
-            pr_id
+            merge_commit_sha, merged_by_id, pr_id
         )
         .execute(&self.pool)
         .await
@@ -175,6 +207,8 @@ impl PullRequestService {
                 id, number, title, body, status, author_id,
                 repository_id, base_branch, head_branch, 
                 is_merged as "is_merged: bool",
+                mergeable as "mergeable: bool", mergeable_state, head_sha, head_status,
+                merge_commit_sha, merged_by_id,
                 created_at, updated_at, merged_at, closed_at
             FROM pull_requests
             WHERE id = ?
@@ -187,5 +221,492 @@ impl PullRequestService {
 
         Ok(pr)
     }
-}
 
+    /// Resolves the commit the head branch points at now.
+    pub async fn head_sha(&self, pr: &PullRequest) -> Result<String, String> {
+        let (repo_id, head_branch) = (pr.repository_id.clone(), pr.head_branch.clone());
+        self.git.blocking(move |git| git.resolve_sha(&repo_id, &head_branch)).await
+    }
+
+    /// Why the protection on the base branch stops `user_id` from merging `head_sha`, if it does.
+    pub async fn check_merge(&self, pr: &PullRequest, head_sha: &str, user_id: &str, is_admin: bool) -> Result<Option<String>, String> {
+        match self.protections.get_protection(&pr.repository_id, &pr.base_branch).await? {
+            Some(protection) => self.protections.check_merge(&protection, &pr.id, head_sha, user_id, is_admin).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Merges the head branch into the base in git, held to `head_sha` so a push after the merge
+    /// checks cannot slip in. Recording the merge is left to merge_pull_request.
+    pub async fn merge_branches(
+        &self,
+        pr: &PullRequest,
+        head_sha: &str,
+        method: MergeMethod,
+        message: &str,
+        committer_name: &str,
+        committer_email: &str,
+    ) -> Result<BranchMerge, String> {
+        let (repo_id, base_branch, head_branch) = (pr.repository_id.clone(), pr.base_branch.clone(), pr.head_branch.clone());
+        let (head_sha, message) = (head_sha.to_string(), message.to_string());
+        let (committer_name, committer_email) = (committer_name.to_string(), committer_email.to_string());
+
+        self.git
+            .blocking(move |git| {
+                let heads_before = git.branch_heads(&repo_id).unwrap_or_default();
+                let outcome = git.merge_branches(
+                    &repo_id,
+                    &base_branch,
+                    &head_branch,
+                    &head_sha,
+                    method,
+                    &message,
+                    &committer_name,
+                    &committer_email,
+                )?;
+                let heads_after = git.branch_heads(&repo_id).unwrap_or_default();
+
+                Ok(BranchMerge { outcome, heads_before, heads_after })
+            })
+            .await
+    }
+
+    // Re-evaluates open pull requests on the given branches in the background; failures are only logged
+    pub fn schedule_branch_refresh(&self, repo_id: &str, branches: Vec<String>) {
+        let service = self.clone();
+        let repo_id = repo_id.to_string();
+
+        tokio::spawn(async move {
+            if let Err(err) = service.refresh_mergeability(&repo_id, &branches).await {
+                log::error!("Failed to refresh mergeability for {}: {}", repo_id, err);
+            }
+
+            if let Err(err) = service.refresh_review_threads(&repo_id, &branches).await {
+                log::error!("Failed to refresh review threads for {}: {}", repo_id, err);
+            }
+        });
+    }
+
+    // Recomputes mergeability and the head commit status of open pull requests whose base or head is one of the given branches
+    pub async fn refresh_mergeability(&self, repo_id: &str, branches: &[String]) -> Result<(), String> {
+        let mut checked = HashSet::new();
+
+        for branch in branches {
+            let prs = sqlx::query!(
+                r#"
+                SELECT id, base_branch, head_branch
+                FROM pull_requests
+                WHERE repository_id = ? AND status = 'open' AND (base_branch = ? OR head_branch = ?)
+                "#,
+                repo_id, branch, branch
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+            for pr in prs {
+                if !checked.insert(pr.id.clone()) {
+                    continue;
+                }
+
+                let pr_id = pr.id.clone();
+
+                let repository_id = repo_id.to_string();
+                let (merge_check, head_sha) = self.git
+                    .blocking(move |git| {
+                        let merge_check = git.is_mergeable(&repository_id, &pr.base_branch, &pr.head_branch);
+                        Ok((merge_check, git.resolve_sha(&repository_id, &pr.head_branch).ok()))
+                    })
+                    .await?;
+
+                // A missing branch leaves the state unknown rather than failing the whole refresh
+                let (mergeable, state) = match merge_check {
+                    Ok(true) => (Some(true), "clean"),
+                    Ok(false) => (Some(false), "dirty"),
+                    Err(_) => (None, "unknown"),
+                };
+                let head_status = match &head_sha {
+                    Some(sha) => self.statuses.head_state(repo_id, sha).await?.map(|state| state.as_str()),
+                    None => None,
+                };
+
+                sqlx::query!(
+                    r#"
+                    UPDATE pull_requests
+                    SET mergeable = ?, mergeable_state = ?, head_sha = ?, head_status = ?, updated_at = updated_at
+                    WHERE id = ?
+                    "#,
+                    mergeable, state, head_sha, head_status, pr_id
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn list_reviews(&self, pr_id: &str) -> Result<Vec<PullRequestReview>, String> {
+        let reviews = sqlx::query_as!(
+            PullRequestReview,
+            r#"
+            SELECT id, pull_request_id, author_id, state, body, commit_id, submitted_at
+            FROM pull_request_reviews
+            WHERE pull_request_id = ?
+            ORDER BY submitted_at ASC, id ASC
+            "#,
+            pr_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(reviews)
+    }
+
+    pub async fn get_review(&self, pr_id: &str, review_id: &str) -> Result<PullRequestReview, String> {
+        let review = sqlx::query_as!(
+            PullRequestReview,
+            r#"
+            SELECT id, pull_request_id, author_id, state, body, commit_id, submitted_at
+            FROM pull_request_reviews
+            WHERE pull_request_id = ? AND id = ?
+            "#,
+            pr_id, review_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        review.ok_or_else(|| "Review not found".to_string())
+    }
+
+    // Stores a review and its line comments together so a failed comment never leaves a partial review
+    pub async fn submit_review(&self, pr: &PullRequest, author_id: &str, request: &SubmitReviewRequest) -> Result<PullRequestReview, String> {
+        let has_body = request.body.as_deref().is_some_and(|body| !body.trim().is_empty());
+
+        if request.event != ReviewState::Approve && !has_body && request.comments.is_empty() {
+            return Err("A review that is not an approval needs a body or line comments".to_string());
+        }
+
+        let commit_id = self.review_commit(pr, request.commit_id.as_deref()).await?;
+
+        for comment in &request.comments {
+            if comment.body.trim().is_empty() {
+                return Err("Comment body cannot be empty".to_string());
+            }
+            self.check_comment_position(pr, &comment.path, comment.line, &commit_id).await?;
+        }
+
+        let review_id = format!("review_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO pull_request_reviews (id, pull_request_id, author_id, state, body, commit_id, submitted_at)
+            VALUES (?, ?, ?, ?, ?, ?, NOW())
+            "#,
+            review_id, pr.id, author_id, request.event.as_str(), request.body, commit_id
+        )
+        .execute(&mut *transaction)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        for comment in &request.comments {
+            let comment_id = format!("rc_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+            sqlx::query!(
+                r#"
+                INSERT INTO review_comments (
+                    id, pull_request_id, review_id, author_id, path,
+                    line, commit_id, original_line, original_commit_id, body,
+                    created_at, updated_at
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, NOW(), NOW())
+                "#,
+                comment_id, pr.id, review_id, author_id, comment.path,
+                comment.line, commit_id, comment.line, commit_id, comment.body
+            )
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+        }
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        self.get_review(&pr.id, &review_id).await
+    }
+
+    pub async fn list_review_comments(&self, pr_id: &str, limit: u32, offset: u32) -> Result<Vec<ReviewComment>, String> {
+        let comments = sqlx::query_as!(
+            ReviewComment,
+            r#"
+            SELECT
+                id, pull_request_id, review_id, author_id, in_reply_to_id,
+                path, line, commit_id, original_line, original_commit_id, body,
+                is_resolved as "is_resolved: bool", resolved_by_id,
+                is_outdated as "is_outdated: bool",
+                created_at, updated_at
+            FROM review_comments
+            WHERE pull_request_id = ?
+            ORDER BY created_at ASC, id ASC
+            LIMIT ? OFFSET ?
+            "#,
+            pr_id, limit, offset
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(comments)
+    }
+
+    pub async fn list_review_comments_for_review(&self, review_id: &str) -> Result<Vec<ReviewComment>, String> {
+        let comments = sqlx::query_as!(
+            ReviewComment,
+            r#"
+            SELECT
+                id, pull_request_id, review_id, author_id, in_reply_to_id,
+                path, line, commit_id, original_line, original_commit_id, body,
+                is_resolved as "is_resolved: bool", resolved_by_id,
+                is_outdated as "is_outdated: bool",
+                created_at, updated_at
+            FROM review_comments
+            WHERE review_id = ?
+            ORDER BY created_at ASC, id ASC
+            "#,
+            review_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(comments)
+    }
+
+    pub async fn get_review_comment(&self, pr_id: &str, comment_id: &str) -> Result<ReviewComment, String> {
+        let comment = sqlx::query_as!(
+            ReviewComment,
+            r#"
+            SELECT
+                id, pull_request_id, review_id, author_id, in_reply_to_id,
+                path, line, commit_id, original_line, original_commit_id, body,
+                is_resolved as "is_resolved: bool", resolved_by_id,
+                is_outdated as "is_outdated: bool",
+                created_at, updated_at
+            FROM review_comments
+            WHERE pull_request_id = ? AND id = ?
+            "#,
+            pr_id, comment_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        comment.ok_or_else(|| "Review comment not found".to_string())
+    }
+
+    // Starts a new thread on a line, or replies to an existing thread outside of a review
+    pub async fn create_review_comment(&self, pr: &PullRequest, author_id: &str, request: &CreateReviewCommentRequest) -> Result<ReviewComment, String> {
+        if request.body.trim().is_empty() {
+            return Err("Comment body cannot be empty".to_string());
+        }
+
+        let comment_id = format!("rc_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        match request.in_reply_to.as_deref() {
+            Some(reply_to) => {
+                // Replies always hang off the thread root, never off another reply
+                let target = self.get_review_comment(&pr.id, reply_to).await?;
+                let root = match target.in_reply_to_id.as_deref() {
+                    Some(root_id) => self.get_review_comment(&pr.id, root_id).await?,
+                    None => target,
+                };
+
+                sqlx::query!(
+                    r#"
+                    INSERT INTO review_comments (
+                        id, pull_request_id, author_id, in_reply_to_id, path,
+                        line, commit_id, original_line, original_commit_id, body,
+                        is_resolved, resolved_by_id, is_outdated, created_at, updated_at
+                    )
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, NOW(), NOW())
+                    "#,
+                    comment_id, pr.id, author_id, root.id, root.path,
+                    root.line, root.commit_id, root.original_line, root.original_commit_id, request.body,
+                    root.is_resolved, root.resolved_by_id, root.is_outdated
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+            }
+            None => {
+                let (path, line) = match (request.path.as_deref(), request.line) {
+                    (Some(path), Some(line)) => (path, line),
+                    _ => return Err("path and line are required for a new review comment".to_string()),
+                };
+
+                let commit_id = self.review_commit(pr, request.commit_id.as_deref()).await?;
+                self.check_comment_position(pr, path, line, &commit_id).await?;
+
+                sqlx::query!(
+                    r#"
+                    INSERT INTO review_comments (
+                        id, pull_request_id, author_id, path,
+                        line, commit_id, original_line, original_commit_id, body,
+                        created_at, updated_at
+                    )
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, NOW(), NOW())
+                    "#,
+                    comment_id, pr.id, author_id, path,
+                    line, commit_id, line, commit_id, request.body
+                )
+                .execute(&self.pool)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+            }
+        }
+
+        self.get_review_comment(&pr.id, &comment_id).await
+    }
+
+    // Resolution applies to the whole thread rooted at root_id
+    pub async fn set_thread_resolved(&self, pr_id: &str, root_id: &str, resolved: bool, user_id: &str) -> Result<(), String> {
+        let resolved_by = resolved.then_some(user_id);
+
+        sqlx::query!(
+            r#"
+            UPDATE review_comments
+            SET is_resolved = ?, resolved_by_id = ?
+            WHERE pull_request_id = ? AND (id = ? OR in_reply_to_id = ?)
+            "#,
+            resolved, resolved_by, pr_id, root_id, root_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    // Moves review threads along with the head branch, or marks them outdated when their line changed
+    pub async fn refresh_review_threads(&self, repo_id: &str, branches: &[String]) -> Result<(), String> {
+        for branch in branches {
+            let prs = sqlx::query!(
+                "SELECT id FROM pull_requests WHERE repository_id = ? AND status = 'open' AND head_branch = ?",
+                repo_id, branch
+            )
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+            // The branch may have been deleted by the push
+            let (repository_id, reference) = (repo_id.to_string(), format!("refs/heads/{}", branch));
+            let head = match self.git.blocking(move |git| git.resolve_sha(&repository_id, &reference)).await {
+                Ok(sha) => sha,
+                Err(_) => continue,
+            };
+
+            for pr in prs {
+                let threads = sqlx::query!(
+                    r#"
+                    SELECT id, path, line, commit_id
+                    FROM review_comments
+                    WHERE pull_request_id = ? AND in_reply_to_id IS NULL
+                        AND is_outdated = FALSE AND commit_id <> ?
+                    "#,
+                    pr.id, head
+                )
+                .fetch_all(&self.pool)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+
+                let positions: Vec<_> = threads.iter()
+                    .map(|thread| (thread.path.clone(), thread.commit_id.clone(), thread.line as u32))
+                    .collect();
+                let (repository_id, to) = (repo_id.to_string(), head.clone());
+                let remapped = self.git
+                    .blocking(move |git| {
+                        Ok(positions.iter()
+                            .map(|(path, from, line)| git.remap_line(&repository_id, path, from, &to, *line).unwrap_or(None))
+                            .collect::<Vec<_>>())
+                    })
+                    .await?;
+
+                for (thread, remapped) in threads.into_iter().zip(remapped) {
+                    match remapped {
+                        Some(line) => {
+                            sqlx::query!(
+                                "UPDATE review_comments SET line = ?, commit_id = ? WHERE id = ? OR in_reply_to_id = ?",
+                                line, head, thread.id, thread.id
+                            )
+                            .execute(&self.pool)
+                            .await
+                            .map_err(|e| format!("Database error: {}", e))?;
+                        }
+                        None => {
+                            sqlx::query!(
+                                "UPDATE review_comments SET is_outdated = TRUE WHERE id = ? OR in_reply_to_id = ?",
+                                thread.id, thread.id
+                            )
+                            .execute(&self.pool)
+                            .await
+                            .map_err(|e| format!("Database error: {}", e))?;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Resolves the commit a review is anchored to, defaulting to the head of the pull request
+    async fn review_commit(&self, pr: &PullRequest, commit_id: Option<&str>) -> Result<String, String> {
+        let (repo_id, head_branch) = (pr.repository_id.clone(), pr.head_branch.clone());
+        let commit_id = commit_id.map(str::to_string);
+
+        self.git
+            .blocking(move |git| {
+                let commit_id = match commit_id {
+                    Some(commit_id) => commit_id,
+                    None => return git.resolve_sha(&repo_id, &format!("refs/heads/{}", head_branch)),
+                };
+
+                if !git.branch_contains(&repo_id, &head_branch, &commit_id)? {
+                    return Err(format!("Commit {} is not part of the pull request", commit_id));
+                }
+
+                git.resolve_sha(&repo_id, &commit_id)
+            })
+            .await
+    }
+
+    // A line comment must point at a file changed by the pull request and a line that exists at commit_id
+    async fn check_comment_position(&self, pr: &PullRequest, path: &str, line: i32, commit_id: &str) -> Result<(), String> {
+        let (repo_id, base_branch, head_branch) = (pr.repository_id.clone(), pr.base_branch.clone(), pr.head_branch.clone());
+        let (path, commit_id) = (path.to_string(), commit_id.to_string());
+
+        self.git
+            .blocking(move |git| {
+                let (files, _) = git.compare_files(&repo_id, &base_branch, &head_branch)?;
+
+                if !files.iter().any(|file| file.filename == path) {
+                    return Err(format!("{} is not part of the pull request diff", path));
+                }
+
+                let lines = git.count_lines(&repo_id, &commit_id, &path)?;
+
+                if line < 1 || line as usize > lines {
+                    return Err(format!("Line {} does not exist in {}", line, path));
+                }
+
+                Ok(())
+            })
+            .await
+    }
+}
@@ -1,16 +1,22 @@
-use crate::models::Repository;
+use crate::models::{Repository, CreateRepositoryRequest};
+use crate::services::GitService;
+use crate::utils::templates::{find_gitignore_template, find_license_template};
+use chrono::{Datelike, Utc};
 use sqlx::MySqlPool;
+use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct RepositoryService {
     pool: MySqlPool,
+    git: GitService,
 }
 
 impl RepositoryService {
-    pub fn new(pool: MySqlPool) -> Self {
-        Self { pool }
+    pub fn new(pool: MySqlPool, git: GitService) -> Self {
+        Self { pool, git }
     }
 
+    // owner is either a username or, for organization repositories, the organization name
     pub async fn get_repository(&self, owner: &str, name: &str) -> Result<Repository, String> {
         let repo = sqlx::query_as!(
             Repository,
@@ -21,10 +27,11 @@ impl RepositoryService {
                 r.star_count, r.fork_count, r.watch_count, r.size,
                 r.created_at, r.updated_at, r.pushed_at
             FROM repositories r
-            INNER JOIN users u ON r.owner_id = u.id
-            WHERE u.username = ? AND r.name = ?
+            LEFT JOIN users u ON r.owner_id = u.id AND r.organization_id IS NULL
+            LEFT JOIN organizations o ON r.organization_id = o.id
+            WHERE r.name = ? AND (u.username = ? OR o.name = ?)
             "#,
-            owner, name
+            name, owner, owner
         )
         .fetch_optional(&self.pool)
         .await
@@ -44,7 +51,7 @@ impl RepositoryService {
                 r.created_at, r.updated_at, r.pushed_at
             FROM repositories r
             INNER JOIN users u ON r.owner_id = u.id
-            WHERE u.username = ?
+            WHERE u.username = ? AND r.organization_id IS NULL
             ORDER BY r.updated_at DESC
             "#,
             username
@@ -56,20 +63,93 @@ impl RepositoryService {
         Ok(repos)
     }
 
-    pub async fn create_repository(&self, owner_id: &str, name: &str, description: Option<&str>, is_private: bool) -> Result<Repository, String> {
+    pub async fn list_organization_repositories(&self, org_id: &str) -> Result<Vec<Repository>, String> {
+        let repos = sqlx::query_as!(
+            Repository,
+            r#"
+            SELECT 
+                id, name, description, is_private, is_fork, is_archived,
+                owner_id, organization_id, default_branch, language,
+                star_count, fork_count, watch_count, size,
+                created_at, updated_at, pushed_at
+            FROM repositories
+            WHERE organization_id = ?
+            ORDER BY updated_at DESC
+            "#,
+            org_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(repos)
+    }
+
+    // organization_id is set for repositories created under an organization; owner_id is still the creator
+    pub async fn create_repository(&self, owner_id: &str, organization_id: Option<&str>, request: &CreateRepositoryRequest) -> Result<Repository, String> {
+        // Resolve templates up front so an unknown name fails before anything is created
+        let gitignore = match request.gitignore_template.as_deref() {
+            Some(name) => Some(find_gitignore_template(name)
+                .ok_or_else(|| format!("Unknown gitignore template: {}", name))?),
+            None => None,
+        };
+
+        let license = match request.license_template.as_deref() {
+            Some(key) => Some(find_license_template(key)
+                .ok_or_else(|| format!("Unknown license template: {}", key))?),
+            None => None,
+        };
+
+        let repo_id = format!("repo_{}", Uuid::new_v4().to_string().replace("-", ""));
+
         // Insert the repository
-        let result = sqlx::query!(
+        sqlx::query!(
             r#"
-            INSERT INTO repositories (name, description, is_private, owner_id, default_branch, language, star_count, fork_count, watch_count, size, created_at, updated_at)
-            VALUES (?, ?, ?, ?, 'main', NULL, 0, 0, 0, 0, NOW(), NOW())
+            INSERT INTO repositories (id, name, description, is_private, owner_id, organization_id, default_branch, language, star_count, fork_count, watch_count, size, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, 'main', NULL, 0, 0, 0, 0, NOW(), NOW())
             "#,
-            name, description, is_private, owner_id
+            repo_id, request.name, request.description, request.is_private, owner_id, organization_id
         )
         .execute(&self.pool)
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-        let repo_id = result.last_insert_id().to_string();
+        // Create the bare git repository backing the new row
+        let id = repo_id.clone();
+        if let Err(err) = self.git.blocking(move |git| git.init_repository(&id, "main")).await {
+            self.discard_repository(&repo_id).await?;
+            return Err(err);
+        }
+
+        let auto_init = request.auto_init.unwrap_or(false);
+
+        if auto_init || gitignore.is_some() || license.is_some() {
+            let (author_name, author_email) = self.git_identity(owner_id).await?;
+            let mut files = Vec::new();
+
+            if auto_init {
+                let readme = match request.description.as_deref() {
+                    Some(description) => format!("# {}\n\n{}\n", request.name, description),
+                    None => format!("# {}\n", request.name),
+                };
+                files.push(("README.md".to_string(), readme));
+            }
+
+            if let Some(template) = gitignore {
+                files.push((".gitignore".to_string(), template.source.to_string()));
+            }
+
+            if let Some(template) = license {
+                files.push(("LICENSE".to_string(), template.render(Utc::now().year(), &author_name)));
+            }
+
+            let id = repo_id.clone();
+            let commit = self.git.blocking(move |git| git.create_initial_commit(&id, "main", &author_name, &author_email, &files));
+            if let Err(err) = commit.await {
+                self.discard_repository(&repo_id).await?;
+                return Err(err);
+            }
+        }
 
         // Fetch the created repository
         let repo = sqlx::query_as!(
@@ -92,7 +172,37 @@ impl RepositoryService {
         Ok(repo)
     }
 
-    pub async fn update_repository(&self, repo_id: &str, name: Option<&str>, description: Option<&str>, is_private: Option<bool>) -> Result<Repository, String> {
+    // Name and email used when the server writes commits or tags on a user's behalf
+    pub async fn git_identity(&self, user_id: &str) -> Result<(String, String), String> {
+        let user = sqlx::query!(
+            "SELECT username, email, full_name FROM users WHERE id = ?",
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok((user.full_name.unwrap_or(user.username), user.email))
+    }
+
+    // Rolls back a partially created repository
+    async fn discard_repository(&self, repo_id: &str) -> Result<(), String> {
+        sqlx::query!("DELETE FROM repositories WHERE id = ?", repo_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        let repo_id = repo_id.to_string();
+        self.git.blocking(move |git| git.delete_repository(&repo_id)).await
+    }
+
+    pub async fn update_repository(&self, repo_id: &str, name: Option<&str>, description: Option<&str>, is_private: Option<bool>, default_branch: Option<&str>) -> Result<Repository, String> {
+        // Move HEAD first so the stored default branch always names a real branch
+        if let Some(branch) = default_branch {
+            let (id, branch) = (repo_id.to_string(), branch.to_string());
+            self.git.blocking(move |git| git.set_default_branch(&id, &branch)).await?;
+        }
+
         // Update the repository
         sqlx::query!(
             r#"
@@ -101,10 +211,11 @@ impl RepositoryService {
                 name = COALESCE(?, name),
                 description = COALESCE(?, description),
                 is_private = COALESCE(?, is_private),
+                default_branch = COALESCE(?, default_branch),
                 updated_at = NOW()
             WHERE id = ?
             "#,
-            name, description, is_private, repo_id
+            name, description, is_private, default_branch, repo_id
         )
         .execute(&self.pool)
         .await
@@ -140,18 +251,33 @@ impl RepositoryService {
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
+        let repo_id = repo_id.to_string();
+        self.git.blocking(move |git| git.delete_repository(&repo_id)).await
+    }
+
+    pub async fn record_push(&self, repo_id: &str) -> Result<(), String> {
+        sqlx::query!(
+            "UPDATE repositories SET pushed_at = NOW(), updated_at = NOW() WHERE id = ?",
+            repo_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
         Ok(())
     }
 
-    pub async fn star_repository(&self, user_id: &str, repo_id: &str) -> Result<(), String> {
+    // Returns whether the repository was not already starred by the user
+    pub async fn star_repository(&self, user_id: &str, repo_id: &str) -> Result<bool, String> {
         // Insert star record
-        sqlx::query!(
+        let inserted = sqlx::query!(
             "INSERT IGNORE INTO stars (user_id, repository_id, created_at) VALUES (?, ?, NOW())",
             user_id, repo_id
         )
         .execute(&self.pool)
         .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        .map_err(|e| format!("Database error: {}", e))?
+        .rows_affected() > 0;
 
         // Update star count
         sqlx::query!(
@@ -162,18 +288,20 @@ impl RepositoryService {
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-        Ok(())
+        Ok(inserted)
     }
 
-    pub async fn unstar_repository(&self, user_id: &str, repo_id: &str) -> Result<(), String> {
+    // Returns whether the user had starred the repository
+    pub async fn unstar_repository(&self, user_id: &str, repo_id: &str) -> Result<bool, String> {
         // Remove star record
-        sqlx::query!(
+        let deleted = sqlx::query!(
             "DELETE FROM stars WHERE user_id = ? AND repository_id = ?",
             user_id, repo_id
         )
         .execute(&self.pool)
         .await
-        .map_err(|e| format!("Database error: {}", e))?;
+        .map_err(|e| format!("Database error: {}", e))?
+        .rows_affected() > 0;
 
         // Update star count
         sqlx::query!(
@@ -184,7 +312,7 @@ impl RepositoryService {
         .await
         .map_err(|e| format!("Database error: {}", e))?;
 
-        Ok(())
+        Ok(deleted)
     }
 
     pub async fn is_repository_starred(&self, user_id: &str, repo_id: &str) -> Result<bool, String> {
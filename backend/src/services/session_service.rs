@@ -0,0 +1,308 @@
+use crate::models::{Session, SessionTokens, UserWithPassword};
+use crate::services::access_token_service::{hash_token, to_hex};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Access tokens are short-lived; a session stays signed in for as long as it keeps refreshing
+pub const ACCESS_TOKEN_TTL_SECONDS: i64 = 15 * 60;
+pub const REFRESH_TOKEN_TTL_SECONDS: i64 = 30 * 24 * 60 * 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub username: String,
+    pub email: String,
+    pub sid: String, // Session the token was issued to
+    pub jti: String,
+    pub exp: usize,
+    pub iat: usize,
+}
+
+fn session_key(session_id: &str) -> String {
+    format!("session:{}", session_id)
+}
+
+fn user_sessions_key(user_id: &str) -> String {
+    format!("user_sessions:{}", user_id)
+}
+
+fn refresh_key(token_hash: &str) -> String {
+    format!("refresh:{}", token_hash)
+}
+
+// Rotated refresh tokens are remembered so that replaying one can be detected
+fn used_refresh_key(token_hash: &str) -> String {
+    format!("refresh_used:{}", token_hash)
+}
+
+fn denied_token_key(jti: &str) -> String {
+    format!("denylist:token:{}", jti)
+}
+
+fn denied_session_key(session_id: &str) -> String {
+    format!("denylist:session:{}", session_id)
+}
+
+fn redis_error(e: redis::RedisError) -> String {
+    format!("Redis error: {}", e)
+}
+
+fn parse_timestamp(value: Option<&String>) -> DateTime<Utc> {
+    value
+        .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+        .map(|value| value.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+}
+
+fn generate_refresh_token() -> Result<String, String> {
+    let mut secret = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut secret)
+        .map_err(|_| "Failed to generate refresh token".to_string())?;
+
+    Ok(to_hex(&secret))
+}
+
+#[derive(Clone)]
+pub struct SessionService {
+    // Shared by every clone and reconnected as needed, since the denylist is read on every request
+    redis: ConnectionManager,
+    jwt_secret: String,
+}
+
+impl SessionService {
+    pub fn new(redis: ConnectionManager, jwt_secret: String) -> Self {
+        Self { redis, jwt_secret }
+    }
+
+    pub async fn create_session(
+        &self,
+        user: &UserWithPassword,
+        user_agent: Option<&str>,
+        ip_address: Option<&str>,
+    ) -> Result<SessionTokens, String> {
+        let session_id = format!("sess_{}", Uuid::new_v4().to_string().replace("-", ""));
+        let refresh_token = generate_refresh_token()?;
+        let refresh_hash = hash_token(&refresh_token);
+        let now = Utc::now().to_rfc3339();
+
+        let mut conn = self.redis.clone();
+        redis::pipe()
+            .atomic()
+            .hset_multiple(
+                session_key(&session_id),
+                &[
+                    ("user_id", user.id.as_str()),
+                    ("user_agent", user_agent.unwrap_or("")),
+                    ("ip_address", ip_address.unwrap_or("")),
+                    ("created_at", now.as_str()),
+                    ("last_used", now.as_str()),
+                    ("refresh_hash", refresh_hash.as_str()),
+                ],
+            )
+            .ignore()
+            .cmd("EXPIRE").arg(session_key(&session_id)).arg(REFRESH_TOKEN_TTL_SECONDS).ignore()
+            .cmd("SET").arg(refresh_key(&refresh_hash)).arg(&session_id).arg("EX").arg(REFRESH_TOKEN_TTL_SECONDS).ignore()
+            .sadd(user_sessions_key(&user.id), &session_id).ignore()
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(redis_error)?;
+
+        self.issue_tokens(user, &session_id, refresh_token)
+    }
+
+    /// Exchanges a refresh token for a new one, returning the session id, its user id
+    /// and the replacement token. Each refresh token can be used exactly once.
+    pub async fn rotate_refresh_token(&self, refresh_token: &str) -> Result<(String, String, String), String> {
+        let token_hash = hash_token(refresh_token);
+        let mut conn = self.redis.clone();
+
+        // GETDEL keeps the token single-use even when two refreshes race
+        let session_id: Option<String> = redis::cmd("GETDEL")
+            .arg(refresh_key(&token_hash))
+            .query_async(&mut conn)
+            .await
+            .map_err(redis_error)?;
+
+        let session_id = match session_id {
+            Some(session_id) => session_id,
+            None => {
+                // A rotated token coming back means it leaked, so the whole session is ended
+                let reused: Option<String> = conn.get(used_refresh_key(&token_hash)).await.map_err(redis_error)?;
+                if let Some(session_id) = reused {
+                    self.revoke_session(&session_id).await?;
+                    return Err("Refresh token has already been used; the session has been revoked".to_string());
+                }
+
+                return Err("Invalid or expired refresh token".to_string());
+            }
+        };
+
+        let user_id: Option<String> = conn.hget(session_key(&session_id), "user_id").await.map_err(redis_error)?;
+        let user_id = user_id.ok_or("Session has expired".to_string())?;
+
+        let new_token = generate_refresh_token()?;
+        let new_hash = hash_token(&new_token);
+        let now = Utc::now().to_rfc3339();
+
+        redis::pipe()
+            .atomic()
+            .cmd("SET").arg(used_refresh_key(&token_hash)).arg(&session_id).arg("EX").arg(REFRESH_TOKEN_TTL_SECONDS).ignore()
+            .cmd("SET").arg(refresh_key(&new_hash)).arg(&session_id).arg("EX").arg(REFRESH_TOKEN_TTL_SECONDS).ignore()
+            .hset_multiple(session_key(&session_id), &[("refresh_hash", new_hash.as_str()), ("last_used", now.as_str())])
+            .ignore()
+            .cmd("EXPIRE").arg(session_key(&session_id)).arg(REFRESH_TOKEN_TTL_SECONDS).ignore()
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(redis_error)?;
+
+        Ok((session_id, user_id, new_token))
+    }
+
+    pub fn issue_tokens(&self, user: &UserWithPassword, session_id: &str, refresh_token: String) -> Result<SessionTokens, String> {
+        let now = Utc::now();
+        let claims = Claims {
+            sub: user.id.clone(),
+            username: user.username.clone(),
+            email: user.email.clone(),
+            sid: session_id.to_string(),
+            jti: Uuid::new_v4().to_string().replace("-", ""),
+            exp: (now + Duration::seconds(ACCESS_TOKEN_TTL_SECONDS)).timestamp() as usize,
+            iat: now.timestamp() as usize,
+        };
+
+        let access_token = encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.jwt_secret.as_ref())
+        )
+        .map_err(|e| format!("Token generation error: {}", e))?;
+
+        Ok(SessionTokens {
+            session_id: session_id.to_string(),
+            access_token,
+            refresh_token,
+            expires_in: ACCESS_TOKEN_TTL_SECONDS,
+        })
+    }
+
+    pub fn decode_access_token(&self, token: &str) -> Result<Claims, String> {
+        decode::<Claims>(
+            token,
+            &DecodingKey::from_secret(self.jwt_secret.as_ref()),
+            &Validation::default()
+        )
+        .map(|data| data.claims)
+        .map_err(|e| format!("Invalid token: {}", e))
+    }
+
+    // True when the token itself was logged out or its session has been revoked
+    pub async fn is_denied(&self, jti: &str, session_id: &str) -> Result<bool, String> {
+        let mut conn = self.redis.clone();
+        let denied: i64 = redis::cmd("EXISTS")
+            .arg(denied_token_key(jti))
+            .arg(denied_session_key(session_id))
+            .query_async(&mut conn)
+            .await
+            .map_err(redis_error)?;
+
+        Ok(denied > 0)
+    }
+
+    // Denied only until the token would have expired anyway
+    pub async fn deny_token(&self, jti: &str, expires_at: i64) -> Result<(), String> {
+        let remaining = expires_at - Utc::now().timestamp();
+        if remaining <= 0 {
+            return Ok(());
+        }
+
+        let mut conn = self.redis.clone();
+        redis::cmd("SET")
+            .arg(denied_token_key(jti))
+            .arg(1)
+            .arg("EX")
+            .arg(remaining)
+            .query_async::<_, ()>(&mut conn)
+            .await
+            .map_err(redis_error)
+    }
+
+    pub async fn list_sessions(&self, user_id: &str, current_session_id: Option<&str>) -> Result<Vec<Session>, String> {
+        let mut conn = self.redis.clone();
+        let session_ids: Vec<String> = conn.smembers(user_sessions_key(user_id)).await.map_err(redis_error)?;
+
+        let mut sessions = Vec::new();
+        for session_id in session_ids {
+            let fields: HashMap<String, String> = conn.hgetall(session_key(&session_id)).await.map_err(redis_error)?;
+
+            // Expired sessions disappear on their own; drop them from the index as well
+            if fields.is_empty() {
+                conn.srem::<_, _, ()>(user_sessions_key(user_id), &session_id).await.map_err(redis_error)?;
+                continue;
+            }
+
+            sessions.push(Session {
+                current: current_session_id == Some(session_id.as_str()),
+                user_agent: fields.get("user_agent").filter(|value| !value.is_empty()).cloned(),
+                ip_address: fields.get("ip_address").filter(|value| !value.is_empty()).cloned(),
+                created_at: parse_timestamp(fields.get("created_at")),
+                last_used: parse_timestamp(fields.get("last_used")),
+                id: session_id,
+            });
+        }
+
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.last_used));
+        Ok(sessions)
+    }
+
+    // Revokes one of the user's own sessions; other users' sessions are reported as missing
+    pub async fn revoke_user_session(&self, user_id: &str, session_id: &str) -> Result<(), String> {
+        let mut conn = self.redis.clone();
+        let owner: Option<String> = conn.hget(session_key(session_id), "user_id").await.map_err(redis_error)?;
+
+        if owner.as_deref() != Some(user_id) {
+            return Err("Session not found".to_string());
+        }
+
+        self.revoke_session(session_id).await
+    }
+
+    // Signs the user out everywhere, e.g. after a password reset
+    pub async fn revoke_all_sessions(&self, user_id: &str) -> Result<(), String> {
+        let mut conn = self.redis.clone();
+        let session_ids: Vec<String> = conn.smembers(user_sessions_key(user_id)).await.map_err(redis_error)?;
+
+        for session_id in session_ids {
+            self.revoke_session(&session_id).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn revoke_session(&self, session_id: &str) -> Result<(), String> {
+        let mut conn = self.redis.clone();
+        let fields: HashMap<String, String> = conn.hgetall(session_key(session_id)).await.map_err(redis_error)?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic().del(session_key(session_id)).ignore();
+
+        if let Some(refresh_hash) = fields.get("refresh_hash") {
+            pipe.del(refresh_key(refresh_hash)).ignore();
+        }
+
+        if let Some(user_id) = fields.get("user_id") {
+            pipe.srem(user_sessions_key(user_id), session_id).ignore();
+        }
+
+        // Every access token issued to the session expires within one access token lifetime
+        pipe.cmd("SET").arg(denied_session_key(session_id)).arg(1).arg("EX").arg(ACCESS_TOKEN_TTL_SECONDS).ignore();
+
+        pipe.query_async::<_, ()>(&mut conn).await.map_err(redis_error)
+    }
+}
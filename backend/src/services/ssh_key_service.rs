@@ -0,0 +1,132 @@
+use crate::models::{CreateSshKeyRequest, SshKey};
+use crate::utils::ssh::parse_public_key;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct SshKeyService {
+    pool: MySqlPool,
+}
+
+impl SshKeyService {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_keys(&self, user_id: &str) -> Result<Vec<SshKey>, String> {
+        let keys = sqlx::query_as!(
+            SshKey,
+            r#"
+            SELECT id, title, key_type, key_data, fingerprint, created_at, last_used
+            FROM ssh_keys
+            WHERE user_id = ?
+            ORDER BY created_at DESC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(keys)
+    }
+
+    pub async fn get_key(&self, user_id: &str, key_id: &str) -> Result<SshKey, String> {
+        let key = sqlx::query_as!(
+            SshKey,
+            r#"
+            SELECT id, title, key_type, key_data, fingerprint, created_at, last_used
+            FROM ssh_keys
+            WHERE id = ? AND user_id = ?
+            "#,
+            key_id, user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        key.ok_or_else(|| "SSH key not found".to_string())
+    }
+
+    pub async fn add_key(&self, user_id: &str, request: &CreateSshKeyRequest) -> Result<SshKey, String> {
+        let public_key = parse_public_key(&request.key)?;
+
+        let title = request
+            .title
+            .as_deref()
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .or(public_key.comment.as_deref())
+            .ok_or("A title is required for keys without a comment")?;
+
+        if title.len() > 255 {
+            return Err("Title must be at most 255 characters".to_string());
+        }
+
+        // A key identifies exactly one user, across all accounts
+        let existing = sqlx::query!("SELECT id FROM ssh_keys WHERE fingerprint = ?", public_key.fingerprint)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        if existing.is_some() {
+            return Err("Key is already in use".to_string());
+        }
+
+        let key_id = format!("key_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO ssh_keys (id, user_id, title, key_type, key_data, fingerprint, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, NOW())
+            "#,
+            key_id, user_id, title, public_key.key_type, public_key.key_data, public_key.fingerprint
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_key(user_id, &key_id).await
+    }
+
+    pub async fn delete_key(&self, user_id: &str, key_id: &str) -> Result<(), String> {
+        let result = sqlx::query!("DELETE FROM ssh_keys WHERE id = ? AND user_id = ?", key_id, user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("SSH key not found".to_string());
+        }
+
+        Ok(())
+    }
+
+    // Resolves the owner of a key offered to the SSH server as (user_id, username), recording the use
+    pub async fn authenticate(&self, fingerprint: &str) -> Result<Option<(String, String)>, String> {
+        let key = sqlx::query!(
+            r#"
+            SELECT k.id, k.user_id, u.username
+            FROM ssh_keys k
+            INNER JOIN users u ON k.user_id = u.id
+            WHERE k.fingerprint = ?
+            "#,
+            fingerprint
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let key = match key {
+            Some(key) => key,
+            None => return Ok(None),
+        };
+
+        sqlx::query!("UPDATE ssh_keys SET last_used = NOW() WHERE id = ?", key.id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(Some((key.user_id, key.username)))
+    }
+}
@@ -0,0 +1,418 @@
+use crate::models::{Team, TeamMember, TeamRepository, TeamRole, Permission, CreateTeamRequest, UpdateTeamRequest};
+use sqlx::MySqlPool;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct TeamService {
+    pool: MySqlPool,
+}
+
+// URL-safe team identifier derived from its name, e.g. "Core Devs" -> "core-devs"
+fn slugify(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+// True when candidate is team_id itself or sits somewhere below it, given each team's parent
+fn is_descendant_or_self(parents: &HashMap<String, Option<String>>, candidate: &str, team_id: &str) -> bool {
+    let mut current = Some(candidate);
+    let mut seen = HashSet::new();
+
+    while let Some(id) = current {
+        if id == team_id {
+            return true;
+        }
+        // Stops on a cycle that is already stored rather than walking it forever
+        if !seen.insert(id) {
+            return false;
+        }
+
+        current = parents.get(id).and_then(|parent| parent.as_deref());
+    }
+
+    false
+}
+
+impl TeamService {
+    pub fn new(pool: MySqlPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list_teams(&self, org_id: &str) -> Result<Vec<Team>, String> {
+        let teams = sqlx::query_as!(
+            Team,
+            r#"
+            SELECT id, organization_id, name, slug, description, parent_id, created_at, updated_at
+            FROM teams
+            WHERE organization_id = ?
+            ORDER BY name ASC
+            "#,
+            org_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(teams)
+    }
+
+    pub async fn list_child_teams(&self, team_id: &str) -> Result<Vec<Team>, String> {
+        let teams = sqlx::query_as!(
+            Team,
+            r#"
+            SELECT id, organization_id, name, slug, description, parent_id, created_at, updated_at
+            FROM teams
+            WHERE parent_id = ?
+            ORDER BY name ASC
+            "#,
+            team_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(teams)
+    }
+
+    pub async fn get_team(&self, org_id: &str, slug: &str) -> Result<Team, String> {
+        let team = sqlx::query_as!(
+            Team,
+            r#"
+            SELECT id, organization_id, name, slug, description, parent_id, created_at, updated_at
+            FROM teams
+            WHERE organization_id = ? AND slug = ?
+            "#,
+            org_id, slug
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        team.ok_or_else(|| "Team not found".to_string())
+    }
+
+    pub async fn create_team(&self, org_id: &str, request: &CreateTeamRequest) -> Result<Team, String> {
+        let slug = slugify(&request.name);
+        if slug.is_empty() {
+            return Err("Team name must contain letters or digits".to_string());
+        }
+
+        if self.get_team(org_id, &slug).await.is_ok() {
+            return Err(format!("A team named {} already exists", request.name));
+        }
+
+        let parent_id = match request.parent_team.as_deref() {
+            Some(parent) => Some(self.get_team(org_id, parent).await?.id),
+            None => None,
+        };
+
+        let team_id = format!("team_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+        sqlx::query!(
+            r#"
+            INSERT INTO teams (id, organization_id, name, slug, description, parent_id, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, NOW(), NOW())
+            "#,
+            team_id, org_id, request.name, slug, request.description, parent_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_team(org_id, &slug).await
+    }
+
+    pub async fn update_team(&self, team: &Team, request: &UpdateTeamRequest) -> Result<Team, String> {
+        let slug = match request.name.as_deref() {
+            Some(name) => {
+                let slug = slugify(name);
+                if slug.is_empty() {
+                    return Err("Team name must contain letters or digits".to_string());
+                }
+                if slug != team.slug && self.get_team(&team.organization_id, &slug).await.is_ok() {
+                    return Err(format!("A team named {} already exists", name));
+                }
+                slug
+            }
+            None => team.slug.clone(),
+        };
+
+        let parent_id = match request.parent_team.as_deref() {
+            Some("") => None,
+            Some(parent) => {
+                let parent = self.get_team(&team.organization_id, parent).await?;
+                let parents = self.team_parents(&team.organization_id).await?;
+                if is_descendant_or_self(&parents, &parent.id, &team.id) {
+                    return Err("A team cannot be nested under itself or one of its child teams".to_string());
+                }
+                Some(parent.id)
+            }
+            None => team.parent_id.clone(),
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE teams
+            SET
+                name = COALESCE(?, name),
+                slug = ?,
+                description = COALESCE(?, description),
+                parent_id = ?,
+                updated_at = NOW()
+            WHERE id = ?
+            "#,
+            request.name, slug, request.description, parent_id, team.id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_team(&team.organization_id, &slug).await
+    }
+
+    // Every team in the organization mapped to its parent, for walking up the hierarchy
+    async fn team_parents(&self, org_id: &str) -> Result<HashMap<String, Option<String>>, String> {
+        let teams = sqlx::query!("SELECT id, parent_id FROM teams WHERE organization_id = ?", org_id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(teams.into_iter().map(|team| (team.id, team.parent_id)).collect())
+    }
+
+    // Child teams move up to the deleted team's parent instead of losing their inherited access
+    pub async fn delete_team(&self, team: &Team) -> Result<(), String> {
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        sqlx::query!("UPDATE teams SET parent_id = ? WHERE parent_id = ?", team.parent_id, team.id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!("DELETE FROM teams WHERE id = ?", team.id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn list_members(&self, team_id: &str) -> Result<Vec<TeamMember>, String> {
+        let members = sqlx::query_as!(
+            TeamMember,
+            r#"
+            SELECT m.user_id, u.username, m.role, m.created_at
+            FROM team_members m
+            INNER JOIN users u ON m.user_id = u.id
+            WHERE m.team_id = ?
+            ORDER BY u.username ASC
+            "#,
+            team_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(members)
+    }
+
+    // None when the user is not a direct member of the team
+    pub async fn get_member_role(&self, team_id: &str, user_id: &str) -> Result<Option<TeamRole>, String> {
+        let member = sqlx::query!(
+            "SELECT role FROM team_members WHERE team_id = ? AND user_id = ?",
+            team_id, user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(member.map(|member| match member.role.as_str() {
+            "maintainer" => TeamRole::Maintainer,
+            _ => TeamRole::Member,
+        }))
+    }
+
+    pub async fn set_member(&self, team_id: &str, user_id: &str, role: TeamRole) -> Result<(), String> {
+        sqlx::query!(
+            r#"
+            INSERT INTO team_members (team_id, user_id, role, created_at)
+            VALUES (?, ?, ?, NOW())
+            ON DUPLICATE KEY UPDATE role = VALUES(role)
+            "#,
+            team_id, user_id, role.as_str()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn remove_member(&self, team_id: &str, user_id: &str) -> Result<(), String> {
+        let result = sqlx::query!(
+            "DELETE FROM team_members WHERE team_id = ? AND user_id = ?",
+            team_id, user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Membership not found".to_string());
+        }
+
+        Ok(())
+    }
+
+    // Users leaving an organization lose their team memberships with it
+    pub async fn remove_member_from_organization(&self, org_id: &str, user_id: &str) -> Result<(), String> {
+        sqlx::query!(
+            r#"
+            DELETE m FROM team_members m
+            INNER JOIN teams t ON m.team_id = t.id
+            WHERE t.organization_id = ? AND m.user_id = ?
+            "#,
+            org_id, user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn list_repositories(&self, team_id: &str) -> Result<Vec<TeamRepository>, String> {
+        let repos = sqlx::query_as!(
+            TeamRepository,
+            r#"
+            SELECT tr.repository_id, r.name, tr.permission
+            FROM team_repositories tr
+            INNER JOIN repositories r ON tr.repository_id = r.id
+            WHERE tr.team_id = ?
+            ORDER BY r.name ASC
+            "#,
+            team_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(repos)
+    }
+
+    pub async fn set_repository_permission(&self, team_id: &str, repo_id: &str, permission: Permission) -> Result<(), String> {
+        sqlx::query!(
+            r#"
+            INSERT INTO team_repositories (team_id, repository_id, permission, created_at)
+            VALUES (?, ?, ?, NOW())
+            ON DUPLICATE KEY UPDATE permission = VALUES(permission)
+            "#,
+            team_id, repo_id, permission.as_str()
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    pub async fn remove_repository(&self, team_id: &str, repo_id: &str) -> Result<(), String> {
+        let result = sqlx::query!(
+            "DELETE FROM team_repositories WHERE team_id = ? AND repository_id = ?",
+            team_id, repo_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Repository is not granted to this team".to_string());
+        }
+
+        Ok(())
+    }
+
+    // Highest grant on the repository across the user's teams and all of their ancestors.
+    // Teams of an org that requires 2FA only count for members who have it enabled
+    pub async fn team_permission(&self, user_id: &str, repo_id: &str) -> Result<Option<Permission>, String> {
+        let grants = sqlx::query!(
+            r#"
+            WITH RECURSIVE user_teams (id, parent_id) AS (
+                SELECT t.id, t.parent_id
+                FROM teams t
+                INNER JOIN team_members m ON m.team_id = t.id
+                INNER JOIN organizations o ON o.id = t.organization_id
+                INNER JOIN users u ON u.id = m.user_id
+                WHERE m.user_id = ?
+                    AND (o.two_factor_required = 0 OR u.two_factor_enabled = 1)
+                UNION
+                SELECT p.id, p.parent_id
+                FROM teams p
+                INNER JOIN user_teams ut ON ut.parent_id = p.id
+            )
+            SELECT tr.permission
+            FROM team_repositories tr
+            INNER JOIN user_teams ut ON tr.team_id = ut.id
+            WHERE tr.repository_id = ?
+            "#,
+            user_id, repo_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(grants.iter().filter_map(|grant| Permission::parse(&grant.permission)).max())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slugs_keep_only_lowercase_words() {
+        assert_eq!(slugify("Core Devs"), "core-devs");
+        assert_eq!(slugify("  Front-end / UI  "), "front-end-ui");
+        assert_eq!(slugify("Ops_2024!"), "ops-2024");
+        assert_eq!(slugify("Équipe"), "quipe");
+        assert_eq!(slugify("***"), "");
+    }
+
+    fn parents(edges: &[(&str, Option<&str>)]) -> HashMap<String, Option<String>> {
+        edges.iter().map(|(id, parent)| (id.to_string(), parent.map(str::to_string))).collect()
+    }
+
+    #[test]
+    fn teams_cannot_nest_under_themselves_or_their_children() {
+        // engineering > backend > databases, and design on its own
+        let parents = parents(&[
+            ("engineering", None),
+            ("backend", Some("engineering")),
+            ("databases", Some("backend")),
+            ("design", None),
+        ]);
+
+        assert!(is_descendant_or_self(&parents, "engineering", "engineering"));
+        assert!(is_descendant_or_self(&parents, "databases", "engineering"));
+        assert!(is_descendant_or_self(&parents, "backend", "engineering"));
+        assert!(!is_descendant_or_self(&parents, "engineering", "backend"));
+        assert!(!is_descendant_or_self(&parents, "design", "engineering"));
+        assert!(!is_descendant_or_self(&parents, "unknown", "engineering"));
+    }
+
+    #[test]
+    fn stored_cycles_end_the_walk() {
+        let parents = parents(&[("a", Some("b")), ("b", Some("a")), ("c", None)]);
+
+        assert!(!is_descendant_or_self(&parents, "a", "c"));
+        assert!(is_descendant_or_self(&parents, "a", "b"));
+    }
+}
@@ -0,0 +1,349 @@
+use crate::models::{RecoveryCodes, TwoFactorEnrollment, TwoFactorStatus};
+use crate::services::access_token_service::{hash_token, to_hex};
+use crate::utils::totp;
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use qrcode::render::svg;
+use qrcode::QrCode;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+const ISSUER: &str = "DevIT";
+const RECOVERY_CODE_COUNT: usize = 10;
+
+// Partial tokens only prove the password step; they cannot be used as a bearer token
+const LOGIN_CHALLENGE_AUDIENCE: &str = "two-factor-login";
+const LOGIN_CHALLENGE_TTL_MINUTES: i64 = 5;
+
+// Consecutive wrong codes before verification is paused
+const MAX_FAILED_ATTEMPTS: i32 = 5;
+const LOCKOUT_MINUTES: i64 = 15;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LoginChallengeClaims {
+    sub: String,
+    aud: String,
+    exp: usize,
+    iat: usize,
+}
+
+fn generate_recovery_codes() -> Result<Vec<String>, String> {
+    let rng = SystemRandom::new();
+
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 5];
+            rng.fill(&mut bytes)
+                .map_err(|_| "Failed to generate recovery codes".to_string())?;
+            let code = to_hex(&bytes);
+            Ok(format!("{}-{}", &code[..5], &code[5..]))
+        })
+        .collect()
+}
+
+// Recovery codes are accepted with or without the dash and in any case
+fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+
+    hash_token(&normalized)
+}
+
+#[derive(Clone)]
+pub struct TwoFactorService {
+    pool: MySqlPool,
+    token_secret: String,
+}
+
+impl TwoFactorService {
+    pub fn new(pool: MySqlPool, token_secret: String) -> Self {
+        Self { pool, token_secret }
+    }
+
+    pub async fn is_enabled(&self, user_id: &str) -> Result<bool, String> {
+        let user = sqlx::query!(
+            r#"SELECT two_factor_enabled as "two_factor_enabled: bool" FROM users WHERE id = ?"#,
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(user.is_some_and(|user| user.two_factor_enabled))
+    }
+
+    pub async fn get_status(&self, user_id: &str) -> Result<TwoFactorStatus, String> {
+        let remaining = sqlx::query!(
+            "SELECT COUNT(*) as count FROM two_factor_recovery_codes WHERE user_id = ? AND used_at IS NULL",
+            user_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(TwoFactorStatus {
+            enabled: self.is_enabled(user_id).await?,
+            recovery_codes_remaining: remaining.count,
+        })
+    }
+
+    // Starting again before confirming simply replaces the pending secret
+    pub async fn begin_enrollment(&self, user_id: &str, username: &str) -> Result<TwoFactorEnrollment, String> {
+        if self.is_enabled(user_id).await? {
+            return Err("Two-factor authentication is already enabled".to_string());
+        }
+
+        let secret = totp::generate_secret()?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO two_factor_secrets (user_id, secret, created_at)
+            VALUES (?, ?, NOW())
+            ON DUPLICATE KEY UPDATE secret = VALUES(secret), last_used_step = NULL,
+                failed_attempts = 0, locked_until = NULL, created_at = NOW()
+            "#,
+            user_id, secret
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let otpauth_uri = totp::otpauth_uri(ISSUER, username, &secret);
+        let qr_code_svg = QrCode::new(otpauth_uri.as_bytes())
+            .map_err(|e| format!("Failed to render QR code: {}", e))?
+            .render::<svg::Color>()
+            .min_dimensions(200, 200)
+            .build();
+
+        Ok(TwoFactorEnrollment { secret, otpauth_uri, qr_code_svg })
+    }
+
+    pub async fn confirm_enrollment(&self, user_id: &str, code: &str) -> Result<RecoveryCodes, String> {
+        if self.is_enabled(user_id).await? {
+            return Err("Two-factor authentication is already enabled".to_string());
+        }
+
+        self.verify(user_id, code, false).await?;
+
+        sqlx::query!("UPDATE users SET two_factor_enabled = 1 WHERE id = ?", user_id)
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        self.replace_recovery_codes(user_id).await
+    }
+
+    pub async fn disable(&self, user_id: &str, code: &str) -> Result<(), String> {
+        if !self.is_enabled(user_id).await? {
+            return Err("Two-factor authentication is not enabled".to_string());
+        }
+
+        let requiring = self.requiring_organizations(user_id).await?;
+        if !requiring.is_empty() {
+            return Err(format!("Two-factor authentication is required by {}", requiring.join(", ")));
+        }
+
+        self.verify(user_id, code, true).await?;
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        sqlx::query!("UPDATE users SET two_factor_enabled = 0 WHERE id = ?", user_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!("DELETE FROM two_factor_secrets WHERE user_id = ?", user_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        sqlx::query!("DELETE FROM two_factor_recovery_codes WHERE user_id = ?", user_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        Ok(())
+    }
+
+    // Needs a code from the app, since a recovery code may be what leaked
+    pub async fn regenerate_recovery_codes(&self, user_id: &str, code: &str) -> Result<RecoveryCodes, String> {
+        if !self.is_enabled(user_id).await? {
+            return Err("Two-factor authentication is not enabled".to_string());
+        }
+
+        self.verify(user_id, code, false).await?;
+        self.replace_recovery_codes(user_id).await
+    }
+
+    async fn replace_recovery_codes(&self, user_id: &str) -> Result<RecoveryCodes, String> {
+        let codes = generate_recovery_codes()?;
+
+        let mut transaction = self.pool.begin().await
+            .map_err(|e| format!("Transaction error: {}", e))?;
+
+        sqlx::query!("DELETE FROM two_factor_recovery_codes WHERE user_id = ?", user_id)
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        for code in &codes {
+            let code_id = format!("rc_{}", Uuid::new_v4().to_string().replace("-", ""));
+            let code_hash = hash_recovery_code(code);
+
+            sqlx::query!(
+                "INSERT INTO two_factor_recovery_codes (id, user_id, code_hash, created_at) VALUES (?, ?, ?, NOW())",
+                code_id, user_id, code_hash
+            )
+            .execute(&mut *transaction)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+        }
+
+        transaction.commit().await
+            .map_err(|e| format!("Transaction commit error: {}", e))?;
+
+        Ok(RecoveryCodes { recovery_codes: codes })
+    }
+
+    // Names of the user's organizations that require two-factor authentication
+    pub async fn requiring_organizations(&self, user_id: &str) -> Result<Vec<String>, String> {
+        let orgs = sqlx::query!(
+            r#"
+            SELECT o.name
+            FROM organizations o
+            INNER JOIN organization_members m ON m.organization_id = o.id
+            WHERE m.user_id = ? AND o.two_factor_required = 1
+            ORDER BY o.name ASC
+            "#,
+            user_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(orgs.into_iter().map(|org| org.name).collect())
+    }
+
+    pub fn create_login_challenge(&self, user_id: &str) -> Result<String, String> {
+        let now = Utc::now();
+        let claims = LoginChallengeClaims {
+            sub: user_id.to_string(),
+            aud: LOGIN_CHALLENGE_AUDIENCE.to_string(),
+            exp: (now + Duration::minutes(LOGIN_CHALLENGE_TTL_MINUTES)).timestamp() as usize,
+            iat: now.timestamp() as usize,
+        };
+
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(self.token_secret.as_ref())
+        )
+        .map_err(|e| format!("Token generation error: {}", e))
+    }
+
+    /// Completes a login started with a password, returning the user id once the code checks out.
+    pub async fn complete_login(&self, challenge: &str, code: &str) -> Result<String, String> {
+        let mut validation = Validation::default();
+        validation.set_audience(&[LOGIN_CHALLENGE_AUDIENCE]);
+
+        let claims = decode::<LoginChallengeClaims>(
+            challenge,
+            &DecodingKey::from_secret(self.token_secret.as_ref()),
+            &validation
+        )
+        .map(|data| data.claims)
+        .map_err(|_| "Invalid or expired two-factor token".to_string())?;
+
+        self.verify(&claims.sub, code, true).await?;
+        Ok(claims.sub)
+    }
+
+    // Accepts a current TOTP code (each step only once) or, when allowed, an unused recovery code
+    async fn verify(&self, user_id: &str, code: &str, allow_recovery: bool) -> Result<(), String> {
+        let credentials = sqlx::query!(
+            "SELECT secret, locked_until FROM two_factor_secrets WHERE user_id = ?",
+            user_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or("Two-factor authentication is not set up".to_string())?;
+
+        if credentials.locked_until.is_some_and(|until| until > Utc::now()) {
+            return Err("Too many failed attempts; try again later".to_string());
+        }
+
+        let accepted = match totp::verify_code(&credentials.secret, code, Utc::now().timestamp() as u64) {
+            Some(step) => self.claim_step(user_id, step as i64).await?,
+            None if allow_recovery => self.use_recovery_code(user_id, code).await?,
+            None => false,
+        };
+
+        if !accepted {
+            // locked_until is assigned first so it sees the count before this failure
+            sqlx::query!(
+                r#"
+                UPDATE two_factor_secrets
+                SET locked_until = IF(failed_attempts + 1 >= ?, NOW() + INTERVAL ? MINUTE, locked_until),
+                    failed_attempts = failed_attempts + 1
+                WHERE user_id = ?
+                "#,
+                MAX_FAILED_ATTEMPTS, LOCKOUT_MINUTES, user_id
+            )
+            .execute(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+            return Err("Invalid two-factor code".to_string());
+        }
+
+        sqlx::query!(
+            "UPDATE two_factor_secrets SET failed_attempts = 0, locked_until = NULL WHERE user_id = ?",
+            user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    // Records a TOTP step as used. False when it or a later step was already used, which
+    // the conditional write decides even for two requests racing with the same code
+    async fn claim_step(&self, user_id: &str, step: i64) -> Result<bool, String> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE two_factor_secrets
+            SET last_used_step = ?
+            WHERE user_id = ? AND (last_used_step IS NULL OR last_used_step < ?)
+            "#,
+            step, user_id, step
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    async fn use_recovery_code(&self, user_id: &str, code: &str) -> Result<bool, String> {
+        let result = sqlx::query!(
+            "UPDATE two_factor_recovery_codes SET used_at = NOW() WHERE user_id = ? AND code_hash = ? AND used_at IS NULL",
+            user_id, hash_recovery_code(code)
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
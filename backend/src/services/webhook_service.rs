@@ -0,0 +1,981 @@
+use crate::models::{
+    CreateWebhookRequest, DeliveryStatus, Repository, UpdateWebhookRequest, Webhook, WebhookDelivery,
+    WebhookDeliveryRequest, WebhookDeliveryResponse, WebhookDeliverySummary, WebhookEvent,
+};
+use crate::services::access_token_service::to_hex;
+use crate::services::GitService;
+use chrono::{DateTime, Utc};
+use futures_util::future::join_all;
+use ring::hmac;
+use serde_json::{json, Value};
+use sqlx::MySqlPool;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+const MAX_WEBHOOKS: i64 = 20; // Per repository or organization
+const MAX_ATTEMPTS: i32 = 5;
+const RETRY_BASE_SECONDS: i64 = 30; // Attempts are 30s, 2m, 8m and 32m apart
+const DELIVERY_TIMEOUT_SECONDS: u64 = 10;
+// A claimed delivery is not picked up again for this long, so an attempt cut short by a restart is retried
+const DELIVERY_LEASE_SECONDS: i64 = 60;
+const RETRY_POLL_SECONDS: u64 = 15;
+const MAX_RESPONSE_BODY_BYTES: usize = 64 * 1024;
+const MAX_PUSH_COMMITS: usize = 20;
+
+pub const ZERO_SHA: &str = "0000000000000000000000000000000000000000";
+
+/// Whose webhooks an operation applies to.
+pub enum WebhookOwner {
+    Repository(String),
+    Organization(String),
+}
+
+impl WebhookOwner {
+    fn ids(&self) -> (Option<&str>, Option<&str>) {
+        match self {
+            WebhookOwner::Repository(id) => (Some(id.as_str()), None),
+            WebhookOwner::Organization(id) => (None, Some(id.as_str())),
+        }
+    }
+}
+
+// The value of X-DevIT-Signature-256: an HMAC-SHA256 of the raw body keyed with the hook's secret
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    format!("sha256={}", to_hex(hmac::sign(&key, body).as_ref()))
+}
+
+fn retry_delay(attempts: i32) -> i64 {
+    RETRY_BASE_SECONDS * 4i64.pow(attempts.saturating_sub(1) as u32)
+}
+
+// Loopback, private, link-local (including the 169.254.169.254 metadata service), unique local
+// and carrier-grade NAT addresses, none of which a webhook may be sent to
+fn is_internal_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_internal_address(IpAddr::V4(mapped));
+            }
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+        }
+    }
+}
+
+fn validate_url(url: &str) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|_| "Invalid webhook URL".to_string())?;
+
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() || url.len() > 500 {
+        return Err("Invalid webhook URL".to_string());
+    }
+
+    Ok(parsed)
+}
+
+/// Resolves webhook hosts for the HTTP client, dropping internal addresses so a host that passed
+/// validation cannot be re-pointed at the internal network before the connection is made.
+/// Hosts in WEBHOOK_ALLOWED_HOSTS resolve to every address.
+struct WebhookResolver {
+    allowed_hosts: Arc<Vec<String>>,
+}
+
+impl WebhookResolver {
+    fn is_allowed(&self, host: &str) -> bool {
+        self.allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+
+    async fn lookup(&self, host: &str) -> Result<Vec<SocketAddr>, String> {
+        let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, 0))
+            .await
+            .map_err(|_| format!("Could not resolve {}", host))?
+            .collect();
+
+        if self.is_allowed(host) {
+            return Ok(addrs);
+        }
+
+        let public: Vec<SocketAddr> = addrs.into_iter().filter(|addr| !is_internal_address(addr.ip())).collect();
+        if public.is_empty() {
+            return Err(format!("{} resolves to an internal address", host));
+        }
+
+        Ok(public)
+    }
+
+    // Hosts written as IP addresses never reach the resolver, so they are checked here
+    async fn check_url(&self, url: &reqwest::Url) -> Result<(), String> {
+        let host = url.host_str().ok_or_else(|| "Invalid webhook URL".to_string())?;
+
+        // IPv6 hosts keep their brackets in URLs
+        let ip: IpAddr = match host.trim_start_matches('[').trim_end_matches(']').parse() {
+            Ok(ip) => ip,
+            Err(_) => return self.lookup(host).await.map(|_| ()),
+        };
+
+        if is_internal_address(ip) && !self.is_allowed(&ip.to_string()) {
+            return Err(format!("{} is an internal address", ip));
+        }
+
+        Ok(())
+    }
+}
+
+impl reqwest::dns::Resolve for WebhookResolver {
+    fn resolve(&self, name: hyper::client::connect::dns::Name) -> reqwest::dns::Resolving {
+        let resolver = WebhookResolver { allowed_hosts: self.allowed_hosts.clone() };
+
+        Box::pin(async move {
+            let addrs = resolver.lookup(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+fn event_list(events: &[WebhookEvent]) -> Result<String, String> {
+    let mut names: Vec<&str> = Vec::new();
+    for event in events {
+        if *event == WebhookEvent::Ping {
+            return Err("Webhooks cannot subscribe to ping".to_string());
+        }
+        if !names.contains(&event.as_str()) {
+            names.push(event.as_str());
+        }
+    }
+
+    if names.is_empty() {
+        return Err("At least one event is required".to_string());
+    }
+
+    Ok(names.join(","))
+}
+
+fn parse_headers(headers: &str) -> HashMap<String, String> {
+    serde_json::from_str(headers).unwrap_or_default()
+}
+
+struct WebhookRow {
+    id: String,
+    repository_id: Option<String>,
+    organization_id: Option<String>,
+    url: String,
+    secret: Option<String>,
+    events: String,
+    active: bool,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookRow {
+    fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        self.events.split(',').any(|name| name == event.as_str())
+    }
+}
+
+impl From<WebhookRow> for Webhook {
+    fn from(row: WebhookRow) -> Self {
+        Self {
+            id: row.id,
+            repository_id: row.repository_id,
+            organization_id: row.organization_id,
+            url: row.url,
+            has_secret: row.secret.is_some_and(|secret| !secret.is_empty()),
+            events: row.events.split(',').filter_map(WebhookEvent::parse).collect(),
+            active: row.active,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+struct DeliveryRow {
+    id: String,
+    guid: String,
+    event: String,
+    action: Option<String>,
+    redelivery: bool,
+    status: String,
+    response_status: Option<i16>,
+    duration_ms: Option<i32>,
+    attempts: i32,
+    next_attempt_at: Option<DateTime<Utc>>,
+    created_at: Option<DateTime<Utc>>,
+    delivered_at: Option<DateTime<Utc>>,
+}
+
+impl From<DeliveryRow> for WebhookDeliverySummary {
+    fn from(row: DeliveryRow) -> Self {
+        Self {
+            id: row.id,
+            guid: row.guid,
+            event: row.event,
+            action: row.action,
+            redelivery: row.redelivery,
+            status: DeliveryStatus::parse(&row.status),
+            response_status: row.response_status.map(|status| status as u16),
+            duration_ms: row.duration_ms.map(|duration| duration as u32),
+            attempts: row.attempts as u32,
+            next_attempt_at: row.next_attempt_at,
+            created_at: row.created_at,
+            delivered_at: row.delivered_at,
+        }
+    }
+}
+
+// What came of a single POST to the hook's URL
+struct AttemptOutcome {
+    response_status: Option<u16>,
+    response_headers: Option<String>,
+    response_body: Option<String>,
+    error: Option<String>,
+}
+
+/// Outbound webhooks: signed JSON POSTs for repository activity, logged per delivery and
+/// retried with exponential backoff until the receiver answers with a 2xx status.
+/// Events are dispatched in the background, so callers never wait on a receiver.
+#[derive(Clone)]
+pub struct WebhookService {
+    pool: MySqlPool,
+    git: GitService,
+    http: reqwest::Client,
+    resolver: Arc<WebhookResolver>,
+}
+
+impl WebhookService {
+    pub fn new(pool: MySqlPool, git: GitService, allowed_hosts: Vec<String>) -> Self {
+        let resolver = Arc::new(WebhookResolver { allowed_hosts: Arc::new(allowed_hosts) });
+
+        Self {
+            pool,
+            git,
+            // No proxy, so connections go to the addresses the resolver checked
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(DELIVERY_TIMEOUT_SECONDS))
+                .redirect(reqwest::redirect::Policy::none())
+                .no_proxy()
+                .dns_resolver(resolver.clone())
+                .build()
+                .expect("Failed to build the webhook HTTP client"),
+            resolver,
+        }
+    }
+
+    // Rejects URLs that are malformed or point at the internal network
+    async fn check_url(&self, url: &str) -> Result<(), String> {
+        let parsed = validate_url(url)?;
+        self.resolver.check_url(&parsed).await
+    }
+
+    pub async fn list_webhooks(&self, owner: &WebhookOwner) -> Result<Vec<Webhook>, String> {
+        let (repository_id, organization_id) = owner.ids();
+
+        let hooks = sqlx::query_as!(
+            WebhookRow,
+            r#"
+            SELECT id, repository_id, organization_id, url, secret, events,
+                active as "active: bool", created_at, updated_at
+            FROM webhooks
+            WHERE repository_id = ? OR organization_id = ?
+            ORDER BY created_at
+            "#,
+            repository_id, organization_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(hooks.into_iter().map(Webhook::from).collect())
+    }
+
+    async fn find_webhook(&self, owner: &WebhookOwner, hook_id: &str) -> Result<WebhookRow, String> {
+        let (repository_id, organization_id) = owner.ids();
+
+        sqlx::query_as!(
+            WebhookRow,
+            r#"
+            SELECT id, repository_id, organization_id, url, secret, events,
+                active as "active: bool", created_at, updated_at
+            FROM webhooks
+            WHERE id = ? AND (repository_id = ? OR organization_id = ?)
+            "#,
+            hook_id, repository_id, organization_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Webhook not found".to_string())
+    }
+
+    pub async fn get_webhook(&self, owner: &WebhookOwner, hook_id: &str) -> Result<Webhook, String> {
+        Ok(self.find_webhook(owner, hook_id).await?.into())
+    }
+
+    /// Creates a hook and sends it a ping, the same way GitHub confirms a new hook.
+    pub async fn create_webhook(&self, owner: &WebhookOwner, user_id: &str, request: &CreateWebhookRequest) -> Result<Webhook, String> {
+        self.check_url(&request.url).await?;
+        let events = event_list(request.events.as_deref().unwrap_or(&[WebhookEvent::Push]))?;
+
+        let (repository_id, organization_id) = owner.ids();
+
+        let count = sqlx::query!(
+            r#"SELECT COUNT(*) as "count!: i64" FROM webhooks WHERE repository_id = ? OR organization_id = ?"#,
+            repository_id, organization_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .count;
+
+        if count >= MAX_WEBHOOKS {
+            return Err(format!("A maximum of {} webhooks is allowed", MAX_WEBHOOKS));
+        }
+
+        let hook_id = format!("hook_{}", Uuid::new_v4().to_string().replace("-", ""));
+        let secret = request.secret.as_deref().filter(|secret| !secret.is_empty());
+
+        sqlx::query!(
+            r#"
+            INSERT INTO webhooks (id, repository_id, organization_id, url, secret, events, active, created_by, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, NOW(), NOW())
+            "#,
+            hook_id, repository_id, organization_id, request.url, secret, events,
+            request.active.unwrap_or(true), user_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let hook = self.find_webhook(owner, &hook_id).await?;
+        if hook.active {
+            self.schedule_ping(&hook_id, user_id);
+        }
+
+        Ok(hook.into())
+    }
+
+    pub async fn update_webhook(&self, owner: &WebhookOwner, hook_id: &str, request: &UpdateWebhookRequest) -> Result<Webhook, String> {
+        self.find_webhook(owner, hook_id).await?;
+
+        if let Some(url) = &request.url {
+            self.check_url(url).await?;
+        }
+
+        let events = match &request.events {
+            Some(events) => Some(event_list(events)?),
+            None => None,
+        };
+
+        // An empty secret clears it, which COALESCE alone cannot express
+        let clear_secret = request.secret.as_deref() == Some("");
+        let secret = request.secret.as_deref().filter(|secret| !secret.is_empty());
+
+        sqlx::query!(
+            r#"
+            UPDATE webhooks
+            SET url = COALESCE(?, url),
+                secret = IF(?, NULL, COALESCE(?, secret)),
+                events = COALESCE(?, events),
+                active = COALESCE(?, active),
+                updated_at = NOW()
+            WHERE id = ?
+            "#,
+            request.url, clear_secret, secret, events, request.active, hook_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        self.get_webhook(owner, hook_id).await
+    }
+
+    pub async fn delete_webhook(&self, owner: &WebhookOwner, hook_id: &str) -> Result<(), String> {
+        let (repository_id, organization_id) = owner.ids();
+
+        let result = sqlx::query!(
+            "DELETE FROM webhooks WHERE id = ? AND (repository_id = ? OR organization_id = ?)",
+            hook_id, repository_id, organization_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if result.rows_affected() == 0 {
+            return Err("Webhook not found".to_string());
+        }
+
+        Ok(())
+    }
+
+    pub async fn list_deliveries(&self, owner: &WebhookOwner, hook_id: &str) -> Result<Vec<WebhookDeliverySummary>, String> {
+        self.find_webhook(owner, hook_id).await?;
+
+        let deliveries = sqlx::query_as!(
+            DeliveryRow,
+            r#"
+            SELECT id, guid, event, action, redelivery as "redelivery: bool", status,
+                response_status, duration_ms, attempts, next_attempt_at, created_at, delivered_at
+            FROM webhook_deliveries
+            WHERE webhook_id = ?
+            ORDER BY created_at DESC
+            LIMIT 100
+            "#,
+            hook_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(deliveries.into_iter().map(WebhookDeliverySummary::from).collect())
+    }
+
+    pub async fn get_delivery(&self, owner: &WebhookOwner, hook_id: &str, delivery_id: &str) -> Result<WebhookDelivery, String> {
+        let hook = self.find_webhook(owner, hook_id).await?;
+
+        let row = sqlx::query!(
+            r#"
+            SELECT id, guid, event, action, redelivery as "redelivery: bool", status,
+                response_status, duration_ms, attempts, next_attempt_at, created_at, delivered_at,
+                request_headers, request_body, response_headers, response_body, error
+            FROM webhook_deliveries
+            WHERE id = ? AND webhook_id = ?
+            "#,
+            delivery_id, hook_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Delivery not found".to_string())?;
+
+        let response = row.response_status.map(|_| WebhookDeliveryResponse {
+            headers: row.response_headers.as_deref().map(parse_headers).unwrap_or_default(),
+            body: row.response_body.clone().unwrap_or_default(),
+        });
+
+        Ok(WebhookDelivery {
+            url: hook.url,
+            error: row.error,
+            request: WebhookDeliveryRequest {
+                headers: parse_headers(&row.request_headers),
+                payload: serde_json::from_str(&row.request_body).unwrap_or(Value::Null),
+            },
+            response,
+            summary: WebhookDeliverySummary::from(DeliveryRow {
+                id: row.id,
+                guid: row.guid,
+                event: row.event,
+                action: row.action,
+                redelivery: row.redelivery,
+                status: row.status,
+                response_status: row.response_status,
+                duration_ms: row.duration_ms,
+                attempts: row.attempts,
+                next_attempt_at: row.next_attempt_at,
+                created_at: row.created_at,
+                delivered_at: row.delivered_at,
+            }),
+        })
+    }
+
+    /// Sends a past delivery's payload again as a new delivery, signed with the hook's current secret.
+    pub async fn redeliver(&self, owner: &WebhookOwner, hook_id: &str, delivery_id: &str) -> Result<WebhookDelivery, String> {
+        let hook = self.find_webhook(owner, hook_id).await?;
+
+        let original = sqlx::query!(
+            "SELECT guid, event, action, request_body FROM webhook_deliveries WHERE id = ? AND webhook_id = ?",
+            delivery_id, hook_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Delivery not found".to_string())?;
+
+        let redelivery_id = self
+            .queue_delivery(&hook, &original.event, original.action.as_deref(), &original.request_body, Some(&original.guid))
+            .await?;
+
+        self.attempt(&redelivery_id).await?;
+        self.get_delivery(owner, hook_id, &redelivery_id).await
+    }
+
+    /// Sends a ping to the hook right away and returns the delivery.
+    pub async fn ping(&self, owner: &WebhookOwner, hook_id: &str, user_id: &str) -> Result<WebhookDelivery, String> {
+        self.find_webhook(owner, hook_id).await?;
+
+        let delivery_id = self.send_ping(hook_id, user_id).await?;
+        self.get_delivery(owner, hook_id, &delivery_id).await
+    }
+
+    fn schedule_ping(&self, hook_id: &str, user_id: &str) {
+        let service = self.clone();
+        let hook_id = hook_id.to_string();
+        let user_id = user_id.to_string();
+
+        tokio::spawn(async move {
+            if let Err(err) = service.send_ping(&hook_id, &user_id).await {
+                log::error!("Failed to ping webhook {}: {}", hook_id, err);
+            }
+        });
+    }
+
+    async fn send_ping(&self, hook_id: &str, user_id: &str) -> Result<String, String> {
+        let hook = sqlx::query_as!(
+            WebhookRow,
+            r#"
+            SELECT id, repository_id, organization_id, url, secret, events,
+                active as "active: bool", created_at, updated_at
+            FROM webhooks
+            WHERE id = ?
+            "#,
+            hook_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?
+        .ok_or_else(|| "Webhook not found".to_string())?;
+
+        let payload = json!({
+            "zen": "Keep it logically awesome.",
+            "hook_id": hook.id,
+            "hook": {
+                "id": hook.id,
+                "url": hook.url,
+                "events": hook.events.split(',').collect::<Vec<_>>(),
+                "active": hook.active,
+            },
+        });
+        let body = self
+            .envelope(hook.repository_id.as_deref(), hook.organization_id.as_deref(), user_id, payload)
+            .await?;
+
+        let delivery_id = self.queue_delivery(&hook, WebhookEvent::Ping.as_str(), None, &body, None).await?;
+        self.attempt(&delivery_id).await?;
+
+        Ok(delivery_id)
+    }
+
+    /// Delivers an event on a repository to its active hooks and its organization's, in the background.
+    /// `payload` holds the event-specific fields; repository, organization and sender are added here.
+    pub fn dispatch(&self, repo: &Repository, sender_id: &str, event: WebhookEvent, payload: Value) {
+        let service = self.clone();
+        let repo = repo.clone();
+        let sender_id = sender_id.to_string();
+
+        tokio::spawn(async move {
+            if let Err(err) = service.deliver_event(&repo, &sender_id, event, payload).await {
+                log::error!("Failed to dispatch {} webhooks for {}: {}", event.as_str(), repo.id, err);
+            }
+        });
+    }
+
+    /// Sends a push event for every branch that moved between two snapshots of branch tips.
+    pub fn dispatch_push(&self, repo: &Repository, pusher_id: &str, before: HashMap<String, String>, after: HashMap<String, String>) {
+        let service = self.clone();
+        let repo = repo.clone();
+        let pusher_id = pusher_id.to_string();
+
+        tokio::spawn(async move {
+            let mut branches: Vec<&String> = after.keys().chain(before.keys()).collect();
+            branches.sort();
+            branches.dedup();
+
+            for branch in branches {
+                let old = before.get(branch);
+                let new = after.get(branch);
+                if old == new {
+                    continue;
+                }
+
+                let commits = match new {
+                    Some(new) => {
+                        let (repo_id, branch, old, new) = (repo.id.clone(), branch.clone(), old.cloned(), new.clone());
+                        service
+                            .git
+                            .blocking(move |git| git.pushed_commits(&repo_id, &branch, old.as_deref(), &new, MAX_PUSH_COMMITS))
+                            .await
+                            .unwrap_or_else(|err| {
+                                log::error!("Failed to list pushed commits for {}: {}", repo.id, err);
+                                Vec::new()
+                            })
+                    }
+                    None => Vec::new(),
+                };
+
+                let payload = json!({
+                    "ref": format!("refs/heads/{}", branch),
+                    "before": old.map(|sha| sha.as_str()).unwrap_or(ZERO_SHA),
+                    "after": new.map(|sha| sha.as_str()).unwrap_or(ZERO_SHA),
+                    "created": old.is_none(),
+                    "deleted": new.is_none(),
+                    "head_commit": commits.first(),
+                    "commits": commits,
+                });
+
+                if let Err(err) = service.deliver_event(&repo, &pusher_id, WebhookEvent::Push, payload).await {
+                    log::error!("Failed to dispatch push webhooks for {}: {}", repo.id, err);
+                }
+            }
+        });
+    }
+
+    async fn deliver_event(&self, repo: &Repository, sender_id: &str, event: WebhookEvent, payload: Value) -> Result<(), String> {
+        let hooks = sqlx::query_as!(
+            WebhookRow,
+            r#"
+            SELECT id, repository_id, organization_id, url, secret, events,
+                active as "active: bool", created_at, updated_at
+            FROM webhooks
+            WHERE active = TRUE AND (repository_id = ? OR organization_id = ?)
+            "#,
+            repo.id, repo.organization_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let hooks: Vec<WebhookRow> = hooks.into_iter().filter(|hook| hook.subscribes_to(event)).collect();
+        if hooks.is_empty() {
+            return Ok(());
+        }
+
+        let action = payload.get("action").and_then(|action| action.as_str()).map(|action| action.to_string());
+        let body = self
+            .envelope(Some(&repo.id), repo.organization_id.as_deref(), sender_id, payload)
+            .await?;
+
+        let mut delivery_ids = Vec::with_capacity(hooks.len());
+        for hook in &hooks {
+            delivery_ids.push(self.queue_delivery(hook, event.as_str(), action.as_deref(), &body, None).await?);
+        }
+
+        self.attempt_all(delivery_ids).await;
+        Ok(())
+    }
+
+    // Adds the repository, organization and sender objects every payload carries
+    async fn envelope(&self, repository_id: Option<&str>, organization_id: Option<&str>, sender_id: &str, payload: Value) -> Result<String, String> {
+        let mut body = match payload {
+            Value::Object(fields) => fields,
+            _ => serde_json::Map::new(),
+        };
+
+        if let Some(repository_id) = repository_id {
+            let repo = sqlx::query!(
+                r#"
+                SELECT r.id, r.name, r.description, r.is_private as "is_private: bool", r.default_branch,
+                    r.created_at, r.pushed_at, COALESCE(o.name, u.username) as "owner!: String"
+                FROM repositories r
+                JOIN users u ON u.id = r.owner_id
+                LEFT JOIN organizations o ON o.id = r.organization_id
+                WHERE r.id = ?
+                "#,
+                repository_id
+            )
+            .fetch_one(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+            body.insert("repository".to_string(), json!({
+                "id": repo.id,
+                "name": repo.name,
+                "full_name": format!("{}/{}", repo.owner, repo.name),
+                "owner": repo.owner,
+                "description": repo.description,
+                "private": repo.is_private,
+                "default_branch": repo.default_branch,
+                "created_at": repo.created_at,
+                "pushed_at": repo.pushed_at,
+            }));
+        }
+
+        if let Some(organization_id) = organization_id {
+            let org = sqlx::query!("SELECT id, name FROM organizations WHERE id = ?", organization_id)
+                .fetch_one(&self.pool)
+                .await
+                .map_err(|e| format!("Database error: {}", e))?;
+
+            body.insert("organization".to_string(), json!({ "id": org.id, "name": org.name }));
+        }
+
+        let sender = sqlx::query!("SELECT id, username, avatar_url FROM users WHERE id = ?", sender_id)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| format!("Database error: {}", e))?;
+
+        if let Some(sender) = sender {
+            body.insert("sender".to_string(), json!({
+                "id": sender.id,
+                "username": sender.username,
+                "avatar_url": sender.avatar_url,
+            }));
+        }
+
+        serde_json::to_string(&body).map_err(|e| format!("Serialization error: {}", e))
+    }
+
+    // Records a delivery with its signed request, ready for its first attempt
+    async fn queue_delivery(&self, hook: &WebhookRow, event: &str, action: Option<&str>, body: &str, redelivery_guid: Option<&str>) -> Result<String, String> {
+        let delivery_id = format!("whd_{}", Uuid::new_v4().to_string().replace("-", ""));
+        let guid = redelivery_guid.map(|guid| guid.to_string()).unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert("User-Agent".to_string(), "DevIT-Hookshot".to_string());
+        headers.insert("X-DevIT-Event".to_string(), event.to_string());
+        headers.insert("X-DevIT-Delivery".to_string(), guid.clone());
+        headers.insert("X-DevIT-Hook-ID".to_string(), hook.id.clone());
+        if let Some(secret) = hook.secret.as_deref().filter(|secret| !secret.is_empty()) {
+            headers.insert("X-DevIT-Signature-256".to_string(), sign_payload(secret, body.as_bytes()));
+        }
+        let headers = serde_json::to_string(&headers).map_err(|e| format!("Serialization error: {}", e))?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO webhook_deliveries (
+                id, webhook_id, guid, event, action, redelivery, request_headers, request_body,
+                attempts, status, next_attempt_at, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, 0, 'pending', NOW(), NOW())
+            "#,
+            delivery_id, hook.id, guid, event, action, redelivery_guid.is_some(), headers, body
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(delivery_id)
+    }
+
+    async fn attempt_all(&self, delivery_ids: Vec<String>) {
+        let attempts = delivery_ids.iter().map(|delivery_id| async move {
+            if let Err(err) = self.attempt(delivery_id).await {
+                log::error!("Failed to deliver webhook {}: {}", delivery_id, err);
+            }
+        });
+
+        join_all(attempts).await;
+    }
+
+    // Makes one attempt at a pending delivery that is due, unless another worker already claimed it
+    async fn attempt(&self, delivery_id: &str) -> Result<(), String> {
+        let claimed = sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET next_attempt_at = DATE_ADD(NOW(), INTERVAL ? SECOND)
+            WHERE id = ? AND status = 'pending' AND next_attempt_at <= NOW()
+            "#,
+            DELIVERY_LEASE_SECONDS, delivery_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        if claimed.rows_affected() == 0 {
+            return Ok(());
+        }
+
+        let delivery = sqlx::query!(
+            r#"
+            SELECT d.request_headers, d.request_body, d.attempts, w.url
+            FROM webhook_deliveries d
+            JOIN webhooks w ON w.id = d.webhook_id
+            WHERE d.id = ?
+            "#,
+            delivery_id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        let started = Instant::now();
+        let outcome = self.post(&delivery.url, &parse_headers(&delivery.request_headers), delivery.request_body).await;
+        let duration_ms = started.elapsed().as_millis().min(i32::MAX as u128) as i32;
+
+        let attempts = delivery.attempts + 1;
+        let delivered = outcome.response_status.is_some_and(|status| (200..300).contains(&status));
+        let (status, retry_in) = if delivered {
+            ("delivered", None)
+        } else if attempts >= MAX_ATTEMPTS {
+            ("failed", None)
+        } else {
+            ("pending", Some(retry_delay(attempts)))
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET response_status = ?, response_headers = ?, response_body = ?, error = ?, duration_ms = ?,
+                attempts = ?, status = ?, next_attempt_at = IF(? IS NULL, NULL, DATE_ADD(NOW(), INTERVAL ? SECOND)),
+                delivered_at = NOW()
+            WHERE id = ?
+            "#,
+            outcome.response_status, outcome.response_headers, outcome.response_body, outcome.error, duration_ms,
+            attempts, status, retry_in, retry_in, delivery_id
+        )
+        .execute(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(())
+    }
+
+    async fn post(&self, url: &str, headers: &HashMap<String, String>, body: String) -> AttemptOutcome {
+        // Checked again on every attempt, since DNS and the allowlist can change after the hook is saved
+        if let Err(error) = self.check_url(url).await {
+            return AttemptOutcome {
+                response_status: None,
+                response_headers: None,
+                response_body: None,
+                error: Some(error),
+            };
+        }
+
+        let mut request = self.http.post(url).body(body);
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                let error = if e.is_timeout() {
+                    format!("Timed out after {} seconds", DELIVERY_TIMEOUT_SECONDS)
+                } else {
+                    format!("Failed to connect: {}", e)
+                };
+
+                return AttemptOutcome {
+                    response_status: None,
+                    response_headers: None,
+                    response_body: None,
+                    error: Some(error.chars().take(500).collect()),
+                };
+            }
+        };
+
+        let response_status = response.status().as_u16();
+        let response_headers: HashMap<String, String> = response
+            .headers()
+            .iter()
+            .map(|(name, value)| (name.to_string(), String::from_utf8_lossy(value.as_bytes()).into_owned()))
+            .collect();
+
+        // Only the start of a large response is kept
+        let mut response = response;
+        let mut response_body = Vec::new();
+        while let Ok(Some(chunk)) = response.chunk().await {
+            response_body.extend_from_slice(&chunk);
+            if response_body.len() >= MAX_RESPONSE_BODY_BYTES {
+                response_body.truncate(MAX_RESPONSE_BODY_BYTES);
+                break;
+            }
+        }
+
+        AttemptOutcome {
+            response_status: Some(response_status),
+            response_headers: serde_json::to_string(&response_headers).ok(),
+            response_body: Some(String::from_utf8_lossy(&response_body).into_owned()),
+            error: None,
+        }
+    }
+
+    /// Retries failed deliveries once their backoff has passed, and picks up deliveries
+    /// whose attempt was interrupted. Runs for the life of the server.
+    pub fn spawn_retry_worker(&self) {
+        let service = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(RETRY_POLL_SECONDS));
+            loop {
+                interval.tick().await;
+
+                match service.due_deliveries().await {
+                    Ok(delivery_ids) => service.attempt_all(delivery_ids).await,
+                    Err(err) => log::error!("Failed to load due webhook deliveries: {}", err),
+                }
+            }
+        });
+    }
+
+    async fn due_deliveries(&self) -> Result<Vec<String>, String> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id FROM webhook_deliveries
+            WHERE status = 'pending' AND next_attempt_at <= NOW()
+            ORDER BY next_attempt_at
+            LIMIT 100
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| format!("Database error: {}", e))?;
+
+        Ok(rows.into_iter().map(|row| row.id).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn internal(ip: &str) -> bool {
+        is_internal_address(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn internal_addresses_are_rejected() {
+        for ip in ["127.0.0.1", "10.1.2.3", "172.16.0.1", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1"] {
+            assert!(internal(ip), "{} should be internal", ip);
+        }
+    }
+
+    #[test]
+    fn public_addresses_are_allowed() {
+        for ip in ["8.8.8.8", "172.32.0.1", "100.128.0.1", "2606:4700::1111"] {
+            assert!(!internal(ip), "{} should be public", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn allowlisted_hosts_may_be_internal() {
+        let resolver = WebhookResolver { allowed_hosts: Arc::new(vec!["127.0.0.1".to_string()]) };
+
+        assert!(resolver.check_url(&validate_url("http://127.0.0.1:8080/hook").unwrap()).await.is_ok());
+        assert!(resolver.check_url(&validate_url("http://10.0.0.1/hook").unwrap()).await.is_err());
+        assert!(resolver.check_url(&validate_url("http://[::1]/hook").unwrap()).await.is_err());
+    }
+
+    #[test]
+    fn payloads_are_signed_with_hmac_sha256() {
+        assert_eq!(
+            sign_payload("It's a Secret to Everybody", b"Hello, World!"),
+            "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17"
+        );
+        assert_eq!(
+            sign_payload("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn signatures_depend_on_the_secret() {
+        assert_ne!(sign_payload("one", b"{}"), sign_payload("two", b"{}"));
+    }
+}
@@ -0,0 +1,526 @@
+// Git over SSH: clients authenticate with a registered public key and may only run
+// git-upload-pack or git-receive-pack against repositories they have access to
+
+use async_trait::async_trait;
+use russh::server::{Auth, Handle, Msg, Server, Session};
+use russh::{Channel, ChannelId, CryptoVec};
+use russh_keys::key::{KeyPair, PublicKey};
+use russh_keys::PublicKeyBase64;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, ChildStdin};
+use crate::handlers::git_http::{check_push, updated_branches};
+use crate::models::{Permission, Repository};
+use crate::services::{ActivityService, BranchProtectionService, GitService, PermissionService, PullRequestService, RepositoryService, SshKeyService};
+use crate::services::git_service::{parse_push_request, push_rejection_report, GitRpc, PushRequest, StatelessRpc};
+use crate::utils::ssh::fingerprint;
+
+#[derive(Clone)]
+pub struct GitSshServer {
+    ssh_keys: SshKeyService,
+    repos: RepositoryService,
+    git: GitService,
+    permissions: PermissionService,
+    protections: BranchProtectionService,
+    pull_requests: PullRequestService,
+    activity: ActivityService,
+}
+
+impl GitSshServer {
+    pub fn new(
+        ssh_keys: SshKeyService,
+        repos: RepositoryService,
+        git: GitService,
+        permissions: PermissionService,
+        protections: BranchProtectionService,
+        pull_requests: PullRequestService,
+        activity: ActivityService,
+    ) -> Self {
+        Self { ssh_keys, repos, git, permissions, protections, pull_requests, activity }
+    }
+}
+
+impl Server for GitSshServer {
+    type Handler = GitSshSession;
+
+    fn new_client(&mut self, _peer_addr: Option<SocketAddr>) -> GitSshSession {
+        GitSshSession {
+            server: self.clone(),
+            user: None,
+            protocol: None,
+            channels: HashMap::new(),
+        }
+    }
+}
+
+/// Listens for git SSH connections until the process exits.
+pub async fn run(mut server: GitSshServer, host: &str, port: u16, host_key_path: &str) -> Result<(), String> {
+    let config = russh::server::Config {
+        keys: vec![load_or_create_host_key(Path::new(host_key_path))?],
+        methods: russh::MethodSet::PUBLICKEY,
+        auth_rejection_time: Duration::from_secs(1),
+        auth_rejection_time_initial: Some(Duration::from_secs(0)),
+        inactivity_timeout: Some(Duration::from_secs(600)),
+        ..Default::default()
+    };
+
+    log::info!("Starting git SSH server on {}:{}", host, port);
+
+    server
+        .run_on_address(Arc::new(config), (host, port))
+        .await
+        .map_err(|e| format!("SSH server error: {}", e))
+}
+
+// The host key is generated on first start and reused so clients' known_hosts stay valid
+fn load_or_create_host_key(path: &Path) -> Result<KeyPair, String> {
+    if path.exists() {
+        return russh_keys::load_secret_key(path, None)
+            .map_err(|e| format!("Failed to load SSH host key: {}", e));
+    }
+
+    let key = KeyPair::generate_ed25519().ok_or("Failed to generate SSH host key")?;
+
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create SSH host key directory: {}", e))?;
+    }
+
+    // The private key must only be readable by the server's own user
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| format!("Failed to write SSH host key: {}", e))?;
+    russh_keys::encode_pkcs8_pem(&key, file).map_err(|e| format!("Failed to write SSH host key: {}", e))?;
+
+    Ok(key)
+}
+
+// Parses e.g. `git-upload-pack '/owner/repo.git'` into the service and repository
+fn parse_git_command(command: &str) -> Option<(GitRpc, String, String)> {
+    let (service, path) = command.trim().split_once(' ')?;
+    let rpc = GitRpc::from_service_name(service)?;
+
+    let path = path.trim().trim_matches('\'').trim_matches('"');
+    let path = path.trim_start_matches('/').trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    let (owner, name) = path.split_once('/')?;
+    if owner.is_empty() || name.is_empty() || name.contains('/') {
+        return None;
+    }
+
+    Some((rpc, owner.to_string(), name.to_string()))
+}
+
+// A push whose commands are still arriving
+struct PendingPush {
+    repo: Repository,
+    user_id: String,
+    is_admin: bool,
+    input: Vec<u8>,
+}
+
+// A push that passed branch protection, with its pack being streamed into receive-pack
+struct RunningPush {
+    repo: Repository,
+    user_id: String,
+    heads_before: HashMap<String, String>,
+    process: StatelessRpc,
+    received: usize,
+}
+
+enum PushStart {
+    Running(Box<RunningPush>),
+    Refused(Vec<u8>), // receive-pack's response when branch protection refuses the push
+}
+
+enum ChannelState {
+    Idle,
+    UploadPack(ChildStdin),
+    // Receive-pack commands are run through the same checks as a smart HTTP push before git starts
+    ReceivePack(Box<PendingPush>),
+    Pushing(Box<RunningPush>),
+}
+
+pub struct GitSshSession {
+    server: GitSshServer,
+    user: Option<(String, String)>, // (user_id, username) of the authenticated key's owner
+    protocol: Option<String>,
+    channels: HashMap<ChannelId, ChannelState>,
+}
+
+impl GitSshSession {
+    async fn start_command(&mut self, channel: ChannelId, command: &str, session: &mut Session) -> Result<(), String> {
+        let (rpc, owner, name) = parse_git_command(command)
+            .ok_or("Only git-upload-pack and git-receive-pack are supported")?;
+        let (user_id, username) = self.user.clone().ok_or("Not authenticated")?;
+
+        let not_found = || format!("Repository {}/{} not found", owner, name);
+
+        let repo = self.server.repos.get_repository(&owner, &name).await.map_err(|_| not_found())?;
+
+        let permission = self
+            .server
+            .permissions
+            .effective_permission(Some(&user_id), &repo)
+            .await
+            .map_err(|err| {
+                log::error!("Failed to resolve permissions for {}/{}: {}", owner, name, err);
+                "Failed to check permissions".to_string()
+            })?
+            .ok_or_else(not_found)?;
+
+        match rpc {
+            GitRpc::UploadPack => {
+                let mut child = self.server.git.spawn_upload_pack(&repo.id, self.protocol.as_deref())?;
+                let stdin = child.stdin.take().ok_or("Failed to open git stdin")?;
+
+                self.channels.insert(channel, ChannelState::UploadPack(stdin));
+                tokio::spawn(forward_output(session.handle(), channel, child));
+            }
+            GitRpc::ReceivePack => {
+                if permission < Permission::Write {
+                    return Err(format!("Permission to {}/{} denied to {}", owner, name, username));
+                }
+
+                if repo.is_archived_bool() {
+                    return Err("Repository is archived".to_string());
+                }
+
+                let advertisement = self
+                    .server
+                    .git
+                    .ref_advertisement(&repo.id, rpc, self.protocol.as_deref())
+                    .await?;
+                session.data(channel, CryptoVec::from(advertisement));
+
+                self.channels.insert(channel, ChannelState::ReceivePack(Box::new(PendingPush {
+                    repo,
+                    user_id,
+                    is_admin: permission == Permission::Admin,
+                    input: Vec::new(),
+                })));
+            }
+        }
+
+        Ok(())
+    }
+
+    // Checks a push once its commands are in, then starts receive-pack with them
+    async fn start_push(&self, push: PendingPush, request: &PushRequest) -> Result<PushStart, String> {
+        let check = check_push(&push.repo, &push.user_id, push.is_admin, request, &self.server.protections)
+            .await
+            .map_err(|err| {
+                log::error!("Failed to check branch protection for {}: {}", push.repo.name, err);
+                "Failed to check branch protection".to_string()
+            })?;
+
+        if !check.violations.is_empty() {
+            return Ok(PushStart::Refused(push_rejection_report(request, &check.violations)));
+        }
+
+        let repo_id = push.repo.id.clone();
+        let heads_before = self.server.git.blocking(move |git| git.branch_heads(&repo_id)).await.unwrap_or_default();
+        let mut process = self.server.git.spawn_stateless_rpc(
+            &push.repo.id,
+            GitRpc::ReceivePack,
+            self.protocol.as_deref(),
+            &check.fast_forward_refs,
+        )?;
+        // A failed write means git stopped reading early; finishing the push reports why
+        let _ = process.write(&push.input).await;
+
+        Ok(PushStart::Running(Box::new(RunningPush {
+            repo: push.repo,
+            user_id: push.user_id,
+            heads_before,
+            process,
+            received: push.input.len(),
+        })))
+    }
+
+    async fn run_push(&self, push: RunningPush) -> Result<Vec<u8>, String> {
+        let repo = push.repo;
+        let output = push.process.finish().await?;
+
+        if let Err(err) = self.server.repos.record_push(&repo.id).await {
+            log::error!("Failed to record push for {}: {}", repo.name, err);
+        }
+
+        let heads_before = push.heads_before;
+        let repo_id = repo.id.clone();
+        let heads_after = self.server.git.blocking(move |git| git.branch_heads(&repo_id)).await.unwrap_or_default();
+        let updated = updated_branches(&heads_before, &heads_after);
+
+        if !updated.is_empty() {
+            self.server.pull_requests.schedule_branch_refresh(&repo.id, updated);
+            self.server.activity.push(&repo, &push.user_id, heads_before, heads_after);
+        }
+
+        Ok(output)
+    }
+
+    async fn finish_push(&mut self, channel: ChannelId, push: RunningPush, session: &mut Session) {
+        match self.run_push(push).await {
+            Ok(output) => {
+                session.data(channel, CryptoVec::from(output));
+                close_channel(channel, 0, session);
+            }
+            Err(err) => fail(channel, &err, session),
+        }
+    }
+
+    // Collects a push's commands until they are complete, then checks them and starts receive-pack
+    async fn receive_commands(&mut self, channel: ChannelId, mut push: Box<PendingPush>, data: &[u8], session: &mut Session) {
+        push.input.extend_from_slice(data);
+
+        let max_request_bytes = self.server.git.max_request_bytes();
+        if push.input.len() > max_request_bytes {
+            fail(channel, &too_large(max_request_bytes), session);
+            return;
+        }
+
+        let request = match parse_push_request(&push.input) {
+            Ok(Some((request, _))) => request,
+            Ok(None) => {
+                self.channels.insert(channel, ChannelState::ReceivePack(push));
+                return;
+            }
+            Err(err) => {
+                fail(channel, &err, session);
+                return;
+            }
+        };
+
+        // Without a pack to wait for, a push is complete as soon as its commands have arrived
+        let deletes_only = request.updates.iter().all(|update| update.is_delete());
+
+        match self.start_push(*push, &request).await {
+            Ok(PushStart::Running(running)) if deletes_only => self.finish_push(channel, *running, session).await,
+            Ok(PushStart::Running(running)) => {
+                self.channels.insert(channel, ChannelState::Pushing(running));
+            }
+            Ok(PushStart::Refused(report)) => {
+                session.data(channel, CryptoVec::from(report));
+                close_channel(channel, 0, session);
+            }
+            Err(err) => fail(channel, &err, session),
+        }
+    }
+}
+
+fn too_large(max_request_bytes: usize) -> String {
+    format!("Push exceeds the {} MB limit", max_request_bytes / (1024 * 1024))
+}
+
+fn close_channel(channel: ChannelId, exit_status: u32, session: &mut Session) {
+    session.exit_status_request(channel, exit_status);
+    session.eof(channel);
+    session.close(channel);
+}
+
+// Reports an error on the client's stderr and ends the command
+fn fail(channel: ChannelId, message: &str, session: &mut Session) {
+    session.extended_data(channel, 1, CryptoVec::from(format!("{}\n", message).into_bytes()));
+    close_channel(channel, 1, session);
+}
+
+// Streams upload-pack's output back to the client, then reports its exit status
+async fn forward_output(handle: Handle, channel: ChannelId, mut child: Child) {
+    let stderr = child.stderr.take().map(|mut stderr| {
+        tokio::spawn(async move {
+            let mut errors = Vec::new();
+            let _ = stderr.read_to_end(&mut errors).await;
+            errors
+        })
+    });
+
+    if let Some(mut stdout) = child.stdout.take() {
+        let mut buffer = vec![0u8; 32 * 1024];
+        loop {
+            match stdout.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    if handle.data(channel, CryptoVec::from_slice(&buffer[..read])).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    let exit_status = match child.wait().await {
+        Ok(status) if status.success() => 0,
+        _ => 1,
+    };
+
+    if let Some(task) = stderr {
+        if let Ok(errors) = task.await {
+            if !errors.is_empty() {
+                let _ = handle.extended_data(channel, 1, CryptoVec::from(errors)).await;
+            }
+        }
+    }
+
+    let _ = handle.exit_status_request(channel, exit_status).await;
+    let _ = handle.eof(channel).await;
+    let _ = handle.close(channel).await;
+}
+
+#[async_trait]
+impl russh::server::Handler for GitSshSession {
+    type Error = russh::Error;
+
+    async fn auth_publickey(&mut self, _user: &str, public_key: &PublicKey) -> Result<Auth, Self::Error> {
+        // Everyone connects as git@; the key alone identifies the user
+        match self.server.ssh_keys.authenticate(&fingerprint(&public_key.public_key_bytes())).await {
+            Ok(Some(user)) => {
+                self.user = Some(user);
+                Ok(Auth::Accept)
+            }
+            Ok(None) => Ok(Auth::Reject { proceed_with_methods: None }),
+            Err(err) => {
+                log::error!("Failed to look up SSH key: {}", err);
+                Ok(Auth::Reject { proceed_with_methods: None })
+            }
+        }
+    }
+
+    async fn channel_open_session(&mut self, channel: Channel<Msg>, _session: &mut Session) -> Result<bool, Self::Error> {
+        self.channels.insert(channel.id(), ChannelState::Idle);
+        Ok(true)
+    }
+
+    async fn env_request(
+        &mut self,
+        _channel: ChannelId,
+        variable_name: &str,
+        variable_value: &str,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        // Clients request protocol v2 through GIT_PROTOCOL, as with the Git-Protocol HTTP header
+        if variable_name == "GIT_PROTOCOL" {
+            self.protocol = Some(variable_value.to_string());
+        }
+
+        Ok(())
+    }
+
+    async fn shell_request(&mut self, channel: ChannelId, session: &mut Session) -> Result<(), Self::Error> {
+        let greeting = match &self.user {
+            Some((_, username)) => format!("Hi {}! You've successfully authenticated, but DevIT does not provide shell access.", username),
+            None => "Shell access is not available.".to_string(),
+        };
+
+        fail(channel, &greeting, session);
+        Ok(())
+    }
+
+    async fn exec_request(&mut self, channel: ChannelId, data: &[u8], session: &mut Session) -> Result<(), Self::Error> {
+        let command = String::from_utf8_lossy(data).to_string();
+        session.channel_success(channel);
+
+        if let Err(err) = self.start_command(channel, &command, session).await {
+            self.channels.remove(&channel);
+            fail(channel, &err, session);
+        }
+
+        Ok(())
+    }
+
+    async fn data(&mut self, channel: ChannelId, data: &[u8], session: &mut Session) -> Result<(), Self::Error> {
+        match self.channels.get_mut(&channel) {
+            Some(ChannelState::UploadPack(stdin)) => {
+                // upload-pack exits on its own when the client goes away
+                let _ = stdin.write_all(data).await;
+            }
+            Some(ChannelState::Pushing(push)) => {
+                push.received += data.len();
+                let max_request_bytes = self.server.git.max_request_bytes();
+
+                if push.received > max_request_bytes {
+                    // Dropping the push kills receive-pack before it stores anything
+                    self.channels.remove(&channel);
+                    fail(channel, &too_large(max_request_bytes), session);
+                } else {
+                    // A failed write means git stopped reading early; finishing the push reports why
+                    let _ = push.process.write(data).await;
+                }
+            }
+            Some(ChannelState::ReceivePack(_)) => {
+                if let Some(ChannelState::ReceivePack(push)) = self.channels.remove(&channel) {
+                    self.receive_commands(channel, push, data, session).await;
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    async fn channel_eof(&mut self, channel: ChannelId, session: &mut Session) -> Result<(), Self::Error> {
+        // Closing stdin lets upload-pack finish; a push is complete once its pack has arrived
+        match self.channels.remove(&channel) {
+            Some(ChannelState::Pushing(push)) => self.finish_push(channel, *push, session).await,
+            Some(ChannelState::ReceivePack(_)) => fail(channel, "Truncated pkt-line", session),
+            Some(ChannelState::UploadPack(stdin)) => drop(stdin),
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    async fn channel_close(&mut self, channel: ChannelId, _session: &mut Session) -> Result<(), Self::Error> {
+        self.channels.remove(&channel);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn git_commands_name_the_service_and_repository() {
+        for command in [
+            "git-upload-pack 'alice/project.git'",
+            "git-upload-pack '/alice/project.git'",
+            "git-upload-pack \"alice/project\"",
+            "git-upload-pack alice/project/",
+        ] {
+            assert_eq!(
+                parse_git_command(command),
+                Some((GitRpc::UploadPack, "alice".to_string(), "project".to_string())),
+                "{}",
+                command
+            );
+        }
+
+        assert_eq!(
+            parse_git_command("git-receive-pack 'acme/tools.git'"),
+            Some((GitRpc::ReceivePack, "acme".to_string(), "tools".to_string()))
+        );
+    }
+
+    #[test]
+    fn other_commands_are_refused() {
+        for command in [
+            "",
+            "git-upload-pack",
+            "git-upload-archive 'alice/project.git'",
+            "sh -c 'alice/project'",
+            "git-upload-pack 'project.git'",
+            "git-upload-pack 'alice/group/project.git'",
+            "git-upload-pack '/project.git'",
+        ] {
+            assert_eq!(parse_git_command(command), None, "{}", command);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+// @username mentions in issue, pull request and comment bodies
+
+use crate::utils::validation::is_valid_username;
+
+// Mentions beyond this many in one body are ignored
+const MAX_MENTIONS: usize = 50;
+
+fn is_username_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Usernames mentioned in a Markdown body, in order and without duplicates. Mentions inside code
+/// spans and fenced code blocks, and the domain part of email addresses, do not count.
+pub fn mentioned_usernames(body: &str) -> Vec<String> {
+    let mut usernames: Vec<String> = Vec::new();
+    let mut in_fence = false;
+
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        // Odd segments between backticks are code spans
+        for segment in line.split('`').step_by(2) {
+            let chars: Vec<char> = segment.chars().collect();
+            let mut i = 0;
+
+            while i < chars.len() {
+                let starts_mention = chars[i] == '@' && (i == 0 || !(is_username_char(chars[i - 1]) || chars[i - 1] == '.'));
+                if !starts_mention {
+                    i += 1;
+                    continue;
+                }
+
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|c| !is_username_char(*c))
+                    .map(|len| i + 1 + len)
+                    .unwrap_or(chars.len());
+                let username: String = chars[i + 1..end].iter().collect();
+
+                if is_valid_username(&username) && !usernames.iter().any(|known| known.eq_ignore_ascii_case(&username)) {
+                    usernames.push(username);
+                    if usernames.len() == MAX_MENTIONS {
+                        return usernames;
+                    }
+                }
+                i = end;
+            }
+        }
+    }
+
+    usernames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mentions_are_found_in_order_without_duplicates() {
+        assert_eq!(
+            mentioned_usernames("@alice can you and @bob-2 look?\n(cc @Alice, @carol_x)"),
+            vec!["alice", "bob-2", "carol_x"]
+        );
+    }
+
+    #[test]
+    fn emails_and_short_names_are_not_mentions() {
+        assert_eq!(mentioned_usernames("mail dev@example.com or foo.@bar, not @ab or @"), Vec::<String>::new());
+        assert_eq!(mentioned_usernames("a@alice b@@bob"), vec!["bob"]);
+    }
+
+    #[test]
+    fn code_is_not_searched_for_mentions() {
+        let body = "see `@alice` and @bob\n```\n@carol\n```\n  ```rust\n@dave\n```\n@erin `@frank` @grace";
+
+        assert_eq!(mentioned_usernames(body), vec!["bob", "erin", "grace"]);
+    }
+
+    #[test]
+    fn mentions_are_capped() {
+        let body: String = (0..MAX_MENTIONS + 10).map(|i| format!("@user{} ", i)).collect();
+
+        assert_eq!(mentioned_usernames(&body).len(), MAX_MENTIONS);
+    }
+}
@@ -3,3 +3,9 @@
 pub mod validation;
 pub mod response;
 pub mod jwt;
+pub mod templates;
+pub mod pagination;
+pub mod ssh;
+pub mod totp;
+pub mod pipeline;
+pub mod mentions;
@@ -0,0 +1,13 @@
+// Shared page/per_page handling for list endpoints
+
+pub const DEFAULT_PER_PAGE: u32 = 30;
+pub const MAX_PER_PAGE: u32 = 100;
+
+// Returns (limit, offset) for a 1-based page number. Pages past the end of u32 stay on the last
+// reachable offset, which is past any real listing, instead of overflowing.
+pub fn page_bounds(page: Option<u32>, per_page: Option<u32>) -> (u32, u32) {
+    let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+    let page = page.unwrap_or(1).max(1);
+
+    (per_page, (page - 1).saturating_mul(per_page))
+}
@@ -0,0 +1,347 @@
+// Parsing and trigger matching for .devit/pipelines/*.yml files
+//
+//   name: CI
+//   on:
+//     push:
+//       branches: [main, "release/*"]
+//     pull_request:
+//   env:
+//     RUST_LOG: info
+//   jobs:
+//     test:
+//       runs-on: [local]
+//       timeout-minutes: 30
+//       steps:
+//         - name: Test
+//           run: cargo test
+//       artifacts: [target/debug/app]
+//     deploy:
+//       needs: test
+//       steps:
+//         - run: ./deploy.sh
+
+use crate::models::{PipelineEvent, RunnerStep};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+pub const PIPELINE_DIR: &str = ".devit/pipelines";
+pub const DEFAULT_TIMEOUT_MINUTES: u32 = 60;
+const MAX_TIMEOUT_MINUTES: u32 = 360;
+const MAX_JOBS: usize = 50;
+const MAX_STEPS: usize = 100;
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    #[default]
+    None,
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            OneOrMany::None => Vec::new(),
+            OneOrMany::One(value) => vec![value.clone()],
+            OneOrMany::Many(values) => values.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BranchFilter {
+    #[serde(default)]
+    pub branches: OneOrMany, // Any branch when empty
+}
+
+/// `on: push`, `on: [push, pull_request]` or a map of events to branch filters.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Triggers {
+    Event(String),
+    Events(Vec<String>),
+    Filtered(HashMap<String, Option<BranchFilter>>),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct StepSpec {
+    pub name: Option<String>,
+    pub run: String,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(rename = "working-directory")]
+    pub working_directory: Option<String>,
+    #[serde(rename = "continue-on-error", default)]
+    pub continue_on_error: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct JobSpec {
+    pub name: Option<String>,
+    #[serde(rename = "runs-on", default)]
+    pub runs_on: OneOrMany,
+    #[serde(default)]
+    pub needs: OneOrMany,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(rename = "timeout-minutes")]
+    pub timeout_minutes: Option<u32>,
+    pub steps: Vec<StepSpec>,
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+}
+
+impl JobSpec {
+    pub fn runner_steps(&self) -> Vec<RunnerStep> {
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| RunnerStep {
+                number: index as i32 + 1,
+                name: step_name(step, index),
+                run: step.run.clone(),
+                env: step.env.clone(),
+                working_directory: step.working_directory.clone(),
+                continue_on_error: step.continue_on_error,
+            })
+            .collect()
+    }
+
+    pub fn timeout(&self) -> u32 {
+        self.timeout_minutes.unwrap_or(DEFAULT_TIMEOUT_MINUTES)
+    }
+}
+
+// Unnamed steps are shown by the first line of their command
+pub fn step_name(step: &StepSpec, index: usize) -> String {
+    match &step.name {
+        Some(name) => name.clone(),
+        None => {
+            let command = step.run.lines().next().unwrap_or_default().trim();
+            if command.is_empty() {
+                format!("Step {}", index + 1)
+            } else {
+                format!("Run {}", command.chars().take(100).collect::<String>())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PipelineSpec {
+    pub name: Option<String>,
+    #[serde(rename = "on")]
+    pub triggers: Triggers,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    pub jobs: BTreeMap<String, JobSpec>,
+}
+
+impl PipelineSpec {
+    // Push filters match the pushed branch, pull request filters the base branch
+    pub fn matches(&self, event: PipelineEvent, branch: &str) -> bool {
+        match &self.triggers {
+            Triggers::Event(name) => name == event.as_str(),
+            Triggers::Events(names) => names.iter().any(|name| name == event.as_str()),
+            Triggers::Filtered(events) => match events.get(event.as_str()) {
+                None => false,
+                Some(None) => true,
+                Some(Some(filter)) => {
+                    let patterns = filter.branches.to_vec();
+                    patterns.is_empty() || patterns.iter().any(|pattern| glob_match(pattern, branch))
+                }
+            },
+        }
+    }
+}
+
+/// Parses and validates a pipeline file. `path` names the file in errors.
+pub fn parse_pipeline(path: &str, contents: &[u8]) -> Result<PipelineSpec, String> {
+    let spec: PipelineSpec =
+        serde_yaml::from_slice(contents).map_err(|e| format!("Invalid pipeline {}: {}", path, e))?;
+
+    if spec.jobs.is_empty() {
+        return Err(format!("Pipeline {} has no jobs", path));
+    }
+    if spec.jobs.len() > MAX_JOBS {
+        return Err(format!("Pipeline {} has more than {} jobs", path, MAX_JOBS));
+    }
+
+    for (key, job) in &spec.jobs {
+        if key.is_empty() || key.len() > 100 || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(format!("Job key '{}' may only contain letters, digits, '-' and '_'", key));
+        }
+        if job.steps.is_empty() || job.steps.len() > MAX_STEPS {
+            return Err(format!("Job '{}' must have between 1 and {} steps", key, MAX_STEPS));
+        }
+        if job.timeout_minutes.is_some_and(|minutes| minutes == 0 || minutes > MAX_TIMEOUT_MINUTES) {
+            return Err(format!("Job '{}' timeout-minutes must be between 1 and {}", key, MAX_TIMEOUT_MINUTES));
+        }
+        if job.runs_on.to_vec().iter().any(|label| label.is_empty() || label.contains(',')) {
+            return Err(format!("Job '{}' has an invalid runs-on label", key));
+        }
+        for need in job.needs.to_vec() {
+            if !spec.jobs.contains_key(&need) {
+                return Err(format!("Job '{}' needs unknown job '{}'", key, need));
+            }
+        }
+        for artifact in &job.artifacts {
+            if artifact.starts_with('/') || artifact.split('/').any(|part| part == "..") {
+                return Err(format!("Job '{}' artifact '{}' must be a path inside the workspace", key, artifact));
+            }
+        }
+    }
+
+    if let Some(key) = find_cycle(&spec.jobs) {
+        return Err(format!("Job '{}' depends on itself through needs", key));
+    }
+
+    Ok(spec)
+}
+
+fn find_cycle(jobs: &BTreeMap<String, JobSpec>) -> Option<String> {
+    fn visit<'a>(key: &'a str, jobs: &'a BTreeMap<String, JobSpec>, visiting: &mut HashSet<&'a str>, done: &mut HashSet<&'a str>) -> bool {
+        if done.contains(key) {
+            return false;
+        }
+        if !visiting.insert(key) {
+            return true;
+        }
+
+        let cyclic = jobs[key]
+            .needs
+            .to_vec()
+            .iter()
+            .any(|need| jobs.get_key_value(need).is_some_and(|(need, _)| visit(need, jobs, visiting, done)));
+
+        visiting.remove(key);
+        done.insert(key);
+        cyclic
+    }
+
+    let mut done = HashSet::new();
+    jobs.keys()
+        .find(|key| visit(key, jobs, &mut HashSet::new(), &mut done))
+        .cloned()
+}
+
+// Matches branch names against patterns where `*` matches anything except `/` and `**` matches anything
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[u8], name: &[u8]) -> bool {
+        match pattern {
+            [] => name.is_empty(),
+            [b'*', b'*', rest @ ..] => (0..=name.len()).any(|i| matches(rest, &name[i..])),
+            [b'*', rest @ ..] => (0..=name.len())
+                .take_while(|&i| i == 0 || name[i - 1] != b'/')
+                .any(|i| matches(rest, &name[i..])),
+            [c, rest @ ..] => name.first() == Some(c) && matches(rest, &name[1..]),
+        }
+    }
+
+    matches(pattern.as_bytes(), name.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PIPELINE: &str = r#"
+name: CI
+on:
+  push:
+    branches: [main, "release/*"]
+  pull_request:
+jobs:
+  test:
+    runs-on: [local]
+    timeout-minutes: 30
+    steps:
+      - name: Test
+        run: cargo test
+    artifacts: [target/debug/app]
+  deploy:
+    needs: test
+    steps:
+      - run: ./deploy.sh
+"#;
+
+    fn pipeline_with_jobs(jobs: &str) -> Result<PipelineSpec, String> {
+        parse_pipeline("ci.yml", format!("on: push\njobs:\n{}", jobs).as_bytes())
+    }
+
+    #[test]
+    fn pipelines_are_parsed() {
+        let spec = parse_pipeline("ci.yml", PIPELINE.as_bytes()).unwrap();
+
+        assert_eq!(spec.name.as_deref(), Some("CI"));
+        assert_eq!(spec.jobs["test"].runs_on.to_vec(), vec!["local".to_string()]);
+        assert_eq!(spec.jobs["test"].timeout(), 30);
+        assert_eq!(spec.jobs["deploy"].needs.to_vec(), vec!["test".to_string()]);
+        assert_eq!(spec.jobs["deploy"].timeout(), DEFAULT_TIMEOUT_MINUTES);
+
+        let steps = spec.jobs["deploy"].runner_steps();
+        assert_eq!(steps[0].number, 1);
+        assert_eq!(steps[0].name, "Run ./deploy.sh");
+    }
+
+    #[test]
+    fn triggers_filter_on_event_and_branch() {
+        let spec = parse_pipeline("ci.yml", PIPELINE.as_bytes()).unwrap();
+
+        assert!(spec.matches(PipelineEvent::Push, "main"));
+        assert!(spec.matches(PipelineEvent::Push, "release/1.0"));
+        assert!(!spec.matches(PipelineEvent::Push, "feature"));
+        assert!(spec.matches(PipelineEvent::PullRequest, "feature"));
+
+        let spec = pipeline_with_jobs("  test:\n    steps:\n      - run: make\n").unwrap();
+        assert!(spec.matches(PipelineEvent::Push, "anything"));
+        assert!(!spec.matches(PipelineEvent::PullRequest, "main"));
+    }
+
+    #[test]
+    fn invalid_jobs_are_rejected() {
+        for (jobs, error) in [
+            ("  bad key:\n    steps:\n      - run: make\n", "may only contain"),
+            ("  test:\n    steps: []\n", "between 1 and"),
+            ("  test:\n    timeout-minutes: 0\n    steps:\n      - run: make\n", "timeout-minutes"),
+            ("  test:\n    needs: build\n    steps:\n      - run: make\n", "unknown job 'build'"),
+            ("  test:\n    artifacts: [../secrets]\n    steps:\n      - run: make\n", "inside the workspace"),
+        ] {
+            let result = pipeline_with_jobs(jobs);
+            assert!(result.as_ref().is_err_and(|e| e.contains(error)), "{:?}: {:?}", jobs, result.map(|spec| spec.name));
+        }
+
+        assert!(parse_pipeline("ci.yml", b"on: push\njobs: {}\n").is_err());
+        assert!(parse_pipeline("ci.yml", b"not: [yaml").is_err_and(|e| e.contains("ci.yml")));
+    }
+
+    #[test]
+    fn needs_cycles_are_rejected() {
+        let result = pipeline_with_jobs(
+            "  a:\n    needs: c\n    steps:\n      - run: a\n  b:\n    needs: a\n    steps:\n      - run: b\n  c:\n    needs: [b]\n    steps:\n      - run: c\n",
+        );
+        assert!(result.is_err_and(|e| e.contains("depends on itself")));
+
+        let result = pipeline_with_jobs("  a:\n    needs: a\n    steps:\n      - run: a\n");
+        assert!(result.is_err_and(|e| e.contains("'a'")));
+
+        // Two jobs sharing a need is not a cycle
+        let result = pipeline_with_jobs(
+            "  a:\n    steps:\n      - run: a\n  b:\n    needs: a\n    steps:\n      - run: b\n  c:\n    needs: [a, b]\n    steps:\n      - run: c\n",
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn globs_only_cross_slashes_with_double_stars() {
+        assert!(glob_match("main", "main"));
+        assert!(!glob_match("main", "main2"));
+        assert!(glob_match("release/*", "release/1.0"));
+        assert!(!glob_match("release/*", "release/1.0/hotfix"));
+        assert!(glob_match("release/**", "release/1.0/hotfix"));
+        assert!(glob_match("*-stable", "2024-stable"));
+        assert!(!glob_match("*", "feature/x"));
+        assert!(glob_match("**", "feature/x"));
+    }
+}
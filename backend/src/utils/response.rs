@@ -23,6 +23,7 @@ pub fn error_response<S: AsRef<str>>(message: S, status_code: u16) -> HttpRespon
         401 => HttpResponse::Unauthorized(),
         403 => HttpResponse::Forbidden(),
         404 => HttpResponse::NotFound(),
+        409 => HttpResponse::Conflict(),
         500 => HttpResponse::InternalServerError(),
         501 => HttpResponse::NotImplemented(),
         _ => HttpResponse::InternalServerError(),
@@ -0,0 +1,91 @@
+use base64::{engine::general_purpose::{STANDARD, STANDARD_NO_PAD}, Engine as _};
+use ring::digest::{digest, SHA256};
+
+const SUPPORTED_KEY_TYPES: &[&str] = &[
+    "ssh-ed25519",
+    "ssh-rsa",
+    "ecdsa-sha2-nistp256",
+    "ecdsa-sha2-nistp384",
+    "ecdsa-sha2-nistp521",
+    "sk-ssh-ed25519@openssh.com",
+    "sk-ecdsa-sha2-nistp256@openssh.com",
+];
+
+const MIN_RSA_BITS: usize = 2048;
+
+#[derive(Debug)]
+pub struct PublicKey {
+    pub key_type: String,
+    pub key_data: String, // "<type> <base64>", without the comment
+    pub comment: Option<String>,
+    pub fingerprint: String,
+}
+
+/// Parses a public key in the OpenSSH `authorized_keys` format, e.g. the contents of `id_ed25519.pub`.
+pub fn parse_public_key(input: &str) -> Result<PublicKey, String> {
+    let mut parts = input.split_whitespace();
+    let key_type = parts.next().ok_or("Public key is empty")?;
+    let encoded = parts.next().ok_or("Public key is missing its key data")?;
+    let comment = parts.collect::<Vec<_>>().join(" ");
+
+    if !SUPPORTED_KEY_TYPES.contains(&key_type) {
+        return Err(format!("Unsupported key type: {}", key_type));
+    }
+
+    let blob = STANDARD
+        .decode(encoded)
+        .map_err(|_| "Public key data is not valid base64".to_string())?;
+
+    // The blob repeats the key type, followed by type-specific fields
+    let mut fields = blob.as_slice();
+    if read_string(&mut fields)? != key_type.as_bytes() {
+        return Err("Public key data does not match its key type".to_string());
+    }
+
+    match key_type {
+        "ssh-ed25519" if read_string(&mut fields)?.len() != 32 => {
+            return Err("Invalid ed25519 public key".to_string());
+        }
+        "ssh-rsa" => {
+            let _exponent = read_string(&mut fields)?;
+            if rsa_bits(read_string(&mut fields)?) < MIN_RSA_BITS {
+                return Err(format!("RSA keys must be at least {} bits", MIN_RSA_BITS));
+            }
+        }
+        _ => {}
+    }
+
+    Ok(PublicKey {
+        key_type: key_type.to_string(),
+        key_data: format!("{} {}", key_type, encoded),
+        comment: (!comment.is_empty()).then_some(comment),
+        fingerprint: fingerprint(&blob),
+    })
+}
+
+/// OpenSSH-style SHA256 fingerprint of a public key blob.
+pub fn fingerprint(blob: &[u8]) -> String {
+    format!("SHA256:{}", STANDARD_NO_PAD.encode(digest(&SHA256, blob)))
+}
+
+// Reads one length-prefixed string from the SSH wire format
+fn read_string<'a>(input: &mut &'a [u8]) -> Result<&'a [u8], String> {
+    let invalid = || "Public key data is truncated".to_string();
+
+    let len = input.get(..4).ok_or_else(invalid)?;
+    let len = u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    let data = input.get(4..4 + len).ok_or_else(invalid)?;
+
+    *input = &input[4 + len..];
+    Ok(data)
+}
+
+fn rsa_bits(modulus: &[u8]) -> usize {
+    // mpints carry a leading zero byte when the high bit is set
+    let modulus = match modulus.iter().position(|&byte| byte != 0) {
+        Some(start) => &modulus[start..],
+        None => return 0,
+    };
+
+    modulus.len() * 8 - modulus[0].leading_zeros() as usize
+}
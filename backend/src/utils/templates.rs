@@ -0,0 +1,93 @@
+// Bundled .gitignore and LICENSE templates used when initializing repositories
+
+pub struct GitignoreTemplate {
+    pub name: &'static str,
+    pub source: &'static str,
+}
+
+pub struct LicenseTemplate {
+    pub key: &'static str,
+    pub name: &'static str,
+    pub spdx_id: &'static str,
+    pub body: &'static str,
+}
+
+impl LicenseTemplate {
+    // Fills in the copyright placeholders used by the permissive licenses
+    pub fn render(&self, year: i32, fullname: &str) -> String {
+        self.body
+            .replace("[year]", &year.to_string())
+            .replace("[fullname]", fullname)
+    }
+}
+
+pub static GITIGNORE_TEMPLATES: &[GitignoreTemplate] = &[
+    GitignoreTemplate { name: "C", source: include_str!("../../templates/gitignore/C.gitignore") },
+    GitignoreTemplate { name: "C++", source: include_str!("../../templates/gitignore/C++.gitignore") },
+    GitignoreTemplate { name: "Go", source: include_str!("../../templates/gitignore/Go.gitignore") },
+    GitignoreTemplate { name: "Java", source: include_str!("../../templates/gitignore/Java.gitignore") },
+    GitignoreTemplate { name: "Node", source: include_str!("../../templates/gitignore/Node.gitignore") },
+    GitignoreTemplate { name: "Python", source: include_str!("../../templates/gitignore/Python.gitignore") },
+    GitignoreTemplate { name: "Ruby", source: include_str!("../../templates/gitignore/Ruby.gitignore") },
+    GitignoreTemplate { name: "Rust", source: include_str!("../../templates/gitignore/Rust.gitignore") },
+    GitignoreTemplate { name: "Swift", source: include_str!("../../templates/gitignore/Swift.gitignore") },
+];
+
+pub static LICENSE_TEMPLATES: &[LicenseTemplate] = &[
+    LicenseTemplate {
+        key: "apache-2.0",
+        name: "Apache License 2.0",
+        spdx_id: "Apache-2.0",
+        body: include_str!("../../templates/licenses/apache-2.0.txt"),
+    },
+    LicenseTemplate {
+        key: "bsd-2-clause",
+        name: "BSD 2-Clause \"Simplified\" License",
+        spdx_id: "BSD-2-Clause",
+        body: include_str!("../../templates/licenses/bsd-2-clause.txt"),
+    },
+    LicenseTemplate {
+        key: "bsd-3-clause",
+        name: "BSD 3-Clause \"New\" or \"Revised\" License",
+        spdx_id: "BSD-3-Clause",
+        body: include_str!("../../templates/licenses/bsd-3-clause.txt"),
+    },
+    LicenseTemplate {
+        key: "gpl-3.0",
+        name: "GNU General Public License v3.0",
+        spdx_id: "GPL-3.0",
+        body: include_str!("../../templates/licenses/gpl-3.0.txt"),
+    },
+    LicenseTemplate {
+        key: "isc",
+        name: "ISC License",
+        spdx_id: "ISC",
+        body: include_str!("../../templates/licenses/isc.txt"),
+    },
+    LicenseTemplate {
+        key: "mit",
+        name: "MIT License",
+        spdx_id: "MIT",
+        body: include_str!("../../templates/licenses/mit.txt"),
+    },
+    LicenseTemplate {
+        key: "mpl-2.0",
+        name: "Mozilla Public License 2.0",
+        spdx_id: "MPL-2.0",
+        body: include_str!("../../templates/licenses/mpl-2.0.txt"),
+    },
+    LicenseTemplate {
+        key: "unlicense",
+        name: "The Unlicense",
+        spdx_id: "Unlicense",
+        body: include_str!("../../templates/licenses/unlicense.txt"),
+    },
+];
+
+pub fn find_gitignore_template(name: &str) -> Option<&'static GitignoreTemplate> {
+    GITIGNORE_TEMPLATES.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+}
+
+pub fn find_license_template(key: &str) -> Option<&'static LicenseTemplate> {
+    LICENSE_TEMPLATES.iter().find(|t| t.key.eq_ignore_ascii_case(key))
+}